@@ -0,0 +1,90 @@
+//! Cluster URL and keypair resolution.
+//!
+//! Reads the Solana CLI's `config.yml` so ops staff share one setup with
+//! `solana`; `--url` and `--keypair` override it per invocation.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rice_supply_client::rpc::{DEVNET_URL, LOCALNET_URL};
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    json_rpc_url: Option<String>,
+    keypair_path: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    pub url: String,
+    pub keypair_path: PathBuf,
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+pub fn default_config_path() -> PathBuf {
+    home_dir().join(".config/solana/cli/config.yml")
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Short cluster names the `solana` CLI also accepts for `--url`.
+fn resolve_url(url: &str) -> String {
+    match url {
+        "localhost" | "l" => LOCALNET_URL.to_string(),
+        "devnet" | "d" => DEVNET_URL.to_string(),
+        "testnet" | "t" => "https://api.testnet.solana.com".to_string(),
+        "mainnet-beta" | "m" => "https://api.mainnet-beta.solana.com".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl Config {
+    pub fn load(
+        config_path: Option<&Path>,
+        url: Option<&str>,
+        keypair_path: Option<&Path>,
+    ) -> Result<Self> {
+        let file = match config_path {
+            Some(path) => read_config_file(path)?,
+            None => {
+                let path = default_config_path();
+                if path.exists() {
+                    read_config_file(&path)?
+                } else {
+                    ConfigFile::default()
+                }
+            }
+        };
+
+        let url = url
+            .map(str::to_string)
+            .or(file.json_rpc_url)
+            .unwrap_or_else(|| LOCALNET_URL.to_string());
+        let keypair_path = keypair_path
+            .map(Path::to_path_buf)
+            .or_else(|| file.keypair_path.as_deref().map(expand_home))
+            .unwrap_or_else(|| home_dir().join(".config/solana/id.json"));
+
+        Ok(Self {
+            url: resolve_url(&url),
+            keypair_path,
+        })
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("reading config {}", path.display()))?;
+    serde_yaml::from_str(&text).with_context(|| format!("parsing config {}", path.display()))
+}
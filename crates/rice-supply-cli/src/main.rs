@@ -0,0 +1,768 @@
+//! `rice-supply-cli`: operate the rice-supply-chain program from a terminal.
+
+mod config;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use rice_supply_chain::{
+    AmendmentKind, BatchStatus, Gs1Key, OrganicAmendment, Organization, PaymentMethod,
+    QualityGrade, RiceVariety, TokenUnit, TransactionStatus, ValidationStatus, WaterRegime,
+};
+use rice_supply_client::accounts::{ChainTransaction, RiceBatch, RiceSupplyAccount};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::json::ToJson;
+use rice_supply_client::lineage::Lineage;
+use rice_supply_client::rpc::RpcClient;
+use rice_supply_client::{
+    pda, read_keypair_file, Instruction, Pubkey, RiceSupplyClient, Signature, Signer,
+};
+use rice_supply_gs1::epcis::{self, Products};
+use rice_supply_gs1::{DigitalLink, Gtin};
+use rice_supply_provenance::Report;
+use serde_json::json;
+
+use crate::config::Config;
+
+#[derive(Parser)]
+#[command(name = "rice-supply-cli", version, about)]
+struct Cli {
+    /// Solana CLI config file [default: ~/.config/solana/cli/config.yml]
+    #[arg(long, short = 'C', global = true, env = "RICE_SUPPLY_CONFIG")]
+    config: Option<PathBuf>,
+    /// RPC URL or cluster name (localhost, devnet, testnet, mainnet-beta)
+    #[arg(long, short = 'u', global = true)]
+    url: Option<String>,
+    /// Fee payer and signing authority keypair file
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage chain actors
+    #[command(subcommand)]
+    Actor(ActorCommand),
+    /// Register production seasons
+    #[command(subcommand)]
+    Season(SeasonCommand),
+    /// Mint rice batches
+    #[command(subcommand)]
+    Batch(BatchCommand),
+    /// Create and settle chain transactions
+    #[command(subcommand, name = "tx")]
+    Transaction(TransactionCommand),
+    /// Print any program account as JSON
+    Dump { address: Pubkey },
+    /// Print the address derived from a seed
+    Address {
+        #[arg(value_parser = ["actor", "season", "milled-rice", "batch", "tx"])]
+        kind: String,
+        seed: String,
+    },
+    /// Export a batch's lineage for trading partners
+    #[command(subcommand)]
+    Export(ExportCommand),
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// GS1 EPCIS 2.0 JSON-LD document of every event behind a batch
+    Epcis {
+        /// Batch seed
+        batch: String,
+        /// GTIN of the harvested paddy
+        #[arg(long)]
+        paddy_gtin: Gtin,
+        /// GTIN of the milled rice
+        #[arg(long)]
+        rice_gtin: Gtin,
+        /// Write the document to a file instead of stdout
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+    /// Customer-facing report of where a batch came from and who handled it
+    Provenance {
+        /// Batch seed
+        batch: String,
+        #[arg(long, value_parser = ["json", "html"], default_value = "json")]
+        format: String,
+        /// Write the report to a file instead of stdout
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ActorCommand {
+    Create(CreateActor),
+    Update(UpdateActor),
+    /// Mark an actor inactive
+    Deactivate {
+        seed: String,
+    },
+    /// Register the keypair as the wallet holding the actor's batch tokens
+    RegisterWallet {
+        seed: String,
+    },
+}
+
+#[derive(Args)]
+struct CreateActor {
+    seed: String,
+    /// Display name [default: the seed]
+    #[arg(long)]
+    name: Option<String>,
+    #[arg(long, value_parser = parse_organization)]
+    organization: Organization,
+    /// Role label; repeat for several
+    #[arg(long = "actor-type")]
+    actor_type: Vec<String>,
+    #[arg(long)]
+    pin: String,
+    #[arg(long)]
+    farm_id: Option<Pubkey>,
+    #[arg(long)]
+    farmer_id: Option<Pubkey>,
+    #[arg(long, default_value_t = 0)]
+    assigned_tps: u64,
+    #[arg(long)]
+    address: Option<String>,
+}
+
+#[derive(Args)]
+struct UpdateActor {
+    seed: String,
+    #[arg(long)]
+    name: Option<String>,
+    #[arg(long, value_parser = parse_organization)]
+    organization: Option<Organization>,
+    /// Replaces all role labels; repeat for several
+    #[arg(long = "actor-type")]
+    actor_type: Vec<String>,
+    #[arg(long)]
+    pin: Option<String>,
+    #[arg(long)]
+    farm_id: Option<Pubkey>,
+    #[arg(long)]
+    farmer_id: Option<Pubkey>,
+    #[arg(long)]
+    assigned_tps: Option<u64>,
+    #[arg(long)]
+    address: Option<String>,
+    #[arg(long)]
+    active: Option<bool>,
+    #[arg(long)]
+    balance: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum SeasonCommand {
+    Create(CreateSeason),
+    /// Approve or reject a season as an accredited validator signed for by
+    /// the keypair
+    Validate {
+        seed: String,
+        /// pending, approved or rejected
+        #[arg(value_parser = parse_validation_status)]
+        status: ValidationStatus,
+        /// Validator `ChainActor` address
+        #[arg(long)]
+        validator: Pubkey,
+    },
+    /// Mint carbon credits for an approved CarbonSmart season to its farmer
+    IssueCredits {
+        seed: String,
+        /// Validator `ChainActor` that approved the season, signed for by the
+        /// keypair
+        #[arg(long)]
+        validator: Pubkey,
+        /// Seed of the season's CarbonSmart certification
+        #[arg(long)]
+        certification: String,
+        /// Registered token wallet of the season's farmer
+        #[arg(long)]
+        wallet: Pubkey,
+    },
+    /// Burn carbon credits of a season held by the keypair
+    RetireCredits {
+        seed: String,
+        /// Amount in kilograms of CO2e
+        amount_kg_co2e: u64,
+    },
+}
+
+#[derive(Args)]
+struct CreateSeason {
+    seed: String,
+    /// Farmer `ChainActor` address
+    #[arg(long)]
+    farmer: Pubkey,
+    #[arg(long)]
+    crop_year: String,
+    #[arg(long, default_value_t = 0)]
+    processed_yield_kg: u64,
+    /// e.g. nsic-rc-222, psb-rc-18, traditional
+    #[arg(long, value_parser = parse_variety)]
+    variety: Option<RiceVariety>,
+    #[arg(long)]
+    planned_practice: Option<String>,
+    /// Unix timestamp
+    #[arg(long)]
+    planting_date: Option<i64>,
+    /// continuous-flooding, single-drainage, awd, rainfed or upland
+    #[arg(long, value_parser = parse_water_regime)]
+    irrigation_practice: Option<WaterRegime>,
+    #[arg(long)]
+    fertilizer_used: Option<String>,
+    #[arg(long)]
+    pesticide_used: Option<String>,
+    /// Unix timestamp
+    #[arg(long)]
+    harvest_date: Option<i64>,
+    #[arg(long)]
+    total_yield_kg: Option<u64>,
+    #[arg(long)]
+    moisture_content: Option<u32>,
+    #[arg(long)]
+    cultivated_area_m2: Option<u64>,
+    /// <KIND>:<KG_PER_HA>, KIND one of straw-shortly-before,
+    /// straw-long-before, compost, farmyard-manure or green-manure; repeat
+    /// for several
+    #[arg(long = "amendment", value_parser = parse_amendment)]
+    amendments: Vec<OrganicAmendment>,
+}
+
+#[derive(Subcommand)]
+enum BatchCommand {
+    Create(CreateBatch),
+    /// Mint the batch as a Token-2022 token to the keypair's wallet
+    Tokenize(TokenizeBatch),
+}
+
+#[derive(Args)]
+struct CreateBatch {
+    seed: String,
+    /// `MilledRice` address the batch is cut from
+    #[arg(long)]
+    milled_rice: Pubkey,
+    #[arg(long)]
+    weight_kg: u32,
+    #[arg(long, default_value_t = 0)]
+    quality_score: u32,
+    /// Opaque QR payload; use --gtin and --lot for a GS1 Digital Link instead
+    #[arg(long, required_unless_present = "gtin", conflicts_with = "gtin")]
+    qr_code: Option<String>,
+    /// GTIN of the packed rice, encoded with --lot as a GS1 Digital Link
+    #[arg(long, requires = "lot")]
+    gtin: Option<Gtin>,
+    /// Lot number (AI 10)
+    #[arg(long, requires = "gtin")]
+    lot: Option<String>,
+    /// Serial number (AI 21), when the batch is a single trade item
+    #[arg(long, requires = "gtin")]
+    serial: Option<String>,
+    /// Resolver domain of the Digital Link
+    #[arg(long, requires = "gtin", default_value = Gs1Key::CANONICAL_RESOLVER)]
+    resolver: String,
+}
+
+impl CreateBatch {
+    fn qr_code(&self) -> Result<String> {
+        match (&self.qr_code, self.gtin, &self.lot) {
+            (Some(qr_code), _, _) => Ok(qr_code.clone()),
+            (None, Some(gtin), Some(lot)) => {
+                Ok(DigitalLink::new(gtin, lot, self.serial.as_deref())?.uri(&self.resolver))
+            }
+            _ => bail!("pass --qr-code, or --gtin with --lot"),
+        }
+    }
+}
+
+#[derive(Args)]
+struct TokenizeBatch {
+    seed: String,
+    /// Seed of the actor holding the batch; the keypair must be its
+    /// registered wallet
+    #[arg(long)]
+    actor: String,
+    #[arg(long, value_parser = parse_token_unit, default_value = "batch")]
+    unit: TokenUnit,
+    /// Token name [default: "Rice batch <SEED>"]
+    #[arg(long)]
+    name: Option<String>,
+    #[arg(long, default_value = "RICE")]
+    symbol: String,
+    /// Metadata URI [default: the batch QR code]
+    #[arg(long)]
+    uri: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum TransactionCommand {
+    Create(CreateTransaction),
+    /// Mark a transaction completed, signing as its seller
+    Settle {
+        seed: String,
+        #[arg(long)]
+        reference: Option<String>,
+    },
+    /// Cancel a pending transaction, signing as its seller
+    Cancel {
+        seed: String,
+    },
+}
+
+#[derive(Args)]
+struct CreateTransaction {
+    seed: String,
+    /// Seller `ChainActor` address
+    #[arg(long)]
+    from: Pubkey,
+    /// Buyer `ChainActor` address
+    #[arg(long)]
+    to: Pubkey,
+    /// Rice batch address; repeat for several
+    #[arg(long = "batch")]
+    batches: Vec<Pubkey>,
+    #[arg(long)]
+    amount: u64,
+    #[arg(long, value_parser = parse_payment_method)]
+    payment_method: PaymentMethod,
+    #[arg(long)]
+    reference: Option<String>,
+    #[arg(long, value_parser = parse_quality_grade)]
+    quality: Option<QualityGrade>,
+}
+
+fn parse_organization(value: &str) -> Result<Organization, String> {
+    Ok(match value {
+        "farmer" => Organization::Farmer,
+        "miller" => Organization::Miller,
+        "distributor" => Organization::Distributor,
+        "retailer" => Organization::Retailer,
+        "government" => Organization::Government,
+        "cooperative" => Organization::Cooperative,
+        _ => {
+            return Err(
+                "expected farmer, miller, distributor, retailer, government or cooperative".into(),
+            )
+        }
+    })
+}
+
+fn parse_validation_status(value: &str) -> Result<ValidationStatus, String> {
+    Ok(match value {
+        "pending" => ValidationStatus::Pending,
+        "approved" => ValidationStatus::Approved,
+        "rejected" => ValidationStatus::Rejected,
+        _ => return Err("expected pending, approved or rejected".into()),
+    })
+}
+
+fn parse_token_unit(value: &str) -> Result<TokenUnit, String> {
+    Ok(match value {
+        "batch" => TokenUnit::Batch,
+        "kg" => TokenUnit::Kilogram,
+        _ => return Err("expected batch or kg".into()),
+    })
+}
+
+fn parse_water_regime(value: &str) -> Result<WaterRegime, String> {
+    Ok(match value {
+        "continuous-flooding" => WaterRegime::ContinuousFlooding,
+        "single-drainage" => WaterRegime::SingleDrainage,
+        "awd" | "multiple-drainage" => WaterRegime::MultipleDrainage,
+        "rainfed" => WaterRegime::Rainfed,
+        "upland" => WaterRegime::Upland,
+        _ => {
+            return Err(
+                "expected continuous-flooding, single-drainage, awd, rainfed or upland".into(),
+            )
+        }
+    })
+}
+
+fn parse_amendment(value: &str) -> Result<OrganicAmendment, String> {
+    let (kind, rate) = value.split_once(':').ok_or("expected <KIND>:<KG_PER_HA>")?;
+    let kind = match kind {
+        "straw-shortly-before" => AmendmentKind::StrawShortlyBefore,
+        "straw-long-before" => AmendmentKind::StrawLongBefore,
+        "compost" => AmendmentKind::Compost,
+        "farmyard-manure" => AmendmentKind::FarmyardManure,
+        "green-manure" => AmendmentKind::GreenManure,
+        _ => {
+            return Err(
+                "expected straw-shortly-before, straw-long-before, compost, \
+                 farmyard-manure or green-manure"
+                    .into(),
+            )
+        }
+    };
+    Ok(OrganicAmendment {
+        kind,
+        rate_kg_per_ha: rate.parse().map_err(|error| format!("{error}"))?,
+    })
+}
+
+fn parse_payment_method(value: &str) -> Result<PaymentMethod, String> {
+    Ok(match value {
+        "cash" => PaymentMethod::Cash,
+        "bank-transfer" => PaymentMethod::BankTransfer,
+        "digital-wallet" => PaymentMethod::DigitalWallet,
+        "cryptocurrency" => PaymentMethod::Cryptocurrency,
+        "credit" => PaymentMethod::Credit,
+        _ => {
+            return Err(
+                "expected cash, bank-transfer, digital-wallet, cryptocurrency or credit".into(),
+            )
+        }
+    })
+}
+
+fn parse_quality_grade(value: &str) -> Result<QualityGrade, String> {
+    Ok(match value {
+        "premium" => QualityGrade::Premium,
+        "grade1" => QualityGrade::Grade1,
+        "grade2" => QualityGrade::Grade2,
+        "grade3" => QualityGrade::Grade3,
+        "off-grade" => QualityGrade::OffGrade,
+        _ => return Err("expected premium, grade1, grade2, grade3 or off-grade".into()),
+    })
+}
+
+fn parse_variety(value: &str) -> Result<RiceVariety, String> {
+    let code = |digits: &str| digits.parse::<u16>().map_err(|error| error.to_string());
+    if let Some(digits) = value.strip_prefix("nsic-rc-") {
+        return Ok(RiceVariety::NsicRc(code(digits)?));
+    }
+    if let Some(digits) = value.strip_prefix("psb-rc-") {
+        return Ok(RiceVariety::PsbRc(code(digits)?));
+    }
+    match value {
+        "traditional" => Ok(RiceVariety::Traditional),
+        "other" => Ok(RiceVariety::Other),
+        _ => Err("expected nsic-rc-<code>, psb-rc-<code>, traditional or other".into()),
+    }
+}
+
+fn report(signature: &Signature, address: &Pubkey) {
+    println!("Address:   {address}");
+    println!("Signature: {signature}");
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(
+        cli.config.as_deref(),
+        cli.url.as_deref(),
+        cli.keypair.as_deref(),
+    )?;
+
+    match cli.command {
+        Command::Address { kind, seed } => {
+            let (address, _) = match kind.as_str() {
+                "actor" => pda::chain_actor(&seed),
+                "season" => pda::production_season(&seed),
+                "milled-rice" => pda::milled_rice(&seed),
+                "batch" => pda::rice_batch(&seed),
+                _ => pda::chain_transaction(&seed),
+            };
+            println!("{address}");
+            Ok(())
+        }
+        Command::Dump { address } => dump(&config, &address),
+        Command::Export(command) => export(&config, command),
+        command => {
+            let payer = read_keypair_file(&config.keypair_path).map_err(|error| {
+                anyhow::anyhow!("reading keypair {}: {error}", config.keypair_path.display())
+            })?;
+            let client = RiceSupplyClient::new(&config.url, payer);
+            run(&client, command)
+        }
+    }
+}
+
+fn dump(config: &Config, address: &Pubkey) -> Result<()> {
+    let rpc = RpcClient::new(&config.url);
+    let Some(account) = rpc.get_account(address)? else {
+        bail!("account {address} not found");
+    };
+    if account.owner != rice_supply_client::PROGRAM_ID {
+        bail!(
+            "account {address} is owned by {}, not the program",
+            account.owner
+        );
+    }
+    let decoded = RiceSupplyAccount::decode(&account.data)
+        .context("unknown account discriminator")?
+        .with_context(|| format!("decoding {address}"))?;
+    let mut output = decoded.to_json();
+    output["address"] = json!(address.to_string());
+    output["lamports"] = json!(account.lamports);
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+fn export(config: &Config, command: ExportCommand) -> Result<()> {
+    let rpc = RpcClient::new(&config.url);
+    let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let (document, output) = match command {
+        ExportCommand::Epcis {
+            batch,
+            paddy_gtin,
+            rice_gtin,
+            output,
+        } => {
+            let address = pda::rice_batch(&batch).0;
+            let lineage = Lineage::fetch(&rpc, &address)
+                .with_context(|| format!("tracing batch {address}"))?;
+            let products = Products {
+                paddy: paddy_gtin,
+                milled_rice: rice_gtin,
+            };
+            let document = epcis::document(&lineage, &products, created);
+            (serde_json::to_string_pretty(&document)?, output)
+        }
+        ExportCommand::Provenance {
+            batch,
+            format,
+            output,
+        } => {
+            let address = pda::rice_batch(&batch).0;
+            let report = Report::fetch(&rpc, &address)
+                .with_context(|| format!("tracing batch {address}"))?;
+            let document = match format.as_str() {
+                "html" => report.to_html(created),
+                _ => serde_json::to_string_pretty(&report.to_json(created))?,
+            };
+            (document, output)
+        }
+    };
+    match output {
+        Some(path) => {
+            fs::write(&path, document).with_context(|| format!("writing {}", path.display()))?
+        }
+        None => println!("{document}"),
+    }
+    Ok(())
+}
+
+fn send(client: &RiceSupplyClient, instruction: Instruction, address: Pubkey) -> Result<()> {
+    let signature = client.send(&[instruction])?;
+    report(&signature, &address);
+    Ok(())
+}
+
+fn run(client: &RiceSupplyClient, command: Command) -> Result<()> {
+    let payer = client.payer().pubkey();
+    match command {
+        Command::Actor(ActorCommand::Create(actor)) => {
+            let address = pda::chain_actor(&actor.seed).0;
+            let instruction = instructions::create_chain_actor(
+                &payer,
+                args::CreateChainActor {
+                    name: actor.name.unwrap_or_else(|| actor.seed.clone()),
+                    actor_seed: actor.seed,
+                    actor_type: actor.actor_type,
+                    farm_id: actor.farm_id,
+                    farmer_id: actor.farmer_id,
+                    assigned_tps: actor.assigned_tps,
+                    pin: actor.pin,
+                    organization: actor.organization,
+                    address: actor.address,
+                },
+            );
+            send(client, instruction, address)
+        }
+        Command::Actor(ActorCommand::Update(actor)) => {
+            let address = pda::chain_actor(&actor.seed).0;
+            let instruction = instructions::update_chain_actor(
+                &payer,
+                args::UpdateChainActor {
+                    actor_seed: actor.seed,
+                    name: actor.name,
+                    actor_type: (!actor.actor_type.is_empty()).then_some(actor.actor_type),
+                    farm_id: actor.farm_id,
+                    farmer_id: actor.farmer_id,
+                    assigned_tps: actor.assigned_tps,
+                    pin: actor.pin,
+                    organization: actor.organization,
+                    address: actor.address,
+                    is_active: actor.active,
+                    balance: actor.balance,
+                },
+            );
+            send(client, instruction, address)
+        }
+        Command::Actor(ActorCommand::Deactivate { seed }) => {
+            let address = pda::chain_actor(&seed).0;
+            send(
+                client,
+                instructions::delete_chain_actor(&payer, &seed),
+                address,
+            )
+        }
+        Command::Actor(ActorCommand::RegisterWallet { seed }) => {
+            let chain_actor = pda::chain_actor(&seed).0;
+            send(
+                client,
+                instructions::register_custodian(&payer, &chain_actor, &payer),
+                pda::custodian(&payer).0,
+            )
+        }
+        Command::Season(SeasonCommand::Create(season)) => {
+            let address = pda::production_season(&season.seed).0;
+            let instruction = instructions::create_production_season(
+                &payer,
+                args::CreateProductionSeason {
+                    season_seed: season.seed,
+                    farmer_id: season.farmer,
+                    crop_year: season.crop_year,
+                    processed_yield_kg: season.processed_yield_kg,
+                    variety: season.variety,
+                    planned_practice: season.planned_practice,
+                    planting_date: season.planting_date,
+                    irrigation_practice: season.irrigation_practice,
+                    fertilizer_used: season.fertilizer_used,
+                    pesticide_used: season.pesticide_used,
+                    harvest_date: season.harvest_date,
+                    total_yield_kg: season.total_yield_kg,
+                    moisture_content: season.moisture_content,
+                    cultivated_area_m2: season.cultivated_area_m2,
+                    organic_amendments: season.amendments,
+                },
+            );
+            send(client, instruction, address)
+        }
+        Command::Season(SeasonCommand::Validate {
+            seed,
+            status,
+            validator,
+        }) => {
+            let instruction =
+                instructions::validate_production_season(&payer, &validator, &seed, status);
+            send(client, instruction, pda::production_season(&seed).0)
+        }
+        Command::Season(SeasonCommand::IssueCredits {
+            seed,
+            validator,
+            certification,
+            wallet,
+        }) => {
+            let address = pda::production_season(&seed).0;
+            let instruction = instructions::issue_carbon_credits(
+                &payer,
+                &payer,
+                &validator,
+                &address,
+                &pda::certification(&certification).0,
+                &wallet,
+            );
+            send(client, instruction, pda::carbon_credit_mint(&address).0)
+        }
+        Command::Season(SeasonCommand::RetireCredits {
+            seed,
+            amount_kg_co2e,
+        }) => {
+            let address = pda::production_season(&seed).0;
+            send(
+                client,
+                instructions::retire_carbon_credits(&payer, &address, amount_kg_co2e),
+                address,
+            )
+        }
+        Command::Batch(BatchCommand::Create(batch)) => {
+            let address = pda::rice_batch(&batch.seed).0;
+            let qr_code = batch.qr_code()?;
+            let instruction = instructions::create_rice_batch(
+                &payer,
+                args::CreateRiceBatch {
+                    batch_seed: batch.seed,
+                    milled_rice_id: batch.milled_rice,
+                    batch_status: BatchStatus::Created,
+                    quality_score: batch.quality_score,
+                    weight_kg: batch.weight_kg,
+                    qr_code,
+                },
+            );
+            send(client, instruction, address)
+        }
+        Command::Batch(BatchCommand::Tokenize(batch)) => {
+            let address = pda::rice_batch(&batch.seed).0;
+            let uri = match batch.uri {
+                Some(uri) => uri,
+                None => client.fetch::<RiceBatch>(&address)?.qr_code,
+            };
+            let instruction = instructions::tokenize_rice_batch(
+                &payer,
+                &pda::chain_actor(&batch.actor).0,
+                &payer,
+                &address,
+                args::TokenizeRiceBatch {
+                    token_unit: batch.unit,
+                    name: batch
+                        .name
+                        .unwrap_or_else(|| format!("Rice batch {}", batch.seed)),
+                    symbol: batch.symbol,
+                    uri,
+                },
+            );
+            send(client, instruction, pda::batch_mint(&address).0)
+        }
+        Command::Transaction(TransactionCommand::Create(transaction)) => {
+            let address = pda::chain_transaction(&transaction.seed).0;
+            let instruction = instructions::create_chain_transaction(
+                &payer,
+                args::CreateChainTransaction {
+                    transaction_seed: transaction.seed,
+                    from_actor_id: transaction.from,
+                    to_actor_id: transaction.to,
+                    rice_batch_ids: transaction.batches,
+                    amount: transaction.amount,
+                    payment_method: transaction.payment_method,
+                    payment_reference: transaction.reference,
+                    geotag: None,
+                    quality: transaction.quality,
+                },
+            );
+            send(client, instruction, address)
+        }
+        Command::Transaction(TransactionCommand::Settle { seed, reference }) => {
+            let address = pda::chain_transaction(&seed).0;
+            let transaction: ChainTransaction = client.fetch(&address)?;
+            let instruction = instructions::update_chain_transaction(
+                &payer,
+                &transaction.from_actor_id,
+                args::UpdateChainTransaction {
+                    transaction_seed: seed,
+                    amount: None,
+                    payment_method: None,
+                    payment_reference: reference,
+                    geotag: None,
+                    quality: None,
+                    transaction_status: Some(TransactionStatus::Completed),
+                },
+                &transaction.rice_batch_ids,
+            );
+            send(client, instruction, address)
+        }
+        Command::Transaction(TransactionCommand::Cancel { seed }) => {
+            let address = pda::chain_transaction(&seed).0;
+            let transaction: ChainTransaction = client.fetch(&address)?;
+            send(
+                client,
+                instructions::delete_chain_transaction(&payer, &transaction.from_actor_id, &seed),
+                address,
+            )
+        }
+        Command::Dump { .. } | Command::Address { .. } | Command::Export(_) => {
+            unreachable!("handled without a keypair")
+        }
+    }
+}
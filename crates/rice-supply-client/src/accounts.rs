@@ -0,0 +1,72 @@
+//! Decoding of raw program account data.
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+
+pub use rice_supply_chain::{
+    Accreditation, Certification, ChainActor, ChainTransaction, Custodian, EmissionFactors,
+    InputApplication, IssuerKey, MilledRice, PostHarvestEvent, ProductionSeason, ProgramConfig,
+    QrIndex, QualityTest, RetailPackRun, RiceBatch, Shipment, StorageRecord, SubstanceRegistry,
+    Warehouse,
+};
+
+/// Decodes one account, checking its discriminator.
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> anchor_lang::Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+macro_rules! program_accounts {
+    ($($name:ident),* $(,)?) => {
+        /// Any account owned by the program, told apart by discriminator.
+        #[derive(Clone)]
+        pub enum RiceSupplyAccount {
+            $($name($name),)*
+        }
+
+        impl RiceSupplyAccount {
+            /// Decodes `data` as whichever account type its discriminator
+            /// names. Returns `None` for unknown discriminators.
+            pub fn decode(data: &[u8]) -> Option<anchor_lang::Result<Self>> {
+                $(
+                    if data.starts_with($name::DISCRIMINATOR) {
+                        return Some(decode::<$name>(data).map(Self::$name));
+                    }
+                )*
+                None
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$name(_) => stringify!($name),)*
+                }
+            }
+        }
+    };
+}
+
+program_accounts!(
+    ChainActor,
+    ProductionSeason,
+    QrIndex,
+    MilledRice,
+    RiceBatch,
+    ChainTransaction,
+    InputApplication,
+    SubstanceRegistry,
+    QualityTest,
+    Shipment,
+    Warehouse,
+    StorageRecord,
+    PostHarvestEvent,
+    RetailPackRun,
+    IssuerKey,
+    ProgramConfig,
+    Accreditation,
+    Certification,
+    Custodian,
+    EmissionFactors,
+);
+
+/// The discriminator of account type `T`, as used in RPC memcmp filters.
+pub fn discriminator<T: Discriminator>() -> &'static [u8] {
+    T::DISCRIMINATOR
+}
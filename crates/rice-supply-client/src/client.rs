@@ -0,0 +1,107 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use solana_keypair::Keypair;
+use solana_signature::Signature;
+use solana_signer::signers::Signers;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::error::{ClientError, Result};
+use crate::rpc::{Commitment, RpcClient, LOCALNET_URL};
+
+/// Sends program instructions paid for by one keypair and reads back
+/// decoded program accounts.
+pub struct RiceSupplyClient {
+    rpc: RpcClient,
+    payer: Keypair,
+}
+
+impl RiceSupplyClient {
+    pub fn new(rpc_url: impl Into<String>, payer: Keypair) -> Self {
+        Self::with_commitment(rpc_url, payer, Commitment::default())
+    }
+
+    pub fn with_commitment(
+        rpc_url: impl Into<String>,
+        payer: Keypair,
+        commitment: Commitment,
+    ) -> Self {
+        Self {
+            rpc: RpcClient::with_commitment(rpc_url, commitment),
+            payer,
+        }
+    }
+
+    /// A client for `solana-test-validator` on its default port.
+    pub fn localnet(payer: Keypair) -> Self {
+        Self::new(LOCALNET_URL, payer)
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn payer(&self) -> &Keypair {
+        &self.payer
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        rice_supply_chain::ID
+    }
+
+    /// Sends `instructions` in one transaction signed by the payer alone.
+    pub fn send(&self, instructions: &[Instruction]) -> Result<Signature> {
+        self.send_with_signers(instructions, &[] as &[&Keypair])
+    }
+
+    /// Sends `instructions` signed by the payer and `signers`.
+    pub fn send_with_signers<S: Signers + ?Sized>(
+        &self,
+        instructions: &[Instruction],
+        signers: &S,
+    ) -> Result<Signature> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        transaction.try_partial_sign(&[&self.payer], blockhash)?;
+        transaction.try_sign(signers, blockhash)?;
+        self.rpc.send_and_confirm_transaction(&transaction)
+    }
+
+    pub fn airdrop(&self, lamports: u64) -> Result<Signature> {
+        let signature = self.rpc.request_airdrop(&self.payer.pubkey(), lamports)?;
+        self.rpc.confirm_transaction(&signature)?;
+        Ok(signature)
+    }
+
+    /// Fetches and decodes a program account, failing if it does not exist.
+    pub fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        self.fetch_optional(address)?
+            .ok_or(ClientError::AccountNotFound(*address))
+    }
+
+    pub fn fetch_optional<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>> {
+        let Some(account) = self.rpc.get_account(address)? else {
+            return Ok(None);
+        };
+        if account.owner != rice_supply_chain::ID {
+            return Err(ClientError::WrongOwner(*address));
+        }
+        crate::accounts::decode(&account.data)
+            .map(Some)
+            .map_err(|error| ClientError::Decode(*address, error))
+    }
+
+    /// Fetches every account of type `T` the program owns.
+    pub fn fetch_all<T: AccountDeserialize + Discriminator>(&self) -> Result<Vec<(Pubkey, T)>> {
+        self.rpc
+            .get_program_accounts(&rice_supply_chain::ID, T::DISCRIMINATOR)?
+            .into_iter()
+            .map(|(address, account)| {
+                crate::accounts::decode(&account.data)
+                    .map(|decoded| (address, decoded))
+                    .map_err(|error| ClientError::Decode(address, error))
+            })
+            .collect()
+    }
+}
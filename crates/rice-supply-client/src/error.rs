@@ -0,0 +1,33 @@
+use anchor_lang::prelude::Pubkey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("RPC transport error: {0}")]
+    Transport(#[from] ureq::Error),
+    #[error("RPC error {code}: {message}")]
+    Rpc {
+        code: i64,
+        message: String,
+        /// Program logs from a failed simulation, when the node returns them.
+        logs: Vec<String>,
+    },
+    #[error("unexpected RPC response: {0}")]
+    Response(String),
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} is not owned by the program")]
+    WrongOwner(Pubkey),
+    #[error("account {0} could not be decoded: {1}")]
+    Decode(Pubkey, anchor_lang::error::Error),
+    #[error("signing failed: {0}")]
+    Signing(#[from] solana_signer::SignerError),
+    #[error("transaction {signature} failed: {error}")]
+    TransactionFailed {
+        signature: String,
+        error: serde_json::Value,
+    },
+    #[error("timed out waiting for transaction {0}")]
+    ConfirmationTimeout(String),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,917 @@
+//! Typed builders for every `rice_supply_chain` instruction.
+//!
+//! Builders take the program's own argument structs from [`args`] where an
+//! instruction has more than a seed or two, derive every PDA they can, and
+//! ask the caller only for accounts that cannot be derived (signers and
+//! actor accounts) or for the on-chain counter a seed depends on.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, spl_associated_token_account};
+use anchor_spl::token_2022::{self, spl_token_2022};
+use rice_supply_chain::accounts;
+use rice_supply_chain::{
+    AccreditationKind, BatchStatus, SubstanceStatus, TokenUnit, ValidationStatus, ID,
+};
+
+use crate::pda;
+
+/// Argument structs generated from the program's instruction handlers.
+pub use rice_supply_chain::instruction as args;
+
+fn build(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+fn with_remaining(mut instruction: Instruction, keys: &[Pubkey], is_writable: bool) -> Instruction {
+    instruction.accounts.extend(keys.iter().map(|key| {
+        if is_writable {
+            AccountMeta::new(*key, false)
+        } else {
+            AccountMeta::new_readonly(*key, false)
+        }
+    }));
+    instruction
+}
+
+// Chain Actor Instructions
+pub fn create_chain_actor(user: &Pubkey, args: args::CreateChainActor) -> Instruction {
+    let accounts = accounts::CreateChainActor {
+        chain_actor: pda::chain_actor(&args.actor_seed).0,
+        user: *user,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+pub fn update_chain_actor(authority: &Pubkey, args: args::UpdateChainActor) -> Instruction {
+    let accounts = accounts::UpdateChainActor {
+        chain_actor: pda::chain_actor(&args.actor_seed).0,
+        authority: *authority,
+    };
+    build(accounts, args)
+}
+
+pub fn delete_chain_actor(authority: &Pubkey, actor_seed: &str) -> Instruction {
+    let accounts = accounts::DeleteChainActor {
+        chain_actor: pda::chain_actor(actor_seed).0,
+        authority: *authority,
+    };
+    build(
+        accounts,
+        args::DeleteChainActor {
+            actor_seed: actor_seed.to_string(),
+        },
+    )
+}
+
+// Production Season Instructions
+pub fn create_production_season(user: &Pubkey, args: args::CreateProductionSeason) -> Instruction {
+    let accounts = accounts::CreateProductionSeason {
+        production_season: pda::production_season(&args.season_seed).0,
+        substance_registry: pda::substance_registry().0,
+        emission_factors: pda::emission_factors().0,
+        user: *user,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+/// `farmer` is the season's `farmer_id`, and `user` its authority.
+pub fn update_production_season(
+    user: &Pubkey,
+    farmer: &Pubkey,
+    args: args::UpdateProductionSeason,
+) -> Instruction {
+    let accounts = accounts::UpdateProductionSeason {
+        production_season: pda::production_season(&args.season_seed).0,
+        substance_registry: pda::substance_registry().0,
+        emission_factors: pda::emission_factors().0,
+        farmer: *farmer,
+        user: *user,
+    };
+    build(accounts, args)
+}
+
+/// Records `validation_status` on the season as the accredited validator
+/// `validator`, signed by its `authority`.
+pub fn validate_production_season(
+    authority: &Pubkey,
+    validator: &Pubkey,
+    season_seed: &str,
+    validation_status: ValidationStatus,
+) -> Instruction {
+    let accounts = accounts::ValidateProductionSeason {
+        production_season: pda::production_season(season_seed).0,
+        validator: *validator,
+        accreditation: pda::accreditation(validator, AccreditationKind::Validator).0,
+        authority: *authority,
+    };
+    build(
+        accounts,
+        args::ValidateProductionSeason {
+            season_seed: season_seed.to_string(),
+            validation_status,
+        },
+    )
+}
+
+/// `farmer` is the season's `farmer_id`, and `user` its authority.
+pub fn delete_production_season(user: &Pubkey, farmer: &Pubkey, season_seed: &str) -> Instruction {
+    let accounts = accounts::DeleteProductionSeason {
+        production_season: pda::production_season(season_seed).0,
+        farmer: *farmer,
+        user: *user,
+    };
+    build(
+        accounts,
+        args::DeleteProductionSeason {
+            season_seed: season_seed.to_string(),
+        },
+    )
+}
+
+// Milled Rice Instructions
+/// `production_season` is the season of `args.farmer_id` the paddy came from,
+/// and `farmer_authority` that farmer's authority, which signs alongside
+/// `user`.
+pub fn create_milled_rice(
+    user: &Pubkey,
+    production_season: &Pubkey,
+    farmer_authority: &Pubkey,
+    args: args::CreateMilledRice,
+) -> Instruction {
+    let accounts = accounts::CreateMilledRice {
+        milled_rice: pda::milled_rice(&args.milling_seed).0,
+        production_season: *production_season,
+        farmer: args.farmer_id,
+        farmer_authority: *farmer_authority,
+        user: *user,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+/// Signed by the wallet that created the record; `production_season` is the
+/// record's `MilledRice::production_season`.
+pub fn update_milled_rice(
+    user: &Pubkey,
+    production_season: &Pubkey,
+    args: args::UpdateMilledRice,
+) -> Instruction {
+    let accounts = accounts::UpdateMilledRice {
+        milled_rice: pda::milled_rice(&args.milling_seed).0,
+        production_season: *production_season,
+        user: *user,
+    };
+    build(accounts, args)
+}
+
+// Rice Batch Instructions
+/// `user` must have created the `MilledRice` at `args.milled_rice_id`.
+pub fn create_rice_batch(user: &Pubkey, args: args::CreateRiceBatch) -> Instruction {
+    let accounts = accounts::CreateRiceBatch {
+        rice_batch: pda::rice_batch(&args.batch_seed).0,
+        qr_index: pda::qr_index(&args.qr_code).0,
+        milled_rice: args.milled_rice_id,
+        user: *user,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+/// `holder` is the batch's `RiceBatch::current_holder`, signed for by
+/// `user`; a batch with no holder is signed for by its creator.
+/// `milled_rice` is the batch's `RiceBatch::milled_rice_id`.
+pub fn update_rice_batch(
+    user: &Pubkey,
+    holder: Option<&Pubkey>,
+    milled_rice: &Pubkey,
+    args: args::UpdateRiceBatch,
+) -> Instruction {
+    let accounts = accounts::UpdateRiceBatch {
+        rice_batch: pda::rice_batch(&args.batch_seed).0,
+        milled_rice: *milled_rice,
+        holder: holder.copied(),
+        user: *user,
+    };
+    build(accounts, args)
+}
+
+/// `current_qr_code` is the batch's stored code, whose index entry is closed.
+/// `holder` and `user` are as for [`update_rice_batch`].
+pub fn set_rice_batch_qr_code(
+    user: &Pubkey,
+    holder: Option<&Pubkey>,
+    batch_seed: &str,
+    current_qr_code: &str,
+    qr_code: String,
+) -> Instruction {
+    let accounts = accounts::SetRiceBatchQrCode {
+        rice_batch: pda::rice_batch(batch_seed).0,
+        old_qr_index: pda::qr_index(current_qr_code).0,
+        new_qr_index: pda::qr_index(&qr_code).0,
+        holder: holder.copied(),
+        user: *user,
+        system_program: system_program::ID,
+    };
+    build(
+        accounts,
+        args::SetRiceBatchQrCode {
+            batch_seed: batch_seed.to_string(),
+            qr_code,
+        },
+    )
+}
+
+/// `qr_code` is the batch's stored code, whose index entry is closed.
+/// `holder` and `user` are as for [`update_rice_batch`].
+pub fn delete_rice_batch(
+    user: &Pubkey,
+    holder: Option<&Pubkey>,
+    batch_seed: &str,
+    qr_code: &str,
+) -> Instruction {
+    let accounts = accounts::DeleteRiceBatch {
+        rice_batch: pda::rice_batch(batch_seed).0,
+        qr_index: pda::qr_index(qr_code).0,
+        holder: holder.copied(),
+        user: *user,
+    };
+    build(
+        accounts,
+        args::DeleteRiceBatch {
+            batch_seed: batch_seed.to_string(),
+        },
+    )
+}
+
+// Chain Transaction Instructions
+pub fn create_chain_transaction(user: &Pubkey, args: args::CreateChainTransaction) -> Instruction {
+    let accounts = accounts::CreateChainTransaction {
+        chain_transaction: pda::chain_transaction(&args.transaction_seed).0,
+        user: *user,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+/// `user` signs for `from_actor`, the transaction's `from_actor_id`.
+/// `rice_batch_ids` are the transaction's batches, which completing it
+/// updates; pass an empty slice for any other update.
+pub fn update_chain_transaction(
+    user: &Pubkey,
+    from_actor: &Pubkey,
+    args: args::UpdateChainTransaction,
+    rice_batch_ids: &[Pubkey],
+) -> Instruction {
+    let accounts = accounts::UpdateChainTransaction {
+        chain_transaction: pda::chain_transaction(&args.transaction_seed).0,
+        from_actor: *from_actor,
+        user: *user,
+    };
+    with_remaining(build(accounts, args), rice_batch_ids, true)
+}
+
+pub fn delete_chain_transaction(
+    user: &Pubkey,
+    from_actor: &Pubkey,
+    transaction_seed: &str,
+) -> Instruction {
+    let accounts = accounts::DeleteChainTransaction {
+        chain_transaction: pda::chain_transaction(transaction_seed).0,
+        from_actor: *from_actor,
+        user: *user,
+    };
+    build(
+        accounts,
+        args::DeleteChainTransaction {
+            transaction_seed: transaction_seed.to_string(),
+        },
+    )
+}
+
+// Program Config Instructions
+pub fn initialize_config(admin: &Pubkey) -> Instruction {
+    let accounts = accounts::InitializeConfig {
+        config: pda::config().0,
+        admin: *admin,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::InitializeConfig {})
+}
+
+pub fn set_config_admin(admin: &Pubkey, new_admin: Pubkey) -> Instruction {
+    let accounts = accounts::UpdateConfig {
+        config: pda::config().0,
+        admin: *admin,
+    };
+    build(accounts, args::SetConfigAdmin { new_admin })
+}
+
+// Accreditation Instructions
+pub fn grant_accreditation(
+    admin: &Pubkey,
+    chain_actor: &Pubkey,
+    kind: AccreditationKind,
+    valid_until: Option<i64>,
+) -> Instruction {
+    let accounts = accounts::GrantAccreditation {
+        accreditation: pda::accreditation(chain_actor, kind).0,
+        chain_actor: *chain_actor,
+        config: pda::config().0,
+        admin: *admin,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::GrantAccreditation { kind, valid_until })
+}
+
+pub fn revoke_accreditation(
+    admin: &Pubkey,
+    chain_actor: &Pubkey,
+    kind: AccreditationKind,
+) -> Instruction {
+    let accounts = accounts::RevokeAccreditation {
+        accreditation: pda::accreditation(chain_actor, kind).0,
+        config: pda::config().0,
+        admin: *admin,
+    };
+    build(accounts, args::RevokeAccreditation {})
+}
+
+// Certification Instructions
+/// `subject` is the certified `ProductionSeason` or `RiceBatch`.
+pub fn issue_certification(
+    authority: &Pubkey,
+    certifier: &Pubkey,
+    subject: &Pubkey,
+    args: args::IssueCertification,
+) -> Instruction {
+    let accounts = accounts::IssueCertification {
+        certification: pda::certification(&args.certification_seed).0,
+        certifier: *certifier,
+        accreditation: pda::accreditation(certifier, AccreditationKind::Certifier).0,
+        subject: *subject,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+pub fn revoke_certification(
+    authority: &Pubkey,
+    certifier: &Pubkey,
+    certification_seed: &str,
+) -> Instruction {
+    let accounts = accounts::RevokeCertification {
+        certification: pda::certification(certification_seed).0,
+        certifier: *certifier,
+        accreditation: pda::accreditation(certifier, AccreditationKind::Certifier).0,
+        authority: *authority,
+    };
+    build(
+        accounts,
+        args::RevokeCertification {
+            certification_seed: certification_seed.to_string(),
+        },
+    )
+}
+
+// Input Application Instructions
+pub fn add_input_application(
+    authority: &Pubkey,
+    applicator: &Pubkey,
+    production_season: &Pubkey,
+    args: args::AddInputApplication,
+) -> Instruction {
+    let accounts = accounts::AddInputApplication {
+        input_application: pda::input_application(&args.application_seed).0,
+        production_season: *production_season,
+        substance_registry: pda::substance_registry().0,
+        applicator: *applicator,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+pub fn remove_input_application(
+    authority: &Pubkey,
+    applicator: &Pubkey,
+    production_season: &Pubkey,
+    application_seed: &str,
+) -> Instruction {
+    let accounts = accounts::RemoveInputApplication {
+        input_application: pda::input_application(application_seed).0,
+        production_season: *production_season,
+        applicator: *applicator,
+        authority: *authority,
+    };
+    build(
+        accounts,
+        args::RemoveInputApplication {
+            application_seed: application_seed.to_string(),
+        },
+    )
+}
+
+// Substance Registry Instructions
+pub fn initialize_substance_registry(admin: &Pubkey) -> Instruction {
+    let accounts = accounts::InitializeSubstanceRegistry {
+        substance_registry: pda::substance_registry().0,
+        config: pda::config().0,
+        admin: *admin,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::InitializeSubstanceRegistry {})
+}
+
+pub fn upsert_substance(
+    admin: &Pubkey,
+    active_ingredient: String,
+    status: SubstanceStatus,
+    max_rate_g_per_ha: Option<u64>,
+) -> Instruction {
+    let accounts = accounts::UpdateSubstanceRegistry {
+        substance_registry: pda::substance_registry().0,
+        config: pda::config().0,
+        admin: *admin,
+    };
+    build(
+        accounts,
+        args::UpsertSubstance {
+            active_ingredient,
+            status,
+            max_rate_g_per_ha,
+        },
+    )
+}
+
+pub fn remove_substance(admin: &Pubkey, active_ingredient: String) -> Instruction {
+    let accounts = accounts::UpdateSubstanceRegistry {
+        substance_registry: pda::substance_registry().0,
+        config: pda::config().0,
+        admin: *admin,
+    };
+    build(accounts, args::RemoveSubstance { active_ingredient })
+}
+
+// Emission Factor Instructions
+pub fn initialize_emission_factors(admin: &Pubkey) -> Instruction {
+    let accounts = accounts::InitializeEmissionFactors {
+        emission_factors: pda::emission_factors().0,
+        config: pda::config().0,
+        admin: *admin,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::InitializeEmissionFactors {})
+}
+
+pub fn set_emission_factors(admin: &Pubkey, args: args::SetEmissionFactors) -> Instruction {
+    let accounts = accounts::UpdateEmissionFactors {
+        emission_factors: pda::emission_factors().0,
+        config: pda::config().0,
+        admin: *admin,
+    };
+    build(accounts, args)
+}
+
+// Quality Test Instructions
+/// `chain` is the batch's `previous_test` chain from its latest test, newest
+/// first, down to the first test sampled no later than `args.sample_date`.
+pub fn record_quality_test(
+    authority: &Pubkey,
+    laboratory: &Pubkey,
+    rice_batch: &Pubkey,
+    chain: &[Pubkey],
+    args: args::RecordQualityTest,
+) -> Instruction {
+    let accounts = accounts::RecordQualityTest {
+        quality_test: pda::quality_test(&args.test_seed).0,
+        rice_batch: *rice_batch,
+        laboratory: *laboratory,
+        accreditation: pda::accreditation(laboratory, AccreditationKind::Laboratory).0,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    with_remaining(build(accounts, args), chain, true)
+}
+
+/// `previous_tests` is the test's `previous_test` chain, newest first; it is
+/// only read when the invalidated test is the batch's latest.
+pub fn invalidate_quality_test(
+    authority: &Pubkey,
+    laboratory: &Pubkey,
+    rice_batch: &Pubkey,
+    test_seed: &str,
+    previous_tests: &[Pubkey],
+) -> Instruction {
+    let accounts = accounts::InvalidateQualityTest {
+        quality_test: pda::quality_test(test_seed).0,
+        rice_batch: *rice_batch,
+        laboratory: *laboratory,
+        authority: *authority,
+    };
+    let instruction = build(
+        accounts,
+        args::InvalidateQualityTest {
+            test_seed: test_seed.to_string(),
+        },
+    );
+    with_remaining(instruction, previous_tests, false)
+}
+
+// Shipment Instructions
+/// `holder` is the `RiceBatch::current_holder` of every listed batch, signed
+/// for by `holder_authority`; batches with no holder are signed for by their
+/// creator.
+pub fn create_shipment(
+    authority: &Pubkey,
+    carrier: &Pubkey,
+    holder: Option<&Pubkey>,
+    holder_authority: &Pubkey,
+    args: args::CreateShipment,
+) -> Instruction {
+    let accounts = accounts::CreateShipment {
+        shipment: pda::shipment(&args.shipment_seed).0,
+        carrier: *carrier,
+        authority: *authority,
+        holder: holder.copied(),
+        holder_authority: *holder_authority,
+        system_program: system_program::ID,
+    };
+    let rice_batch_ids = args.rice_batch_ids.clone();
+    with_remaining(build(accounts, args), &rice_batch_ids, false)
+}
+
+/// `rice_batch_ids` must list the shipment's batches in order.
+pub fn depart_shipment(
+    authority: &Pubkey,
+    carrier: &Pubkey,
+    shipment_seed: &str,
+    rice_batch_ids: &[Pubkey],
+) -> Instruction {
+    let accounts = accounts::MoveShipment {
+        shipment: pda::shipment(shipment_seed).0,
+        carrier: *carrier,
+        authority: *authority,
+    };
+    let instruction = build(
+        accounts,
+        args::DepartShipment {
+            shipment_seed: shipment_seed.to_string(),
+        },
+    );
+    with_remaining(instruction, rice_batch_ids, true)
+}
+
+/// `rice_batch_ids` must list the shipment's batches in order.
+pub fn arrive_shipment(
+    authority: &Pubkey,
+    carrier: &Pubkey,
+    shipment_seed: &str,
+    seal_numbers: Vec<String>,
+    rice_batch_ids: &[Pubkey],
+) -> Instruction {
+    let accounts = accounts::MoveShipment {
+        shipment: pda::shipment(shipment_seed).0,
+        carrier: *carrier,
+        authority: *authority,
+    };
+    let instruction = build(
+        accounts,
+        args::ArriveShipment {
+            shipment_seed: shipment_seed.to_string(),
+            seal_numbers,
+        },
+    );
+    with_remaining(instruction, rice_batch_ids, true)
+}
+
+// Warehouse Instructions
+pub fn create_warehouse(
+    authority: &Pubkey,
+    operator: &Pubkey,
+    args: args::CreateWarehouse,
+) -> Instruction {
+    let accounts = accounts::CreateWarehouse {
+        warehouse: pda::warehouse(&args.warehouse_seed).0,
+        operator: *operator,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+pub fn update_warehouse(
+    authority: &Pubkey,
+    operator: &Pubkey,
+    args: args::UpdateWarehouse,
+) -> Instruction {
+    let accounts = accounts::UpdateWarehouse {
+        warehouse: pda::warehouse(&args.warehouse_seed).0,
+        operator: *operator,
+        authority: *authority,
+    };
+    build(accounts, args)
+}
+
+/// `storage_count` is the batch's current `RiceBatch::storage_count`.
+/// `holder` is the batch's `RiceBatch::current_holder`, signed for by
+/// `holder_authority`; a batch with no holder is signed for by its creator.
+pub fn store_batch(
+    authority: &Pubkey,
+    operator: &Pubkey,
+    warehouse_seed: &str,
+    rice_batch: &Pubkey,
+    storage_count: u16,
+    holder: Option<&Pubkey>,
+    holder_authority: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::StoreBatch {
+        warehouse: pda::warehouse(warehouse_seed).0,
+        rice_batch: *rice_batch,
+        storage_record: pda::storage_record(rice_batch, storage_count).0,
+        operator: *operator,
+        authority: *authority,
+        holder: holder.copied(),
+        holder_authority: *holder_authority,
+        system_program: system_program::ID,
+    };
+    build(
+        accounts,
+        args::StoreBatch {
+            warehouse_seed: warehouse_seed.to_string(),
+        },
+    )
+}
+
+/// `storage_count` is the batch's current `RiceBatch::storage_count`; the
+/// most recent storage record is closed out.
+pub fn release_batch(
+    authority: &Pubkey,
+    operator: &Pubkey,
+    warehouse_seed: &str,
+    rice_batch: &Pubkey,
+    storage_count: u16,
+) -> Instruction {
+    let accounts = accounts::ReleaseBatch {
+        warehouse: pda::warehouse(warehouse_seed).0,
+        rice_batch: *rice_batch,
+        storage_record: pda::storage_record(rice_batch, storage_count.saturating_sub(1)).0,
+        operator: *operator,
+        authority: *authority,
+    };
+    build(
+        accounts,
+        args::ReleaseBatch {
+            warehouse_seed: warehouse_seed.to_string(),
+        },
+    )
+}
+
+// Post-Harvest Instructions
+/// `farmer` is the season's `farmer_id`, and `farmer_authority` its
+/// authority, which signs alongside the facility's.
+pub fn record_post_harvest_event(
+    authority: &Pubkey,
+    facility: &Pubkey,
+    production_season: &Pubkey,
+    farmer: &Pubkey,
+    farmer_authority: &Pubkey,
+    args: args::RecordPostHarvestEvent,
+) -> Instruction {
+    let accounts = accounts::RecordPostHarvestEvent {
+        post_harvest_event: pda::post_harvest_event(&args.event_seed).0,
+        production_season: *production_season,
+        farmer: *farmer,
+        farmer_authority: *farmer_authority,
+        facility: *facility,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+// Retail Pack Instructions
+/// `next_pack_serial` is the batch's current `RiceBatch::next_pack_serial`.
+/// `packer` must hold the batch, or `authority` must have created it.
+pub fn create_retail_packs(
+    authority: &Pubkey,
+    packer: &Pubkey,
+    rice_batch: &Pubkey,
+    next_pack_serial: u64,
+    pack_size_g: u32,
+    pack_count: u32,
+) -> Instruction {
+    let accounts = accounts::CreateRetailPacks {
+        retail_pack_run: pda::retail_pack_run(rice_batch, next_pack_serial).0,
+        rice_batch: *rice_batch,
+        packer: *packer,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(
+        accounts,
+        args::CreateRetailPacks {
+            pack_size_g,
+            pack_count,
+        },
+    )
+}
+
+// Issuing Key Instructions
+/// `miller` needs a live `AccreditationKind::Miller` accreditation.
+pub fn register_issuing_key(
+    authority: &Pubkey,
+    miller: &Pubkey,
+    issuing_key: Pubkey,
+) -> Instruction {
+    let accounts = accounts::RegisterIssuingKey {
+        issuer_key: pda::issuer_key(miller, &issuing_key).0,
+        miller: *miller,
+        accreditation: pda::accreditation(miller, AccreditationKind::Miller).0,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::RegisterIssuingKey { issuing_key })
+}
+
+pub fn revoke_issuing_key(
+    authority: &Pubkey,
+    miller: &Pubkey,
+    issuing_key: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::RevokeIssuingKey {
+        issuer_key: pda::issuer_key(miller, issuing_key).0,
+        miller: *miller,
+        authority: *authority,
+    };
+    build(accounts, args::RevokeIssuingKey {})
+}
+
+// Verification Instructions
+pub fn assert_batch_status(rice_batch: &Pubkey, batch_status: BatchStatus) -> Instruction {
+    let accounts = accounts::AssertRiceBatch {
+        rice_batch: *rice_batch,
+    };
+    build(accounts, args::AssertBatchStatus { batch_status })
+}
+
+pub fn assert_batch_holder(rice_batch: &Pubkey, holder: Pubkey) -> Instruction {
+    let accounts = accounts::AssertRiceBatch {
+        rice_batch: *rice_batch,
+    };
+    build(accounts, args::AssertBatchHolder { holder })
+}
+
+pub fn assert_season_approved(production_season: &Pubkey) -> Instruction {
+    let accounts = accounts::AssertProductionSeason {
+        production_season: *production_season,
+    };
+    build(accounts, args::AssertSeasonApproved {})
+}
+
+// Token Instructions
+pub fn register_custodian(
+    authority: &Pubkey,
+    chain_actor: &Pubkey,
+    wallet: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::RegisterCustodian {
+        custodian: pda::custodian(wallet).0,
+        chain_actor: *chain_actor,
+        wallet: *wallet,
+        authority: *authority,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::RegisterCustodian {})
+}
+
+/// Mints the batch to `wallet`, which must be registered as the token
+/// wallet of `chain_actor` with [`register_custodian`]. `chain_actor` holds
+/// the batch, or `authority` created it while it has no holder.
+pub fn tokenize_rice_batch(
+    authority: &Pubkey,
+    chain_actor: &Pubkey,
+    wallet: &Pubkey,
+    rice_batch: &Pubkey,
+    args: args::TokenizeRiceBatch,
+) -> Instruction {
+    let token_mint = pda::batch_mint(rice_batch).0;
+    let accounts = accounts::TokenizeRiceBatch {
+        rice_batch: *rice_batch,
+        token_mint,
+        mint_authority: pda::mint_authority().0,
+        extra_account_meta_list: pda::extra_account_metas(&token_mint).0,
+        custodian: pda::custodian(wallet).0,
+        chain_actor: *chain_actor,
+        wallet: *wallet,
+        holder_token: pda::batch_token_account(rice_batch, wallet),
+        authority: *authority,
+        token_program: token_2022::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+/// Creates `wallet`'s token account for a batch's mint unless it exists.
+pub fn create_batch_token_account(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    rice_batch: &Pubkey,
+) -> Instruction {
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        payer,
+        wallet,
+        &pda::batch_mint(rice_batch).0,
+        &token_2022::ID,
+    )
+}
+
+/// A Token-2022 transfer of `amount` base units of a batch token from
+/// `owner`'s token account to `recipient`'s, carrying the accounts the
+/// program's transfer hook resolves.
+pub fn transfer_batch_tokens(
+    owner: &Pubkey,
+    recipient: &Pubkey,
+    rice_batch: &Pubkey,
+    token_unit: TokenUnit,
+    amount: u64,
+) -> Instruction {
+    let token_mint = pda::batch_mint(rice_batch).0;
+    let mut instruction = spl_token_2022::instruction::transfer_checked(
+        &token_2022::ID,
+        &pda::batch_token_account(rice_batch, owner),
+        &token_mint,
+        &pda::batch_token_account(rice_batch, recipient),
+        owner,
+        &[],
+        amount,
+        token_unit.decimals(),
+    )
+    .expect("Token-2022 program ID");
+    instruction.accounts.extend([
+        AccountMeta::new(*rice_batch, false),
+        AccountMeta::new_readonly(pda::custodian(recipient).0, false),
+        AccountMeta::new_readonly(ID, false),
+        AccountMeta::new_readonly(pda::extra_account_metas(&token_mint).0, false),
+    ]);
+    instruction
+}
+
+// Carbon Credit Instructions
+/// Mints a season's carbon credits to `wallet`, the registered wallet of
+/// the season's farmer. `validator_authority` signs for `validator`, the
+/// actor that approved the season.
+pub fn issue_carbon_credits(
+    payer: &Pubkey,
+    validator_authority: &Pubkey,
+    validator: &Pubkey,
+    production_season: &Pubkey,
+    certification: &Pubkey,
+    wallet: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::IssueCarbonCredits {
+        production_season: *production_season,
+        validator: *validator,
+        accreditation: pda::accreditation(validator, AccreditationKind::Validator).0,
+        validator_authority: *validator_authority,
+        certification: *certification,
+        emission_factors: pda::emission_factors().0,
+        credit_mint: pda::carbon_credit_mint(production_season).0,
+        mint_authority: pda::mint_authority().0,
+        custodian: pda::custodian(wallet).0,
+        wallet: *wallet,
+        farmer_token: pda::carbon_credit_account(production_season, wallet),
+        payer: *payer,
+        token_program: token_2022::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+    build(accounts, args::IssueCarbonCredits {})
+}
+
+/// Burns `amount_kg_co2e` of a season's credits from `owner`'s associated
+/// token account.
+pub fn retire_carbon_credits(
+    owner: &Pubkey,
+    production_season: &Pubkey,
+    amount_kg_co2e: u64,
+) -> Instruction {
+    let accounts = accounts::RetireCarbonCredits {
+        production_season: *production_season,
+        credit_mint: pda::carbon_credit_mint(production_season).0,
+        owner_token: pda::carbon_credit_account(production_season, owner),
+        owner: *owner,
+        token_program: token_2022::ID,
+    };
+    build(accounts, args::RetireCarbonCredits { amount_kg_co2e })
+}
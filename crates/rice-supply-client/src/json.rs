@@ -0,0 +1,590 @@
+//! JSON rendering of program accounts for dumps and reports.
+//!
+//! Pubkeys render as base58, hashes as lowercase hex, and enums as their
+//! variant name. Field names match the Rust structs. Times stay unix seconds;
+//! [`timestamp`] formats one for exports.
+
+use anchor_lang::prelude::Pubkey;
+use rice_supply_chain::{
+    AccreditationKind, AmendmentKind, BatchStatus, ByProducts, CarbonCredits, CertificationScheme,
+    ComplianceStatus, DryingMethod, GeoPoint, Gs1Key, InputKind, MillingType, OrganicAmendment,
+    Organization, PaymentMethod, QualityGrade, ResidueResult, RiceVariety, ShipmentStatus,
+    SubjectKind, SubstanceEntry, SubstanceStatus, TransactionStatus, ValidationStatus, WaterRegime,
+};
+use serde_json::{json, Map, Value};
+
+use crate::accounts::{
+    Accreditation, Certification, ChainActor, ChainTransaction, Custodian, EmissionFactors,
+    InputApplication, IssuerKey, MilledRice, PostHarvestEvent, ProductionSeason, ProgramConfig,
+    QrIndex, QualityTest, RetailPackRun, RiceBatch, RiceSupplyAccount, Shipment, StorageRecord,
+    SubstanceRegistry, Warehouse,
+};
+
+pub trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+impl ToJson for Pubkey {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl ToJson for [u8; 32] {
+    fn to_json(&self) -> Value {
+        Value::String(self.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+macro_rules! plain_json {
+    ($($ty:ty),*) => {
+        $(impl ToJson for $ty {
+            fn to_json(&self) -> Value {
+                json!(self)
+            }
+        })*
+    };
+}
+
+plain_json!(bool, u8, u16, u32, u64, i32, i64);
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Value {
+        self.as_ref().map_or(Value::Null, ToJson::to_json)
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+macro_rules! unit_enum_json {
+    ($ty:ident { $($variant:ident),* $(,)? }) => {
+        impl ToJson for $ty {
+            fn to_json(&self) -> Value {
+                Value::String(match self {
+                    $($ty::$variant => stringify!($variant),)*
+                }.to_string())
+            }
+        }
+    };
+}
+
+unit_enum_json!(Organization {
+    Farmer,
+    Miller,
+    Distributor,
+    Retailer,
+    Government,
+    Cooperative
+});
+unit_enum_json!(ValidationStatus {
+    Pending,
+    Approved,
+    Rejected
+});
+unit_enum_json!(BatchStatus {
+    Created,
+    InTransit,
+    Delivered,
+    Consumed
+});
+unit_enum_json!(PaymentMethod {
+    Cash,
+    BankTransfer,
+    DigitalWallet,
+    Cryptocurrency,
+    Credit
+});
+unit_enum_json!(TransactionStatus {
+    Pending,
+    Completed,
+    Failed,
+    Cancelled
+});
+unit_enum_json!(MillingType {
+    WellMilled,
+    RegularMilled,
+    Undermilled,
+    Brown
+});
+unit_enum_json!(QualityGrade {
+    Premium,
+    Grade1,
+    Grade2,
+    Grade3,
+    OffGrade
+});
+unit_enum_json!(AccreditationKind {
+    Certifier,
+    Laboratory,
+    Miller,
+    Validator
+});
+unit_enum_json!(CertificationScheme {
+    CarbonSmart,
+    Organic,
+    PhilGap,
+    SustainableRicePlatform
+});
+unit_enum_json!(SubjectKind {
+    ProductionSeason,
+    RiceBatch
+});
+unit_enum_json!(InputKind {
+    Fertilizer,
+    Insecticide,
+    Herbicide,
+    Fungicide,
+    Molluscicide,
+    Other
+});
+unit_enum_json!(SubstanceStatus { Banned, Restricted });
+unit_enum_json!(ComplianceStatus {
+    Compliant,
+    Restricted,
+    NonCompliant
+});
+unit_enum_json!(ShipmentStatus {
+    Planned,
+    InTransit,
+    Arrived
+});
+unit_enum_json!(DryingMethod {
+    SunDrying,
+    FlatBedDryer,
+    RecirculatingBatchDryer,
+    ContinuousFlowDryer,
+    Other,
+});
+unit_enum_json!(WaterRegime {
+    ContinuousFlooding,
+    SingleDrainage,
+    MultipleDrainage,
+    Rainfed,
+    Upland,
+});
+unit_enum_json!(AmendmentKind {
+    StrawShortlyBefore,
+    StrawLongBefore,
+    Compost,
+    FarmyardManure,
+    GreenManure,
+});
+
+impl ToJson for RiceVariety {
+    fn to_json(&self) -> Value {
+        match self {
+            RiceVariety::NsicRc(code) => Value::String(format!("NSIC Rc {code}")),
+            RiceVariety::PsbRc(code) => Value::String(format!("PSB Rc {code}")),
+            RiceVariety::Traditional => Value::String("Traditional".to_string()),
+            RiceVariety::Other => Value::String("Other".to_string()),
+        }
+    }
+}
+
+impl ToJson for Gs1Key {
+    fn to_json(&self) -> Value {
+        json!({
+            "gtin": format!("{:014}", self.gtin),
+            "lot": self.lot,
+            "serial": self.serial,
+            "canonical_uri": self.canonical_uri(),
+        })
+    }
+}
+
+macro_rules! struct_json {
+    ($ty:ident { $($field:ident),* $(,)? }) => {
+        impl ToJson for $ty {
+            fn to_json(&self) -> Value {
+                let mut object = Map::new();
+                $(object.insert(stringify!($field).to_string(), self.$field.to_json());)*
+                Value::Object(object)
+            }
+        }
+    };
+}
+
+struct_json!(GeoPoint {
+    latitude_microdeg,
+    longitude_microdeg,
+    accuracy_m,
+    captured_at
+});
+struct_json!(CarbonCredits {
+    mint,
+    avoided_methane_g,
+    issued_kg_co2e,
+    retired_kg_co2e,
+    issued_at
+});
+struct_json!(OrganicAmendment {
+    kind,
+    rate_kg_per_ha
+});
+struct_json!(ByProducts {
+    husk_kg,
+    bran_kg,
+    brokens_kg,
+    brewers_rice_kg
+});
+struct_json!(SubstanceEntry {
+    active_ingredient,
+    status,
+    max_rate_g_per_ha
+});
+struct_json!(ResidueResult {
+    analyte,
+    measured_ug_per_kg,
+    mrl_ug_per_kg
+});
+
+struct_json!(ChainActor {
+    public_key,
+    authority,
+    name,
+    actor_type,
+    farm_id,
+    farmer_id,
+    assigned_tps,
+    is_active,
+    balance,
+    pin,
+    organization,
+    address,
+    created_at,
+    updated_at,
+});
+struct_json!(ProductionSeason {
+    public_key,
+    farmer_id,
+    crop_year,
+    processed_yield_kg,
+    variety,
+    planned_practice,
+    planting_date,
+    irrigation_practice,
+    fertilizer_used,
+    pesticide_used,
+    harvest_date,
+    total_yield_kg,
+    moisture_content,
+    validation_status,
+    validator_id,
+    compliance_status,
+    nitrogen_applied_g_per_ha,
+    pre_harvest_clear_at,
+    post_harvest_loss_kg,
+    milled_paddy_kg,
+    cultivated_area_m2,
+    organic_amendments,
+    methane_estimate_g,
+    carbon_credits,
+    created_at,
+    updated_at,
+});
+struct_json!(QrIndex {
+    rice_batch,
+    payload_hash,
+    created_at
+});
+struct_json!(MilledRice {
+    public_key,
+    farmer_id,
+    production_season,
+    creator,
+    total_weight_kg,
+    allocated_kg,
+    milling_type,
+    quality,
+    photo_urls,
+    moisture,
+    total_weight_processed_kg,
+    by_products,
+    milling_recovery_bp,
+    created_at,
+    updated_at,
+});
+struct_json!(RiceBatch {
+    public_key,
+    milled_rice_id,
+    batch_status,
+    quality_score,
+    weight_kg,
+    qr_code,
+    gs1,
+    effective_grade,
+    latest_quality_test,
+    current_warehouse,
+    current_holder,
+    creator,
+    token_mint,
+    storage_count,
+    remaining_weight_g,
+    next_pack_serial,
+    created_at,
+    updated_at,
+});
+struct_json!(ChainTransaction {
+    public_key,
+    from_actor_id,
+    to_actor_id,
+    rice_batch_ids,
+    amount,
+    payment_method,
+    payment_reference,
+    geotag,
+    quality,
+    transaction_status,
+    created_at,
+    updated_at,
+});
+struct_json!(InputApplication {
+    public_key,
+    production_season,
+    applicator,
+    input_kind,
+    product,
+    active_ingredient,
+    rate_g_per_ha,
+    nitrogen_content_bp,
+    applied_at,
+    pre_harvest_interval_days,
+    created_at,
+    updated_at,
+});
+struct_json!(SubstanceRegistry {
+    entries,
+    created_at,
+    updated_at
+});
+struct_json!(QualityTest {
+    public_key,
+    rice_batch,
+    laboratory,
+    sample_date,
+    moisture_bp,
+    broken_grain_bp,
+    chalkiness_bp,
+    foreign_matter_bp,
+    residue_results,
+    overall_grade,
+    report_hash,
+    is_valid,
+    previous_test,
+    created_at,
+    updated_at,
+});
+struct_json!(Shipment {
+    public_key,
+    carrier,
+    vehicle_id,
+    origin,
+    destination,
+    departed_at,
+    arrived_at,
+    rice_batch_ids,
+    seal_numbers,
+    shipment_status,
+    created_at,
+    updated_at,
+});
+struct_json!(Warehouse {
+    public_key,
+    operator,
+    name,
+    location,
+    capacity_kg,
+    stored_kg,
+    stored_batch_ids,
+    is_active,
+    created_at,
+    updated_at,
+});
+struct_json!(StorageRecord {
+    public_key,
+    rice_batch,
+    warehouse,
+    weight_kg,
+    stored_at,
+    released_at
+});
+struct_json!(PostHarvestEvent {
+    public_key,
+    production_season,
+    facility,
+    drying_method,
+    started_at,
+    ended_at,
+    start_moisture_bp,
+    end_moisture_bp,
+    weight_in_kg,
+    weight_out_kg,
+    created_at,
+    updated_at,
+});
+struct_json!(RetailPackRun {
+    public_key,
+    rice_batch,
+    packer,
+    pack_size_g,
+    first_serial,
+    pack_count,
+    created_at,
+});
+struct_json!(IssuerKey {
+    public_key,
+    miller,
+    issuing_key,
+    is_active,
+    created_at,
+    updated_at
+});
+struct_json!(ProgramConfig {
+    admin,
+    created_at,
+    updated_at
+});
+struct_json!(Accreditation {
+    public_key,
+    actor,
+    kind,
+    granted_by,
+    valid_until,
+    is_active,
+    created_at,
+    updated_at,
+});
+struct_json!(Custodian {
+    public_key,
+    chain_actor,
+    wallet,
+    created_at
+});
+impl ToJson for EmissionFactors {
+    fn to_json(&self) -> Value {
+        let by_variant = |variants: &[Value], values: &[u16]| {
+            let pairs = variants.iter().zip(values);
+            Value::Object(
+                pairs
+                    .map(|(variant, value)| (variant.as_str().unwrap().to_string(), json!(value)))
+                    .collect(),
+            )
+        };
+        let water_regimes = [
+            WaterRegime::ContinuousFlooding,
+            WaterRegime::SingleDrainage,
+            WaterRegime::MultipleDrainage,
+            WaterRegime::Rainfed,
+            WaterRegime::Upland,
+        ]
+        .map(|water_regime| water_regime.to_json());
+        let amendment_kinds = [
+            AmendmentKind::StrawShortlyBefore,
+            AmendmentKind::StrawLongBefore,
+            AmendmentKind::Compost,
+            AmendmentKind::FarmyardManure,
+            AmendmentKind::GreenManure,
+        ]
+        .map(|amendment_kind| amendment_kind.to_json());
+        json!({
+            "baseline_methane_g_per_ha_day": self.baseline_methane_g_per_ha_day,
+            "water_regime_scaling_bp": by_variant(&water_regimes, &self.water_regime_scaling_bp),
+            "amendment_conversion_bp": by_variant(&amendment_kinds, &self.amendment_conversion_bp),
+            "amendment_exponent_bp": self.amendment_exponent_bp,
+            "methane_gwp_100": self.methane_gwp_100,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+        })
+    }
+}
+struct_json!(Certification {
+    public_key,
+    scheme,
+    issuer,
+    subject,
+    subject_kind,
+    issued_at,
+    expires_at,
+    evidence_hash,
+    is_revoked,
+    revoked_at,
+    created_at,
+    updated_at,
+});
+
+macro_rules! account_json {
+    ($($name:ident),* $(,)?) => {
+        impl ToJson for RiceSupplyAccount {
+            /// `{"type": "<AccountName>", "data": {...}}`.
+            fn to_json(&self) -> Value {
+                let data = match self {
+                    $(RiceSupplyAccount::$name(account) => account.to_json(),)*
+                };
+                json!({ "type": self.name(), "data": data })
+            }
+        }
+    };
+}
+
+account_json!(
+    ChainActor,
+    ProductionSeason,
+    QrIndex,
+    MilledRice,
+    RiceBatch,
+    ChainTransaction,
+    InputApplication,
+    SubstanceRegistry,
+    QualityTest,
+    Shipment,
+    Warehouse,
+    StorageRecord,
+    PostHarvestEvent,
+    RetailPackRun,
+    IssuerKey,
+    ProgramConfig,
+    Accreditation,
+    Certification,
+    Custodian,
+    EmissionFactors,
+);
+
+/// RFC 3339 UTC time of a unix timestamp.
+pub fn timestamp(unix: i64) -> String {
+    let (year, month, day) = civil_from_days(unix.div_euclid(86_400));
+    let seconds = unix.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Proleptic Gregorian date of a day count from 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
@@ -0,0 +1,27 @@
+//! Rust client for the `rice_supply_chain` program.
+//!
+//! - [`pda`] derives the address of every account type.
+//! - [`instructions`] builds each program instruction with its accounts.
+//! - [`accounts`] decodes raw account data and [`json`] renders it.
+//! - [`lineage`] gathers the records a rice batch traces back to.
+//! - [`RiceSupplyClient`] sends transactions and fetches accounts over any
+//!   JSON-RPC endpoint, including `solana-test-validator`.
+
+pub mod accounts;
+mod client;
+mod error;
+pub mod instructions;
+pub mod json;
+pub mod lineage;
+pub mod pda;
+pub mod rpc;
+
+pub use client::RiceSupplyClient;
+pub use error::{ClientError, Result};
+pub use rice_supply_chain::ID as PROGRAM_ID;
+
+pub use anchor_lang::prelude::Pubkey;
+pub use anchor_lang::solana_program::instruction::Instruction;
+pub use solana_keypair::{read_keypair_file, Keypair};
+pub use solana_signature::Signature;
+pub use solana_signer::Signer;
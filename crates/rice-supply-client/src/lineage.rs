@@ -0,0 +1,143 @@
+//! The records a rice batch was made from and the transactions that moved it.
+//!
+//! A batch names its `MilledRice` record, which names the farmer and the
+//! season the paddy was harvested in. Transactions and shipments are found by
+//! scanning for ones that list the batch.
+
+use std::collections::BTreeSet;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use rice_supply_chain::{
+    ChainActor, ChainTransaction, MilledRice, ProductionSeason, RiceBatch, Shipment,
+};
+
+use crate::rpc::{AccountData, RpcClient};
+use crate::{ClientError, Result};
+
+#[derive(Clone)]
+pub struct Lineage {
+    pub rice_batch: RiceBatch,
+    /// `None` when the batch's `milled_rice_id` names no milled-rice record.
+    pub milled_rice: Option<MilledRice>,
+    /// The milled rice's source season; empty when it is not among the
+    /// candidates.
+    pub production_seasons: Vec<ProductionSeason>,
+    /// Transactions listing the batch, oldest first.
+    pub chain_transactions: Vec<ChainTransaction>,
+    /// Shipments carrying the batch, oldest first.
+    pub shipments: Vec<Shipment>,
+}
+
+impl Lineage {
+    /// Assembles the lineage of `rice_batch` from candidate accounts, keeping
+    /// only the seasons, transactions and shipments that belong to it.
+    pub fn new(
+        rice_batch: RiceBatch,
+        milled_rice: Option<MilledRice>,
+        production_seasons: impl IntoIterator<Item = ProductionSeason>,
+        chain_transactions: impl IntoIterator<Item = ChainTransaction>,
+        shipments: impl IntoIterator<Item = Shipment>,
+    ) -> Self {
+        let milled_rice =
+            milled_rice.filter(|milled_rice| milled_rice.public_key == rice_batch.milled_rice_id);
+        let mut production_seasons: Vec<ProductionSeason> = match &milled_rice {
+            Some(milled_rice) => production_seasons
+                .into_iter()
+                .filter(|season| season.public_key == milled_rice.production_season)
+                .collect(),
+            None => Vec::new(),
+        };
+        production_seasons.sort_by_key(|season| (season.created_at, season.public_key));
+        let mut chain_transactions: Vec<ChainTransaction> = chain_transactions
+            .into_iter()
+            .filter(|transaction| transaction.rice_batch_ids.contains(&rice_batch.public_key))
+            .collect();
+        chain_transactions
+            .sort_by_key(|transaction| (transaction.created_at, transaction.public_key));
+        let mut shipments: Vec<Shipment> = shipments
+            .into_iter()
+            .filter(|shipment| shipment.rice_batch_ids.contains(&rice_batch.public_key))
+            .collect();
+        shipments.sort_by_key(|shipment| (shipment.created_at, shipment.public_key));
+        Lineage {
+            rice_batch,
+            milled_rice,
+            production_seasons,
+            chain_transactions,
+            shipments,
+        }
+    }
+
+    /// Fetches the batch at `address` and everything it traces back to.
+    pub fn fetch(rpc: &RpcClient, address: &Pubkey) -> Result<Self> {
+        let rice_batch: RiceBatch =
+            fetch_one(rpc, address)?.ok_or(ClientError::AccountNotFound(*address))?;
+        let milled_rice = fetch_one(rpc, &rice_batch.milled_rice_id)?;
+        Ok(Self::new(
+            rice_batch,
+            milled_rice,
+            fetch_all::<ProductionSeason>(rpc)?,
+            fetch_all::<ChainTransaction>(rpc)?,
+            fetch_all::<Shipment>(rpc)?,
+        ))
+    }
+
+    /// Every actor the lineage names: the farmer, season validators, both
+    /// sides of each transaction and the carriers.
+    pub fn actor_ids(&self) -> BTreeSet<Pubkey> {
+        let farmer = self
+            .milled_rice
+            .iter()
+            .map(|milled_rice| milled_rice.farmer_id);
+        let validators = self
+            .production_seasons
+            .iter()
+            .filter_map(|season| season.validator_id);
+        let parties = self
+            .chain_transactions
+            .iter()
+            .flat_map(|transaction| [transaction.from_actor_id, transaction.to_actor_id]);
+        let carriers = self.shipments.iter().map(|shipment| shipment.carrier);
+        farmer
+            .chain(validators)
+            .chain(parties)
+            .chain(carriers)
+            .collect()
+    }
+
+    /// Fetches the `ChainActor` records behind [`Lineage::actor_ids`],
+    /// skipping addresses that hold none.
+    pub fn fetch_actors(&self, rpc: &RpcClient) -> Result<Vec<ChainActor>> {
+        let mut actors = Vec::new();
+        for address in self.actor_ids() {
+            actors.extend(fetch_one::<ChainActor>(rpc, &address)?);
+        }
+        Ok(actors)
+    }
+}
+
+fn fetch_one<T: AccountDeserialize + Discriminator>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> Result<Option<T>> {
+    match rpc.get_account(address)? {
+        Some(account) if is_account_of::<T>(&account) => decode(address, &account).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn fetch_all<T: AccountDeserialize + Discriminator>(rpc: &RpcClient) -> Result<Vec<T>> {
+    rpc.get_program_accounts(&rice_supply_chain::ID, T::DISCRIMINATOR)?
+        .iter()
+        .map(|(address, account)| decode(address, account))
+        .collect()
+}
+
+fn is_account_of<T: Discriminator>(account: &AccountData) -> bool {
+    account.owner == rice_supply_chain::ID && account.data.starts_with(T::DISCRIMINATOR)
+}
+
+fn decode<T: AccountDeserialize>(address: &Pubkey, account: &AccountData) -> Result<T> {
+    crate::accounts::decode(&account.data).map_err(|error| ClientError::Decode(*address, error))
+}
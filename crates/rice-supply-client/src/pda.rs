@@ -0,0 +1,139 @@
+//! PDA derivation for every account type the program creates.
+//!
+//! Each function mirrors the `seeds = [...]` constraint of the account's
+//! `init` context and returns the address with its bump.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use rice_supply_chain::{AccreditationKind, QrIndex, ID};
+
+fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &ID)
+}
+
+pub fn chain_actor(actor_seed: &str) -> (Pubkey, u8) {
+    find(&[b"chain_actor", actor_seed.as_bytes()])
+}
+
+pub fn production_season(season_seed: &str) -> (Pubkey, u8) {
+    find(&[b"production_season", season_seed.as_bytes()])
+}
+
+pub fn milled_rice(milling_seed: &str) -> (Pubkey, u8) {
+    find(&[b"milled_rice", milling_seed.as_bytes()])
+}
+
+pub fn rice_batch(batch_seed: &str) -> (Pubkey, u8) {
+    find(&[b"rice_batch", batch_seed.as_bytes()])
+}
+
+/// Reverse-lookup entry for a batch QR payload.
+pub fn qr_index(qr_code: &str) -> (Pubkey, u8) {
+    find(&[b"qr_index", &QrIndex::payload_hash(qr_code)])
+}
+
+pub fn chain_transaction(transaction_seed: &str) -> (Pubkey, u8) {
+    find(&[b"chain_transaction", transaction_seed.as_bytes()])
+}
+
+pub fn config() -> (Pubkey, u8) {
+    find(&[b"config"])
+}
+
+pub fn accreditation(chain_actor: &Pubkey, kind: AccreditationKind) -> (Pubkey, u8) {
+    find(&[b"accreditation", chain_actor.as_ref(), &[kind as u8]])
+}
+
+pub fn certification(certification_seed: &str) -> (Pubkey, u8) {
+    find(&[b"certification", certification_seed.as_bytes()])
+}
+
+pub fn input_application(application_seed: &str) -> (Pubkey, u8) {
+    find(&[b"input_application", application_seed.as_bytes()])
+}
+
+pub fn substance_registry() -> (Pubkey, u8) {
+    find(&[b"substance_registry"])
+}
+
+pub fn emission_factors() -> (Pubkey, u8) {
+    find(&[b"emission_factors"])
+}
+
+pub fn quality_test(test_seed: &str) -> (Pubkey, u8) {
+    find(&[b"quality_test", test_seed.as_bytes()])
+}
+
+pub fn shipment(shipment_seed: &str) -> (Pubkey, u8) {
+    find(&[b"shipment", shipment_seed.as_bytes()])
+}
+
+pub fn warehouse(warehouse_seed: &str) -> (Pubkey, u8) {
+    find(&[b"warehouse", warehouse_seed.as_bytes()])
+}
+
+/// The `index`-th storage record of a batch, counting from zero.
+pub fn storage_record(rice_batch: &Pubkey, index: u16) -> (Pubkey, u8) {
+    find(&[b"storage_record", rice_batch.as_ref(), &index.to_le_bytes()])
+}
+
+pub fn post_harvest_event(event_seed: &str) -> (Pubkey, u8) {
+    find(&[b"post_harvest_event", event_seed.as_bytes()])
+}
+
+/// The pack run whose serial range starts at `first_serial`.
+pub fn retail_pack_run(rice_batch: &Pubkey, first_serial: u64) -> (Pubkey, u8) {
+    find(&[
+        b"retail_pack_run",
+        rice_batch.as_ref(),
+        &first_serial.to_le_bytes(),
+    ])
+}
+
+pub fn issuer_key(miller: &Pubkey, issuing_key: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"issuer_key", miller.as_ref(), issuing_key.as_ref()])
+}
+
+/// Token-wallet registration of `wallet`.
+pub fn custodian(wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"custodian", wallet.as_ref()])
+}
+
+/// Token-2022 mint of a tokenized batch.
+pub fn batch_mint(rice_batch: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"batch_mint", rice_batch.as_ref()])
+}
+
+/// Mint and metadata authority of every batch and carbon credit mint.
+pub fn mint_authority() -> (Pubkey, u8) {
+    find(&[b"mint_authority"])
+}
+
+/// Associated Token-2022 account of `wallet` for a batch's mint.
+pub fn batch_token_account(rice_batch: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(
+        wallet,
+        &batch_mint(rice_batch).0,
+        &anchor_spl::token_2022::ID,
+    )
+}
+
+/// Token-2022 mint of a season's carbon credits.
+pub fn carbon_credit_mint(production_season: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"carbon_credit_mint", production_season.as_ref()])
+}
+
+/// Associated Token-2022 account of `wallet` for a season's carbon credits.
+pub fn carbon_credit_account(production_season: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(
+        wallet,
+        &carbon_credit_mint(production_season).0,
+        &anchor_spl::token_2022::ID,
+    )
+}
+
+/// Transfer hook accounts of a batch mint, as the transfer hook interface
+/// locates them.
+pub fn extra_account_metas(mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"extra-account-metas", mint.as_ref()])
+}
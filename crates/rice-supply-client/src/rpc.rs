@@ -0,0 +1,311 @@
+//! Minimal blocking JSON-RPC client covering the calls this crate needs.
+
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::Hash;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_signature::Signature;
+use solana_transaction::Transaction;
+
+use crate::error::{ClientError, Result};
+
+pub const LOCALNET_URL: &str = "http://127.0.0.1:8899";
+pub const DEVNET_URL: &str = "https://api.devnet.solana.com";
+
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(400);
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Commitment {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
+
+    fn parse(status: &str) -> Option<Self> {
+        match status {
+            "processed" => Some(Commitment::Processed),
+            "confirmed" => Some(Commitment::Confirmed),
+            "finalized" => Some(Commitment::Finalized),
+            _ => None,
+        }
+    }
+}
+
+/// An account as returned by `getAccountInfo`, with its data decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountData {
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+    pub executable: bool,
+}
+
+#[derive(Deserialize)]
+struct UiAccount {
+    lamports: u64,
+    owner: String,
+    data: (String, String),
+    executable: bool,
+}
+
+impl UiAccount {
+    fn into_account(self) -> Result<AccountData> {
+        Ok(AccountData {
+            lamports: self.lamports,
+            owner: parse_pubkey(&self.owner)?,
+            data: STANDARD
+                .decode(&self.data.0)
+                .map_err(|error| ClientError::Response(error.to_string()))?,
+            executable: self.executable,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct WithContext<T> {
+    value: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureStatus {
+    err: Option<Value>,
+    confirmation_status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct KeyedAccount {
+    pubkey: String,
+    account: UiAccount,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+    #[serde(default)]
+    data: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcErrorObject>,
+}
+
+pub struct RpcClient {
+    url: String,
+    agent: ureq::Agent,
+    commitment: Commitment,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_commitment(url, Commitment::default())
+    }
+
+    pub fn with_commitment(url: impl Into<String>, commitment: Commitment) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .into();
+        Self {
+            url: url.into(),
+            agent,
+            commitment,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        self.commitment
+    }
+
+    fn call<T: for<'de> Deserialize<'de>>(&self, method: &str, params: Value) -> Result<T> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response: RpcResponse<T> = self
+            .agent
+            .post(&self.url)
+            .send_json(&request)?
+            .body_mut()
+            .read_json()?;
+        if let Some(error) = response.error {
+            let logs = error
+                .data
+                .as_ref()
+                .and_then(|data| data.get("logs"))
+                .and_then(|logs| serde_json::from_value(logs.clone()).ok())
+                .unwrap_or_default();
+            return Err(ClientError::Rpc {
+                code: error.code,
+                message: error.message,
+                logs,
+            });
+        }
+        response
+            .result
+            .ok_or_else(|| ClientError::Response(format!("{method} returned no result")))
+    }
+
+    pub fn get_account(&self, address: &Pubkey) -> Result<Option<AccountData>> {
+        let response: WithContext<Option<UiAccount>> = self.call(
+            "getAccountInfo",
+            json!([
+                address.to_string(),
+                { "encoding": "base64", "commitment": self.commitment.as_str() }
+            ]),
+        )?;
+        response.value.map(UiAccount::into_account).transpose()
+    }
+
+    pub fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<AccountData>>> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let keys: Vec<String> = chunk.iter().map(Pubkey::to_string).collect();
+            let response: WithContext<Vec<Option<UiAccount>>> = self.call(
+                "getMultipleAccounts",
+                json!([keys, { "encoding": "base64", "commitment": self.commitment.as_str() }]),
+            )?;
+            for account in response.value {
+                accounts.push(account.map(UiAccount::into_account).transpose()?);
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// All accounts owned by `program_id` whose data starts with `prefix`.
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        prefix: &[u8],
+    ) -> Result<Vec<(Pubkey, AccountData)>> {
+        let mut config = json!({ "encoding": "base64", "commitment": self.commitment.as_str() });
+        if !prefix.is_empty() {
+            config["filters"] = json!([
+                { "memcmp": { "offset": 0, "bytes": bs58::encode(prefix).into_string() } }
+            ]);
+        }
+        let accounts: Vec<KeyedAccount> = self.call(
+            "getProgramAccounts",
+            json!([program_id.to_string(), config]),
+        )?;
+        accounts
+            .into_iter()
+            .map(|keyed| Ok((parse_pubkey(&keyed.pubkey)?, keyed.account.into_account()?)))
+            .collect()
+    }
+
+    pub fn get_balance(&self, address: &Pubkey) -> Result<u64> {
+        let response: WithContext<u64> = self.call(
+            "getBalance",
+            json!([address.to_string(), { "commitment": self.commitment.as_str() }]),
+        )?;
+        Ok(response.value)
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash> {
+        #[derive(Deserialize)]
+        struct Blockhash {
+            blockhash: String,
+        }
+        let response: WithContext<Blockhash> = self.call(
+            "getLatestBlockhash",
+            json!([{ "commitment": self.commitment.as_str() }]),
+        )?;
+        Hash::from_str(&response.value.blockhash)
+            .map_err(|error| ClientError::Response(error.to_string()))
+    }
+
+    pub fn request_airdrop(&self, address: &Pubkey, lamports: u64) -> Result<Signature> {
+        let signature: String = self.call(
+            "requestAirdrop",
+            json!([address.to_string(), lamports, { "commitment": self.commitment.as_str() }]),
+        )?;
+        parse_signature(&signature)
+    }
+
+    /// Submits a signed transaction after preflight simulation.
+    pub fn send_transaction(&self, transaction: &Transaction) -> Result<Signature> {
+        let wire = bincode::serialize(transaction)
+            .map_err(|error| ClientError::Response(error.to_string()))?;
+        let signature: String = self.call(
+            "sendTransaction",
+            json!([
+                STANDARD.encode(wire),
+                { "encoding": "base64", "preflightCommitment": self.commitment.as_str() }
+            ]),
+        )?;
+        parse_signature(&signature)
+    }
+
+    /// Polls until `signature` reaches the client's commitment level.
+    pub fn confirm_transaction(&self, signature: &Signature) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            let response: WithContext<Vec<Option<SignatureStatus>>> = self.call(
+                "getSignatureStatuses",
+                json!([[signature.to_string()], { "searchTransactionHistory": true }]),
+            )?;
+            if let Some(Some(status)) = response.value.into_iter().next() {
+                if let Some(error) = status.err {
+                    return Err(ClientError::TransactionFailed {
+                        signature: signature.to_string(),
+                        error,
+                    });
+                }
+                let reached = status
+                    .confirmation_status
+                    .as_deref()
+                    .and_then(Commitment::parse)
+                    .is_some_and(|level| level >= self.commitment);
+                if reached {
+                    return Ok(());
+                }
+            }
+            if started.elapsed() > CONFIRMATION_TIMEOUT {
+                return Err(ClientError::ConfirmationTimeout(signature.to_string()));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    pub fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<Signature> {
+        let signature = self.send_transaction(transaction)?;
+        self.confirm_transaction(&signature)?;
+        Ok(signature)
+    }
+}
+
+fn parse_pubkey(text: &str) -> Result<Pubkey> {
+    Pubkey::from_str(text).map_err(|error| ClientError::Response(error.to_string()))
+}
+
+fn parse_signature(text: &str) -> Result<Signature> {
+    Signature::from_str(text).map_err(|error| ClientError::Response(error.to_string()))
+}
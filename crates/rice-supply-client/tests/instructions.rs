@@ -0,0 +1,137 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, AnchorDeserialize, Discriminator};
+use rice_supply_chain::{BatchStatus, Organization, QrIndex};
+use rice_supply_client::accounts::RiceSupplyAccount;
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::json::ToJson;
+use rice_supply_client::{pda, PROGRAM_ID};
+
+fn actor_args(actor_seed: &str) -> args::CreateChainActor {
+    args::CreateChainActor {
+        actor_seed: actor_seed.to_string(),
+        name: "Dela Cruz Farm".to_string(),
+        actor_type: vec!["producer".to_string()],
+        farm_id: None,
+        farmer_id: None,
+        assigned_tps: 0,
+        pin: "1234".to_string(),
+        organization: Organization::Farmer,
+        address: None,
+    }
+}
+
+#[test]
+fn pdas_match_the_program_seeds() {
+    let (address, bump) = pda::chain_actor("farmer-1");
+    assert_eq!(
+        Pubkey::create_program_address(&[b"chain_actor", b"farmer-1", &[bump]], &PROGRAM_ID)
+            .unwrap(),
+        address
+    );
+
+    let rice_batch = pda::rice_batch("batch-1").0;
+    let (address, bump) = pda::retail_pack_run(&rice_batch, 7);
+    assert_eq!(
+        Pubkey::create_program_address(
+            &[
+                b"retail_pack_run",
+                rice_batch.as_ref(),
+                &7u64.to_le_bytes(),
+                &[bump]
+            ],
+            &PROGRAM_ID
+        )
+        .unwrap(),
+        address
+    );
+}
+
+#[test]
+fn builders_encode_the_seed_as_the_first_argument() {
+    let user = Pubkey::new_unique();
+    let instruction = instructions::create_chain_actor(&user, actor_args("farmer-1"));
+    assert_eq!(instruction.program_id, PROGRAM_ID);
+    assert_eq!(
+        &instruction.data[..8],
+        args::CreateChainActor::DISCRIMINATOR
+    );
+
+    let decoded = args::CreateChainActor::deserialize(&mut &instruction.data[8..]).unwrap();
+    assert_eq!(decoded.actor_seed, "farmer-1");
+    assert_eq!(decoded.name, "Dela Cruz Farm");
+    // Borsh strings are a u32 length followed by the bytes.
+    assert_eq!(&instruction.data[8..12], &8u32.to_le_bytes());
+    assert_eq!(&instruction.data[12..20], b"farmer-1");
+
+    let instruction = instructions::delete_chain_actor(&user, "farmer-1");
+    let decoded = args::DeleteChainActor::deserialize(&mut &instruction.data[8..]).unwrap();
+    assert_eq!(decoded.actor_seed, "farmer-1");
+}
+
+#[test]
+fn builders_derive_accounts_and_mark_signers() {
+    let user = Pubkey::new_unique();
+    let instruction = instructions::create_chain_actor(&user, actor_args("farmer-1"));
+    let metas: Vec<_> = instruction
+        .accounts
+        .iter()
+        .map(|meta| (meta.pubkey, meta.is_signer, meta.is_writable))
+        .collect();
+    assert_eq!(
+        metas,
+        vec![
+            (pda::chain_actor("farmer-1").0, false, true),
+            (user, true, true),
+            (anchor_lang::system_program::ID, false, false),
+        ]
+    );
+
+    let batch_args = |qr_code: &str| args::CreateRiceBatch {
+        batch_seed: "batch-1".to_string(),
+        milled_rice_id: Pubkey::default(),
+        batch_status: BatchStatus::Created,
+        quality_score: 90,
+        weight_kg: 500,
+        qr_code: qr_code.to_string(),
+    };
+    let qr_code = "https://id.gs1.org/01/04800016644504/10/LOT1";
+    let instruction = instructions::create_rice_batch(&user, batch_args(qr_code));
+    let keys: Vec<_> = instruction
+        .accounts
+        .iter()
+        .map(|meta| meta.pubkey)
+        .collect();
+    assert!(keys.contains(&pda::rice_batch("batch-1").0));
+    assert!(keys.contains(&pda::qr_index(qr_code).0));
+
+    // A resolver domain does not change the index entry.
+    let moved = instructions::create_rice_batch(
+        &user,
+        batch_args("https://rice.example.ph/01/04800016644504/10/LOT1"),
+    );
+    assert_eq!(instruction.accounts, moved.accounts);
+}
+
+#[test]
+fn program_accounts_decode_by_discriminator() {
+    let qr_index = QrIndex {
+        rice_batch: pda::rice_batch("batch-1").0,
+        payload_hash: QrIndex::payload_hash("QR-1"),
+        created_at: 1_700_000_000,
+    };
+    let mut data = Vec::new();
+    qr_index.try_serialize(&mut data).unwrap();
+
+    let decoded = RiceSupplyAccount::decode(&data).unwrap().unwrap();
+    assert_eq!(decoded.name(), "QrIndex");
+    let RiceSupplyAccount::QrIndex(decoded) = decoded else {
+        panic!("decoded as {}", decoded.name());
+    };
+    assert_eq!(decoded.rice_batch, qr_index.rice_batch);
+
+    let json = decoded.to_json();
+    assert_eq!(json["rice_batch"], qr_index.rice_batch.to_string());
+    assert_eq!(json["created_at"], 1_700_000_000);
+
+    assert!(RiceSupplyAccount::decode(&[0; 16]).is_none());
+}
@@ -0,0 +1,249 @@
+use arbitrary::Arbitrary;
+use rice_supply_chain::{
+    AccreditationKind, BatchStatus, Organization, RiceVariety, TransactionStatus, ValidationStatus,
+};
+
+/// Index into the handful of seeds used per account kind.
+pub type Slot = u8;
+
+pub(crate) const SLOTS: u8 = 3;
+
+pub(crate) fn seed(prefix: &str, slot: Slot) -> String {
+    format!("{prefix}-{}", slot % SLOTS)
+}
+
+/// One instruction. Enum arguments are picked by index, and strings and
+/// vectors are left unbounded so oversize values reach the handlers.
+#[derive(Arbitrary, Debug, Clone)]
+pub enum Action {
+    CreateChainActor {
+        actor: Slot,
+        organization: u8,
+        name: String,
+        actor_type: Vec<String>,
+        pin: String,
+        address: Option<String>,
+    },
+    UpdateChainActor {
+        actor: Slot,
+        name: Option<String>,
+        actor_type: Option<Vec<String>>,
+        pin: Option<String>,
+        organization: Option<u8>,
+        address: Option<String>,
+        is_active: Option<bool>,
+        balance: Option<u64>,
+    },
+    DeleteChainActor {
+        actor: Slot,
+    },
+    CreateProductionSeason {
+        season: Slot,
+        crop_year: String,
+        processed_yield_kg: u64,
+        variety: Option<u16>,
+        fertilizer_used: Option<String>,
+        pesticide_used: Option<String>,
+        harvest_date: Option<i64>,
+        total_yield_kg: Option<u64>,
+    },
+    UpdateProductionSeason {
+        season: Slot,
+        crop_year: Option<String>,
+        variety: Option<u16>,
+        planned_practice: Option<String>,
+        fertilizer_used: Option<String>,
+        pesticide_used: Option<String>,
+        harvest_date: Option<i64>,
+        total_yield_kg: Option<u64>,
+    },
+    ValidateProductionSeason {
+        season: Slot,
+        validator: Slot,
+        validation_status: u8,
+    },
+    DeleteProductionSeason {
+        season: Slot,
+    },
+    CreateMilledRice {
+        milling: Slot,
+        total_weight_kg: u32,
+        total_weight_processed_kg: u32,
+        by_products: [u32; 4],
+        photo_urls: Vec<String>,
+    },
+    UpdateMilledRice {
+        milling: Slot,
+        total_weight_kg: Option<u32>,
+        total_weight_processed_kg: Option<u32>,
+        by_products: Option<[u32; 4]>,
+        photo_urls: Option<Vec<String>>,
+    },
+    CreateRiceBatch {
+        batch: Slot,
+        batch_status: u8,
+        weight_kg: u32,
+        qr_code: String,
+    },
+    UpdateRiceBatch {
+        batch: Slot,
+        batch_status: Option<u8>,
+        quality_score: Option<u32>,
+        weight_kg: Option<u32>,
+    },
+    SetRiceBatchQrCode {
+        batch: Slot,
+        qr_code: String,
+    },
+    /// A GS1 Digital Link QR code with raw lot and serial text, under one of
+    /// a few resolvers so equivalent links collide.
+    SetRiceBatchDigitalLink {
+        batch: Slot,
+        resolver: u8,
+        lot: String,
+        serial: Option<String>,
+    },
+    DeleteRiceBatch {
+        batch: Slot,
+    },
+    CreateChainTransaction {
+        transaction: Slot,
+        batches: Vec<Slot>,
+        amount: u64,
+        payment_reference: Option<String>,
+        geotag: Option<(i32, i32)>,
+    },
+    UpdateChainTransaction {
+        transaction: Slot,
+        amount: Option<u64>,
+        payment_reference: Option<String>,
+        geotag: Option<(i32, i32)>,
+        transaction_status: Option<u8>,
+    },
+    DeleteChainTransaction {
+        transaction: Slot,
+    },
+    RecordPostHarvestEvent {
+        event: Slot,
+        season: Slot,
+        facility: Slot,
+        started_at: i64,
+        duration_s: u32,
+        start_moisture_bp: u16,
+        end_moisture_bp: u16,
+        weight_in_kg: u64,
+        weight_out_kg: u64,
+    },
+    CreateShipment {
+        shipment: Slot,
+        carrier: Slot,
+        vehicle_id: String,
+        batches: Vec<Slot>,
+        seal_numbers: Vec<String>,
+    },
+    DepartShipment {
+        shipment: Slot,
+    },
+    ArriveShipment {
+        shipment: Slot,
+        break_seal: bool,
+    },
+    CreateWarehouse {
+        warehouse: Slot,
+        operator: Slot,
+        name: String,
+        capacity_kg: u64,
+    },
+    UpdateWarehouse {
+        warehouse: Slot,
+        name: Option<String>,
+        capacity_kg: Option<u64>,
+        is_active: Option<bool>,
+    },
+    StoreBatch {
+        warehouse: Slot,
+        batch: Slot,
+    },
+    ReleaseBatch {
+        warehouse: Slot,
+        batch: Slot,
+    },
+    CreateRetailPacks {
+        batch: Slot,
+        packer: Slot,
+        pack_size_g: u32,
+        pack_count: u32,
+    },
+    GrantAccreditation {
+        actor: Slot,
+        kind: u8,
+    },
+    Warp {
+        seconds: u32,
+    },
+}
+
+pub(crate) fn organization(choice: u8) -> Organization {
+    match choice % 6 {
+        0 => Organization::Farmer,
+        1 => Organization::Miller,
+        2 => Organization::Distributor,
+        3 => Organization::Retailer,
+        4 => Organization::Government,
+        _ => Organization::Cooperative,
+    }
+}
+
+pub(crate) fn variety(code: u16) -> RiceVariety {
+    RiceVariety::NsicRc(code)
+}
+
+pub(crate) fn accreditation_kind(choice: u8) -> AccreditationKind {
+    match choice % 4 {
+        0 => AccreditationKind::Certifier,
+        1 => AccreditationKind::Laboratory,
+        2 => AccreditationKind::Miller,
+        _ => AccreditationKind::Validator,
+    }
+}
+
+pub(crate) fn validation_status(choice: u8) -> ValidationStatus {
+    match choice % 3 {
+        0 => ValidationStatus::Pending,
+        1 => ValidationStatus::Approved,
+        _ => ValidationStatus::Rejected,
+    }
+}
+
+pub(crate) fn batch_status(choice: u8) -> BatchStatus {
+    match choice % 4 {
+        0 => BatchStatus::Created,
+        1 => BatchStatus::InTransit,
+        2 => BatchStatus::Delivered,
+        _ => BatchStatus::Consumed,
+    }
+}
+
+pub(crate) fn transaction_status(choice: u8) -> TransactionStatus {
+    match choice % 4 {
+        0 => TransactionStatus::Pending,
+        1 => TransactionStatus::Completed,
+        2 => TransactionStatus::Failed,
+        _ => TransactionStatus::Cancelled,
+    }
+}
+
+pub(crate) fn digital_link(resolver: u8, lot: &str, serial: Option<&str>) -> String {
+    const RESOLVERS: [&str; 3] = [
+        "https://id.gs1.org",
+        "https://rice.example.ph",
+        "http://rice.example.ph/scan",
+    ];
+    let resolver = RESOLVERS[resolver as usize % RESOLVERS.len()];
+    let mut qr_code = format!("{resolver}/01/04800016644504/10/{lot}");
+    if let Some(serial) = serial {
+        qr_code.push_str("/21/");
+        qr_code.push_str(serial);
+    }
+    qr_code
+}
@@ -0,0 +1,714 @@
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::AccountDeserialize;
+use rice_supply_chain::{
+    ByProducts, ChainActor, ChainTransaction, GeoPoint, MilledRice, MillingType, PaymentMethod,
+    ProductionSeason, QualityGrade, RiceBatch, Shipment, Warehouse,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+use rice_supply_svm::{Account, Svm, LAMPORTS_PER_SOL};
+
+use crate::action::{self, seed, Action, Slot, SLOTS};
+use crate::invariants::{self, Statuses};
+
+/// A program instance with an admin, a plain user wallet and one authority
+/// wallet per actor slot.
+pub struct Harness {
+    svm: Svm,
+    user: Pubkey,
+    authorities: Vec<Pubkey>,
+    statuses: Statuses,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let mut svm = Svm::new();
+        let user = Pubkey::new_unique();
+        svm.airdrop(&user, 1_000 * LAMPORTS_PER_SOL);
+        let authorities = (0..SLOTS)
+            .map(|_| {
+                let authority = Pubkey::new_unique();
+                svm.airdrop(&authority, 1_000 * LAMPORTS_PER_SOL);
+                authority
+            })
+            .collect();
+
+        svm.send_instruction(instructions::initialize_config(&user), &[user])
+            .expect("initialize_config");
+        svm.send_instruction(instructions::initialize_substance_registry(&user), &[user])
+            .expect("initialize_substance_registry");
+        svm.send_instruction(instructions::initialize_emission_factors(&user), &[user])
+            .expect("initialize_emission_factors");
+
+        let statuses = invariants::check(&svm, &Statuses::default());
+        Harness {
+            svm,
+            user,
+            authorities,
+            statuses,
+        }
+    }
+
+    pub fn svm(&self) -> &Svm {
+        &self.svm
+    }
+
+    /// Sends `action` as one transaction and checks the invariants. Failed
+    /// transactions must leave every account untouched.
+    pub fn execute(&mut self, action: &Action) {
+        let Some((instruction, signers)) = self.instruction(action) else {
+            return;
+        };
+        let before: HashMap<Pubkey, Account> = self
+            .svm
+            .accounts()
+            .map(|(address, account)| (*address, account.clone()))
+            .collect();
+
+        if self.svm.send_instruction(instruction, &signers).is_err() {
+            let after: HashMap<Pubkey, Account> = self
+                .svm
+                .accounts()
+                .map(|(address, account)| (*address, account.clone()))
+                .collect();
+            assert!(
+                before == after,
+                "failed transaction changed accounts: {action:?}"
+            );
+        }
+        self.statuses = invariants::check(&self.svm, &self.statuses);
+    }
+
+    fn actor(&self, slot: Slot) -> (Pubkey, Pubkey) {
+        let authority = self.authorities[(slot % SLOTS) as usize];
+        (authority, pda::chain_actor(&seed("actor", slot)).0)
+    }
+
+    /// The wallet that signs for `actor`, or the plain user when the actor
+    /// does not exist.
+    fn authority_of(&self, actor: &Pubkey) -> Pubkey {
+        self.load::<ChainActor>(actor)
+            .map(|chain_actor| chain_actor.authority)
+            .unwrap_or(self.user)
+    }
+
+    fn load<T: AccountDeserialize>(&self, address: &Pubkey) -> Option<T> {
+        self.svm.account(address).ok()
+    }
+
+    fn batch(&self, slot: Slot) -> (String, Pubkey, Option<RiceBatch>) {
+        let batch_seed = seed("batch", slot);
+        let address = pda::rice_batch(&batch_seed).0;
+        (batch_seed, address, self.load(&address))
+    }
+
+    /// A batch's holder and the wallet that signs for it: the holder's
+    /// authority, or the creator while there is none.
+    fn custody(&self, batch: Option<&RiceBatch>) -> (Option<Pubkey>, Pubkey) {
+        let holder = batch.and_then(|batch| batch.current_holder);
+        let authority = match (&holder, batch) {
+            (Some(holder), _) => self.authority_of(holder),
+            (None, Some(batch)) => batch.creator,
+            (None, None) => self.user,
+        };
+        (holder, authority)
+    }
+
+    /// A season's farmer and the wallet that signs for it.
+    fn farmer(&self, season: Slot) -> (Pubkey, Pubkey) {
+        let address = pda::production_season(&seed("season", season)).0;
+        let farmer = self
+            .load::<ProductionSeason>(&address)
+            .map_or(self.actor(season).1, |production_season| {
+                production_season.farmer_id
+            });
+        (self.authority_of(&farmer), farmer)
+    }
+
+    /// A transaction's seller and the wallet that signs for it.
+    fn seller(
+        &self,
+        transaction: Slot,
+        chain_transaction: Option<&ChainTransaction>,
+    ) -> (Pubkey, Pubkey) {
+        let from_actor = chain_transaction.map_or(self.actor(transaction).1, |chain_transaction| {
+            chain_transaction.from_actor_id
+        });
+        (self.authority_of(&from_actor), from_actor)
+    }
+
+    fn instruction(&mut self, action: &Action) -> Option<(Instruction, Vec<Pubkey>)> {
+        let user = self.user;
+        let instruction = match action.clone() {
+            Action::CreateChainActor {
+                actor,
+                organization,
+                name,
+                actor_type,
+                pin,
+                address,
+            } => {
+                let (authority, _) = self.actor(actor);
+                let args = args::CreateChainActor {
+                    actor_seed: seed("actor", actor),
+                    name,
+                    actor_type,
+                    farm_id: None,
+                    farmer_id: None,
+                    assigned_tps: 0,
+                    pin,
+                    organization: action::organization(organization),
+                    address,
+                };
+                return Some((
+                    instructions::create_chain_actor(&authority, args),
+                    vec![authority],
+                ));
+            }
+            Action::UpdateChainActor {
+                actor,
+                name,
+                actor_type,
+                pin,
+                organization,
+                address,
+                is_active,
+                balance,
+            } => {
+                let (_, chain_actor) = self.actor(actor);
+                let authority = self.authority_of(&chain_actor);
+                let args = args::UpdateChainActor {
+                    actor_seed: seed("actor", actor),
+                    name,
+                    actor_type,
+                    farm_id: None,
+                    farmer_id: None,
+                    assigned_tps: None,
+                    pin,
+                    organization: organization.map(action::organization),
+                    address,
+                    is_active,
+                    balance,
+                };
+                return Some((
+                    instructions::update_chain_actor(&authority, args),
+                    vec![authority],
+                ));
+            }
+            Action::DeleteChainActor { actor } => {
+                let (_, chain_actor) = self.actor(actor);
+                let authority = self.authority_of(&chain_actor);
+                return Some((
+                    instructions::delete_chain_actor(&authority, &seed("actor", actor)),
+                    vec![authority],
+                ));
+            }
+            Action::CreateProductionSeason {
+                season,
+                crop_year,
+                processed_yield_kg,
+                variety,
+                fertilizer_used,
+                pesticide_used,
+                harvest_date,
+                total_yield_kg,
+            } => instructions::create_production_season(
+                &user,
+                args::CreateProductionSeason {
+                    season_seed: seed("season", season),
+                    farmer_id: self.actor(season).1,
+                    crop_year,
+                    processed_yield_kg,
+                    variety: variety.map(action::variety),
+                    planned_practice: None,
+                    planting_date: None,
+                    irrigation_practice: None,
+                    fertilizer_used,
+                    pesticide_used,
+                    harvest_date,
+                    total_yield_kg,
+                    moisture_content: None,
+                    cultivated_area_m2: None,
+                    organic_amendments: Vec::new(),
+                },
+            ),
+            Action::UpdateProductionSeason {
+                season,
+                crop_year,
+                variety,
+                planned_practice,
+                fertilizer_used,
+                pesticide_used,
+                harvest_date,
+                total_yield_kg,
+            } => {
+                let (authority, farmer) = self.farmer(season);
+                let instruction = instructions::update_production_season(
+                    &authority,
+                    &farmer,
+                    args::UpdateProductionSeason {
+                        season_seed: seed("season", season),
+                        crop_year,
+                        processed_yield_kg: None,
+                        variety: variety.map(action::variety),
+                        planned_practice,
+                        planting_date: None,
+                        irrigation_practice: None,
+                        fertilizer_used,
+                        pesticide_used,
+                        harvest_date,
+                        total_yield_kg,
+                        moisture_content: None,
+                        cultivated_area_m2: None,
+                        organic_amendments: None,
+                    },
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::ValidateProductionSeason {
+                season,
+                validator,
+                validation_status,
+            } => {
+                let (_, validator) = self.actor(validator);
+                let authority = self.authority_of(&validator);
+                return Some((
+                    instructions::validate_production_season(
+                        &authority,
+                        &validator,
+                        &seed("season", season),
+                        action::validation_status(validation_status),
+                    ),
+                    vec![authority],
+                ));
+            }
+            Action::DeleteProductionSeason { season } => {
+                let (authority, farmer) = self.farmer(season);
+                let instruction = instructions::delete_production_season(
+                    &authority,
+                    &farmer,
+                    &seed("season", season),
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::CreateMilledRice {
+                milling,
+                total_weight_kg,
+                total_weight_processed_kg,
+                by_products,
+                photo_urls,
+            } => {
+                // Seasons and millings in the same slot share a farmer.
+                let farmer = self.actor(milling).1;
+                let farmer_authority = self.authority_of(&farmer);
+                let instruction = instructions::create_milled_rice(
+                    &user,
+                    &pda::production_season(&seed("season", milling)).0,
+                    &farmer_authority,
+                    args::CreateMilledRice {
+                        milling_seed: seed("milling", milling),
+                        farmer_id: farmer,
+                        total_weight_kg,
+                        milling_type: MillingType::WellMilled,
+                        quality: QualityGrade::Grade1,
+                        photo_urls,
+                        moisture: 1_400,
+                        total_weight_processed_kg,
+                        by_products: by_products_from(by_products),
+                    },
+                );
+                return Some((instruction, vec![user, farmer_authority]));
+            }
+            Action::UpdateMilledRice {
+                milling,
+                total_weight_kg,
+                total_weight_processed_kg,
+                by_products,
+                photo_urls,
+            } => instructions::update_milled_rice(
+                &user,
+                &self
+                    .load::<MilledRice>(&pda::milled_rice(&seed("milling", milling)).0)
+                    .map_or(
+                        pda::production_season(&seed("season", milling)).0,
+                        |milled_rice| milled_rice.production_season,
+                    ),
+                args::UpdateMilledRice {
+                    milling_seed: seed("milling", milling),
+                    total_weight_kg,
+                    milling_type: None,
+                    quality: None,
+                    photo_urls,
+                    moisture: None,
+                    total_weight_processed_kg,
+                    by_products: by_products.map(by_products_from),
+                },
+            ),
+            Action::CreateRiceBatch {
+                batch,
+                batch_status,
+                weight_kg,
+                qr_code,
+            } => instructions::create_rice_batch(
+                &user,
+                args::CreateRiceBatch {
+                    batch_seed: seed("batch", batch),
+                    milled_rice_id: pda::milled_rice(&seed("milling", batch)).0,
+                    batch_status: action::batch_status(batch_status),
+                    quality_score: 90,
+                    weight_kg,
+                    qr_code,
+                },
+            ),
+            Action::UpdateRiceBatch {
+                batch,
+                batch_status,
+                quality_score,
+                weight_kg,
+            } => {
+                let (batch_seed, _, rice_batch) = self.batch(batch);
+                let (holder, authority) = self.custody(rice_batch.as_ref());
+                let milled_rice = rice_batch.map(|rice_batch| rice_batch.milled_rice_id)?;
+                let instruction = instructions::update_rice_batch(
+                    &authority,
+                    holder.as_ref(),
+                    &milled_rice,
+                    args::UpdateRiceBatch {
+                        batch_seed,
+                        batch_status: batch_status.map(action::batch_status),
+                        quality_score,
+                        weight_kg,
+                    },
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::SetRiceBatchQrCode { batch, qr_code } => {
+                let (batch_seed, _, rice_batch) = self.batch(batch);
+                let (holder, authority) = self.custody(rice_batch.as_ref());
+                let current_qr_code = rice_batch.map(|rice_batch| rice_batch.qr_code)?;
+                let instruction = instructions::set_rice_batch_qr_code(
+                    &authority,
+                    holder.as_ref(),
+                    &batch_seed,
+                    &current_qr_code,
+                    qr_code,
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::SetRiceBatchDigitalLink {
+                batch,
+                resolver,
+                lot,
+                serial,
+            } => {
+                let (batch_seed, _, rice_batch) = self.batch(batch);
+                let (holder, authority) = self.custody(rice_batch.as_ref());
+                let current_qr_code = rice_batch.map(|rice_batch| rice_batch.qr_code)?;
+                let qr_code = action::digital_link(resolver, &lot, serial.as_deref());
+                let instruction = instructions::set_rice_batch_qr_code(
+                    &authority,
+                    holder.as_ref(),
+                    &batch_seed,
+                    &current_qr_code,
+                    qr_code,
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::DeleteRiceBatch { batch } => {
+                let (batch_seed, _, rice_batch) = self.batch(batch);
+                let (holder, authority) = self.custody(rice_batch.as_ref());
+                let qr_code = rice_batch.map(|rice_batch| rice_batch.qr_code)?;
+                let instruction = instructions::delete_rice_batch(
+                    &authority,
+                    holder.as_ref(),
+                    &batch_seed,
+                    &qr_code,
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::CreateChainTransaction {
+                transaction,
+                batches,
+                amount,
+                payment_reference,
+                geotag,
+            } => instructions::create_chain_transaction(
+                &user,
+                args::CreateChainTransaction {
+                    transaction_seed: seed("transaction", transaction),
+                    from_actor_id: self.actor(transaction).1,
+                    to_actor_id: self.actor(transaction.wrapping_add(1)).1,
+                    rice_batch_ids: batches.into_iter().map(|slot| self.batch(slot).1).collect(),
+                    amount,
+                    payment_method: PaymentMethod::Cash,
+                    payment_reference,
+                    geotag: geotag.map(geo_point),
+                    quality: None,
+                },
+            ),
+            Action::UpdateChainTransaction {
+                transaction,
+                amount,
+                payment_reference,
+                geotag,
+                transaction_status,
+            } => {
+                let transaction_seed = seed("transaction", transaction);
+                let chain_transaction: Option<ChainTransaction> =
+                    self.load(&pda::chain_transaction(&transaction_seed).0);
+                let (authority, from_actor) = self.seller(transaction, chain_transaction.as_ref());
+                let rice_batch_ids = chain_transaction
+                    .map(|transaction| transaction.rice_batch_ids)
+                    .unwrap_or_default();
+                let instruction = instructions::update_chain_transaction(
+                    &authority,
+                    &from_actor,
+                    args::UpdateChainTransaction {
+                        transaction_seed,
+                        amount,
+                        payment_method: None,
+                        payment_reference,
+                        geotag: geotag.map(geo_point),
+                        quality: None,
+                        transaction_status: transaction_status.map(action::transaction_status),
+                    },
+                    &rice_batch_ids,
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::DeleteChainTransaction { transaction } => {
+                let transaction_seed = seed("transaction", transaction);
+                let chain_transaction: Option<ChainTransaction> =
+                    self.load(&pda::chain_transaction(&transaction_seed).0);
+                let (authority, from_actor) = self.seller(transaction, chain_transaction.as_ref());
+                let instruction = instructions::delete_chain_transaction(
+                    &authority,
+                    &from_actor,
+                    &transaction_seed,
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::RecordPostHarvestEvent {
+                event,
+                season,
+                facility,
+                started_at,
+                duration_s,
+                start_moisture_bp,
+                end_moisture_bp,
+                weight_in_kg,
+                weight_out_kg,
+            } => {
+                let (authority, facility) = self.actor(facility);
+                let production_season = pda::production_season(&seed("season", season)).0;
+                let args = args::RecordPostHarvestEvent {
+                    event_seed: seed("event", event),
+                    drying_method: rice_supply_chain::DryingMethod::SunDrying,
+                    started_at,
+                    ended_at: started_at.saturating_add(duration_s as i64),
+                    start_moisture_bp,
+                    end_moisture_bp,
+                    weight_in_kg,
+                    weight_out_kg,
+                };
+                let farmer = self
+                    .load::<ProductionSeason>(&production_season)
+                    .map_or(self.actor(season).1, |season| season.farmer_id);
+                let farmer_authority = self.authority_of(&farmer);
+                let instruction = instructions::record_post_harvest_event(
+                    &authority,
+                    &facility,
+                    &production_season,
+                    &farmer,
+                    &farmer_authority,
+                    args,
+                );
+                return Some((instruction, vec![authority, farmer_authority]));
+            }
+            Action::CreateShipment {
+                shipment,
+                carrier,
+                vehicle_id,
+                batches,
+                seal_numbers,
+            } => {
+                let (authority, carrier) = self.actor(carrier);
+                // The first batch's holder consents for the whole shipment.
+                let first_batch = batches.first().and_then(|slot| self.batch(*slot).2);
+                let (holder, holder_authority) = self.custody(first_batch.as_ref());
+                let args = args::CreateShipment {
+                    shipment_seed: seed("shipment", shipment),
+                    vehicle_id,
+                    origin: geo_point((14_600_000, 120_980_000)),
+                    destination: geo_point((15_490_000, 120_970_000)),
+                    rice_batch_ids: batches.into_iter().map(|slot| self.batch(slot).1).collect(),
+                    seal_numbers,
+                };
+                let instruction = instructions::create_shipment(
+                    &authority,
+                    &carrier,
+                    holder.as_ref(),
+                    &holder_authority,
+                    args,
+                );
+                return Some((instruction, vec![authority, holder_authority]));
+            }
+            Action::DepartShipment { shipment } => {
+                let shipment_seed = seed("shipment", shipment);
+                let shipment: Shipment = self.load(&pda::shipment(&shipment_seed).0)?;
+                let authority = self.authority_of(&shipment.carrier);
+                let instruction = instructions::depart_shipment(
+                    &authority,
+                    &shipment.carrier,
+                    &shipment_seed,
+                    &shipment.rice_batch_ids,
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::ArriveShipment {
+                shipment,
+                break_seal,
+            } => {
+                let shipment_seed = seed("shipment", shipment);
+                let shipment: Shipment = self.load(&pda::shipment(&shipment_seed).0)?;
+                let authority = self.authority_of(&shipment.carrier);
+                let mut seal_numbers = shipment.seal_numbers.clone();
+                if break_seal {
+                    seal_numbers.pop();
+                }
+                let instruction = instructions::arrive_shipment(
+                    &authority,
+                    &shipment.carrier,
+                    &shipment_seed,
+                    seal_numbers,
+                    &shipment.rice_batch_ids,
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::CreateWarehouse {
+                warehouse,
+                operator,
+                name,
+                capacity_kg,
+            } => {
+                let (authority, operator) = self.actor(operator);
+                let args = args::CreateWarehouse {
+                    warehouse_seed: seed("warehouse", warehouse),
+                    name,
+                    location: geo_point((15_490_000, 120_970_000)),
+                    capacity_kg,
+                };
+                return Some((
+                    instructions::create_warehouse(&authority, &operator, args),
+                    vec![authority],
+                ));
+            }
+            Action::UpdateWarehouse {
+                warehouse,
+                name,
+                capacity_kg,
+                is_active,
+            } => {
+                let warehouse_seed = seed("warehouse", warehouse);
+                let warehouse: Warehouse = self.load(&pda::warehouse(&warehouse_seed).0)?;
+                let authority = self.authority_of(&warehouse.operator);
+                let args = args::UpdateWarehouse {
+                    warehouse_seed,
+                    name,
+                    capacity_kg,
+                    is_active,
+                };
+                let instruction =
+                    instructions::update_warehouse(&authority, &warehouse.operator, args);
+                return Some((instruction, vec![authority]));
+            }
+            Action::StoreBatch { warehouse, batch } | Action::ReleaseBatch { warehouse, batch } => {
+                let warehouse_seed = seed("warehouse", warehouse);
+                let warehouse: Warehouse = self.load(&pda::warehouse(&warehouse_seed).0)?;
+                let authority = self.authority_of(&warehouse.operator);
+                let (_, rice_batch, batch) = self.batch(batch);
+                let storage_count = batch.as_ref().map_or(0, |batch| batch.storage_count);
+                if let Action::ReleaseBatch { .. } = action {
+                    let instruction = instructions::release_batch(
+                        &authority,
+                        &warehouse.operator,
+                        &warehouse_seed,
+                        &rice_batch,
+                        storage_count,
+                    );
+                    return Some((instruction, vec![authority]));
+                }
+                // The holder consents, or the creator while there is none.
+                let (holder, holder_authority) = self.custody(batch.as_ref());
+                let instruction = instructions::store_batch(
+                    &authority,
+                    &warehouse.operator,
+                    &warehouse_seed,
+                    &rice_batch,
+                    storage_count,
+                    holder.as_ref(),
+                    &holder_authority,
+                );
+                return Some((instruction, vec![authority, holder_authority]));
+            }
+            Action::CreateRetailPacks {
+                batch,
+                packer,
+                pack_size_g,
+                pack_count,
+            } => {
+                let (authority, packer) = self.actor(packer);
+                let (_, rice_batch, batch) = self.batch(batch);
+                let next_pack_serial = batch.map_or(0, |batch| batch.next_pack_serial);
+                let instruction = instructions::create_retail_packs(
+                    &authority,
+                    &packer,
+                    &rice_batch,
+                    next_pack_serial,
+                    pack_size_g,
+                    pack_count,
+                );
+                return Some((instruction, vec![authority]));
+            }
+            Action::GrantAccreditation { actor, kind } => instructions::grant_accreditation(
+                &user,
+                &self.actor(actor).1,
+                action::accreditation_kind(kind),
+                None,
+            ),
+            Action::Warp { seconds } => {
+                let unix_timestamp = self.svm.clock().unix_timestamp + seconds as i64;
+                self.svm.warp_to_timestamp(unix_timestamp);
+                return None;
+            }
+        };
+        Some((instruction, vec![user]))
+    }
+}
+
+fn by_products_from([husk_kg, bran_kg, brokens_kg, brewers_rice_kg]: [u32; 4]) -> ByProducts {
+    ByProducts {
+        husk_kg,
+        bran_kg,
+        brokens_kg,
+        brewers_rice_kg,
+    }
+}
+
+fn geo_point((latitude_microdeg, longitude_microdeg): (i32, i32)) -> GeoPoint {
+    GeoPoint {
+        latitude_microdeg,
+        longitude_microdeg,
+        accuracy_m: None,
+        captured_at: 1_700_000_000,
+    }
+}
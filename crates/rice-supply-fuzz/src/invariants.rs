@@ -0,0 +1,306 @@
+//! Properties that hold after every transaction, whether it succeeded or not.
+//!
+//! - Every program-owned account deserializes as a known account type.
+//! - Weights balance: retail packs never exceed their batch, milling output
+//!   and by-products never exceed the paddy input, post-harvest losses and
+//!   milling runs never exceed the harvest, and a warehouse holds exactly its
+//!   open storage records within its capacity.
+//! - Batch, season, transaction and shipment statuses only move along the
+//!   transitions the program allows.
+//! - A batch's GS1 keys are the ones its QR code parses to, and its QR index
+//!   points back at it.
+//! - A batch's current holder is the recipient of a completed chain
+//!   transaction that lists it.
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use rice_supply_chain::{
+    Accreditation, AccreditationKind, BatchStatus, Certification, ChainActor, ChainTransaction,
+    Custodian, EmissionFactors, Gs1Key, InputApplication, IssuerKey, MilledRice, PostHarvestEvent,
+    ProductionSeason, ProgramConfig, QrIndex, QualityTest, RetailPackRun, RiceBatch, Shipment,
+    ShipmentStatus, StorageRecord, SubstanceRegistry, TransactionStatus, ValidationStatus,
+    Warehouse,
+};
+use rice_supply_client::pda;
+use rice_supply_svm::Svm;
+
+/// Last seen status of every status-bearing account.
+#[derive(Default)]
+pub struct Statuses {
+    batches: HashMap<Pubkey, BatchStatus>,
+    seasons: HashMap<Pubkey, ValidationStatus>,
+    transactions: HashMap<Pubkey, TransactionStatus>,
+    shipments: HashMap<Pubkey, ShipmentStatus>,
+}
+
+/// Checks every invariant against the current accounts, comparing statuses
+/// with `previous`, and returns the statuses to compare the next state with.
+///
+/// # Panics
+///
+/// On the first violated invariant.
+pub fn check(svm: &Svm, previous: &Statuses) -> Statuses {
+    let mut statuses = Statuses::default();
+    let mut batches = HashMap::new();
+    let mut warehouses = HashMap::new();
+    let mut storage_records = Vec::new();
+    let mut retail_pack_runs = Vec::new();
+    let mut transactions = Vec::new();
+    let mut milled_paddy_kg = HashMap::new();
+    let mut milling_runs = Vec::new();
+
+    for (address, account) in svm.accounts() {
+        if account.owner != rice_supply_chain::ID {
+            continue;
+        }
+        let data = account.data.as_slice();
+        if is::<RiceBatch>(data) {
+            let rice_batch: RiceBatch = decode(address, data);
+            assert!(
+                rice_batch.remaining_weight_g <= rice_batch.weight_kg as u64 * 1_000,
+                "rice batch {address} has more unpacked weight than it weighs"
+            );
+            assert!(
+                Gs1Key::from_digital_link(&rice_batch.qr_code)
+                    .ok()
+                    .flatten()
+                    == rice_batch.gs1,
+                "rice batch {address} has GS1 keys that differ from its QR code"
+            );
+            let qr_index = pda::qr_index(&rice_batch.qr_code).0;
+            let indexed = svm
+                .get_account(&qr_index)
+                .map(|account| decode::<QrIndex>(&qr_index, &account.data).rice_batch);
+            assert_eq!(
+                indexed,
+                Some(*address),
+                "rice batch {address} is not reachable from its QR code"
+            );
+            if let Some(before) = previous.batches.get(address) {
+                assert!(
+                    *before == rice_batch.batch_status
+                        || before.can_transition_to(&rice_batch.batch_status),
+                    "rice batch {address} made a disallowed status transition"
+                );
+            }
+            statuses
+                .batches
+                .insert(*address, rice_batch.batch_status.clone());
+            batches.insert(*address, rice_batch);
+        } else if is::<ProductionSeason>(data) {
+            let production_season: ProductionSeason = decode(address, data);
+            if let Some(before) = previous.seasons.get(address) {
+                assert!(
+                    *before == production_season.validation_status
+                        || before.can_transition_to(&production_season.validation_status),
+                    "production season {address} made a disallowed status transition"
+                );
+            }
+            statuses
+                .seasons
+                .insert(*address, production_season.validation_status.clone());
+            assert!(
+                production_season.post_harvest_loss_kg + production_season.milled_paddy_kg
+                    <= production_season.total_yield_kg.unwrap_or(0),
+                "production season {address} lost and milled more than it harvested"
+            );
+            milled_paddy_kg.insert(*address, production_season.milled_paddy_kg);
+            if production_season.validation_status == ValidationStatus::Approved {
+                let validator = production_season.validator_id.unwrap_or_else(|| {
+                    panic!("production season {address} approved without a validator")
+                });
+                assert!(
+                    svm.account::<Accreditation>(
+                        &pda::accreditation(&validator, AccreditationKind::Validator).0
+                    )
+                    .is_ok(),
+                    "production season {address} approved by an unaccredited validator"
+                );
+            }
+            if let Some(carbon_credits) = &production_season.carbon_credits {
+                assert!(
+                    carbon_credits.retired_kg_co2e <= carbon_credits.issued_kg_co2e,
+                    "production season {address} retired more credits than it issued"
+                );
+            }
+        } else if is::<ChainTransaction>(data) {
+            let chain_transaction: ChainTransaction = decode(address, data);
+            if let Some(before) = previous.transactions.get(address) {
+                assert!(
+                    *before == chain_transaction.transaction_status
+                        || before.can_transition_to(&chain_transaction.transaction_status),
+                    "chain transaction {address} made a disallowed status transition"
+                );
+            }
+            statuses
+                .transactions
+                .insert(*address, chain_transaction.transaction_status.clone());
+            transactions.push(chain_transaction);
+        } else if is::<Shipment>(data) {
+            let shipment: Shipment = decode(address, data);
+            if let Some(before) = previous.shipments.get(address) {
+                assert!(
+                    matches!(
+                        (before, &shipment.shipment_status),
+                        (
+                            ShipmentStatus::Planned,
+                            ShipmentStatus::Planned | ShipmentStatus::InTransit
+                        ) | (
+                            ShipmentStatus::InTransit,
+                            ShipmentStatus::InTransit | ShipmentStatus::Arrived
+                        ) | (ShipmentStatus::Arrived, ShipmentStatus::Arrived)
+                    ),
+                    "shipment {address} made a disallowed status transition"
+                );
+            }
+            statuses
+                .shipments
+                .insert(*address, shipment.shipment_status);
+        } else if is::<MilledRice>(data) {
+            let milled_rice: MilledRice = decode(address, data);
+            assert!(
+                milled_rice.total_weight_kg as u64 + milled_rice.by_products.total_kg()
+                    <= milled_rice.total_weight_processed_kg as u64,
+                "milled rice {address} weighs more than its paddy input"
+            );
+            milling_runs.push(milled_rice);
+        } else if is::<Warehouse>(data) {
+            let warehouse: Warehouse = decode(address, data);
+            assert!(
+                warehouse.stored_kg <= warehouse.capacity_kg,
+                "warehouse {address} holds more than its capacity"
+            );
+            warehouses.insert(*address, warehouse);
+        } else if is::<StorageRecord>(data) {
+            storage_records.push(decode::<StorageRecord>(address, data));
+        } else if is::<RetailPackRun>(data) {
+            retail_pack_runs.push(decode::<RetailPackRun>(address, data));
+        } else if is::<ChainActor>(data) {
+            decode::<ChainActor>(address, data);
+        } else if is::<QrIndex>(data) {
+            decode::<QrIndex>(address, data);
+        } else if is::<InputApplication>(data) {
+            decode::<InputApplication>(address, data);
+        } else if is::<SubstanceRegistry>(data) {
+            decode::<SubstanceRegistry>(address, data);
+        } else if is::<EmissionFactors>(data) {
+            decode::<EmissionFactors>(address, data);
+        } else if is::<QualityTest>(data) {
+            decode::<QualityTest>(address, data);
+        } else if is::<PostHarvestEvent>(data) {
+            decode::<PostHarvestEvent>(address, data);
+        } else if is::<IssuerKey>(data) {
+            decode::<IssuerKey>(address, data);
+        } else if is::<ProgramConfig>(data) {
+            decode::<ProgramConfig>(address, data);
+        } else if is::<Accreditation>(data) {
+            decode::<Accreditation>(address, data);
+        } else if is::<Certification>(data) {
+            decode::<Certification>(address, data);
+        } else if is::<Custodian>(data) {
+            decode::<Custodian>(address, data);
+        } else {
+            panic!("account {address} is owned by the program but is no known account type");
+        }
+    }
+
+    for (address, warehouse) in &warehouses {
+        let open_records = storage_records
+            .iter()
+            .filter(|record| record.warehouse == *address && record.released_at.is_none());
+        let (count, weight_kg) = open_records.fold((0, 0), |(count, weight_kg), record| {
+            (count + 1, weight_kg + record.weight_kg as u64)
+        });
+        assert_eq!(
+            warehouse.stored_kg, weight_kg,
+            "warehouse {address} total differs from its open storage records"
+        );
+        assert_eq!(
+            warehouse.stored_batch_ids.len(),
+            count,
+            "warehouse {address} batch list differs from its open storage records"
+        );
+        for rice_batch_id in &warehouse.stored_batch_ids {
+            assert!(
+                batches
+                    .get(rice_batch_id)
+                    .is_some_and(|batch| batch.current_warehouse == Some(*address)),
+                "warehouse {address} lists batch {rice_batch_id}, which is not stored there"
+            );
+        }
+    }
+
+    for (address, recorded_kg) in &milled_paddy_kg {
+        let milled_kg: u64 = milling_runs
+            .iter()
+            .filter(|milled_rice| milled_rice.production_season == *address)
+            .map(|milled_rice| milled_rice.total_weight_processed_kg as u64)
+            .sum();
+        assert_eq!(
+            *recorded_kg, milled_kg,
+            "production season {address} milled total differs from its milling runs"
+        );
+    }
+
+    for milled_rice in &milling_runs {
+        let address = milled_rice.public_key;
+        let allocated_kg: u64 = batches
+            .values()
+            .filter(|rice_batch| rice_batch.milled_rice_id == address)
+            .map(|rice_batch| rice_batch.weight_kg as u64)
+            .sum();
+        assert_eq!(
+            milled_rice.allocated_kg as u64, allocated_kg,
+            "milled rice {address} allocation differs from its batches"
+        );
+        assert!(
+            milled_rice.allocated_kg <= milled_rice.total_weight_kg,
+            "milled rice {address} allocated more than its output"
+        );
+    }
+
+    for (address, rice_batch) in &batches {
+        let runs = retail_pack_runs
+            .iter()
+            .filter(|run| run.rice_batch == *address);
+        let (packs, packed_weight_g) = runs.fold((0, 0), |(packs, weight_g), run| {
+            (
+                packs + run.pack_count as u64,
+                weight_g + run.pack_size_g as u64 * run.pack_count as u64,
+            )
+        });
+        assert_eq!(
+            rice_batch.packed_weight_g(),
+            packed_weight_g,
+            "rice batch {address} packed weight differs from its retail pack runs"
+        );
+        assert_eq!(
+            rice_batch.next_pack_serial, packs,
+            "rice batch {address} pack serial differs from its retail pack runs"
+        );
+        // Custody of a tokenized batch follows its tokens instead.
+        if let (Some(holder), None) = (rice_batch.current_holder, rice_batch.token_mint) {
+            assert!(
+                transactions.iter().any(|transaction| {
+                    transaction.to_actor_id == holder
+                        && transaction.transaction_status == TransactionStatus::Completed
+                        && transaction.rice_batch_ids.contains(address)
+                }),
+                "rice batch {address} is held by {holder} without a chain transaction"
+            );
+        }
+    }
+
+    statuses
+}
+
+fn is<T: Discriminator>(data: &[u8]) -> bool {
+    data.starts_with(T::DISCRIMINATOR)
+}
+
+fn decode<T: AccountDeserialize>(address: &Pubkey, data: &[u8]) -> T {
+    T::try_deserialize(&mut &data[..])
+        .unwrap_or_else(|error| panic!("account {address} does not deserialize: {error}"))
+}
@@ -0,0 +1,26 @@
+//! Fuzzing model for the `rice_supply_chain` program.
+//!
+//! - [`Action`] is one instruction with fuzzer-chosen arguments. Seeds come
+//!   from a few slots per account kind so sequences keep hitting the same
+//!   accounts.
+//! - [`Harness`] runs actions in an in-process [`rice_supply_svm::Svm`] and
+//!   checks the [`invariants`] after every transaction, panicking on the
+//!   first violation.
+//!
+//! The `fuzz/` directory at the repository root drives [`run`] from
+//! libFuzzer; `cargo test -p rice-supply-fuzz` replays fixed seeds.
+
+mod action;
+mod harness;
+pub mod invariants;
+
+pub use action::Action;
+pub use harness::Harness;
+
+/// Runs `actions` against a freshly initialized program.
+pub fn run(actions: &[Action]) {
+    let mut harness = Harness::new();
+    for action in actions {
+        harness.execute(action);
+    }
+}
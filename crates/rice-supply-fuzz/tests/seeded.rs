@@ -0,0 +1,39 @@
+//! Replays pseudo-random action sequences from fixed seeds so the invariants
+//! run on every `cargo test`; `fuzz/` explores beyond them.
+
+use arbitrary::{Arbitrary, Unstructured};
+use rice_supply_fuzz::Action;
+
+/// splitmix64, enough to turn a seed into reproducible fuzzer input.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len + 8);
+        while bytes.len() < len {
+            self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            bytes.extend_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+        }
+        bytes.truncate(len);
+        bytes
+    }
+}
+
+#[test]
+fn seeded_action_sequences_hold_invariants() {
+    for seed in 0..50 {
+        let mut rng = SplitMix64(seed);
+        // Decoding each action from its own short input keeps strings and
+        // vectors short, so most actions reach the handler checks.
+        let actions: Vec<Action> = (0..200)
+            .map(|_| {
+                let bytes = rng.bytes(48);
+                Action::arbitrary(&mut Unstructured::new(&bytes)).unwrap()
+            })
+            .collect();
+        rice_supply_fuzz::run(&actions);
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rice_supply_chain::Gs1Key;
+use rice_supply_client::{pda, Pubkey};
+
+use crate::{Gs1Error, Gtin};
+
+/// A GS1 Digital Link naming a lot of a trade item, and optionally one
+/// serialised item from it: `https://id.gs1.org/01/<GTIN>/10/<lot>[/21/<serial>]`.
+///
+/// Parsing and validation are the program's own, so a link accepted here is
+/// accepted as a batch QR code and lands in `RiceBatch::gs1` unchanged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigitalLink {
+    gtin: Gtin,
+    lot: String,
+    serial: Option<String>,
+}
+
+impl DigitalLink {
+    /// Checks `lot` and `serial` against GS1's length and character rules.
+    pub fn new(gtin: Gtin, lot: &str, serial: Option<&str>) -> Result<Self, Gs1Error> {
+        let key = Gs1Key {
+            gtin: gtin.value(),
+            lot: lot.to_string(),
+            serial: serial.map(str::to_string),
+        };
+        key.canonical_uri().parse()
+    }
+
+    pub fn gtin(&self) -> Gtin {
+        self.gtin
+    }
+
+    pub fn lot(&self) -> &str {
+        &self.lot
+    }
+
+    pub fn serial(&self) -> Option<&str> {
+        self.serial.as_deref()
+    }
+
+    /// The keys as stored on-chain.
+    pub fn key(&self) -> Gs1Key {
+        Gs1Key {
+            gtin: self.gtin.value(),
+            lot: self.lot.clone(),
+            serial: self.serial.clone(),
+        }
+    }
+
+    /// The link under a brand's own resolver, e.g. `https://rice.example.ph`.
+    /// Scanners resolve it to the same batch as the canonical form.
+    pub fn uri(&self, resolver: &str) -> String {
+        self.key().uri(resolver.trim_end_matches('/'))
+    }
+
+    pub fn canonical_uri(&self) -> String {
+        self.key().canonical_uri()
+    }
+
+    /// The `QrIndex` PDA that points at the batch carrying this link.
+    pub fn qr_index(&self) -> Pubkey {
+        pda::qr_index(&self.canonical_uri()).0
+    }
+}
+
+impl TryFrom<&Gs1Key> for DigitalLink {
+    type Error = Gs1Error;
+
+    fn try_from(key: &Gs1Key) -> Result<Self, Self::Error> {
+        let gtin = format!("{:014}", key.gtin).parse()?;
+        Self::new(gtin, &key.lot, key.serial.as_deref())
+    }
+}
+
+impl FromStr for DigitalLink {
+    type Err = Gs1Error;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let key = Gs1Key::from_digital_link(uri)
+            .map_err(|_| Gs1Error::InvalidDigitalLink(uri.to_string()))?
+            .ok_or_else(|| Gs1Error::NotDigitalLink(uri.to_string()))?;
+        Ok(DigitalLink {
+            gtin: format!("{:014}", key.gtin).parse()?,
+            lot: key.lot,
+            serial: key.serial,
+        })
+    }
+}
+
+impl fmt::Display for DigitalLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.canonical_uri())
+    }
+}
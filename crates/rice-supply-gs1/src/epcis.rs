@@ -0,0 +1,267 @@
+//! GS1 EPCIS 2.0 events for a batch's lineage.
+//!
+//! - Each production season that was not rejected becomes an `ObjectEvent`
+//!   commissioning its harvested paddy lot.
+//! - The milled-rice record becomes a `TransformationEvent` from those paddy
+//!   lots to a milled-rice lot.
+//! - The batch becomes an `AggregationEvent` packing milled rice into it.
+//! - Each chain transaction becomes a `shipping` `ObjectEvent`, followed by
+//!   `receiving` once completed or `void_shipping` once cancelled or failed.
+//!
+//! Trade items use GS1 Digital Link URIs under the GTINs in [`Products`],
+//! with lot numbers and serials taken from the first 20 base58 characters
+//! of the record's address. A batch whose QR code is a Digital Link keeps
+//! its own GTIN, lot and serial. Actors appear as
+//! `urn:rice-supply:actor:<address>` parties.
+
+use rice_supply_chain::{GeoPoint, Gs1Key, TransactionStatus, ValidationStatus};
+use rice_supply_client::accounts::{ChainTransaction, MilledRice, ProductionSeason, RiceBatch};
+use rice_supply_client::json::timestamp;
+use rice_supply_client::lineage::Lineage;
+use rice_supply_client::Pubkey;
+use serde_json::{json, Value};
+
+use crate::Gtin;
+
+pub const CONTEXT: &str = "https://ref.gs1.org/standards/epcis/epcis-context.jsonld";
+
+const DIGITAL_LINK: &str = "https://id.gs1.org";
+/// Longest lot number (AI 10) or serial (AI 21).
+const REFERENCE_LEN: usize = 20;
+
+/// GTINs of the trade items the events describe.
+#[derive(Clone, Copy, Debug)]
+pub struct Products {
+    /// Harvested paddy, commissioned per production season.
+    pub paddy: Gtin,
+    /// Milled rice, in lots per milling run and units per batch.
+    pub milled_rice: Gtin,
+}
+
+/// An `EPCISDocument` holding every event of `lineage`, created at the unix
+/// time `creation_time`.
+pub fn document(lineage: &Lineage, products: &Products, creation_time: i64) -> Value {
+    json!({
+        "@context": [CONTEXT],
+        "type": "EPCISDocument",
+        "schemaVersion": "2.0",
+        "creationDate": timestamp(creation_time),
+        "epcisBody": { "eventList": events(lineage, products) },
+    })
+}
+
+/// The events of `lineage` in event-time order.
+pub fn events(lineage: &Lineage, products: &Products) -> Vec<Value> {
+    let seasons: Vec<&ProductionSeason> = lineage
+        .production_seasons
+        .iter()
+        .filter(|season| season.validation_status != ValidationStatus::Rejected)
+        .collect();
+
+    let mut events: Vec<(i64, Value)> = seasons
+        .iter()
+        .map(|season| harvest(season, products))
+        .collect();
+    if let Some(milled_rice) = &lineage.milled_rice {
+        events.push(milling(milled_rice, &seasons, products));
+    }
+    events.push(packing(&lineage.rice_batch, products));
+    for transaction in &lineage.chain_transactions {
+        events.extend(transfer(transaction, &lineage.rice_batch, products));
+    }
+    events.sort_by_key(|(event_time, _)| *event_time);
+    events.into_iter().map(|(_, event)| event).collect()
+}
+
+fn harvest(season: &ProductionSeason, products: &Products) -> (i64, Value) {
+    let event_time = season.harvest_date.unwrap_or(season.created_at);
+    let mut event = event(
+        "ObjectEvent",
+        event_time,
+        &season.public_key,
+        "commissioning",
+    );
+    event["action"] = json!("ADD");
+    event["bizStep"] = json!("commissioning");
+    event["disposition"] = json!("active");
+    event["epcList"] = json!([]);
+    event["quantityList"] = json!([quantity(
+        lot(&products.paddy, &season.public_key),
+        season.total_yield_kg,
+    )]);
+    if let Some(harvest_date) = season.harvest_date {
+        event["ilmd"] = json!({ "cbvmda:harvestEndDate": &timestamp(harvest_date)[..10] });
+    }
+    (event_time, event)
+}
+
+fn milling(
+    milled_rice: &MilledRice,
+    seasons: &[&ProductionSeason],
+    products: &Products,
+) -> (i64, Value) {
+    let mut event = event(
+        "TransformationEvent",
+        milled_rice.created_at,
+        &milled_rice.public_key,
+        "milling",
+    );
+    if !seasons.is_empty() {
+        // The processed weight can only be attributed when one season fed the mill.
+        let processed_kg =
+            (seasons.len() == 1).then_some(u64::from(milled_rice.total_weight_processed_kg));
+        event["inputQuantityList"] = seasons
+            .iter()
+            .map(|season| quantity(lot(&products.paddy, &season.public_key), processed_kg))
+            .collect();
+    }
+    event["outputQuantityList"] = json!([quantity(
+        lot(&products.milled_rice, &milled_rice.public_key),
+        Some(u64::from(milled_rice.total_weight_kg)),
+    )]);
+    event["bizStep"] = json!("commissioning");
+    event["disposition"] = json!("active");
+    (milled_rice.created_at, event)
+}
+
+fn packing(rice_batch: &RiceBatch, products: &Products) -> (i64, Value) {
+    let mut event = event(
+        "AggregationEvent",
+        rice_batch.created_at,
+        &rice_batch.public_key,
+        "packing",
+    );
+    event["action"] = json!("ADD");
+    event["parentID"] = json!(batch_unit(rice_batch, products));
+    event["childQuantityList"] = json!([quantity(
+        lot(&products.milled_rice, &rice_batch.milled_rice_id),
+        Some(u64::from(rice_batch.weight_kg)),
+    )]);
+    event["bizStep"] = json!("packing");
+    event["disposition"] = json!("in_progress");
+    (rice_batch.created_at, event)
+}
+
+fn transfer(
+    transaction: &ChainTransaction,
+    traced: &RiceBatch,
+    products: &Products,
+) -> Vec<(i64, Value)> {
+    let shipment = |event_time: i64, step: &str| {
+        let mut event = event("ObjectEvent", event_time, &transaction.public_key, step);
+        event["action"] = json!("OBSERVE");
+        event["bizStep"] = json!(step);
+        event["epcList"] = transaction
+            .rice_batch_ids
+            .iter()
+            .map(|rice_batch| {
+                if *rice_batch == traced.public_key {
+                    json!(batch_unit(traced, products))
+                } else {
+                    json!(unit(&products.milled_rice, rice_batch))
+                }
+            })
+            .collect();
+        event["bizTransactionList"] = json!([{
+            "type": "inv",
+            "bizTransaction": format!("urn:rice-supply:transaction:{}", transaction.public_key),
+        }]);
+        event["sourceList"] = json!([{
+            "type": "owning_party",
+            "source": party(&transaction.from_actor_id),
+        }]);
+        event["destinationList"] = json!([{
+            "type": "owning_party",
+            "destination": party(&transaction.to_actor_id),
+        }]);
+        (event_time, event)
+    };
+
+    let mut shipping = shipment(transaction.created_at, "shipping");
+    shipping.1["disposition"] = json!("in_transit");
+    if let Some(geotag) = &transaction.geotag {
+        shipping.1["readPoint"] = json!({ "id": geo_uri(geotag) });
+    }
+    let mut events = vec![shipping];
+    match transaction.transaction_status {
+        TransactionStatus::Pending => {}
+        TransactionStatus::Completed => {
+            let mut receiving = shipment(transaction.updated_at, "receiving");
+            receiving.1["disposition"] = json!("in_progress");
+            events.push(receiving);
+        }
+        TransactionStatus::Failed | TransactionStatus::Cancelled => {
+            events.push(shipment(transaction.updated_at, "void_shipping"));
+        }
+    }
+    events
+}
+
+fn event(kind: &str, event_time: i64, record: &Pubkey, step: &str) -> Value {
+    json!({
+        "type": kind,
+        "eventID": format!("urn:rice-supply:event:{record}:{step}"),
+        "eventTime": timestamp(event_time),
+        "eventTimeZoneOffset": "+00:00",
+    })
+}
+
+fn quantity(epc_class: String, kilograms: Option<u64>) -> Value {
+    match kilograms {
+        Some(kilograms) => json!({ "epcClass": epc_class, "quantity": kilograms, "uom": "KGM" }),
+        None => json!({ "epcClass": epc_class }),
+    }
+}
+
+/// Digital Link URI of the lot `record` stands for.
+fn lot(gtin: &Gtin, record: &Pubkey) -> String {
+    format!("{DIGITAL_LINK}/01/{gtin}/10/{}", reference(record))
+}
+
+/// Digital Link URI of the single trade item `record` stands for.
+fn unit(gtin: &Gtin, record: &Pubkey) -> String {
+    format!("{DIGITAL_LINK}/01/{gtin}/21/{}", reference(record))
+}
+
+/// Digital Link URI of a batch as one trade item. A lot-level Digital Link
+/// QR code gets the derived serial added.
+fn batch_unit(rice_batch: &RiceBatch, products: &Products) -> String {
+    match &rice_batch.gs1 {
+        Some(gs1) => Gs1Key {
+            serial: Some(
+                gs1.serial
+                    .clone()
+                    .unwrap_or_else(|| reference(&rice_batch.public_key)),
+            ),
+            ..gs1.clone()
+        }
+        .canonical_uri(),
+        None => unit(&products.milled_rice, &rice_batch.public_key),
+    }
+}
+
+fn reference(record: &Pubkey) -> String {
+    record.to_string().chars().take(REFERENCE_LEN).collect()
+}
+
+fn party(actor: &Pubkey) -> String {
+    format!("urn:rice-supply:actor:{actor}")
+}
+
+fn geo_uri(geotag: &GeoPoint) -> String {
+    format!(
+        "geo:{},{}",
+        degrees(geotag.latitude_microdeg),
+        degrees(geotag.longitude_microdeg)
+    )
+}
+
+fn degrees(microdegrees: i32) -> String {
+    let sign = if microdegrees < 0 { "-" } else { "" };
+    let magnitude = microdegrees.unsigned_abs();
+    format!(
+        "{sign}{}.{:06}",
+        magnitude / 1_000_000,
+        magnitude % 1_000_000
+    )
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rice_supply_chain::Gs1Key;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum Gs1Error {
+    #[error("GTIN must be 8, 12, 13 or 14 digits, got {0:?}")]
+    GtinFormat(String),
+    #[error("GTIN {0} has a bad check digit")]
+    GtinCheckDigit(String),
+    #[error("{0:?} is not a GS1 Digital Link")]
+    NotDigitalLink(String),
+    #[error("{0:?} is not a valid GS1 Digital Link with a GTIN and lot")]
+    InvalidDigitalLink(String),
+}
+
+/// A Global Trade Item Number. GTIN-8, -12 and -13 are held as the GTIN-14
+/// they zero-pad to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Gtin(u64);
+
+impl Gtin {
+    /// The number as `Gs1Key::gtin` stores it.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for Gtin {
+    type Err = Gs1Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if !matches!(value.len(), 8 | 12 | 13 | 14) || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Gs1Error::GtinFormat(value.to_string()));
+        }
+        let gtin: u64 = value
+            .parse()
+            .map_err(|_| Gs1Error::GtinFormat(value.to_string()))?;
+        if Gs1Key::check_digit(gtin / 10) != gtin % 10 {
+            return Err(Gs1Error::GtinCheckDigit(value.to_string()));
+        }
+        Ok(Gtin(gtin))
+    }
+}
+
+impl fmt::Display for Gtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:014}", self.0)
+    }
+}
@@ -0,0 +1,16 @@
+//! GS1 identifiers for rice-supply-chain records.
+//!
+//! - [`Gtin`] parses and check-digit validates trade item numbers.
+//! - [`DigitalLink`] generates and validates the GS1 Digital Link URIs the
+//!   program accepts as batch QR codes.
+//! - [`epcis`] maps a batch's [`Lineage`] to a GS1 EPCIS 2.0 JSON-LD
+//!   document.
+//!
+//! [`Lineage`]: rice_supply_client::lineage::Lineage
+
+mod digital_link;
+pub mod epcis;
+mod gtin;
+
+pub use digital_link::DigitalLink;
+pub use gtin::{Gs1Error, Gtin};
@@ -0,0 +1,68 @@
+use rice_supply_chain::Gs1Key;
+use rice_supply_client::pda;
+use rice_supply_gs1::{DigitalLink, Gs1Error, Gtin};
+
+fn gtin() -> Gtin {
+    "4800016644504".parse().unwrap()
+}
+
+#[test]
+fn digital_link_round_trips_through_its_uri() {
+    let link = DigitalLink::new(gtin(), "24/DS-07", Some("0042")).unwrap();
+    let canonical = "https://id.gs1.org/01/04800016644504/10/24%2FDS-07/21/0042";
+    assert_eq!(link.canonical_uri(), canonical);
+    assert_eq!(link.to_string(), canonical);
+    assert_eq!(
+        link.uri("https://rice.example.ph/"),
+        "https://rice.example.ph/01/04800016644504/10/24%2FDS-07/21/0042"
+    );
+
+    let parsed: DigitalLink = link.uri("https://rice.example.ph").parse().unwrap();
+    assert_eq!(parsed, link);
+    assert_eq!(parsed.lot(), "24/DS-07");
+    assert_eq!(parsed.serial(), Some("0042"));
+    assert_eq!(DigitalLink::try_from(&link.key()).unwrap(), link);
+}
+
+#[test]
+fn digital_link_indexes_under_its_canonical_uri() {
+    let link = DigitalLink::new(gtin(), "LOT1", None).unwrap();
+    assert_eq!(
+        link.qr_index(),
+        pda::qr_index("https://rice.example.ph/01/04800016644504/10/LOT1?linkType=all").0
+    );
+    assert_ne!(
+        link.qr_index(),
+        DigitalLink::new(gtin(), "LOT2", None).unwrap().qr_index()
+    );
+}
+
+#[test]
+fn digital_link_rejects_keys_the_program_would() {
+    assert!(matches!(
+        DigitalLink::new(gtin(), "", None),
+        Err(Gs1Error::InvalidDigitalLink(_))
+    ));
+    assert!(matches!(
+        DigitalLink::new(gtin(), "LOT ONE", None),
+        Err(Gs1Error::InvalidDigitalLink(_))
+    ));
+    assert!(matches!(
+        DigitalLink::new(gtin(), &"A".repeat(Gs1Key::MAX_ATTRIBUTE_LEN + 1), None),
+        Err(Gs1Error::InvalidDigitalLink(_))
+    ));
+    assert!(matches!(
+        "https://id.gs1.org/01/04800016644505/10/LOT1".parse::<DigitalLink>(),
+        Err(Gs1Error::InvalidDigitalLink(_))
+    ));
+    assert_eq!(
+        "QR-batch-1".parse::<DigitalLink>(),
+        Err(Gs1Error::NotDigitalLink("QR-batch-1".to_string()))
+    );
+    assert_eq!(
+        "https://x.com/2024/01/15".parse::<DigitalLink>(),
+        Err(Gs1Error::NotDigitalLink(
+            "https://x.com/2024/01/15".to_string()
+        ))
+    );
+}
@@ -0,0 +1,376 @@
+use rice_supply_chain::{
+    BatchStatus, ByProducts, GeoPoint, MillingType, Organization, PaymentMethod, QualityGrade,
+    TransactionStatus,
+};
+use rice_supply_client::accounts::{ChainTransaction, MilledRice, ProductionSeason, RiceBatch};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::lineage::Lineage;
+use rice_supply_client::{pda, Instruction, Pubkey};
+use rice_supply_gs1::epcis::{self, Products};
+use rice_supply_gs1::{DigitalLink, Gs1Error, Gtin};
+use rice_supply_svm::{Svm, LAMPORTS_PER_SOL};
+use serde_json::Value;
+
+const PADDY_GTIN: &str = "4800016644504";
+const RICE_GTIN: &str = "04800016644511";
+
+/// 2024-05-01T00:00:00Z.
+const HARVEST: i64 = 1_714_521_600;
+const DAY: i64 = 86_400;
+
+struct Chain {
+    svm: Svm,
+    user: Pubkey,
+    farmer: Pubkey,
+}
+
+impl Chain {
+    fn new() -> Self {
+        let mut svm = Svm::new();
+        let user = Pubkey::new_unique();
+        svm.airdrop(&user, 100 * LAMPORTS_PER_SOL);
+        let mut chain = Self {
+            svm,
+            user,
+            farmer: pda::chain_actor("farmer").0,
+        };
+        chain.send(instructions::create_chain_actor(
+            &user,
+            args::CreateChainActor {
+                actor_seed: "farmer".to_string(),
+                name: "Juan dela Cruz".to_string(),
+                actor_type: vec!["farmer".to_string()],
+                farm_id: None,
+                farmer_id: None,
+                assigned_tps: 0,
+                pin: "1234".to_string(),
+                organization: Organization::Farmer,
+                address: None,
+            },
+        ));
+        chain.send(instructions::initialize_config(&user));
+        chain.send(instructions::initialize_substance_registry(&user));
+        chain.send(instructions::initialize_emission_factors(&user));
+        chain
+    }
+
+    fn send(&mut self, instruction: Instruction) {
+        let user = self.user;
+        self.svm.send_instruction(instruction, &[user]).unwrap();
+    }
+
+    fn season(&mut self, season_seed: &str, farmer_id: Pubkey) {
+        let user = self.user;
+        self.send(instructions::create_production_season(
+            &user,
+            args::CreateProductionSeason {
+                season_seed: season_seed.to_string(),
+                farmer_id,
+                crop_year: "2024-DS".to_string(),
+                processed_yield_kg: 0,
+                variety: None,
+                planned_practice: None,
+                planting_date: None,
+                irrigation_practice: None,
+                fertilizer_used: None,
+                pesticide_used: None,
+                harvest_date: Some(HARVEST),
+                total_yield_kg: Some(1_200),
+                moisture_content: Some(14),
+                cultivated_area_m2: None,
+                organic_amendments: Vec::new(),
+            },
+        ));
+    }
+
+    fn transaction(&mut self, transaction_seed: &str, rice_batch_ids: Vec<Pubkey>) {
+        let user = self.user;
+        let farmer = self.farmer;
+        self.send(instructions::create_chain_transaction(
+            &user,
+            args::CreateChainTransaction {
+                transaction_seed: transaction_seed.to_string(),
+                from_actor_id: farmer,
+                to_actor_id: Pubkey::new_unique(),
+                rice_batch_ids,
+                amount: 125_000,
+                payment_method: PaymentMethod::BankTransfer,
+                payment_reference: None,
+                geotag: Some(GeoPoint {
+                    latitude_microdeg: 15_580_000,
+                    longitude_microdeg: 120_970_500,
+                    accuracy_m: Some(5),
+                    captured_at: HARVEST + 3 * DAY,
+                }),
+                quality: None,
+            },
+        ));
+    }
+
+    fn settle(&mut self, transaction_seed: &str, transaction_status: TransactionStatus) {
+        let user = self.user;
+        let farmer = self.farmer;
+        let transaction: ChainTransaction = self
+            .svm
+            .account(&pda::chain_transaction(transaction_seed).0)
+            .unwrap();
+        self.send(instructions::update_chain_transaction(
+            &user,
+            &farmer,
+            args::UpdateChainTransaction {
+                transaction_seed: transaction_seed.to_string(),
+                amount: None,
+                payment_method: None,
+                payment_reference: None,
+                geotag: None,
+                quality: None,
+                transaction_status: Some(transaction_status),
+            },
+            &transaction.rice_batch_ids,
+        ));
+    }
+
+    /// A season harvested on day 0, milled on day 1 and cut into `batch-1`
+    /// on day 2, which ships on day 3 and arrives on day 4. An unrelated
+    /// season and transaction sit alongside.
+    fn populated() -> Self {
+        Self::populated_with_qr_code("QR-batch-1")
+    }
+
+    fn populated_with_qr_code(qr_code: &str) -> Self {
+        let mut chain = Self::new();
+        let user = chain.user;
+        let farmer = chain.farmer;
+        chain.svm.warp_to_timestamp(HARVEST);
+        chain.season("season", farmer);
+        chain.season("other-season", Pubkey::new_unique());
+
+        chain.svm.warp_to_timestamp(HARVEST + DAY);
+        chain.send(instructions::create_milled_rice(
+            &user,
+            &pda::production_season("season").0,
+            &user,
+            args::CreateMilledRice {
+                milling_seed: "milling".to_string(),
+                farmer_id: farmer,
+                total_weight_kg: 650,
+                milling_type: MillingType::WellMilled,
+                quality: QualityGrade::Grade1,
+                photo_urls: Vec::new(),
+                moisture: 14,
+                total_weight_processed_kg: 1_000,
+                by_products: ByProducts {
+                    husk_kg: 200,
+                    bran_kg: 80,
+                    brokens_kg: 50,
+                    brewers_rice_kg: 20,
+                },
+            },
+        ));
+
+        chain.svm.warp_to_timestamp(HARVEST + 2 * DAY);
+        chain.send(instructions::create_rice_batch(
+            &user,
+            args::CreateRiceBatch {
+                batch_seed: "batch-1".to_string(),
+                milled_rice_id: pda::milled_rice("milling").0,
+                batch_status: BatchStatus::Created,
+                quality_score: 90,
+                weight_kg: 300,
+                qr_code: qr_code.to_string(),
+            },
+        ));
+
+        chain.svm.warp_to_timestamp(HARVEST + 3 * DAY);
+        chain.transaction("sale", vec![pda::rice_batch("batch-1").0]);
+        chain.transaction("other-sale", vec![Pubkey::new_unique()]);
+        chain.svm.warp_to_timestamp(HARVEST + 4 * DAY);
+        chain.settle("sale", TransactionStatus::Completed);
+        chain
+    }
+
+    fn lineage(&self) -> Lineage {
+        let svm = &self.svm;
+        let seasons = ["season", "other-season"].map(|seed| {
+            svm.account::<ProductionSeason>(&pda::production_season(seed).0)
+                .unwrap()
+        });
+        let transactions = ["sale", "other-sale"].map(|seed| {
+            svm.account::<ChainTransaction>(&pda::chain_transaction(seed).0)
+                .unwrap()
+        });
+        Lineage::new(
+            svm.account::<RiceBatch>(&pda::rice_batch("batch-1").0)
+                .unwrap(),
+            svm.account::<MilledRice>(&pda::milled_rice("milling").0)
+                .ok(),
+            seasons,
+            transactions,
+            Vec::new(),
+        )
+    }
+}
+
+fn products() -> Products {
+    Products {
+        paddy: PADDY_GTIN.parse().unwrap(),
+        milled_rice: RICE_GTIN.parse().unwrap(),
+    }
+}
+
+fn reference(address: Pubkey) -> String {
+    address.to_string().chars().take(20).collect()
+}
+
+#[test]
+fn gtin_is_normalized_to_fourteen_digits_and_check_digit_validated() {
+    let gtin: Gtin = PADDY_GTIN.parse().unwrap();
+    assert_eq!(gtin.to_string(), "04800016644504");
+    assert_eq!(
+        "96385074".parse::<Gtin>().unwrap().to_string(),
+        "00000096385074"
+    );
+    assert_eq!(
+        "4800016644505".parse::<Gtin>(),
+        Err(Gs1Error::GtinCheckDigit("4800016644505".to_string()))
+    );
+    assert!(matches!(
+        "48000166445".parse::<Gtin>(),
+        Err(Gs1Error::GtinFormat(_))
+    ));
+}
+
+#[test]
+fn lineage_keeps_only_the_batch_records() {
+    let lineage = Chain::populated().lineage();
+    assert_eq!(lineage.production_seasons.len(), 1);
+    assert_eq!(
+        lineage.production_seasons[0].public_key,
+        pda::production_season("season").0
+    );
+    assert_eq!(lineage.chain_transactions.len(), 1);
+    assert_eq!(
+        lineage.chain_transactions[0].public_key,
+        pda::chain_transaction("sale").0
+    );
+}
+
+#[test]
+fn batch_lineage_exports_as_an_epcis_document() {
+    let chain = Chain::populated();
+    let document = epcis::document(&chain.lineage(), &products(), HARVEST + 5 * DAY);
+    assert_eq!(document["type"], "EPCISDocument");
+    assert_eq!(document["schemaVersion"], "2.0");
+    assert_eq!(document["@context"][0], epcis::CONTEXT);
+    assert_eq!(document["creationDate"], "2024-05-06T00:00:00Z");
+
+    let events = document["epcisBody"]["eventList"].as_array().unwrap();
+    let steps: Vec<(&str, &str)> = events
+        .iter()
+        .map(|event| {
+            (
+                event["type"].as_str().unwrap(),
+                event["bizStep"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            ("ObjectEvent", "commissioning"),
+            ("TransformationEvent", "commissioning"),
+            ("AggregationEvent", "packing"),
+            ("ObjectEvent", "shipping"),
+            ("ObjectEvent", "receiving"),
+        ]
+    );
+
+    let paddy_lot = format!(
+        "https://id.gs1.org/01/04800016644504/10/{}",
+        reference(pda::production_season("season").0)
+    );
+    let rice_lot = format!(
+        "https://id.gs1.org/01/04800016644511/10/{}",
+        reference(pda::milled_rice("milling").0)
+    );
+    let batch_unit = format!(
+        "https://id.gs1.org/01/04800016644511/21/{}",
+        reference(pda::rice_batch("batch-1").0)
+    );
+
+    let harvest = &events[0];
+    assert_eq!(harvest["eventTime"], "2024-05-01T00:00:00Z");
+    assert_eq!(harvest["action"], "ADD");
+    assert_eq!(harvest["quantityList"][0]["epcClass"], paddy_lot.as_str());
+    assert_eq!(harvest["quantityList"][0]["quantity"], 1_200);
+    assert_eq!(harvest["quantityList"][0]["uom"], "KGM");
+    assert_eq!(harvest["ilmd"]["cbvmda:harvestEndDate"], "2024-05-01");
+
+    let milling = &events[1];
+    assert_eq!(
+        milling["inputQuantityList"][0]["epcClass"],
+        paddy_lot.as_str()
+    );
+    assert_eq!(milling["inputQuantityList"][0]["quantity"], 1_000);
+    assert_eq!(
+        milling["outputQuantityList"][0]["epcClass"],
+        rice_lot.as_str()
+    );
+    assert_eq!(milling["outputQuantityList"][0]["quantity"], 650);
+
+    let packing = &events[2];
+    assert_eq!(packing["parentID"], batch_unit.as_str());
+    assert_eq!(
+        packing["childQuantityList"][0]["epcClass"],
+        rice_lot.as_str()
+    );
+    assert_eq!(packing["childQuantityList"][0]["quantity"], 300);
+
+    let shipping = &events[3];
+    assert_eq!(shipping["eventTime"], "2024-05-04T00:00:00Z");
+    assert_eq!(shipping["epcList"], Value::from(vec![batch_unit.clone()]));
+    assert_eq!(shipping["disposition"], "in_transit");
+    assert_eq!(shipping["readPoint"]["id"], "geo:15.580000,120.970500");
+    assert_eq!(
+        shipping["sourceList"][0]["source"],
+        format!("urn:rice-supply:actor:{}", chain.farmer).as_str()
+    );
+
+    let receiving = &events[4];
+    assert_eq!(receiving["eventTime"], "2024-05-05T00:00:00Z");
+    assert_eq!(receiving["disposition"], "in_progress");
+    assert!(receiving.get("readPoint").is_none());
+}
+
+#[test]
+fn cancelled_transaction_voids_its_shipment() {
+    let mut chain = Chain::populated();
+    chain.transaction("cancelled-sale", vec![pda::rice_batch("batch-1").0]);
+    chain.settle("cancelled-sale", TransactionStatus::Cancelled);
+
+    let mut lineage = chain.lineage();
+    let cancelled = chain
+        .svm
+        .account::<ChainTransaction>(&pda::chain_transaction("cancelled-sale").0)
+        .unwrap();
+    lineage.chain_transactions.push(cancelled);
+
+    let events = epcis::events(&lineage, &products());
+    let last = events.last().unwrap();
+    assert_eq!(last["bizStep"], "void_shipping");
+    assert!(last.get("disposition").is_none());
+}
+
+#[test]
+fn batch_digital_link_names_the_packed_unit() {
+    let link = DigitalLink::new(RICE_GTIN.parse().unwrap(), "24DS-07", None).unwrap();
+    let chain = Chain::populated_with_qr_code(&link.uri("https://rice.example.ph"));
+    let events = epcis::events(&chain.lineage(), &products());
+
+    let batch_unit = format!(
+        "https://id.gs1.org/01/04800016644511/10/24DS-07/21/{}",
+        reference(pda::rice_batch("batch-1").0)
+    );
+    assert_eq!(events[2]["parentID"], batch_unit.as_str());
+    assert_eq!(events[3]["epcList"], Value::from(vec![batch_unit]));
+}
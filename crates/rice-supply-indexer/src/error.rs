@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Client(#[from] rice_supply_client::ClientError),
+    #[error("reading {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {message}", path.display())]
+    Snapshot { path: PathBuf, message: String },
+}
+
+impl IndexerError {
+    /// Whether retrying the sync later may succeed: the RPC node could not be
+    /// reached or answered with an error, or the database was busy.
+    pub fn is_transient(&self) -> bool {
+        use rice_supply_client::ClientError;
+        match self {
+            Self::Client(
+                ClientError::Transport(_) | ClientError::Rpc { .. } | ClientError::Response(_),
+            ) => true,
+            Self::Sqlite(error) => matches!(
+                error.sqlite_error_code(),
+                Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+            ),
+            _ => false,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, IndexerError>;
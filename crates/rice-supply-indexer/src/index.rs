@@ -0,0 +1,448 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rice_supply_client::accounts::{
+    ChainActor, ChainTransaction, MilledRice, ProductionSeason, RiceBatch, RiceSupplyAccount,
+};
+use rice_supply_client::json::ToJson;
+use rice_supply_client::rpc::AccountData;
+use rice_supply_client::Pubkey;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde_json::Value as Json;
+
+use crate::Result;
+
+/// The index schema; applied idempotently when an index is opened.
+pub const SCHEMA: &str = include_str!("schema.sql");
+
+/// A table holding one row per indexed account, keyed by `public_key`.
+struct Table {
+    account_type: &'static str,
+    name: &'static str,
+    /// `(table, column)` of child rows referencing the account.
+    children: &'static [(&'static str, &'static str)],
+}
+
+const TABLES: [Table; 5] = [
+    Table {
+        account_type: "ChainActor",
+        name: "chain_actors",
+        children: &[("chain_actor_types", "chain_actor")],
+    },
+    Table {
+        account_type: "ProductionSeason",
+        name: "production_seasons",
+        children: &[("production_season_amendments", "production_season")],
+    },
+    Table {
+        account_type: "MilledRice",
+        name: "milled_rice",
+        children: &[("milled_rice_photos", "milled_rice")],
+    },
+    Table {
+        account_type: "RiceBatch",
+        name: "rice_batches",
+        children: &[],
+    },
+    Table {
+        account_type: "ChainTransaction",
+        name: "chain_transactions",
+        children: &[("chain_transaction_batches", "chain_transaction")],
+    },
+];
+
+/// Counts from one [`Index::apply`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    /// Accounts with an indexed discriminator whose data did not decode.
+    pub undecodable: Vec<Pubkey>,
+}
+
+pub struct Index {
+    connection: Connection,
+}
+
+impl Index {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Index { connection })
+    }
+
+    /// The underlying connection, for queries.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Writes `accounts` to the index in one transaction. With `prune`,
+    /// `accounts` is taken as the complete program state and indexed
+    /// accounts missing from it are removed.
+    pub fn apply(
+        &mut self,
+        source: &str,
+        accounts: &[(Pubkey, AccountData)],
+        prune: bool,
+    ) -> Result<SyncReport> {
+        let indexed_at = unix_timestamp();
+        let mut report = SyncReport::default();
+        let mut seen = HashSet::new();
+        let transaction = self.connection.transaction()?;
+
+        for (address, account) in accounts {
+            let decoded = match RiceSupplyAccount::decode(&account.data) {
+                Some(Ok(decoded)) if is_indexed(&decoded) => decoded,
+                Some(Ok(_)) | None => continue,
+                Some(Err(_)) => {
+                    report.undecodable.push(*address);
+                    continue;
+                }
+            };
+            seen.insert(address.to_string());
+
+            let stored: Option<Vec<u8>> = transaction
+                .query_row(
+                    "SELECT data FROM accounts WHERE address = ?1",
+                    [address.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match stored {
+                Some(data) if data == account.data => {
+                    report.unchanged += 1;
+                    continue;
+                }
+                Some(_) => report.updated += 1,
+                None => report.inserted += 1,
+            }
+
+            transaction.execute(
+                "INSERT INTO accounts (address, account_type, lamports, data, indexed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (address) DO UPDATE SET
+                     account_type = excluded.account_type,
+                     lamports = excluded.lamports,
+                     data = excluded.data,
+                     indexed_at = excluded.indexed_at",
+                params![
+                    address.to_string(),
+                    decoded.name(),
+                    integer(account.lamports),
+                    account.data,
+                    indexed_at,
+                ],
+            )?;
+            remove_rows(&transaction, &address.to_string())?;
+            insert_rows(&transaction, &decoded)?;
+        }
+
+        if prune {
+            let stale: Vec<String> = {
+                let mut statement = transaction.prepare("SELECT address FROM accounts")?;
+                let addresses = statement
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                addresses
+                    .into_iter()
+                    .filter(|address| !seen.contains(address))
+                    .collect()
+            };
+            for address in &stale {
+                remove_rows(&transaction, address)?;
+                transaction.execute("DELETE FROM accounts WHERE address = ?1", [address])?;
+            }
+            report.removed = stale.len();
+        }
+
+        let total: i64 =
+            transaction.query_row("SELECT COUNT(*) FROM accounts", [], |row| row.get(0))?;
+        transaction.execute(
+            "INSERT INTO sync_state (id, source, synced_at, accounts) VALUES (1, ?1, ?2, ?3)
+             ON CONFLICT (id) DO UPDATE SET
+                 source = excluded.source,
+                 synced_at = excluded.synced_at,
+                 accounts = excluded.accounts",
+            params![source, indexed_at, total],
+        )?;
+        transaction.commit()?;
+        Ok(report)
+    }
+}
+
+fn is_indexed(account: &RiceSupplyAccount) -> bool {
+    TABLES
+        .iter()
+        .any(|table| table.account_type == account.name())
+}
+
+fn remove_rows(transaction: &Transaction, address: &str) -> Result<()> {
+    for table in TABLES {
+        for (child, column) in table.children {
+            transaction.execute(
+                &format!("DELETE FROM {child} WHERE {column} = ?1"),
+                [address],
+            )?;
+        }
+        transaction.execute(
+            &format!("DELETE FROM {} WHERE public_key = ?1", table.name),
+            [address],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_rows(transaction: &Transaction, account: &RiceSupplyAccount) -> Result<()> {
+    match account {
+        RiceSupplyAccount::ChainActor(chain_actor) => insert_chain_actor(transaction, chain_actor),
+        RiceSupplyAccount::ProductionSeason(production_season) => {
+            insert_production_season(transaction, production_season)
+        }
+        RiceSupplyAccount::MilledRice(milled_rice) => insert_milled_rice(transaction, milled_rice),
+        RiceSupplyAccount::RiceBatch(rice_batch) => insert_rice_batch(transaction, rice_batch),
+        RiceSupplyAccount::ChainTransaction(chain_transaction) => {
+            insert_chain_transaction(transaction, chain_transaction)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn insert_chain_actor(transaction: &Transaction, chain_actor: &ChainActor) -> Result<()> {
+    let public_key = chain_actor.public_key.to_string();
+    transaction.execute(
+        "INSERT INTO chain_actors (public_key, authority, name, organization, farm_id, farmer_id,
+             assigned_tps, is_active, balance, address, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            public_key,
+            chain_actor.authority.to_string(),
+            chain_actor.name,
+            label(&chain_actor.organization),
+            chain_actor.farm_id.map(|farm_id| farm_id.to_string()),
+            chain_actor.farmer_id.map(|farmer_id| farmer_id.to_string()),
+            integer(chain_actor.assigned_tps),
+            chain_actor.is_active,
+            integer(chain_actor.balance),
+            chain_actor.address,
+            chain_actor.created_at,
+            chain_actor.updated_at,
+        ],
+    )?;
+    for (position, actor_type) in chain_actor.actor_type.iter().enumerate() {
+        transaction.execute(
+            "INSERT INTO chain_actor_types (chain_actor, position, actor_type) VALUES (?1, ?2, ?3)",
+            params![public_key, position, actor_type],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_production_season(
+    transaction: &Transaction,
+    production_season: &ProductionSeason,
+) -> Result<()> {
+    let public_key = production_season.public_key.to_string();
+    let carbon_credits = production_season.carbon_credits.as_ref();
+    transaction.execute(
+        "INSERT INTO production_seasons (public_key, farmer_id, crop_year, processed_yield_kg,
+             variety, planned_practice, planting_date, irrigation_practice, fertilizer_used,
+             pesticide_used, harvest_date, total_yield_kg, moisture_content, validation_status,
+             validator_id, compliance_status, nitrogen_applied_g_per_ha, pre_harvest_clear_at,
+             post_harvest_loss_kg, milled_paddy_kg, cultivated_area_m2, methane_estimate_g,
+             carbon_credit_mint, avoided_methane_g, credits_issued_kg_co2e,
+             credits_retired_kg_co2e, credits_issued_at, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+             ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29)",
+        params![
+            public_key,
+            production_season.farmer_id.to_string(),
+            production_season.crop_year,
+            integer(production_season.processed_yield_kg),
+            production_season.variety.as_ref().map(label),
+            production_season.planned_practice,
+            production_season.planting_date,
+            production_season.irrigation_practice.as_ref().map(label),
+            production_season.fertilizer_used,
+            production_season.pesticide_used,
+            production_season.harvest_date,
+            production_season.total_yield_kg.map(integer),
+            production_season.moisture_content,
+            label(&production_season.validation_status),
+            production_season
+                .validator_id
+                .map(|validator_id| validator_id.to_string()),
+            label(&production_season.compliance_status),
+            integer(production_season.nitrogen_applied_g_per_ha),
+            production_season.pre_harvest_clear_at,
+            integer(production_season.post_harvest_loss_kg),
+            integer(production_season.milled_paddy_kg),
+            production_season.cultivated_area_m2.map(integer),
+            production_season.methane_estimate_g.map(integer),
+            carbon_credits.map(|carbon_credits| carbon_credits.mint.to_string()),
+            carbon_credits.map(|carbon_credits| integer(carbon_credits.avoided_methane_g)),
+            carbon_credits.map(|carbon_credits| integer(carbon_credits.issued_kg_co2e)),
+            carbon_credits.map(|carbon_credits| integer(carbon_credits.retired_kg_co2e)),
+            carbon_credits.map(|carbon_credits| carbon_credits.issued_at),
+            production_season.created_at,
+            production_season.updated_at,
+        ],
+    )?;
+    for (position, amendment) in production_season.organic_amendments.iter().enumerate() {
+        transaction.execute(
+            "INSERT INTO production_season_amendments (production_season, position, kind,
+                 rate_kg_per_ha)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                public_key,
+                position,
+                label(&amendment.kind),
+                amendment.rate_kg_per_ha
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_milled_rice(transaction: &Transaction, milled_rice: &MilledRice) -> Result<()> {
+    let public_key = milled_rice.public_key.to_string();
+    let by_products = &milled_rice.by_products;
+    transaction.execute(
+        "INSERT INTO milled_rice (public_key, farmer_id, production_season, creator,
+             total_weight_kg, allocated_kg, milling_type, quality, moisture,
+             total_weight_processed_kg, husk_kg, bran_kg, brokens_kg, brewers_rice_kg,
+             milling_recovery_bp, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            public_key,
+            milled_rice.farmer_id.to_string(),
+            milled_rice.production_season.to_string(),
+            milled_rice.creator.to_string(),
+            milled_rice.total_weight_kg,
+            milled_rice.allocated_kg,
+            label(&milled_rice.milling_type),
+            label(&milled_rice.quality),
+            milled_rice.moisture,
+            milled_rice.total_weight_processed_kg,
+            by_products.husk_kg,
+            by_products.bran_kg,
+            by_products.brokens_kg,
+            by_products.brewers_rice_kg,
+            milled_rice.milling_recovery_bp,
+            milled_rice.created_at,
+            milled_rice.updated_at,
+        ],
+    )?;
+    for (position, url) in milled_rice.photo_urls.iter().enumerate() {
+        transaction.execute(
+            "INSERT INTO milled_rice_photos (milled_rice, position, url) VALUES (?1, ?2, ?3)",
+            params![public_key, position, url],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_rice_batch(transaction: &Transaction, rice_batch: &RiceBatch) -> Result<()> {
+    let gs1 = rice_batch.gs1.as_ref();
+    transaction.execute(
+        "INSERT INTO rice_batches (public_key, milled_rice_id, batch_status, quality_score,
+             weight_kg, qr_code, gtin, lot, serial, effective_grade, latest_quality_test,
+             current_warehouse, current_holder, creator, token_mint, storage_count,
+             remaining_weight_g, next_pack_serial, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+             ?18, ?19, ?20)",
+        params![
+            rice_batch.public_key.to_string(),
+            rice_batch.milled_rice_id.to_string(),
+            label(&rice_batch.batch_status),
+            rice_batch.quality_score,
+            rice_batch.weight_kg,
+            rice_batch.qr_code,
+            gs1.map(|gs1| format!("{:014}", gs1.gtin)),
+            gs1.map(|gs1| gs1.lot.as_str()),
+            gs1.and_then(|gs1| gs1.serial.as_deref()),
+            rice_batch.effective_grade.as_ref().map(label),
+            rice_batch.latest_quality_test.map(|test| test.to_string()),
+            rice_batch
+                .current_warehouse
+                .map(|warehouse| warehouse.to_string()),
+            rice_batch.current_holder.map(|holder| holder.to_string()),
+            rice_batch.creator.to_string(),
+            rice_batch.token_mint.map(|mint| mint.to_string()),
+            rice_batch.storage_count,
+            integer(rice_batch.remaining_weight_g),
+            integer(rice_batch.next_pack_serial),
+            rice_batch.created_at,
+            rice_batch.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_chain_transaction(
+    transaction: &Transaction,
+    chain_transaction: &ChainTransaction,
+) -> Result<()> {
+    let public_key = chain_transaction.public_key.to_string();
+    let geotag = chain_transaction.geotag.as_ref();
+    transaction.execute(
+        "INSERT INTO chain_transactions (public_key, from_actor_id, to_actor_id, amount,
+             payment_method, payment_reference, latitude_microdeg, longitude_microdeg, accuracy_m,
+             captured_at, quality, transaction_status, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            public_key,
+            chain_transaction.from_actor_id.to_string(),
+            chain_transaction.to_actor_id.to_string(),
+            integer(chain_transaction.amount),
+            label(&chain_transaction.payment_method),
+            chain_transaction.payment_reference,
+            geotag.map(|geotag| geotag.latitude_microdeg),
+            geotag.map(|geotag| geotag.longitude_microdeg),
+            geotag.and_then(|geotag| geotag.accuracy_m),
+            geotag.map(|geotag| geotag.captured_at),
+            chain_transaction.quality.as_ref().map(label),
+            label(&chain_transaction.transaction_status),
+            chain_transaction.created_at,
+            chain_transaction.updated_at,
+        ],
+    )?;
+    for (position, rice_batch) in chain_transaction.rice_batch_ids.iter().enumerate() {
+        transaction.execute(
+            "INSERT INTO chain_transaction_batches (chain_transaction, position, rice_batch)
+             VALUES (?1, ?2, ?3)",
+            params![public_key, position, rice_batch.to_string()],
+        )?;
+    }
+    Ok(())
+}
+
+/// An enum's variant name, as rendered in JSON dumps.
+fn label(value: &impl ToJson) -> String {
+    match value.to_json() {
+        Json::String(label) => label,
+        other => other.to_string(),
+    }
+}
+
+/// SQLite integers are signed; the rare `u64` above `i64::MAX` is stored as
+/// decimal text rather than rejected.
+fn integer(value: u64) -> Value {
+    i64::try_from(value).map_or_else(|_| Value::Text(value.to_string()), Value::Integer)
+}
+
+fn unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
@@ -0,0 +1,18 @@
+//! SQLite index of `rice_supply_chain` accounts.
+//!
+//! - [`source`] reads program accounts from a validator's RPC or from
+//!   account dump files.
+//! - [`Index`] decodes chain actors, production seasons, milled rice, rice
+//!   batches and chain transactions into the tables in `schema.sql`,
+//!   skipping accounts whose data has not changed since the last sync.
+//!
+//! Other account types are ignored. Actor PINs are not copied into the
+//! index.
+
+mod error;
+mod index;
+pub mod source;
+
+pub use error::{IndexerError, Result};
+pub use index::{Index, SyncReport, SCHEMA};
+pub use rice_supply_client::rpc::AccountData;
@@ -0,0 +1,114 @@
+//! `rice-supply-indexer`: mirror rice-supply-chain accounts into SQLite.
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use rice_supply_client::rpc::{RpcClient, LOCALNET_URL};
+use rice_supply_indexer::{source, Index, IndexerError, SyncReport};
+
+/// Longest wait between retries of a failing sync while polling.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+#[derive(Parser)]
+#[command(name = "rice-supply-indexer", version, about)]
+struct Cli {
+    /// SQLite database file, created if missing
+    #[arg(
+        long,
+        global = true,
+        env = "RICE_SUPPLY_INDEX",
+        default_value = "rice-supply.db"
+    )]
+    db: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index the program's accounts from a validator
+    Rpc {
+        #[arg(long, short = 'u', default_value = LOCALNET_URL)]
+        url: String,
+        /// Keep syncing every SECONDS instead of exiting after one pass.
+        /// Failed passes are retried with exponential backoff.
+        #[arg(long, value_name = "SECONDS")]
+        poll: Option<u64>,
+    },
+    /// Index accounts from `solana account --output json` dumps
+    Import {
+        /// Dump files, or directories of `.json` dumps
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut index =
+        Index::open(&cli.db).with_context(|| format!("opening {}", cli.db.display()))?;
+
+    match cli.command {
+        Command::Rpc { url, poll } => {
+            let rpc = RpcClient::new(&url);
+            let Some(seconds) = poll else {
+                return sync(&rpc, &url, &mut index).with_context(|| format!("syncing from {url}"));
+            };
+            let interval = Duration::from_secs(seconds);
+            let mut failures = 0;
+            loop {
+                let delay = match sync(&rpc, &url, &mut index) {
+                    Ok(()) => {
+                        failures = 0;
+                        interval
+                    }
+                    Err(error) if error.is_transient() => {
+                        failures += 1;
+                        let delay = backoff(interval, failures);
+                        eprintln!(
+                            "warning: syncing from {url} failed: {error}; retrying in {}s",
+                            delay.as_secs()
+                        );
+                        delay
+                    }
+                    Err(error) => return Err(error).with_context(|| format!("syncing from {url}")),
+                };
+                thread::sleep(delay);
+            }
+        }
+        Command::Import { paths } => {
+            for path in paths {
+                let accounts = source::read_dumps(&path)?;
+                let source = path.display().to_string();
+                print_report(&source, &index.apply(&source, &accounts, false)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sync(rpc: &RpcClient, url: &str, index: &mut Index) -> Result<(), IndexerError> {
+    let accounts = source::fetch(rpc)?;
+    print_report(url, &index.apply(url, &accounts, true)?);
+    Ok(())
+}
+
+/// Doubles the poll interval for each consecutive failure, up to
+/// `MAX_BACKOFF`, or the interval itself when that is longer.
+fn backoff(interval: Duration, failures: u32) -> Duration {
+    let delay = interval.saturating_mul(1 << failures.min(16));
+    delay.min(MAX_BACKOFF.max(interval))
+}
+
+fn print_report(source: &str, report: &SyncReport) {
+    println!(
+        "{source}: {} inserted, {} updated, {} unchanged, {} removed",
+        report.inserted, report.updated, report.unchanged, report.removed
+    );
+    for address in &report.undecodable {
+        eprintln!("warning: {address} has an indexed discriminator but did not decode");
+    }
+}
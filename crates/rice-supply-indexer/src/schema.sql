@@ -0,0 +1,175 @@
+-- Normalized tables for the five core account types. Foreign keys document
+-- the relationships for joins but are not enforced: on-chain references may
+-- name wallets or accounts that were never indexed. Bundled SQLite turns
+-- enforcement on by default, so switch it off for this connection.
+PRAGMA foreign_keys = OFF;
+
+-- Raw data of every indexed account, used to skip unchanged accounts.
+CREATE TABLE IF NOT EXISTS accounts (
+    address      TEXT PRIMARY KEY,
+    account_type TEXT NOT NULL,
+    lamports     INTEGER NOT NULL,
+    data         BLOB NOT NULL,
+    indexed_at   INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS chain_actors (
+    public_key   TEXT PRIMARY KEY,
+    authority    TEXT NOT NULL,
+    name         TEXT NOT NULL,
+    organization TEXT NOT NULL,
+    farm_id      TEXT,
+    farmer_id    TEXT,
+    assigned_tps INTEGER NOT NULL,
+    is_active    INTEGER NOT NULL,
+    balance      INTEGER NOT NULL,
+    address      TEXT,
+    created_at   INTEGER NOT NULL,
+    updated_at   INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS chain_actor_types (
+    chain_actor TEXT NOT NULL REFERENCES chain_actors (public_key),
+    position    INTEGER NOT NULL,
+    actor_type  TEXT NOT NULL,
+    PRIMARY KEY (chain_actor, position)
+);
+
+CREATE TABLE IF NOT EXISTS production_seasons (
+    public_key                TEXT PRIMARY KEY,
+    farmer_id                 TEXT NOT NULL REFERENCES chain_actors (public_key),
+    crop_year                 TEXT NOT NULL,
+    processed_yield_kg        INTEGER NOT NULL,
+    variety                   TEXT,
+    planned_practice          TEXT,
+    planting_date             INTEGER,
+    irrigation_practice       TEXT,
+    fertilizer_used           TEXT,
+    pesticide_used            TEXT,
+    harvest_date              INTEGER,
+    total_yield_kg            INTEGER,
+    moisture_content          INTEGER,
+    validation_status         TEXT NOT NULL,
+    validator_id              TEXT REFERENCES chain_actors (public_key),
+    compliance_status         TEXT NOT NULL,
+    nitrogen_applied_g_per_ha INTEGER NOT NULL,
+    pre_harvest_clear_at      INTEGER,
+    post_harvest_loss_kg      INTEGER NOT NULL,
+    milled_paddy_kg           INTEGER NOT NULL,
+    cultivated_area_m2        INTEGER,
+    methane_estimate_g        INTEGER,
+    -- Carbon credits, once issued.
+    carbon_credit_mint        TEXT,
+    avoided_methane_g         INTEGER,
+    credits_issued_kg_co2e    INTEGER,
+    credits_retired_kg_co2e   INTEGER,
+    credits_issued_at         INTEGER,
+    created_at                INTEGER NOT NULL,
+    updated_at                INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS production_seasons_farmer_id ON production_seasons (farmer_id);
+
+CREATE TABLE IF NOT EXISTS production_season_amendments (
+    production_season TEXT NOT NULL REFERENCES production_seasons (public_key),
+    position          INTEGER NOT NULL,
+    kind              TEXT NOT NULL,
+    rate_kg_per_ha    INTEGER NOT NULL,
+    PRIMARY KEY (production_season, position)
+);
+
+CREATE TABLE IF NOT EXISTS milled_rice (
+    public_key                TEXT PRIMARY KEY,
+    farmer_id                 TEXT NOT NULL REFERENCES chain_actors (public_key),
+    production_season         TEXT NOT NULL REFERENCES production_seasons (public_key),
+    creator                   TEXT NOT NULL,
+    total_weight_kg           INTEGER NOT NULL,
+    allocated_kg              INTEGER NOT NULL,
+    milling_type              TEXT NOT NULL,
+    quality                   TEXT NOT NULL,
+    moisture                  INTEGER NOT NULL,
+    total_weight_processed_kg INTEGER NOT NULL,
+    husk_kg                   INTEGER NOT NULL,
+    bran_kg                   INTEGER NOT NULL,
+    brokens_kg                INTEGER NOT NULL,
+    brewers_rice_kg           INTEGER NOT NULL,
+    milling_recovery_bp       INTEGER NOT NULL,
+    created_at                INTEGER NOT NULL,
+    updated_at                INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS milled_rice_farmer_id ON milled_rice (farmer_id);
+
+CREATE TABLE IF NOT EXISTS milled_rice_photos (
+    milled_rice TEXT NOT NULL REFERENCES milled_rice (public_key),
+    position    INTEGER NOT NULL,
+    url         TEXT NOT NULL,
+    PRIMARY KEY (milled_rice, position)
+);
+
+CREATE TABLE IF NOT EXISTS rice_batches (
+    public_key          TEXT PRIMARY KEY,
+    milled_rice_id      TEXT NOT NULL REFERENCES milled_rice (public_key),
+    batch_status        TEXT NOT NULL,
+    quality_score       INTEGER NOT NULL,
+    weight_kg           INTEGER NOT NULL,
+    qr_code             TEXT NOT NULL,
+    -- GS1 keys, when qr_code is a GS1 Digital Link; gtin is 14 digits.
+    gtin                TEXT,
+    lot                 TEXT,
+    serial              TEXT,
+    effective_grade     TEXT,
+    latest_quality_test TEXT,
+    current_warehouse   TEXT,
+    current_holder      TEXT REFERENCES chain_actors (public_key),
+    -- Wallet that created the batch.
+    creator             TEXT NOT NULL,
+    token_mint          TEXT,
+    storage_count       INTEGER NOT NULL,
+    remaining_weight_g  INTEGER NOT NULL,
+    next_pack_serial    INTEGER NOT NULL,
+    created_at          INTEGER NOT NULL,
+    updated_at          INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS rice_batches_milled_rice_id ON rice_batches (milled_rice_id);
+CREATE INDEX IF NOT EXISTS rice_batches_qr_code ON rice_batches (qr_code);
+CREATE INDEX IF NOT EXISTS rice_batches_gtin_lot ON rice_batches (gtin, lot);
+CREATE INDEX IF NOT EXISTS rice_batches_current_holder ON rice_batches (current_holder);
+
+CREATE TABLE IF NOT EXISTS chain_transactions (
+    public_key         TEXT PRIMARY KEY,
+    from_actor_id      TEXT NOT NULL REFERENCES chain_actors (public_key),
+    to_actor_id        TEXT NOT NULL REFERENCES chain_actors (public_key),
+    amount             INTEGER NOT NULL,
+    payment_method     TEXT NOT NULL,
+    payment_reference  TEXT,
+    latitude_microdeg  INTEGER,
+    longitude_microdeg INTEGER,
+    accuracy_m         INTEGER,
+    captured_at        INTEGER,
+    quality            TEXT,
+    transaction_status TEXT NOT NULL,
+    created_at         INTEGER NOT NULL,
+    updated_at         INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS chain_transactions_from_actor_id ON chain_transactions (from_actor_id);
+CREATE INDEX IF NOT EXISTS chain_transactions_to_actor_id ON chain_transactions (to_actor_id);
+
+CREATE TABLE IF NOT EXISTS chain_transaction_batches (
+    chain_transaction TEXT NOT NULL REFERENCES chain_transactions (public_key),
+    position          INTEGER NOT NULL,
+    rice_batch        TEXT NOT NULL REFERENCES rice_batches (public_key),
+    PRIMARY KEY (chain_transaction, position)
+);
+CREATE INDEX IF NOT EXISTS chain_transaction_batches_rice_batch ON chain_transaction_batches (rice_batch);
+
+-- Batches with the farmer whose milled rice they were cut from.
+CREATE VIEW IF NOT EXISTS farmer_batches AS
+SELECT milled_rice.farmer_id, rice_batches.*
+FROM rice_batches
+JOIN milled_rice ON milled_rice.public_key = rice_batches.milled_rice_id;
+
+CREATE TABLE IF NOT EXISTS sync_state (
+    id        INTEGER PRIMARY KEY CHECK (id = 1),
+    source    TEXT NOT NULL,
+    synced_at INTEGER NOT NULL,
+    accounts  INTEGER NOT NULL
+);
@@ -0,0 +1,135 @@
+//! Where program accounts come from.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rice_supply_client::accounts::{
+    discriminator, ChainActor, ChainTransaction, MilledRice, ProductionSeason, RiceBatch,
+};
+use rice_supply_client::rpc::{AccountData, RpcClient};
+use rice_supply_client::Pubkey;
+use serde::Deserialize;
+
+use crate::{IndexerError, Result};
+
+/// Fetches every indexed account type with one `getProgramAccounts` call
+/// per discriminator.
+pub fn fetch(rpc: &RpcClient) -> Result<Vec<(Pubkey, AccountData)>> {
+    let mut accounts = Vec::new();
+    for discriminator in [
+        discriminator::<ChainActor>(),
+        discriminator::<ProductionSeason>(),
+        discriminator::<MilledRice>(),
+        discriminator::<RiceBatch>(),
+        discriminator::<ChainTransaction>(),
+    ] {
+        accounts.extend(rpc.get_program_accounts(&rice_supply_chain::ID, discriminator)?);
+    }
+    Ok(accounts)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DumpFile {
+    One(KeyedAccount),
+    Many(Vec<KeyedAccount>),
+}
+
+/// The `{ "pubkey": ..., "account": ... }` shape written by
+/// `solana account --output json` and returned by `getProgramAccounts`.
+#[derive(Deserialize)]
+struct KeyedAccount {
+    pubkey: String,
+    account: DumpedAccount,
+}
+
+#[derive(Deserialize)]
+struct DumpedAccount {
+    lamports: u64,
+    data: (String, String),
+    owner: String,
+    #[serde(default)]
+    executable: bool,
+}
+
+/// Reads program-owned accounts from JSON account dumps. `path` is a dump
+/// file, holding one keyed account or an array of them, or a directory of
+/// `.json` dump files.
+pub fn read_dumps(path: &Path) -> Result<Vec<(Pubkey, AccountData)>> {
+    let io_error = |source| IndexerError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    if !path.is_dir() {
+        return read_dump_file(path);
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()
+        .map_err(io_error)?;
+    files.retain(|file| {
+        file.extension()
+            .is_some_and(|extension| extension == "json")
+    });
+    files.sort();
+
+    let mut accounts = Vec::new();
+    for file in files {
+        accounts.extend(read_dump_file(&file)?);
+    }
+    Ok(accounts)
+}
+
+fn read_dump_file(path: &Path) -> Result<Vec<(Pubkey, AccountData)>> {
+    let snapshot_error = |message: String| IndexerError::Snapshot {
+        path: path.to_path_buf(),
+        message,
+    };
+    let contents = fs::read(path).map_err(|source| IndexerError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let keyed = match serde_json::from_slice(&contents)
+        .map_err(|error| snapshot_error(error.to_string()))?
+    {
+        DumpFile::One(keyed) => vec![keyed],
+        DumpFile::Many(keyed) => keyed,
+    };
+
+    let mut accounts = Vec::new();
+    for KeyedAccount { pubkey, account } in keyed {
+        let address: Pubkey = pubkey
+            .parse()
+            .map_err(|_| snapshot_error(format!("invalid pubkey {pubkey}")))?;
+        let owner: Pubkey = account
+            .owner
+            .parse()
+            .map_err(|_| snapshot_error(format!("invalid owner of {pubkey}")))?;
+        if owner != rice_supply_chain::ID {
+            continue;
+        }
+        let (data, encoding) = account.data;
+        if encoding != "base64" {
+            return Err(snapshot_error(format!(
+                "account {pubkey} uses {encoding} encoding; dump with base64"
+            )));
+        }
+        let data = STANDARD
+            .decode(data)
+            .map_err(|error| snapshot_error(format!("account {pubkey}: {error}")))?;
+        accounts.push((
+            address,
+            AccountData {
+                lamports: account.lamports,
+                owner,
+                data,
+                executable: account.executable,
+            },
+        ));
+    }
+    Ok(accounts)
+}
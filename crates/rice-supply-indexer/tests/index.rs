@@ -0,0 +1,269 @@
+use rice_supply_chain::{
+    BatchStatus, ByProducts, MillingType, Organization, PaymentMethod, QualityGrade,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::{pda, ClientError, Instruction, Pubkey};
+use rice_supply_indexer::{AccountData, Index, IndexerError};
+use rice_supply_svm::{Svm, LAMPORTS_PER_SOL};
+
+struct Chain {
+    svm: Svm,
+    user: Pubkey,
+}
+
+impl Chain {
+    fn new() -> Self {
+        let mut svm = Svm::new();
+        let user = Pubkey::new_unique();
+        svm.airdrop(&user, 100 * LAMPORTS_PER_SOL);
+        Self { svm, user }
+    }
+
+    fn send(&mut self, instruction: Instruction) {
+        let user = self.user;
+        self.svm.send_instruction(instruction, &[user]).unwrap();
+    }
+
+    fn program_accounts(&self) -> Vec<(Pubkey, AccountData)> {
+        self.svm
+            .accounts()
+            .filter(|(_, account)| account.owner == rice_supply_chain::ID)
+            .map(|(address, account)| {
+                (
+                    *address,
+                    AccountData {
+                        lamports: account.lamports,
+                        owner: account.owner,
+                        data: account.data.clone(),
+                        executable: account.executable,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// A farmer with one season milled into a record cut into two batches,
+    /// one of which is sold in a chain transaction.
+    fn populated() -> Self {
+        let mut chain = Self::new();
+        let user = chain.user;
+        chain.send(instructions::create_chain_actor(
+            &user,
+            args::CreateChainActor {
+                actor_seed: "farmer".to_string(),
+                name: "Juan dela Cruz".to_string(),
+                actor_type: vec!["farmer".to_string(), "seller".to_string()],
+                farm_id: None,
+                farmer_id: None,
+                assigned_tps: 0,
+                pin: "1234".to_string(),
+                organization: Organization::Farmer,
+                address: Some("Nueva Ecija".to_string()),
+            },
+        ));
+        let farmer = pda::chain_actor("farmer").0;
+        chain.send(instructions::initialize_config(&user));
+        chain.send(instructions::initialize_substance_registry(&user));
+        chain.send(instructions::initialize_emission_factors(&user));
+        chain.send(instructions::create_production_season(
+            &user,
+            args::CreateProductionSeason {
+                season_seed: "season".to_string(),
+                farmer_id: farmer,
+                crop_year: "2024-DS".to_string(),
+                processed_yield_kg: 0,
+                variety: None,
+                planned_practice: None,
+                planting_date: None,
+                irrigation_practice: None,
+                fertilizer_used: None,
+                pesticide_used: None,
+                harvest_date: None,
+                total_yield_kg: Some(1_200),
+                moisture_content: None,
+                cultivated_area_m2: None,
+                organic_amendments: Vec::new(),
+            },
+        ));
+        chain.send(instructions::create_milled_rice(
+            &user,
+            &pda::production_season("season").0,
+            &user,
+            args::CreateMilledRice {
+                milling_seed: "milling".to_string(),
+                farmer_id: farmer,
+                total_weight_kg: 650,
+                milling_type: MillingType::WellMilled,
+                quality: QualityGrade::Grade1,
+                photo_urls: vec!["https://example.com/milling.jpg".to_string()],
+                moisture: 14,
+                total_weight_processed_kg: 1_000,
+                by_products: ByProducts {
+                    husk_kg: 200,
+                    bran_kg: 80,
+                    brokens_kg: 50,
+                    brewers_rice_kg: 20,
+                },
+            },
+        ));
+        for batch_seed in ["batch-1", "batch-2"] {
+            chain.send(instructions::create_rice_batch(
+                &user,
+                args::CreateRiceBatch {
+                    batch_seed: batch_seed.to_string(),
+                    milled_rice_id: pda::milled_rice("milling").0,
+                    batch_status: BatchStatus::Created,
+                    quality_score: 90,
+                    weight_kg: 300,
+                    qr_code: format!("QR-{batch_seed}"),
+                },
+            ));
+        }
+        chain.send(instructions::create_chain_transaction(
+            &user,
+            args::CreateChainTransaction {
+                transaction_seed: "sale".to_string(),
+                from_actor_id: farmer,
+                to_actor_id: Pubkey::new_unique(),
+                rice_batch_ids: vec![pda::rice_batch("batch-1").0],
+                amount: 125_000,
+                payment_method: PaymentMethod::Cash,
+                payment_reference: None,
+                geotag: None,
+                quality: Some(QualityGrade::Grade1),
+            },
+        ));
+        chain
+    }
+}
+
+#[test]
+fn indexes_batches_by_farmer() {
+    let chain = Chain::populated();
+    let mut index = Index::open_in_memory().unwrap();
+    let report = index.apply("svm", &chain.program_accounts(), true).unwrap();
+    assert_eq!(report.inserted, 6);
+    assert!(report.undecodable.is_empty());
+
+    let farmer = pda::chain_actor("farmer").0.to_string();
+    let connection = index.connection();
+    let batches: Vec<(String, String)> = connection
+        .prepare("SELECT qr_code, batch_status FROM farmer_batches WHERE farmer_id = ?1 ORDER BY qr_code")
+        .unwrap()
+        .query_map([&farmer], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        batches,
+        vec![
+            ("QR-batch-1".to_string(), "Created".to_string()),
+            ("QR-batch-2".to_string(), "Created".to_string()),
+        ]
+    );
+
+    let sold: String = connection
+        .query_row(
+            "SELECT rice_batches.qr_code
+             FROM chain_transactions
+             JOIN chain_transaction_batches
+                 ON chain_transaction_batches.chain_transaction = chain_transactions.public_key
+             JOIN rice_batches ON rice_batches.public_key = chain_transaction_batches.rice_batch
+             WHERE chain_transactions.from_actor_id = ?1",
+            [&farmer],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(sold, "QR-batch-1");
+
+    let actor_types: i64 = connection
+        .query_row(
+            "SELECT COUNT(*) FROM chain_actor_types WHERE chain_actor = ?1",
+            [&farmer],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(actor_types, 2);
+}
+
+#[test]
+fn resync_skips_unchanged_accounts_and_replaces_updated_ones() {
+    let mut chain = Chain::populated();
+    let mut index = Index::open_in_memory().unwrap();
+    index.apply("svm", &chain.program_accounts(), true).unwrap();
+
+    let user = chain.user;
+    chain.send(instructions::update_rice_batch(
+        &user,
+        None,
+        &pda::milled_rice("milling").0,
+        args::UpdateRiceBatch {
+            batch_seed: "batch-2".to_string(),
+            batch_status: Some(BatchStatus::InTransit),
+            quality_score: None,
+            weight_kg: None,
+        },
+    ));
+    let report = index.apply("svm", &chain.program_accounts(), true).unwrap();
+    assert_eq!(
+        (report.inserted, report.updated, report.unchanged),
+        (0, 1, 5)
+    );
+
+    let status: String = index
+        .connection()
+        .query_row(
+            "SELECT batch_status FROM rice_batches WHERE qr_code = 'QR-batch-2'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(status, "InTransit");
+}
+
+#[test]
+fn prune_removes_closed_accounts_and_their_child_rows() {
+    let chain = Chain::populated();
+    let mut index = Index::open_in_memory().unwrap();
+    index.apply("svm", &chain.program_accounts(), true).unwrap();
+
+    let transaction = pda::chain_transaction("sale").0;
+    let mut accounts = chain.program_accounts();
+    accounts.retain(|(address, _)| *address != transaction);
+
+    let report = index.apply("import", &accounts, false).unwrap();
+    assert_eq!((report.unchanged, report.removed), (5, 0));
+
+    let report = index.apply("svm", &accounts, true).unwrap();
+    assert_eq!(report.removed, 1);
+    let (transactions, links): (i64, i64) = index
+        .connection()
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM chain_transactions),
+                    (SELECT COUNT(*) FROM chain_transaction_batches)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!((transactions, links), (0, 0));
+}
+
+#[test]
+fn rpc_failures_and_a_busy_database_are_transient() {
+    let rpc = IndexerError::Client(ClientError::Rpc {
+        code: -32005,
+        message: "Node is behind".to_string(),
+        logs: Vec::new(),
+    });
+    assert!(rpc.is_transient());
+    let busy = IndexerError::Sqlite(rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+        None,
+    ));
+    assert!(busy.is_transient());
+
+    let wrong_owner = IndexerError::Client(ClientError::WrongOwner(Pubkey::new_unique()));
+    assert!(!wrong_owner.is_transient());
+    let schema = IndexerError::Sqlite(rusqlite::Error::InvalidColumnName("x".to_string()));
+    assert!(!schema.is_transient());
+}
@@ -0,0 +1,246 @@
+//! Static HTML rendering of a [`Report`]: one self-contained page with inline
+//! styles and no scripts, so it can be hosted anywhere or printed.
+
+use std::fmt::Write;
+
+use rice_supply_chain::{GeoPoint, ValidationStatus};
+use rice_supply_client::json::{timestamp, ToJson};
+use rice_supply_client::Pubkey;
+
+use crate::{degrees, Report};
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:60rem;margin:2rem auto;padding:0 1rem;color:#1f2933}\
+h1{margin-bottom:.25rem}h2{border-bottom:1px solid #cbd2d9;padding-bottom:.25rem;margin-top:2rem}\
+table{border-collapse:collapse;width:100%}th,td{text-align:left;padding:.4rem .6rem;border-bottom:1px solid #e4e7eb}\
+dl{display:grid;grid-template-columns:max-content auto;gap:.25rem 1rem}dt{font-weight:600}dd{margin:0}\
+code{font-size:.85em;word-break:break-all}.status{display:inline-block;padding:.2rem .6rem;border-radius:1rem;font-weight:600}\
+.approved{background:#d9f99d}.pending{background:#fef08a}.rejected{background:#fecaca}.unverified{background:#e4e7eb}\
+footer{margin-top:2rem;font-size:.85em;color:#616e7c}";
+
+impl Report {
+    /// The report as a standalone HTML page, stamped with the unix time
+    /// `generated_at`.
+    pub fn to_html(&self, generated_at: i64) -> String {
+        let rice_batch = &self.lineage.rice_batch;
+        let mut page = String::new();
+        let (status, class) = match self.validation_status() {
+            Some(ValidationStatus::Approved) => ("Validated origin", "approved"),
+            Some(ValidationStatus::Pending) => ("Validation pending", "pending"),
+            Some(ValidationStatus::Rejected) => ("Origin rejected", "rejected"),
+            None => ("Origin unknown", "unverified"),
+        };
+        let _ = write!(
+            page,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>Provenance of rice batch {batch}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+             <header>\n<h1>Where this rice came from</h1>\n<p class=\"status {class}\">{status}</p>\n<dl>\n",
+            batch = escape(&rice_batch.qr_code),
+        );
+        row(&mut page, "QR code", &escape(&rice_batch.qr_code));
+        if let Some(gs1) = &rice_batch.gs1 {
+            row(&mut page, "GS1 Digital Link", &link(&gs1.canonical_uri()));
+        }
+        row(&mut page, "Status", &label(&rice_batch.batch_status));
+        row(&mut page, "Weight", &format!("{} kg", rice_batch.weight_kg));
+        if let Some(grade) = &rice_batch.effective_grade {
+            row(&mut page, "Grade", &label(grade));
+        }
+        row(&mut page, "Packed", &date(rice_batch.created_at));
+        page.push_str("</dl>\n</header>\n");
+
+        page.push_str("<section>\n<h2>Farmer</h2>\n");
+        match self.farmer() {
+            Some(farmer) => {
+                page.push_str("<dl>\n");
+                row(&mut page, "Name", &self.party_name(farmer));
+                if let Some(actor) = self.actors.get(farmer) {
+                    row(&mut page, "Organization", &label(&actor.organization));
+                    if let Some(address) = &actor.address {
+                        row(&mut page, "Location", &escape(address));
+                    }
+                }
+                page.push_str("</dl>\n");
+            }
+            None => page.push_str("<p>The milling record of this batch was not found.</p>\n"),
+        }
+        page.push_str("</section>\n");
+
+        page.push_str("<section>\n<h2>Production seasons</h2>\n");
+        if self.lineage.production_seasons.is_empty() {
+            page.push_str("<p>No production seasons recorded.</p>\n");
+        } else {
+            page.push_str(
+                "<table>\n<tr><th>Crop year</th><th>Variety</th><th>Planted</th><th>Harvested</th>\
+                 <th>Yield</th><th>Validation</th><th>Validator</th></tr>\n",
+            );
+            for season in &self.lineage.production_seasons {
+                let _ = writeln!(
+                    page,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&season.crop_year),
+                    season
+                        .variety
+                        .to_json()
+                        .as_str()
+                        .map(escape)
+                        .unwrap_or_default(),
+                    season.planting_date.map(date).unwrap_or_default(),
+                    season.harvest_date.map(date).unwrap_or_default(),
+                    season
+                        .total_yield_kg
+                        .map(|kg| format!("{kg} kg"))
+                        .unwrap_or_default(),
+                    label(&season.validation_status),
+                    season
+                        .validator_id
+                        .map(|validator| self.party_name(&validator))
+                        .unwrap_or_default(),
+                );
+            }
+            page.push_str("</table>\n");
+        }
+        page.push_str("</section>\n");
+
+        if let Some(milled_rice) = &self.lineage.milled_rice {
+            page.push_str("<section>\n<h2>Milling</h2>\n<dl>\n");
+            row(&mut page, "Milled", &date(milled_rice.created_at));
+            row(&mut page, "Milling", &label(&milled_rice.milling_type));
+            row(&mut page, "Quality", &label(&milled_rice.quality));
+            row(
+                &mut page,
+                "Paddy in",
+                &format!("{} kg", milled_rice.total_weight_processed_kg),
+            );
+            row(
+                &mut page,
+                "Rice out",
+                &format!(
+                    "{} kg ({}.{:02}% recovery)",
+                    milled_rice.total_weight_kg,
+                    milled_rice.milling_recovery_bp / 100,
+                    milled_rice.milling_recovery_bp % 100
+                ),
+            );
+            page.push_str("</dl>\n</section>\n");
+        }
+
+        page.push_str("<section>\n<h2>Movements</h2>\n");
+        if self.lineage.chain_transactions.is_empty() {
+            page.push_str("<p>The batch has not changed hands.</p>\n");
+        } else {
+            page.push_str(
+                "<table>\n<tr><th>Date</th><th>From</th><th>To</th><th>Status</th><th>Location</th></tr>\n",
+            );
+            for transaction in &self.lineage.chain_transactions {
+                let _ = writeln!(
+                    page,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    date(transaction.created_at),
+                    self.party_name(&transaction.from_actor_id),
+                    self.party_name(&transaction.to_actor_id),
+                    label(&transaction.transaction_status),
+                    transaction
+                        .geotag
+                        .as_ref()
+                        .map(map_link)
+                        .unwrap_or_default(),
+                );
+            }
+            page.push_str("</table>\n");
+        }
+        page.push_str("</section>\n");
+
+        if !self.lineage.shipments.is_empty() {
+            page.push_str(
+                "<section>\n<h2>Shipments</h2>\n<table>\n<tr><th>Departed</th><th>Arrived</th>\
+                 <th>Carrier</th><th>From</th><th>To</th><th>Status</th></tr>\n",
+            );
+            for shipment in &self.lineage.shipments {
+                let _ = writeln!(
+                    page,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    shipment.departed_at.map(date).unwrap_or_default(),
+                    shipment.arrived_at.map(date).unwrap_or_default(),
+                    self.party_name(&shipment.carrier),
+                    map_link(&shipment.origin),
+                    map_link(&shipment.destination),
+                    label(&shipment.shipment_status),
+                );
+            }
+            page.push_str("</table>\n</section>\n");
+        }
+
+        let _ = write!(
+            page,
+            "<footer>\n<p>Generated {} from the records of program <code>{}</code>. \
+             Batch account <code>{}</code>.</p>\n</footer>\n</body>\n</html>\n",
+            timestamp(generated_at),
+            rice_supply_client::PROGRAM_ID,
+            rice_batch.public_key,
+        );
+        page
+    }
+
+    /// The actor's registered name, or its shortened address.
+    fn party_name(&self, address: &Pubkey) -> String {
+        let name = match self.actors.get(address) {
+            Some(actor) => escape(&actor.name),
+            None => format!("{}…", &address.to_string()[..8]),
+        };
+        format!("<span title=\"{address}\">{name}</span>")
+    }
+}
+
+fn row(page: &mut String, term: &str, definition: &str) {
+    let _ = writeln!(page, "<dt>{term}</dt><dd>{definition}</dd>");
+}
+
+fn date(unix: i64) -> String {
+    timestamp(unix)[..10].to_string()
+}
+
+fn link(uri: &str) -> String {
+    let uri = escape(uri);
+    format!("<a href=\"{uri}\">{uri}</a>")
+}
+
+fn map_link(geotag: &GeoPoint) -> String {
+    let latitude = degrees(geotag.latitude_microdeg);
+    let longitude = degrees(geotag.longitude_microdeg);
+    format!(
+        "<a href=\"https://www.openstreetmap.org/?mlat={latitude:.6}&amp;mlon={longitude:.6}\">\
+         {latitude:.5}, {longitude:.5}</a>"
+    )
+}
+
+/// An enum's variant name as words, e.g. `InTransit` as "In transit".
+fn label(value: &impl ToJson) -> String {
+    let name = value.to_json();
+    let name = name.as_str().unwrap_or_default();
+    let mut words = String::with_capacity(name.len() + 4);
+    for (index, character) in name.chars().enumerate() {
+        if index > 0 && character.is_ascii_uppercase() {
+            words.push(' ');
+            words.push(character.to_ascii_lowercase());
+        } else {
+            words.push(character);
+        }
+    }
+    escape(&words)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,180 @@
+//! Provenance reports answering "where did this bag come from?" for one
+//! rice batch.
+//!
+//! A [`Report`] pairs the batch's [`Lineage`] with the chain actors it names
+//! and renders as structured JSON ([`Report::to_json`]) or as a static HTML
+//! page ([`Report::to_html`]) with the farmer, the production season the
+//! paddy came from and its validation, the milling run, every transaction
+//! that moved the batch and the route of each shipment carrying it.
+//! Payment amounts and references are left out; the report is meant to be
+//! handed to customers.
+
+mod html;
+
+use std::collections::BTreeMap;
+
+use rice_supply_chain::{GeoPoint, ValidationStatus};
+use rice_supply_client::accounts::{ChainActor, ChainTransaction, ProductionSeason, Shipment};
+use rice_supply_client::json::{timestamp, ToJson};
+use rice_supply_client::lineage::Lineage;
+use rice_supply_client::rpc::RpcClient;
+use rice_supply_client::{Pubkey, Result};
+use serde_json::{json, Value};
+
+#[derive(Clone)]
+pub struct Report {
+    pub lineage: Lineage,
+    /// Actors named by the lineage that have a `ChainActor` record.
+    pub actors: BTreeMap<Pubkey, ChainActor>,
+}
+
+impl Report {
+    /// Keeps only the actors `lineage` names.
+    pub fn new(lineage: Lineage, actors: impl IntoIterator<Item = ChainActor>) -> Self {
+        let named = lineage.actor_ids();
+        let actors = actors
+            .into_iter()
+            .filter(|actor| named.contains(&actor.public_key))
+            .map(|actor| (actor.public_key, actor))
+            .collect();
+        Report { lineage, actors }
+    }
+
+    /// Traces the batch at `address` and fetches the actors along the way.
+    pub fn fetch(rpc: &RpcClient, address: &Pubkey) -> Result<Self> {
+        let lineage = Lineage::fetch(rpc, address)?;
+        let actors = lineage.fetch_actors(rpc)?;
+        Ok(Self::new(lineage, actors))
+    }
+
+    /// Validation of the season the batch's paddy was harvested in. `None`
+    /// when the batch traces back to no season.
+    pub fn validation_status(&self) -> Option<ValidationStatus> {
+        self.lineage
+            .production_seasons
+            .first()
+            .map(|season| season.validation_status.clone())
+    }
+
+    /// The farmer of the batch's milled rice.
+    pub fn farmer(&self) -> Option<&Pubkey> {
+        self.lineage
+            .milled_rice
+            .as_ref()
+            .map(|milled_rice| &milled_rice.farmer_id)
+    }
+
+    /// The report as JSON, stamped with the unix time `generated_at`. Times
+    /// are RFC 3339 UTC and actors carry their registered name, organization
+    /// and address when they have a `ChainActor` record.
+    pub fn to_json(&self, generated_at: i64) -> Value {
+        let rice_batch = &self.lineage.rice_batch;
+        json!({
+            "generated_at": timestamp(generated_at),
+            "program_id": rice_supply_client::PROGRAM_ID.to_string(),
+            "validation_status": self.validation_status().to_json(),
+            "rice_batch": {
+                "address": rice_batch.public_key.to_string(),
+                "qr_code": rice_batch.qr_code,
+                "digital_link": rice_batch.gs1.as_ref().map(|gs1| gs1.canonical_uri()),
+                "batch_status": rice_batch.batch_status.to_json(),
+                "weight_kg": rice_batch.weight_kg,
+                "quality_score": rice_batch.quality_score,
+                "effective_grade": rice_batch.effective_grade.to_json(),
+                "packed_at": timestamp(rice_batch.created_at),
+            },
+            "farmer": self.farmer().map(|farmer| self.party(farmer)),
+            "production_seasons": self
+                .lineage
+                .production_seasons
+                .iter()
+                .map(|season| self.season(season))
+                .collect::<Vec<_>>(),
+            "milling": self.lineage.milled_rice.as_ref().map(|milled_rice| json!({
+                "address": milled_rice.public_key.to_string(),
+                "milled_at": timestamp(milled_rice.created_at),
+                "milling_type": milled_rice.milling_type.to_json(),
+                "quality": milled_rice.quality.to_json(),
+                "paddy_kg": milled_rice.total_weight_processed_kg,
+                "milled_kg": milled_rice.total_weight_kg,
+                "milling_recovery_bp": milled_rice.milling_recovery_bp,
+            })),
+            "transactions": self
+                .lineage
+                .chain_transactions
+                .iter()
+                .map(|transaction| self.transaction(transaction))
+                .collect::<Vec<_>>(),
+            "shipments": self
+                .lineage
+                .shipments
+                .iter()
+                .map(|shipment| self.shipment(shipment))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn season(&self, season: &ProductionSeason) -> Value {
+        json!({
+            "address": season.public_key.to_string(),
+            "crop_year": season.crop_year,
+            "variety": season.variety.to_json(),
+            "planting_date": season.planting_date.map(timestamp),
+            "harvest_date": season.harvest_date.map(timestamp),
+            "total_yield_kg": season.total_yield_kg,
+            "validation_status": season.validation_status.to_json(),
+            "validator": season.validator_id.as_ref().map(|validator| self.party(validator)),
+            "compliance_status": season.compliance_status.to_json(),
+        })
+    }
+
+    fn transaction(&self, transaction: &ChainTransaction) -> Value {
+        json!({
+            "address": transaction.public_key.to_string(),
+            "created_at": timestamp(transaction.created_at),
+            "updated_at": timestamp(transaction.updated_at),
+            "from": self.party(&transaction.from_actor_id),
+            "to": self.party(&transaction.to_actor_id),
+            "transaction_status": transaction.transaction_status.to_json(),
+            "location": transaction.geotag.as_ref().map(location),
+            "quality": transaction.quality.to_json(),
+        })
+    }
+
+    fn shipment(&self, shipment: &Shipment) -> Value {
+        json!({
+            "address": shipment.public_key.to_string(),
+            "carrier": self.party(&shipment.carrier),
+            "vehicle_id": shipment.vehicle_id,
+            "origin": location(&shipment.origin),
+            "destination": location(&shipment.destination),
+            "departed_at": shipment.departed_at.map(timestamp),
+            "arrived_at": shipment.arrived_at.map(timestamp),
+            "shipment_status": shipment.shipment_status.to_json(),
+        })
+    }
+
+    fn party(&self, address: &Pubkey) -> Value {
+        let actor = self.actors.get(address);
+        json!({
+            "address": address.to_string(),
+            "name": actor.map(|actor| &actor.name),
+            "organization": actor.map(|actor| actor.organization.to_json()),
+            "location": actor.and_then(|actor| actor.address.as_ref()),
+            "is_active": actor.map(|actor| actor.is_active),
+        })
+    }
+}
+
+fn location(geotag: &GeoPoint) -> Value {
+    json!({
+        "latitude": degrees(geotag.latitude_microdeg),
+        "longitude": degrees(geotag.longitude_microdeg),
+        "accuracy_m": geotag.accuracy_m,
+        "captured_at": timestamp(geotag.captured_at),
+    })
+}
+
+fn degrees(microdegrees: i32) -> f64 {
+    f64::from(microdegrees) / 1e6
+}
@@ -0,0 +1,396 @@
+use rice_supply_chain::{
+    AccreditationKind, BatchStatus, ByProducts, GeoPoint, MillingType, Organization, PaymentMethod,
+    QualityGrade, ValidationStatus,
+};
+use rice_supply_client::accounts::{
+    ChainActor, ChainTransaction, MilledRice, ProductionSeason, RiceBatch, Shipment,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::lineage::Lineage;
+use rice_supply_client::{pda, Instruction, Pubkey};
+use rice_supply_provenance::Report;
+use rice_supply_svm::{Svm, LAMPORTS_PER_SOL};
+use serde_json::Value;
+
+/// 2024-05-01T00:00:00Z.
+const HARVEST: i64 = 1_714_521_600;
+const DAY: i64 = 86_400;
+const ACTORS: [&str; 4] = ["farmer", "validator", "buyer", "carrier"];
+
+struct Chain {
+    svm: Svm,
+    user: Pubkey,
+}
+
+impl Chain {
+    /// A farmer's season harvested on day 0 and milled on day 1 into
+    /// `batch-1`, which the farmer sells to a registered buyer on day 3. A
+    /// second transaction involves an unregistered party.
+    fn new() -> Self {
+        let mut svm = Svm::new();
+        let user = Pubkey::new_unique();
+        svm.airdrop(&user, 100 * LAMPORTS_PER_SOL);
+        let mut chain = Self { svm, user };
+        chain.send(instructions::initialize_config(&user));
+        chain.send(instructions::initialize_substance_registry(&user));
+        chain.send(instructions::initialize_emission_factors(&user));
+
+        chain.svm.warp_to_timestamp(HARVEST);
+        chain.actor("farmer", "Juan <dela> Cruz", Organization::Farmer);
+        chain.actor("validator", "DA Region III", Organization::Government);
+        chain.send(instructions::grant_accreditation(
+            &user,
+            &pda::chain_actor("validator").0,
+            AccreditationKind::Validator,
+            None,
+        ));
+        chain.actor("buyer", "Tarlac Rice Trading", Organization::Distributor);
+        chain.actor("carrier", "Nueva Ecija Haulers", Organization::Distributor);
+        chain.send(instructions::create_production_season(
+            &user,
+            args::CreateProductionSeason {
+                season_seed: "season".to_string(),
+                farmer_id: pda::chain_actor("farmer").0,
+                crop_year: "2024-DS".to_string(),
+                processed_yield_kg: 0,
+                variety: None,
+                planned_practice: None,
+                planting_date: Some(HARVEST - 110 * DAY),
+                irrigation_practice: None,
+                fertilizer_used: None,
+                pesticide_used: None,
+                harvest_date: Some(HARVEST),
+                total_yield_kg: Some(1_200),
+                moisture_content: Some(14),
+                cultivated_area_m2: None,
+                organic_amendments: Vec::new(),
+            },
+        ));
+
+        chain.svm.warp_to_timestamp(HARVEST + DAY);
+        chain.send(instructions::create_milled_rice(
+            &user,
+            &pda::production_season("season").0,
+            &user,
+            args::CreateMilledRice {
+                milling_seed: "milling".to_string(),
+                farmer_id: pda::chain_actor("farmer").0,
+                total_weight_kg: 650,
+                milling_type: MillingType::WellMilled,
+                quality: QualityGrade::Grade1,
+                photo_urls: Vec::new(),
+                moisture: 14,
+                total_weight_processed_kg: 1_000,
+                by_products: ByProducts {
+                    husk_kg: 200,
+                    bran_kg: 80,
+                    brokens_kg: 50,
+                    brewers_rice_kg: 20,
+                },
+            },
+        ));
+        chain.send(instructions::create_rice_batch(
+            &user,
+            args::CreateRiceBatch {
+                batch_seed: "batch-1".to_string(),
+                milled_rice_id: pda::milled_rice("milling").0,
+                batch_status: BatchStatus::Created,
+                quality_score: 90,
+                weight_kg: 300,
+                qr_code: "https://id.gs1.org/01/04800016644511/10/24DS-07".to_string(),
+            },
+        ));
+
+        chain.svm.warp_to_timestamp(HARVEST + 3 * DAY);
+        chain.transaction("sale", pda::chain_actor("buyer").0);
+        chain.transaction("resale", Pubkey::new_unique());
+        chain
+    }
+
+    fn send(&mut self, instruction: Instruction) {
+        let user = self.user;
+        self.svm.send_instruction(instruction, &[user]).unwrap();
+    }
+
+    fn actor(&mut self, actor_seed: &str, name: &str, organization: Organization) {
+        let user = self.user;
+        self.send(instructions::create_chain_actor(
+            &user,
+            args::CreateChainActor {
+                actor_seed: actor_seed.to_string(),
+                name: name.to_string(),
+                actor_type: Vec::new(),
+                farm_id: None,
+                farmer_id: None,
+                assigned_tps: 0,
+                pin: "1234".to_string(),
+                organization,
+                address: Some("Science City of Muñoz, Nueva Ecija".to_string()),
+            },
+        ));
+    }
+
+    fn transaction(&mut self, transaction_seed: &str, to_actor_id: Pubkey) {
+        let user = self.user;
+        self.send(instructions::create_chain_transaction(
+            &user,
+            args::CreateChainTransaction {
+                transaction_seed: transaction_seed.to_string(),
+                from_actor_id: pda::chain_actor("farmer").0,
+                to_actor_id,
+                rice_batch_ids: vec![pda::rice_batch("batch-1").0],
+                amount: 125_000,
+                payment_method: PaymentMethod::BankTransfer,
+                payment_reference: Some("INV-0042".to_string()),
+                geotag: Some(GeoPoint {
+                    latitude_microdeg: 15_580_000,
+                    longitude_microdeg: 120_970_500,
+                    accuracy_m: Some(5),
+                    captured_at: HARVEST + 3 * DAY,
+                }),
+                quality: None,
+            },
+        ));
+    }
+
+    /// Trucks `batch-1` from the mill to the buyer, leaving on day 4 and
+    /// arriving on day 5.
+    fn ship(&mut self) {
+        let user = self.user;
+        let carrier = pda::chain_actor("carrier").0;
+        let batch = [pda::rice_batch("batch-1").0];
+        self.svm.warp_to_timestamp(HARVEST + 4 * DAY);
+        self.send(instructions::create_shipment(
+            &user,
+            &carrier,
+            None,
+            &user,
+            args::CreateShipment {
+                shipment_seed: "route".to_string(),
+                vehicle_id: "NBC-1234".to_string(),
+                origin: GeoPoint {
+                    latitude_microdeg: 15_716_000,
+                    longitude_microdeg: 120_903_000,
+                    accuracy_m: None,
+                    captured_at: HARVEST + 4 * DAY,
+                },
+                destination: GeoPoint {
+                    latitude_microdeg: 15_480_000,
+                    longitude_microdeg: 120_590_000,
+                    accuracy_m: None,
+                    captured_at: HARVEST + 4 * DAY,
+                },
+                rice_batch_ids: batch.to_vec(),
+                seal_numbers: Vec::new(),
+            },
+        ));
+        self.send(instructions::depart_shipment(
+            &user, &carrier, "route", &batch,
+        ));
+        self.svm.warp_to_timestamp(HARVEST + 5 * DAY);
+        self.send(instructions::arrive_shipment(
+            &user,
+            &carrier,
+            "route",
+            Vec::new(),
+            &batch,
+        ));
+    }
+
+    fn validate(&mut self, validation_status: ValidationStatus) {
+        let user = self.user;
+        self.send(instructions::validate_production_season(
+            &user,
+            &pda::chain_actor("validator").0,
+            "season",
+            validation_status,
+        ));
+    }
+
+    fn report(&self) -> Report {
+        let svm = &self.svm;
+        let lineage = Lineage::new(
+            svm.account::<RiceBatch>(&pda::rice_batch("batch-1").0)
+                .unwrap(),
+            svm.account::<MilledRice>(&pda::milled_rice("milling").0)
+                .ok(),
+            [
+                svm.account::<ProductionSeason>(&pda::production_season("season").0)
+                    .unwrap(),
+            ],
+            ["sale", "resale"].map(|seed| {
+                svm.account::<ChainTransaction>(&pda::chain_transaction(seed).0)
+                    .unwrap()
+            }),
+            svm.account::<Shipment>(&pda::shipment("route").0).ok(),
+        );
+        let actors = ACTORS.map(|seed| {
+            svm.account::<ChainActor>(&pda::chain_actor(seed).0)
+                .unwrap()
+        });
+        Report::new(lineage, actors)
+    }
+}
+
+#[test]
+fn report_traces_the_batch_to_its_farmer_and_trading_partners() {
+    let mut chain = Chain::new();
+    chain.validate(ValidationStatus::Approved);
+    let report = chain.report().to_json(HARVEST + 5 * DAY);
+
+    assert_eq!(report["generated_at"], "2024-05-06T00:00:00Z");
+    assert_eq!(report["validation_status"], "Approved");
+    assert_eq!(
+        report["rice_batch"]["digital_link"],
+        "https://id.gs1.org/01/04800016644511/10/24DS-07"
+    );
+    assert_eq!(report["rice_batch"]["packed_at"], "2024-05-02T00:00:00Z");
+
+    let farmer = &report["farmer"];
+    assert_eq!(
+        farmer["address"],
+        pda::chain_actor("farmer").0.to_string().as_str()
+    );
+    assert_eq!(farmer["name"], "Juan <dela> Cruz");
+    assert_eq!(farmer["organization"], "Farmer");
+    assert_eq!(farmer["location"], "Science City of Muñoz, Nueva Ecija");
+
+    let season = &report["production_seasons"][0];
+    assert_eq!(season["harvest_date"], "2024-05-01T00:00:00Z");
+    assert_eq!(season["validation_status"], "Approved");
+    assert_eq!(season["validator"]["name"], "DA Region III");
+
+    assert_eq!(report["milling"]["paddy_kg"], 1_000);
+    assert_eq!(report["milling"]["milling_recovery_bp"], 6_500);
+
+    let transactions = report["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 2);
+    let address = |seed| pda::chain_transaction(seed).0.to_string();
+    let transaction = |seed| {
+        transactions
+            .iter()
+            .find(|transaction| transaction["address"] == address(seed).as_str())
+            .unwrap()
+    };
+    let sale = transaction("sale");
+    assert_eq!(sale["created_at"], "2024-05-04T00:00:00Z");
+    assert_eq!(sale["to"]["name"], "Tarlac Rice Trading");
+    assert_eq!(sale["transaction_status"], "Pending");
+    assert_eq!(sale["location"]["latitude"], 15.58);
+    assert_eq!(sale["location"]["longitude"], 120.9705);
+    assert!(sale.get("amount").is_none());
+    assert!(sale.get("payment_reference").is_none());
+
+    let unregistered = &transaction("resale")["to"];
+    assert!(unregistered["address"].is_string());
+    assert_eq!(unregistered["name"], Value::Null);
+}
+
+#[test]
+fn validation_status_follows_the_seasons_behind_the_batch() {
+    let mut chain = Chain::new();
+    assert!(chain.report().validation_status() == Some(ValidationStatus::Pending));
+    chain.validate(ValidationStatus::Approved);
+    assert!(chain.report().validation_status() == Some(ValidationStatus::Approved));
+    chain.validate(ValidationStatus::Rejected);
+    assert!(chain.report().validation_status() == Some(ValidationStatus::Rejected));
+
+    let mut report = chain.report();
+    report.lineage.milled_rice = None;
+    report.lineage.production_seasons.clear();
+    assert!(report.validation_status().is_none());
+    assert_eq!(report.to_json(HARVEST)["farmer"], Value::Null);
+}
+
+#[test]
+fn lineage_keeps_only_the_season_the_batch_was_milled_from() {
+    let mut chain = Chain::new();
+    let user = chain.user;
+    // The farmer's next season is harvested, and approved, after milling.
+    chain.svm.warp_to_timestamp(HARVEST + 120 * DAY);
+    chain.send(instructions::create_production_season(
+        &user,
+        args::CreateProductionSeason {
+            season_seed: "next-season".to_string(),
+            farmer_id: pda::chain_actor("farmer").0,
+            crop_year: "2024-WS".to_string(),
+            processed_yield_kg: 0,
+            variety: None,
+            planned_practice: None,
+            planting_date: None,
+            irrigation_practice: None,
+            fertilizer_used: None,
+            pesticide_used: None,
+            harvest_date: Some(HARVEST + 120 * DAY),
+            total_yield_kg: Some(1_500),
+            moisture_content: Some(14),
+            cultivated_area_m2: None,
+            organic_amendments: Vec::new(),
+        },
+    ));
+
+    let svm = &chain.svm;
+    let lineage = Lineage::new(
+        svm.account::<RiceBatch>(&pda::rice_batch("batch-1").0)
+            .unwrap(),
+        svm.account::<MilledRice>(&pda::milled_rice("milling").0)
+            .ok(),
+        ["next-season", "season"].map(|seed| {
+            svm.account::<ProductionSeason>(&pda::production_season(seed).0)
+                .unwrap()
+        }),
+        Vec::new(),
+        Vec::new(),
+    );
+    assert_eq!(lineage.production_seasons.len(), 1);
+    assert_eq!(
+        lineage.production_seasons[0].public_key,
+        pda::production_season("season").0
+    );
+}
+
+#[test]
+fn html_page_renders_the_report_with_records_escaped() {
+    let mut chain = Chain::new();
+    chain.validate(ValidationStatus::Approved);
+    let page = chain.report().to_html(HARVEST + 5 * DAY);
+
+    assert!(page.starts_with("<!DOCTYPE html>"));
+    assert!(page.contains("<p class=\"status approved\">Validated origin</p>"));
+    assert!(page.contains("Juan &lt;dela&gt; Cruz"));
+    assert!(!page.contains("<dela>"));
+    assert!(page.contains("Tarlac Rice Trading"));
+    assert!(page.contains("Well milled"));
+    assert!(page.contains("650 kg (65.00% recovery)"));
+    assert!(page.contains("https://www.openstreetmap.org/?mlat=15.580000&amp;mlon=120.970500"));
+    assert!(page.contains("Generated 2024-05-06T00:00:00Z"));
+    assert!(!page.contains("INV-0042"));
+    assert!(!page.contains("<h2>Shipments</h2>"));
+}
+
+#[test]
+fn report_follows_the_route_of_each_shipment() {
+    let mut chain = Chain::new();
+    assert_eq!(
+        chain.report().to_json(HARVEST)["shipments"],
+        Value::Array(Vec::new())
+    );
+    chain.ship();
+    let report = chain.report();
+
+    let json = report.to_json(HARVEST + 6 * DAY);
+    let shipment = &json["shipments"][0];
+    assert_eq!(shipment["carrier"]["name"], "Nueva Ecija Haulers");
+    assert_eq!(shipment["vehicle_id"], "NBC-1234");
+    assert_eq!(shipment["origin"]["latitude"], 15.716);
+    assert_eq!(shipment["destination"]["longitude"], 120.59);
+    assert_eq!(shipment["departed_at"], "2024-05-05T00:00:00Z");
+    assert_eq!(shipment["arrived_at"], "2024-05-06T00:00:00Z");
+    assert_eq!(shipment["shipment_status"], "Arrived");
+    assert_eq!(json["rice_batch"]["batch_status"], "Delivered");
+
+    let page = report.to_html(HARVEST + 6 * DAY);
+    assert!(page.contains("<h2>Shipments</h2>"));
+    assert!(page.contains("Nueva Ecija Haulers"));
+    assert!(page.contains("https://www.openstreetmap.org/?mlat=15.480000&amp;mlon=120.590000"));
+}
@@ -0,0 +1,248 @@
+//! Signed QR payloads for retail packs.
+//!
+//! A payload names the program, the `RiceBatch` PDA and the pack serial, and
+//! carries an ed25519 signature from a key the miller registered on-chain
+//! with `register_issuing_key`. A consumer app can check it offline against
+//! snapshots of the batch, issuer key and pack run accounts. Only payloads
+//! naming `rice_supply_chain::ID` verify.
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rice_supply_chain::{BatchStatus, IssuerKey, RetailPackRun, RiceBatch};
+
+pub use anchor_lang::prelude::Pubkey;
+
+pub const PAYLOAD_VERSION: u8 = 1;
+/// version + program ID + batch + serial + issuing key + signature.
+pub const PAYLOAD_LEN: usize = 1 + 32 + 32 + 8 + 32 + 64;
+
+const SIGNING_DOMAIN: &[u8] = b"rice-supply-qr:v1";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum QrError {
+    #[error("payload is not valid base64url")]
+    Encoding,
+    #[error("payload names program {0}, not the rice supply chain program")]
+    Program(Pubkey),
+    #[error("payload must be {PAYLOAD_LEN} bytes, got {0}")]
+    Length(usize),
+    #[error("unsupported payload version {0}")]
+    Version(u8),
+    #[error("issuing key is not a valid ed25519 key")]
+    IssuingKey,
+    #[error("signature does not match the payload")]
+    Signature,
+    #[error("{0} snapshot does not match the payload")]
+    SnapshotMismatch(&'static str),
+    #[error("{0} snapshot could not be decoded")]
+    SnapshotDecode(&'static str),
+    #[error("issuing key has been revoked")]
+    KeyRevoked,
+    #[error("pack serial {0} was not issued from this batch")]
+    UnknownSerial(u64),
+    #[error("batch has been consumed")]
+    BatchConsumed,
+}
+
+/// Raw account state as returned by RPC or read from a snapshot file.
+#[derive(Clone, Debug)]
+pub struct AccountSnapshot {
+    pub address: Pubkey,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+impl AccountSnapshot {
+    fn decode<T: AccountDeserialize + Discriminator>(
+        &self,
+        name: &'static str,
+    ) -> Result<T, QrError> {
+        if self.owner != rice_supply_chain::ID {
+            return Err(QrError::SnapshotMismatch(name));
+        }
+        T::try_deserialize(&mut self.data.as_slice()).map_err(|_| QrError::SnapshotDecode(name))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QrPayload {
+    pub program_id: Pubkey,
+    pub rice_batch: Pubkey,
+    pub pack_serial: u64,
+    pub issuing_key: Pubkey,
+    pub signature: [u8; 64],
+}
+
+/// Facts established by [`QrPayload::verify`].
+#[derive(Clone)]
+pub struct VerifiedPack {
+    pub rice_batch: RiceBatch,
+    pub miller: Pubkey,
+    pub pack_size_g: u32,
+}
+
+impl QrPayload {
+    pub fn sign(
+        signing_key: &SigningKey,
+        program_id: Pubkey,
+        rice_batch: Pubkey,
+        pack_serial: u64,
+    ) -> Self {
+        let issuing_key = Pubkey::new_from_array(signing_key.verifying_key().to_bytes());
+        let message = signed_message(&program_id, &rice_batch, pack_serial, &issuing_key);
+        Self {
+            program_id,
+            rice_batch,
+            pack_serial,
+            issuing_key,
+            signature: signing_key.sign(&message).to_bytes(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; PAYLOAD_LEN] {
+        let mut bytes = [0u8; PAYLOAD_LEN];
+        bytes[0] = PAYLOAD_VERSION;
+        bytes[1..33].copy_from_slice(self.program_id.as_ref());
+        bytes[33..65].copy_from_slice(self.rice_batch.as_ref());
+        bytes[65..73].copy_from_slice(&self.pack_serial.to_le_bytes());
+        bytes[73..105].copy_from_slice(self.issuing_key.as_ref());
+        bytes[105..].copy_from_slice(&self.signature);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, QrError> {
+        if bytes.len() != PAYLOAD_LEN {
+            return Err(QrError::Length(bytes.len()));
+        }
+        if bytes[0] != PAYLOAD_VERSION {
+            return Err(QrError::Version(bytes[0]));
+        }
+        let pubkey_at =
+            |offset: usize| Pubkey::new_from_array(bytes[offset..offset + 32].try_into().unwrap());
+        Ok(Self {
+            program_id: pubkey_at(1),
+            rice_batch: pubkey_at(33),
+            pack_serial: u64::from_le_bytes(bytes[65..73].try_into().unwrap()),
+            issuing_key: pubkey_at(73),
+            signature: bytes[105..].try_into().unwrap(),
+        })
+    }
+
+    /// The text form printed in the QR code: unpadded base64url.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.to_bytes())
+    }
+
+    pub fn decode(text: &str) -> Result<Self, QrError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(text.trim())
+            .map_err(|_| QrError::Encoding)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn verify_signature(&self) -> Result<(), QrError> {
+        let verifying_key = VerifyingKey::from_bytes(&self.issuing_key.to_bytes())
+            .map_err(|_| QrError::IssuingKey)?;
+        let message = signed_message(
+            &self.program_id,
+            &self.rice_batch,
+            self.pack_serial,
+            &self.issuing_key,
+        );
+        verifying_key
+            .verify(&message, &Signature::from_bytes(&self.signature))
+            .map_err(|_| QrError::Signature)
+    }
+
+    /// Checks that the payload names this program and the signature, then
+    /// that the snapshots show the batch, an active issuing key, and a pack
+    /// run of the batch containing the serial that was packed by the miller
+    /// the key is registered to. The issuer key and pack run snapshots must
+    /// sit at the PDAs their contents derive.
+    pub fn verify(
+        &self,
+        rice_batch: &AccountSnapshot,
+        issuer_key: &AccountSnapshot,
+        retail_pack_run: &AccountSnapshot,
+    ) -> Result<VerifiedPack, QrError> {
+        if self.program_id != rice_supply_chain::ID {
+            return Err(QrError::Program(self.program_id));
+        }
+        self.verify_signature()?;
+
+        let batch: RiceBatch = rice_batch.decode("rice batch")?;
+        if rice_batch.address != self.rice_batch || batch.public_key != self.rice_batch {
+            return Err(QrError::SnapshotMismatch("rice batch"));
+        }
+        if batch.batch_status == BatchStatus::Consumed {
+            return Err(QrError::BatchConsumed);
+        }
+
+        let key: IssuerKey = issuer_key.decode("issuer key")?;
+        if key.issuing_key != self.issuing_key
+            || issuer_key.address != issuer_key_address(&key.miller, &self.issuing_key)
+        {
+            return Err(QrError::SnapshotMismatch("issuer key"));
+        }
+        if !key.is_active {
+            return Err(QrError::KeyRevoked);
+        }
+
+        let run: RetailPackRun = retail_pack_run.decode("retail pack run")?;
+        if run.rice_batch != self.rice_batch
+            || run.packer != key.miller
+            || retail_pack_run.address
+                != retail_pack_run_address(&self.rice_batch, run.first_serial)
+        {
+            return Err(QrError::SnapshotMismatch("retail pack run"));
+        }
+        if !run.contains(self.pack_serial) {
+            return Err(QrError::UnknownSerial(self.pack_serial));
+        }
+
+        Ok(VerifiedPack {
+            rice_batch: batch,
+            miller: key.miller,
+            pack_size_g: run.pack_size_g,
+        })
+    }
+}
+
+/// `IssuerKey` PDA of `issuing_key` registered to `miller`.
+pub fn issuer_key_address(miller: &Pubkey, issuing_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"issuer_key", miller.as_ref(), issuing_key.as_ref()],
+        &rice_supply_chain::ID,
+    )
+    .0
+}
+
+/// `RetailPackRun` PDA of the run starting at `first_serial` of `rice_batch`.
+pub fn retail_pack_run_address(rice_batch: &Pubkey, first_serial: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"retail_pack_run",
+            rice_batch.as_ref(),
+            &first_serial.to_le_bytes(),
+        ],
+        &rice_supply_chain::ID,
+    )
+    .0
+}
+
+fn signed_message(
+    program_id: &Pubkey,
+    rice_batch: &Pubkey,
+    pack_serial: u64,
+    issuing_key: &Pubkey,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(SIGNING_DOMAIN.len() + 32 * 3 + 8);
+    message.extend_from_slice(SIGNING_DOMAIN);
+    message.extend_from_slice(program_id.as_ref());
+    message.extend_from_slice(rice_batch.as_ref());
+    message.extend_from_slice(&pack_serial.to_le_bytes());
+    message.extend_from_slice(issuing_key.as_ref());
+    message
+}
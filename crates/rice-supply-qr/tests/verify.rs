@@ -0,0 +1,332 @@
+use ed25519_dalek::SigningKey;
+use rice_supply_chain::{
+    AccreditationKind, BatchStatus, ByProducts, MillingType, Organization, QualityGrade,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::{pda, Instruction};
+use rice_supply_qr::{AccountSnapshot, Pubkey, QrError, QrPayload, PAYLOAD_LEN, PAYLOAD_VERSION};
+use rice_supply_svm::{Svm, LAMPORTS_PER_SOL};
+
+/// An accredited miller that registered `signing_key` and packed ten packs,
+/// serials 0 to 9, off `batch-1`.
+struct Packed {
+    svm: Svm,
+    /// The miller's authority, which also created the batch.
+    user: Pubkey,
+    signing_key: SigningKey,
+    miller: Pubkey,
+    rice_batch: Pubkey,
+}
+
+impl Packed {
+    fn new() -> Self {
+        let mut svm = Svm::new();
+        let user = Pubkey::new_unique();
+        svm.airdrop(&user, 100 * LAMPORTS_PER_SOL);
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let issuing_key = Pubkey::new_from_array(signing_key.verifying_key().to_bytes());
+        let miller = pda::chain_actor("miller-1").0;
+        let rice_batch = pda::rice_batch("batch-1").0;
+
+        let mut send = |instruction: Instruction| {
+            svm.send(&[instruction], &[user]).unwrap();
+        };
+        send(instructions::initialize_config(&user));
+        send(instructions::create_chain_actor(
+            &user,
+            args::CreateChainActor {
+                actor_seed: "miller-1".to_string(),
+                name: "Nueva Ecija Rice Mill".to_string(),
+                actor_type: vec!["miller".to_string()],
+                farm_id: None,
+                farmer_id: None,
+                assigned_tps: 0,
+                pin: "1234".to_string(),
+                organization: Organization::Miller,
+                address: None,
+            },
+        ));
+        send(instructions::grant_accreditation(
+            &user,
+            &miller,
+            AccreditationKind::Miller,
+            None,
+        ));
+        send(instructions::register_issuing_key(
+            &user,
+            &miller,
+            issuing_key,
+        ));
+        send(instructions::create_production_season(
+            &user,
+            args::CreateProductionSeason {
+                season_seed: "season".to_string(),
+                farmer_id: miller,
+                crop_year: "2024-DS".to_string(),
+                processed_yield_kg: 0,
+                variety: None,
+                planned_practice: None,
+                planting_date: None,
+                irrigation_practice: None,
+                fertilizer_used: None,
+                pesticide_used: None,
+                harvest_date: None,
+                total_yield_kg: Some(1_000),
+                moisture_content: None,
+                cultivated_area_m2: None,
+                organic_amendments: Vec::new(),
+            },
+        ));
+        send(instructions::create_milled_rice(
+            &user,
+            &pda::production_season("season").0,
+            &user,
+            args::CreateMilledRice {
+                milling_seed: "milling".to_string(),
+                farmer_id: miller,
+                total_weight_kg: 650,
+                milling_type: MillingType::WellMilled,
+                quality: QualityGrade::Grade1,
+                photo_urls: Vec::new(),
+                moisture: 14,
+                total_weight_processed_kg: 1_000,
+                by_products: ByProducts {
+                    husk_kg: 200,
+                    bran_kg: 80,
+                    brokens_kg: 50,
+                    brewers_rice_kg: 20,
+                },
+            },
+        ));
+        send(instructions::create_rice_batch(
+            &user,
+            args::CreateRiceBatch {
+                batch_seed: "batch-1".to_string(),
+                milled_rice_id: pda::milled_rice("milling").0,
+                batch_status: BatchStatus::Created,
+                quality_score: 90,
+                weight_kg: 50,
+                qr_code: "QR-batch-1".to_string(),
+            },
+        ));
+        send(instructions::create_retail_packs(
+            &user,
+            &miller,
+            &rice_batch,
+            0,
+            5_000,
+            10,
+        ));
+
+        Self {
+            svm,
+            user,
+            signing_key,
+            miller,
+            rice_batch,
+        }
+    }
+
+    fn snapshot(&self, address: Pubkey) -> AccountSnapshot {
+        let account = self.svm.get_account(&address).unwrap();
+        AccountSnapshot {
+            address,
+            owner: account.owner,
+            data: account.data.clone(),
+        }
+    }
+
+    fn issuer_key(&self) -> Pubkey {
+        let issuing_key = Pubkey::new_from_array(self.signing_key.verifying_key().to_bytes());
+        pda::issuer_key(&self.miller, &issuing_key).0
+    }
+
+    fn payload(&self, pack_serial: u64) -> QrPayload {
+        QrPayload::sign(
+            &self.signing_key,
+            rice_supply_chain::ID,
+            self.rice_batch,
+            pack_serial,
+        )
+    }
+
+    fn verify(&self, payload: &QrPayload) -> Result<(), QrError> {
+        payload
+            .verify(
+                &self.snapshot(self.rice_batch),
+                &self.snapshot(self.issuer_key()),
+                &self.snapshot(pda::retail_pack_run(&self.rice_batch, 0).0),
+            )
+            .map(|_| ())
+    }
+}
+
+#[test]
+fn verify_accepts_a_pack_from_the_run() {
+    let packed = Packed::new();
+    let payload = QrPayload::decode(&packed.payload(9).encode()).unwrap();
+    let verified = payload
+        .verify(
+            &packed.snapshot(packed.rice_batch),
+            &packed.snapshot(packed.issuer_key()),
+            &packed.snapshot(pda::retail_pack_run(&packed.rice_batch, 0).0),
+        )
+        .unwrap();
+    assert_eq!(verified.miller, packed.miller);
+    assert_eq!(verified.pack_size_g, 5_000);
+
+    assert_eq!(
+        packed.verify(&packed.payload(10)),
+        Err(QrError::UnknownSerial(10))
+    );
+}
+
+#[test]
+fn verify_rejects_a_forged_program_id() {
+    let packed = Packed::new();
+    let forged = Pubkey::new_unique();
+    let payload = QrPayload::sign(&packed.signing_key, forged, packed.rice_batch, 0);
+    assert!(payload.verify_signature().is_ok());
+
+    // Snapshots of a look-alike program's accounts do not help either.
+    let owned_by_forged = |address| AccountSnapshot {
+        owner: forged,
+        ..packed.snapshot(address)
+    };
+    assert_eq!(
+        payload
+            .verify(
+                &owned_by_forged(packed.rice_batch),
+                &owned_by_forged(packed.issuer_key()),
+                &owned_by_forged(pda::retail_pack_run(&packed.rice_batch, 0).0),
+            )
+            .err(),
+        Some(QrError::Program(forged))
+    );
+}
+
+#[test]
+fn verify_rejects_snapshots_at_the_wrong_address() {
+    let packed = Packed::new();
+    let payload = packed.payload(0);
+    let elsewhere = |snapshot: AccountSnapshot| AccountSnapshot {
+        address: Pubkey::new_unique(),
+        ..snapshot
+    };
+    let rice_batch = packed.snapshot(packed.rice_batch);
+    let issuer_key = packed.snapshot(packed.issuer_key());
+    let retail_pack_run = packed.snapshot(pda::retail_pack_run(&packed.rice_batch, 0).0);
+
+    assert_eq!(
+        payload
+            .verify(
+                &elsewhere(rice_batch.clone()),
+                &issuer_key,
+                &retail_pack_run
+            )
+            .err(),
+        Some(QrError::SnapshotMismatch("rice batch"))
+    );
+    assert_eq!(
+        payload
+            .verify(
+                &rice_batch,
+                &elsewhere(issuer_key.clone()),
+                &retail_pack_run
+            )
+            .err(),
+        Some(QrError::SnapshotMismatch("issuer key"))
+    );
+    assert_eq!(
+        payload
+            .verify(
+                &rice_batch,
+                &issuer_key,
+                &elsewhere(retail_pack_run.clone())
+            )
+            .err(),
+        Some(QrError::SnapshotMismatch("retail pack run"))
+    );
+}
+
+#[test]
+fn verify_rejects_a_bad_signature() {
+    let packed = Packed::new();
+    let mut payload = packed.payload(0);
+    payload.signature[0] ^= 1;
+    assert_eq!(packed.verify(&payload), Err(QrError::Signature));
+
+    // A signature over another serial does not carry over.
+    let mut payload = packed.payload(0);
+    payload.pack_serial = 1;
+    assert_eq!(packed.verify(&payload), Err(QrError::Signature));
+
+    // Nor does one from a key the miller never registered.
+    let payload = QrPayload::sign(
+        &SigningKey::from_bytes(&[8; 32]),
+        rice_supply_chain::ID,
+        packed.rice_batch,
+        0,
+    );
+    assert_eq!(
+        packed.verify(&payload),
+        Err(QrError::SnapshotMismatch("issuer key"))
+    );
+}
+
+#[test]
+fn verify_rejects_revoked_keys_and_consumed_batches() {
+    let mut packed = Packed::new();
+    let payload = packed.payload(0);
+    let (user, miller) = (packed.user, packed.miller);
+    packed
+        .svm
+        .send(
+            &[instructions::delete_rice_batch(
+                &user,
+                None,
+                "batch-1",
+                "QR-batch-1",
+            )],
+            &[user],
+        )
+        .unwrap();
+    assert_eq!(packed.verify(&payload), Err(QrError::BatchConsumed));
+
+    let mut packed = Packed::new();
+    let user = packed.user;
+    packed
+        .svm
+        .send(
+            &[instructions::revoke_issuing_key(
+                &user,
+                &miller,
+                &payload.issuing_key,
+            )],
+            &[user],
+        )
+        .unwrap();
+    assert_eq!(packed.verify(&payload), Err(QrError::KeyRevoked));
+}
+
+#[test]
+fn decode_rejects_malformed_payloads() {
+    let packed = Packed::new();
+    let bytes = packed.payload(0).to_bytes();
+    assert_eq!(
+        QrPayload::decode(&packed.payload(0).encode()),
+        Ok(packed.payload(0))
+    );
+
+    assert_eq!(QrPayload::decode("not base64!"), Err(QrError::Encoding));
+    assert_eq!(
+        QrPayload::from_bytes(&bytes[..PAYLOAD_LEN - 1]),
+        Err(QrError::Length(PAYLOAD_LEN - 1))
+    );
+    let mut newer = bytes;
+    newer[0] = PAYLOAD_VERSION + 1;
+    assert_eq!(
+        QrPayload::from_bytes(&newer),
+        Err(QrError::Version(PAYLOAD_VERSION + 1))
+    );
+}
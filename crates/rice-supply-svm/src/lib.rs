@@ -0,0 +1,316 @@
+//! In-process execution of `rice_supply_chain` for tests and fuzzing.
+//!
+//! [`Svm`] keeps an account store and runs instructions natively against
+//! the program's Anchor entrypoint, no validator or network needed. The
+//! runtime pieces the program depends on are emulated:
+//!
+//! - the loader's serialized input, so `realloc` and `assign` behave;
+//! - the system program's `CreateAccount`, `Assign`, `Transfer` and
+//!   `Allocate`, reached through CPI;
+//! - the `Clock` and `Rent` sysvars, return data and `emit!` events;
+//! - the Token-2022 and associated token account programs, run from their
+//!   crates, with Token-2022's calls into transfer hooks;
+//! - signer and writable privilege checks, account ownership rules,
+//!   lamport conservation, rent exemption and transaction atomicity.
+//!
+//! Signatures are not verified: `send` takes the signer pubkeys directly,
+//! and fails with [`ExecutionError::MissingSignature`] when an account the
+//! instruction marks as a signer is not among them. Compute units and SBF
+//! memory limits are not modelled either.
+//!
+//! The harness stands in for `solana-program-test` and LiteSVM. The former
+//! is built on the Solana 1.18 crates rather than the 2.x ones Anchor 0.31
+//! uses, and the latter runs the compiled `.so`, which `cargo test` cannot
+//! produce without the SBF toolchain.
+
+mod runtime;
+mod serialization;
+mod system;
+mod token;
+
+use std::collections::{HashMap, HashSet};
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anchor_spl::associated_token::{self, spl_associated_token_account};
+use anchor_spl::token_2022;
+use thiserror::Error;
+
+use crate::runtime::{Context, PreAccount};
+
+/// A program entrypoint, such as the `entry` function Anchor generates.
+pub type ProcessInstruction =
+    for<'info> fn(&Pubkey, &'info [AccountInfo<'info>], &[u8]) -> ProgramResult;
+
+/// Lamports in one SOL.
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// Why an instruction failed.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ExecutionError {
+    #[error("program error: {0}")]
+    Program(ProgramError),
+    #[error("unknown program {0}")]
+    UnknownProgram(Pubkey),
+    #[error("missing signature for {0}")]
+    MissingSignature(Pubkey),
+    #[error("account {0} not passed to the invoked instruction")]
+    MissingAccount(Pubkey),
+    #[error("cross-program invocation escalated privileges of {0}")]
+    PrivilegeEscalation(Pubkey),
+    #[error("read-only account {0} was modified")]
+    ReadonlyModified(Pubkey),
+    #[error("account {0} was modified by a program that does not own it")]
+    ExternalAccountModified(Pubkey),
+    #[error("owner of account {0} was changed illegally")]
+    ModifiedOwner(Pubkey),
+    #[error("lamports were created or destroyed")]
+    UnbalancedLamports,
+    #[error("account {0} is left below the rent-exempt minimum")]
+    NotRentExempt(Pubkey),
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("instruction {index} failed: {error}")]
+pub struct TransactionError {
+    /// Position of the failing instruction in the transaction.
+    pub index: usize,
+    pub error: ExecutionError,
+}
+
+impl TransactionError {
+    /// The custom error code, for comparing with Anchor error enums.
+    pub fn custom_code(&self) -> Option<u32> {
+        match self.error {
+            ExecutionError::Program(ProgramError::Custom(code)) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+/// What a successful transaction left behind.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionMetadata {
+    /// Data logged with `sol_log_data`, i.e. Anchor `emit!` events.
+    pub events: Vec<Vec<u8>>,
+    /// Return data left by the last instruction.
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+impl TransactionMetadata {
+    /// Every emitted event of type `E`, in order.
+    pub fn events<E: Discriminator + AnchorDeserialize>(&self) -> Vec<E> {
+        self.events
+            .iter()
+            .filter_map(|data| data.strip_prefix(E::DISCRIMINATOR))
+            .filter_map(|mut data| E::deserialize(&mut data).ok())
+            .collect()
+    }
+}
+
+pub type TransactionResult = std::result::Result<TransactionMetadata, TransactionError>;
+
+pub struct Svm {
+    accounts: HashMap<Pubkey, Account>,
+    programs: HashMap<Pubkey, ProcessInstruction>,
+    clock: Clock,
+    rent: Rent,
+}
+
+impl Default for Svm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Svm {
+    /// A store with the system, Token-2022 and associated token account
+    /// programs and `rice_supply_chain` deployed.
+    pub fn new() -> Self {
+        let mut svm = Self {
+            accounts: HashMap::new(),
+            programs: HashMap::new(),
+            clock: Clock {
+                unix_timestamp: 1_700_000_000,
+                ..Clock::default()
+            },
+            rent: Rent::default(),
+        };
+        svm.add_program(system_program::ID, system::process_instruction);
+        svm.add_program(token_2022::ID, token::process_instruction);
+        svm.add_program(
+            associated_token::ID,
+            spl_associated_token_account::processor::process_instruction,
+        );
+        svm.add_program(rice_supply_chain::ID, rice_supply_chain::entry);
+        svm
+    }
+
+    /// Deploys `process` at `program_id` as an executable account.
+    pub fn add_program(&mut self, program_id: Pubkey, process: ProcessInstruction) {
+        self.programs.insert(program_id, process);
+        self.accounts.insert(
+            program_id,
+            Account {
+                lamports: self.rent.minimum_balance(0).max(1),
+                data: Vec::new(),
+                owner: Pubkey::default(),
+                executable: true,
+            },
+        );
+    }
+
+    /// Credits `lamports` to `address`, creating a system account if needed.
+    pub fn airdrop(&mut self, address: &Pubkey, lamports: u64) {
+        self.accounts.entry(*address).or_default().lamports += lamports;
+    }
+
+    pub fn get_account(&self, address: &Pubkey) -> Option<&Account> {
+        self.accounts.get(address)
+    }
+
+    pub fn set_account(&mut self, address: Pubkey, account: Account) {
+        self.accounts.insert(address, account);
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = (&Pubkey, &Account)> {
+        self.accounts.iter()
+    }
+
+    pub fn balance(&self, address: &Pubkey) -> u64 {
+        self.accounts
+            .get(address)
+            .map_or(0, |account| account.lamports)
+    }
+
+    /// Deserializes an Anchor account, checking its discriminator.
+    pub fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let account = self
+            .accounts
+            .get(address)
+            .ok_or(ErrorCode::AccountNotInitialized)?;
+        T::try_deserialize(&mut account.data.as_slice())
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// Moves the clock to `unix_timestamp`, advancing the slot with it.
+    pub fn warp_to_timestamp(&mut self, unix_timestamp: i64) {
+        self.clock.slot += 1;
+        self.clock.unix_timestamp = unix_timestamp;
+    }
+
+    pub fn rent(&self) -> &Rent {
+        &self.rent
+    }
+
+    /// Sends a single-instruction transaction.
+    pub fn send_instruction(
+        &mut self,
+        instruction: Instruction,
+        signers: &[Pubkey],
+    ) -> TransactionResult {
+        self.send(&[instruction], signers)
+    }
+
+    /// Executes `instructions` atomically: on failure no account changes.
+    pub fn send(&mut self, instructions: &[Instruction], signers: &[Pubkey]) -> TransactionResult {
+        let signers: HashSet<Pubkey> = signers.iter().copied().collect();
+        let snapshot = self.accounts.clone();
+        let context = Context {
+            programs: self.programs.clone(),
+            clock: self.clock.clone(),
+            rent: self.rent.clone(),
+            frames: Vec::new(),
+            return_data: None,
+            events: Vec::new(),
+            abort: None,
+        };
+
+        let (context, result) = runtime::enter(context, || {
+            for (index, instruction) in instructions.iter().enumerate() {
+                self.process(instruction, &signers)
+                    .map_err(|error| TransactionError { index, error })?;
+            }
+            Ok(())
+        });
+        match result {
+            Ok(()) => Ok(TransactionMetadata {
+                events: context.events,
+                return_data: context.return_data,
+            }),
+            Err(error) => {
+                self.accounts = snapshot;
+                Err(error)
+            }
+        }
+    }
+
+    fn process(
+        &mut self,
+        instruction: &Instruction,
+        signers: &HashSet<Pubkey>,
+    ) -> std::result::Result<(), ExecutionError> {
+        if let Some(meta) = instruction
+            .accounts
+            .iter()
+            .find(|meta| meta.is_signer && !signers.contains(&meta.pubkey))
+        {
+            return Err(ExecutionError::MissingSignature(meta.pubkey));
+        }
+
+        let pre = runtime::unique_metas(&instruction.accounts)
+            .into_iter()
+            .map(|(key, is_writable)| PreAccount {
+                key,
+                is_writable,
+                account: self.accounts.get(&key).cloned().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        let post = runtime::execute(
+            &instruction.program_id,
+            &instruction.accounts,
+            &pre,
+            |key| signers.contains(key),
+            &instruction.data,
+        )?;
+
+        for (pre, post) in pre.iter().zip(post) {
+            if pre.account == post {
+                continue;
+            }
+            if self.is_rent_paying(&post)
+                && (!self.is_rent_paying(&pre.account) || pre.account.data.len() != post.data.len())
+            {
+                return Err(ExecutionError::NotRentExempt(pre.key));
+            }
+            if post.lamports == 0 {
+                self.accounts.remove(&pre.key);
+            } else {
+                self.accounts.insert(pre.key, post);
+            }
+        }
+        Ok(())
+    }
+
+    fn is_rent_paying(&self, account: &Account) -> bool {
+        account.lamports > 0 && !self.rent.is_exempt(account.lamports, account.data.len())
+    }
+}
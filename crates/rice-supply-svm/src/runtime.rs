@@ -0,0 +1,354 @@
+//! Instruction execution and the syscall stubs programs call into.
+//!
+//! The stubs are installed once per process, but everything they touch
+//! lives in a thread-local [`Context`], so independent `Svm`s can run on
+//! parallel test threads.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{deserialize, ProgramResult};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+
+use crate::serialization::{InputAccount, SerializedInput};
+use crate::{Account, ExecutionError, ProcessInstruction};
+
+/// Sysvar syscalls return this on success.
+const SUCCESS: u64 = 0;
+
+pub(crate) struct Context {
+    pub programs: HashMap<Pubkey, ProcessInstruction>,
+    pub clock: Clock,
+    pub rent: Rent,
+    /// Active invocations, innermost last.
+    pub frames: Vec<Frame>,
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+    pub events: Vec<Vec<u8>>,
+    /// First failure inside a CPI; it aborts the whole transaction no matter
+    /// what the caller does with the error it was handed.
+    pub abort: Option<ExecutionError>,
+}
+
+/// One active program invocation.
+pub(crate) struct Frame {
+    pub program_id: Pubkey,
+    /// Each account as of the start of the invocation or the return of its
+    /// latest CPI; the program's own changes are checked against this.
+    pub base: HashMap<Pubkey, Account>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+fn with_context<R>(f: impl FnOnce(&mut Context) -> R) -> R {
+    CONTEXT.with(|context| {
+        f(context
+            .borrow_mut()
+            .as_mut()
+            .expect("syscall outside of Svm execution"))
+    })
+}
+
+/// Runs `f` with `context` installed for this thread and returns both.
+pub(crate) fn enter<R>(context: Context, f: impl FnOnce() -> R) -> (Context, R) {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+
+    CONTEXT.with(|slot| *slot.borrow_mut() = Some(context));
+    let result = f();
+    let context = CONTEXT.with(|slot| slot.borrow_mut().take()).unwrap();
+    (context, result)
+}
+
+pub(crate) fn take_abort() -> Option<ExecutionError> {
+    with_context(|context| context.abort.take())
+}
+
+fn abort(error: ExecutionError) -> ProgramError {
+    let program_error = match &error {
+        ExecutionError::Program(error) => error.clone(),
+        _ => ProgramError::InvalidArgument,
+    };
+    with_context(|context| {
+        context.abort.get_or_insert(error);
+    });
+    program_error
+}
+
+/// One unique account of an instruction, before execution.
+pub(crate) struct PreAccount {
+    pub key: Pubkey,
+    pub is_writable: bool,
+    pub account: Account,
+}
+
+/// Collapses repeated keys, merging their signer and writable flags the
+/// way a transaction message does.
+pub(crate) fn input_accounts<'a>(
+    metas: &[AccountMeta],
+    pre: &'a [PreAccount],
+    signers: impl Fn(&Pubkey) -> bool,
+) -> Vec<InputAccount<'a>> {
+    let mut first_index = HashMap::new();
+    metas
+        .iter()
+        .enumerate()
+        .map(|(index, meta)| {
+            let first = *first_index.entry(meta.pubkey).or_insert(index);
+            let account = pre.iter().find(|pre| pre.key == meta.pubkey).unwrap();
+            InputAccount {
+                key: meta.pubkey,
+                is_signer: signers(&meta.pubkey),
+                is_writable: account.is_writable,
+                account: &account.account,
+                duplicate_of: (first != index).then_some(first),
+            }
+        })
+        .collect()
+}
+
+/// Unique accounts in first-seen order, with writable flags merged.
+pub(crate) fn unique_metas(metas: &[AccountMeta]) -> Vec<(Pubkey, bool)> {
+    let mut unique: Vec<(Pubkey, bool)> = Vec::new();
+    for meta in metas {
+        match unique.iter_mut().find(|(key, _)| *key == meta.pubkey) {
+            Some((_, is_writable)) => *is_writable |= meta.is_writable,
+            None => unique.push((meta.pubkey, meta.is_writable)),
+        }
+    }
+    unique
+}
+
+/// Executes one instruction and returns the post-state of every unique
+/// account, in the order of `pre`.
+pub(crate) fn execute(
+    program_id: &Pubkey,
+    metas: &[AccountMeta],
+    pre: &[PreAccount],
+    signers: impl Fn(&Pubkey) -> bool,
+    data: &[u8],
+) -> std::result::Result<Vec<Account>, ExecutionError> {
+    let process = with_context(|context| context.programs.get(program_id).copied())
+        .ok_or(ExecutionError::UnknownProgram(*program_id))?;
+
+    let inputs = input_accounts(metas, pre, signers);
+    let mut input = SerializedInput::new(program_id, &inputs, data);
+    with_context(|context| {
+        context.frames.push(Frame {
+            program_id: *program_id,
+            base: pre
+                .iter()
+                .map(|pre| (pre.key, pre.account.clone()))
+                .collect(),
+        });
+        context.return_data = None;
+    });
+    let result = {
+        // SAFETY: `input` was laid out by `SerializedInput::new` and outlives
+        // the account infos, which are dropped before it is read back.
+        let (program_id, accounts, data) = unsafe { deserialize(input.as_mut_ptr()) };
+        process(program_id, &accounts, data)
+    };
+    let frame = with_context(|context| context.frames.pop().unwrap());
+    if let Some(error) = take_abort() {
+        return Err(error);
+    }
+    result.map_err(ExecutionError::Program)?;
+
+    let post = (0..inputs.len())
+        .filter_map(|index| input.account(index, inputs[index].account.executable))
+        .collect::<Vec<_>>();
+    let mut pre_total = 0u128;
+    let mut post_total = 0u128;
+    for (pre, post) in pre.iter().zip(&post) {
+        pre_total += pre.account.lamports as u128;
+        post_total += post.lamports as u128;
+        check_change(
+            program_id,
+            &pre.key,
+            pre.is_writable,
+            &frame.base[&pre.key],
+            post,
+        )?;
+    }
+    if pre_total != post_total {
+        return Err(ExecutionError::UnbalancedLamports);
+    }
+    Ok(post)
+}
+
+fn is_zeroed(data: &[u8]) -> bool {
+    data.iter().all(|byte| *byte == 0)
+}
+
+/// The runtime's account rules: only writable accounts change, only the
+/// owner debits lamports or writes data, and ownership moves only from the
+/// current owner over zeroed data. Lamport conservation is checked over the
+/// whole instruction instead, since lamports move between accounts.
+fn check_change(
+    program_id: &Pubkey,
+    key: &Pubkey,
+    is_writable: bool,
+    before: &Account,
+    after: &Account,
+) -> std::result::Result<(), ExecutionError> {
+    if before == after {
+        return Ok(());
+    }
+    if !is_writable {
+        return Err(ExecutionError::ReadonlyModified(*key));
+    }
+    let owned = before.owner == *program_id;
+    if after.executable != before.executable
+        || ((after.data != before.data || after.lamports < before.lamports) && !owned)
+    {
+        return Err(ExecutionError::ExternalAccountModified(*key));
+    }
+    if after.owner != before.owner && (!owned || !is_zeroed(&after.data)) {
+        return Err(ExecutionError::ModifiedOwner(*key));
+    }
+    Ok(())
+}
+
+/// Writes a callee's results back through the caller's account infos.
+fn write_back(info: &AccountInfo, account: &Account) -> ProgramResult {
+    **info.try_borrow_mut_lamports()? = account.lamports;
+    if info.data_len() != account.data.len() {
+        info.resize(account.data.len())?;
+    }
+    info.try_borrow_mut_data()?.copy_from_slice(&account.data);
+    if *info.owner != account.owner {
+        info.assign(&account.owner);
+    }
+    Ok(())
+}
+
+fn invoke_signed(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> std::result::Result<(), ExecutionError> {
+    let caller = with_context(|context| context.frames.last().unwrap().program_id);
+    let pda_signers = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| ExecutionError::Program(ProgramError::InvalidSeeds))?;
+
+    let find = |key: &Pubkey| {
+        account_infos
+            .iter()
+            .find(|info| info.key == key)
+            .ok_or(ExecutionError::MissingAccount(*key))
+    };
+    for meta in &instruction.accounts {
+        let info = find(&meta.pubkey)?;
+        let signed = info.is_signer || pda_signers.contains(&meta.pubkey);
+        if (meta.is_signer && !signed) || (meta.is_writable && !info.is_writable) {
+            return Err(ExecutionError::PrivilegeEscalation(meta.pubkey));
+        }
+    }
+
+    let unique = unique_metas(&instruction.accounts);
+    let mut pre = Vec::with_capacity(unique.len());
+    for (key, is_writable) in unique {
+        let info = find(&key)?;
+        let account = Account {
+            lamports: info.lamports(),
+            data: info
+                .try_borrow_data()
+                .map_err(ExecutionError::Program)?
+                .to_vec(),
+            owner: *info.owner,
+            executable: info.executable,
+        };
+        // The caller's changes so far must be legal before the callee sees them.
+        let before = with_context(|context| context.frames.last().unwrap().base[&key].clone());
+        check_change(&caller, &key, info.is_writable, &before, &account)?;
+        pre.push(PreAccount {
+            key,
+            is_writable,
+            account,
+        });
+    }
+
+    let signed = |key: &Pubkey| {
+        instruction
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == *key && meta.is_signer)
+    };
+    let post = execute(
+        &instruction.program_id,
+        &instruction.accounts,
+        &pre,
+        signed,
+        &instruction.data,
+    )?;
+    for (pre, post) in pre.iter().zip(post) {
+        if pre.account != post {
+            write_back(find(&pre.key)?, &post).map_err(ExecutionError::Program)?;
+        }
+        with_context(|context| {
+            context
+                .frames
+                .last_mut()
+                .unwrap()
+                .base
+                .insert(pre.key, post);
+        });
+    }
+    Ok(())
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        invoke_signed(instruction, account_infos, signers_seeds).map_err(abort)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = with_context(|context| context.clock.clone());
+        // SAFETY: `Clock::get` passes a pointer to a `Clock`.
+        unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let rent = with_context(|context| context.rent.clone());
+        // SAFETY: `Rent::get` passes a pointer to a `Rent`.
+        unsafe { std::ptr::write(var_addr as *mut Rent, rent) };
+        SUCCESS
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        with_context(|context| {
+            let program_id = context.frames.last().unwrap().program_id;
+            context.return_data = (!data.is_empty()).then(|| (program_id, data.to_vec()));
+        });
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with_context(|context| context.return_data.clone())
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        with_context(|context| context.events.push(fields.concat()));
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        with_context(|context| context.frames.len() as u64)
+    }
+}
@@ -0,0 +1,119 @@
+//! The loader's program input layout, so the program sees `AccountInfo`s
+//! backed by one buffer exactly as it would on-chain, realloc padding
+//! included.
+
+use std::mem::size_of;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::entrypoint::{
+    BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER,
+};
+
+use crate::Account;
+
+/// One instruction account as the program will see it.
+pub(crate) struct InputAccount<'a> {
+    pub key: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub account: &'a Account,
+    /// Index of the first occurrence when the key repeats.
+    pub duplicate_of: Option<usize>,
+}
+
+/// Where each unique account's mutable fields live in the buffer.
+struct AccountOffsets {
+    owner: usize,
+    lamports: usize,
+    data_len: usize,
+    data: usize,
+}
+
+pub(crate) struct SerializedInput {
+    /// `u128` backing keeps the buffer 16-byte aligned like the VM's heap.
+    buffer: Vec<u128>,
+    offsets: Vec<Option<AccountOffsets>>,
+}
+
+impl SerializedInput {
+    pub fn new(program_id: &Pubkey, accounts: &[InputAccount], data: &[u8]) -> Self {
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::with_capacity(accounts.len());
+        bytes.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+        for input in accounts {
+            if let Some(index) = input.duplicate_of {
+                bytes.push(index as u8);
+                bytes.extend_from_slice(&[0u8; 7]);
+                offsets.push(None);
+                continue;
+            }
+            bytes.push(NON_DUP_MARKER);
+            bytes.push(input.is_signer as u8);
+            bytes.push(input.is_writable as u8);
+            bytes.push(input.account.executable as u8);
+            bytes.extend_from_slice(&[0u8; 4]);
+            bytes.extend_from_slice(input.key.as_ref());
+            let owner = bytes.len();
+            bytes.extend_from_slice(input.account.owner.as_ref());
+            let lamports = bytes.len();
+            bytes.extend_from_slice(&input.account.lamports.to_le_bytes());
+            let data_len = bytes.len();
+            bytes.extend_from_slice(&(input.account.data.len() as u64).to_le_bytes());
+            let data_offset = bytes.len();
+            bytes.extend_from_slice(&input.account.data);
+            bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            bytes.resize(bytes.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+            bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+            offsets.push(Some(AccountOffsets {
+                owner,
+                lamports,
+                data_len,
+                data: data_offset,
+            }));
+        }
+        bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(program_id.as_ref());
+
+        let mut buffer = vec![0u128; bytes.len().div_ceil(size_of::<u128>())];
+        // SAFETY: the `u128` buffer spans at least `bytes.len()` bytes.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                buffer.as_mut_ptr() as *mut u8,
+                bytes.len(),
+            );
+        }
+        Self { buffer, offsets }
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.buffer.as_mut_ptr() as *mut u8
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: reinterpreting initialized `u128`s as bytes.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.buffer.as_ptr() as *const u8,
+                self.buffer.len() * size_of::<u128>(),
+            )
+        }
+    }
+
+    /// Reads back the state of account `index`, or `None` for duplicates.
+    pub fn account(&self, index: usize, executable: bool) -> Option<Account> {
+        let offsets = self.offsets[index].as_ref()?;
+        let bytes = self.bytes();
+        let read_u64 = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let data_len = read_u64(offsets.data_len) as usize;
+        Some(Account {
+            lamports: read_u64(offsets.lamports),
+            data: bytes[offsets.data..offsets.data + data_len].to_vec(),
+            owner: Pubkey::new_from_array(
+                bytes[offsets.owner..offsets.owner + 32].try_into().unwrap(),
+            ),
+            executable,
+        })
+    }
+}
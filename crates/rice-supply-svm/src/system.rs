@@ -0,0 +1,113 @@
+//! The subset of the system program that Anchor's `init` and plain
+//! transfers rely on: `CreateAccount`, `Assign`, `Transfer` and `Allocate`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::system_program;
+
+/// `SystemError::AccountAlreadyInUse`.
+const ACCOUNT_ALREADY_IN_USE: u32 = 0;
+/// `SystemError::ResultWithNegativeLamports`.
+const RESULT_WITH_NEGATIVE_LAMPORTS: u32 = 1;
+
+const CREATE_ACCOUNT: u32 = 0;
+const ASSIGN: u32 = 1;
+const TRANSFER: u32 = 2;
+const ALLOCATE: u32 = 8;
+
+fn read_u64(data: &[u8], at: usize) -> std::result::Result<u64, ProgramError> {
+    data.get(at..at + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+fn read_pubkey(data: &[u8], at: usize) -> std::result::Result<Pubkey, ProgramError> {
+    data.get(at..at + 32)
+        .map(|bytes| Pubkey::new_from_array(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+fn account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    index: usize,
+) -> std::result::Result<&'a AccountInfo<'info>, ProgramError> {
+    accounts
+        .get(index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)
+}
+
+fn require_signer(info: &AccountInfo) -> ProgramResult {
+    if info.is_signer {
+        Ok(())
+    } else {
+        Err(ProgramError::MissingRequiredSignature)
+    }
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    require_signer(from)?;
+    if !from.data_is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let balance = from.lamports();
+    if balance < lamports {
+        return Err(ProgramError::Custom(RESULT_WITH_NEGATIVE_LAMPORTS));
+    }
+    **from.try_borrow_mut_lamports()? = balance - lamports;
+    let to_balance = to.lamports();
+    **to.try_borrow_mut_lamports()? = to_balance
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+fn allocate(info: &AccountInfo, space: u64) -> ProgramResult {
+    require_signer(info)?;
+    if !info.data_is_empty() || *info.owner != system_program::ID {
+        return Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE));
+    }
+    info.resize(space as usize)
+}
+
+fn assign(info: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if info.owner == owner {
+        return Ok(());
+    }
+    require_signer(info)?;
+    info.assign(owner);
+    Ok(())
+}
+
+pub(crate) fn process_instruction<'info>(
+    _program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    let tag = data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match tag {
+        CREATE_ACCOUNT => {
+            let lamports = read_u64(data, 4)?;
+            let space = read_u64(data, 12)?;
+            let owner = read_pubkey(data, 20)?;
+            let from = account(accounts, 0)?;
+            let to = account(accounts, 1)?;
+            if to.lamports() > 0 {
+                return Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE));
+            }
+            allocate(to, space)?;
+            assign(to, &owner)?;
+            transfer(from, to, lamports)
+        }
+        ASSIGN => assign(account(accounts, 0)?, &read_pubkey(data, 4)?),
+        TRANSFER => transfer(
+            account(accounts, 0)?,
+            account(accounts, 1)?,
+            read_u64(data, 4)?,
+        ),
+        ALLOCATE => allocate(account(accounts, 0)?, read_u64(data, 4)?),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
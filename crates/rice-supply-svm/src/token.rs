@@ -0,0 +1,95 @@
+//! Token-2022, run from its crate. It reaches transfer hooks through
+//! `solana_cpi::invoke`, which does nothing off-chain rather than calling
+//! the syscall stubs, so hooked transfers are completed here: once the
+//! processor has moved the tokens, the hook runs with the same accounts and
+//! `transferring` flags the processor gives it on-chain.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token_2022::spl_token_2022;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_token_2022::extension::transfer_hook::{self, TransferHookAccount};
+use spl_token_2022::extension::{
+    BaseStateWithExtensionsMut, StateWithExtensions, StateWithExtensionsMut,
+};
+use spl_token_2022::instruction::TokenInstruction;
+use spl_token_2022::processor::Processor;
+use spl_token_2022::state::{Account, Mint};
+use spl_transfer_hook_interface::get_extra_account_metas_address;
+use spl_transfer_hook_interface::instruction::{execute, ExecuteInstruction};
+
+pub(crate) fn process_instruction<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    Processor::process(program_id, accounts, data)?;
+    // Interface instructions, such as token metadata ones, do not unpack.
+    match TokenInstruction::unpack(data) {
+        Ok(TokenInstruction::TransferChecked { amount, .. }) => {
+            invoke_transfer_hook(accounts, amount)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `spl_transfer_hook_interface::onchain::invoke_execute` for the mint of
+/// a `TransferChecked`, if it names a hook program.
+fn invoke_transfer_hook<'info>(accounts: &[AccountInfo<'info>], amount: u64) -> ProgramResult {
+    let [source, mint, destination, authority, additional @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let hook_program_id = {
+        let data = mint.try_borrow_data()?;
+        transfer_hook::get_program_id(&StateWithExtensions::<Mint>::unpack(&data)?)
+    };
+    let Some(hook_program_id) = hook_program_id else {
+        return Ok(());
+    };
+
+    set_transferring(source, true)?;
+    set_transferring(destination, true)?;
+    let mut instruction = execute(
+        &hook_program_id,
+        source.key,
+        mint.key,
+        destination.key,
+        authority.key,
+        amount,
+    );
+    let mut account_infos = vec![
+        source.clone(),
+        mint.clone(),
+        destination.clone(),
+        authority.clone(),
+    ];
+    let validation_pubkey = get_extra_account_metas_address(mint.key, &hook_program_id);
+    if let Some(validation) = additional
+        .iter()
+        .find(|info| *info.key == validation_pubkey)
+    {
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(validation_pubkey, false));
+        account_infos.push(validation.clone());
+        ExtraAccountMetaList::add_to_cpi_instruction::<ExecuteInstruction>(
+            &mut instruction,
+            &mut account_infos,
+            &validation.try_borrow_data()?,
+            additional,
+        )?;
+    }
+    invoke(&instruction, &account_infos)?;
+    set_transferring(source, false)?;
+    set_transferring(destination, false)
+}
+
+fn set_transferring(token_account: &AccountInfo, transferring: bool) -> ProgramResult {
+    let mut data = token_account.try_borrow_mut_data()?;
+    let mut state = StateWithExtensionsMut::<Account>::unpack(&mut data)?;
+    state
+        .get_extension_mut::<TransferHookAccount>()?
+        .transferring = transferring.into();
+    Ok(())
+}
@@ -0,0 +1,323 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::system_program;
+use rice_supply_svm::{Account, ExecutionError, Svm, TransactionError, LAMPORTS_PER_SOL};
+
+const ROGUE: Pubkey = Pubkey::new_from_array([7; 32]);
+
+/// Writes a byte of the first account's data.
+const WRITE: u8 = 0;
+/// Takes a lamport from the first account and gives it to no one.
+const BURN: u8 = 1;
+/// Moves a lamport from the first account to the second.
+const MOVE: u8 = 2;
+/// Makes the first account its own.
+const ASSIGN: u8 = 3;
+/// Has the system program move a lamport from the first account to the
+/// second.
+const TRANSFER: u8 = 4;
+/// Like `TRANSFER`, signing for the first account as the `vault` PDA.
+const TRANSFER_SIGNED: u8 = 5;
+
+/// A program that does whatever its first byte of data asks of its
+/// accounts, allowed or not.
+fn rogue<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    match data[0] {
+        WRITE => accounts[0].try_borrow_mut_data()?[0] = 1,
+        BURN => **accounts[0].try_borrow_mut_lamports()? -= 1,
+        MOVE => {
+            **accounts[0].try_borrow_mut_lamports()? -= 1;
+            **accounts[1].try_borrow_mut_lamports()? += 1;
+        }
+        ASSIGN => accounts[0].assign(program_id),
+        TRANSFER => invoke(&transfer(accounts[0].key, accounts[1].key, 1), accounts)?,
+        TRANSFER_SIGNED => {
+            let (_, bump) = Pubkey::find_program_address(&[b"vault"], program_id);
+            invoke_signed(
+                &transfer(accounts[0].key, accounts[1].key, 1),
+                accounts,
+                &[&[b"vault", &[bump]]],
+            )?;
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+    Ok(())
+}
+
+/// The system program's `Transfer`.
+fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction::new_with_bytes(
+        system_program::ID,
+        &data,
+        vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+    )
+}
+
+fn run(op: u8, accounts: Vec<AccountMeta>) -> Instruction {
+    let mut accounts = accounts;
+    accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+    Instruction::new_with_bytes(ROGUE, &[op], accounts)
+}
+
+fn error(result: rice_supply_svm::TransactionResult) -> ExecutionError {
+    result.unwrap_err().error
+}
+
+/// An `Svm` with `rogue` deployed, a rent-exempt account it owns and a
+/// funded wallet.
+struct Rogue {
+    svm: Svm,
+    owned: Pubkey,
+    wallet: Pubkey,
+}
+
+impl Rogue {
+    fn new() -> Self {
+        let mut svm = Svm::new();
+        svm.add_program(ROGUE, rogue);
+        let owned = Pubkey::new_unique();
+        svm.set_account(
+            owned,
+            Account {
+                lamports: svm.rent().minimum_balance(8) + 10,
+                data: vec![0; 8],
+                owner: ROGUE,
+                executable: false,
+            },
+        );
+        let wallet = Pubkey::new_unique();
+        svm.airdrop(&wallet, LAMPORTS_PER_SOL);
+        Self { svm, owned, wallet }
+    }
+
+    /// A rent-exempt account with data, owned by `owner`.
+    fn account(&mut self, owner: Pubkey) -> Pubkey {
+        let address = Pubkey::new_unique();
+        self.svm.set_account(
+            address,
+            Account {
+                lamports: self.svm.rent().minimum_balance(8),
+                data: vec![0; 8],
+                owner,
+                executable: false,
+            },
+        );
+        address
+    }
+}
+
+#[test]
+fn only_writable_accounts_change() {
+    let mut rogue = Rogue::new();
+    let owned = rogue.owned;
+    assert_eq!(
+        error(rogue.svm.send_instruction(
+            run(WRITE, vec![AccountMeta::new_readonly(owned, false)]),
+            &[]
+        )),
+        ExecutionError::ReadonlyModified(owned)
+    );
+    assert_eq!(rogue.svm.get_account(&owned).unwrap().data[0], 0);
+
+    rogue
+        .svm
+        .send_instruction(run(WRITE, vec![AccountMeta::new(owned, false)]), &[])
+        .unwrap();
+    assert_eq!(rogue.svm.get_account(&owned).unwrap().data[0], 1);
+}
+
+#[test]
+fn only_the_owner_writes_data_or_debits() {
+    let mut rogue = Rogue::new();
+    let foreign = rogue.account(Pubkey::new_unique());
+    assert_eq!(
+        error(
+            rogue
+                .svm
+                .send_instruction(run(WRITE, vec![AccountMeta::new(foreign, false)]), &[])
+        ),
+        ExecutionError::ExternalAccountModified(foreign)
+    );
+
+    let (owned, wallet) = (rogue.owned, rogue.wallet);
+    let metas = vec![
+        AccountMeta::new(wallet, true),
+        AccountMeta::new(owned, false),
+    ];
+    assert_eq!(
+        error(rogue.svm.send_instruction(run(MOVE, metas), &[wallet])),
+        ExecutionError::ExternalAccountModified(wallet)
+    );
+
+    // Crediting an account the program does not own is fine.
+    let before = rogue.svm.balance(&wallet);
+    let metas = vec![
+        AccountMeta::new(owned, false),
+        AccountMeta::new(wallet, false),
+    ];
+    rogue.svm.send_instruction(run(MOVE, metas), &[]).unwrap();
+    assert_eq!(rogue.svm.balance(&wallet), before + 1);
+}
+
+#[test]
+fn only_the_owner_hands_an_account_over() {
+    let mut rogue = Rogue::new();
+    let foreign = rogue.account(system_program::ID);
+    assert_eq!(
+        error(
+            rogue
+                .svm
+                .send_instruction(run(ASSIGN, vec![AccountMeta::new(foreign, false)]), &[])
+        ),
+        ExecutionError::ModifiedOwner(foreign)
+    );
+    assert_eq!(
+        rogue.svm.get_account(&foreign).unwrap().owner,
+        system_program::ID
+    );
+}
+
+#[test]
+fn lamports_are_neither_created_nor_destroyed() {
+    let mut rogue = Rogue::new();
+    let owned = rogue.owned;
+    let before = rogue.svm.balance(&owned);
+    assert_eq!(
+        error(
+            rogue
+                .svm
+                .send_instruction(run(BURN, vec![AccountMeta::new(owned, false)]), &[])
+        ),
+        ExecutionError::UnbalancedLamports
+    );
+    assert_eq!(rogue.svm.balance(&owned), before);
+}
+
+#[test]
+fn signers_and_writable_accounts_are_not_escalated() {
+    let mut rogue = Rogue::new();
+    let (wallet, to) = (rogue.wallet, Pubkey::new_unique());
+    rogue.svm.airdrop(&to, LAMPORTS_PER_SOL);
+
+    let metas = vec![AccountMeta::new(wallet, true), AccountMeta::new(to, false)];
+    assert_eq!(
+        error(
+            rogue
+                .svm
+                .send_instruction(run(TRANSFER, metas.clone()), &[])
+        ),
+        ExecutionError::MissingSignature(wallet)
+    );
+
+    let unsigned = vec![AccountMeta::new(wallet, false), AccountMeta::new(to, false)];
+    assert_eq!(
+        error(rogue.svm.send_instruction(run(TRANSFER, unsigned), &[])),
+        ExecutionError::PrivilegeEscalation(wallet)
+    );
+
+    let readonly = vec![
+        AccountMeta::new(wallet, true),
+        AccountMeta::new_readonly(to, false),
+    ];
+    assert_eq!(
+        error(
+            rogue
+                .svm
+                .send_instruction(run(TRANSFER, readonly), &[wallet])
+        ),
+        ExecutionError::PrivilegeEscalation(to)
+    );
+
+    rogue
+        .svm
+        .send_instruction(run(TRANSFER, metas), &[wallet])
+        .unwrap();
+    assert_eq!(rogue.svm.balance(&to), LAMPORTS_PER_SOL + 1);
+}
+
+#[test]
+fn programs_sign_for_their_own_addresses() {
+    let mut rogue = Rogue::new();
+    let vault = Pubkey::find_program_address(&[b"vault"], &ROGUE).0;
+    let (other, to) = (Pubkey::new_unique(), rogue.wallet);
+    rogue.svm.airdrop(&vault, LAMPORTS_PER_SOL);
+    rogue.svm.airdrop(&other, LAMPORTS_PER_SOL);
+
+    let metas = vec![AccountMeta::new(other, false), AccountMeta::new(to, false)];
+    assert_eq!(
+        error(rogue.svm.send_instruction(run(TRANSFER_SIGNED, metas), &[])),
+        ExecutionError::PrivilegeEscalation(other)
+    );
+
+    let metas = vec![AccountMeta::new(vault, false), AccountMeta::new(to, false)];
+    rogue
+        .svm
+        .send_instruction(run(TRANSFER_SIGNED, metas), &[])
+        .unwrap();
+    assert_eq!(rogue.svm.balance(&vault), LAMPORTS_PER_SOL - 1);
+}
+
+#[test]
+fn accounts_stay_rent_exempt_or_close() {
+    let mut rogue = Rogue::new();
+    let (wallet, to) = (rogue.wallet, Pubkey::new_unique());
+    let minimum = rogue.svm.rent().minimum_balance(0);
+
+    // Too little to open `to`, or too much to leave `wallet` standing.
+    assert_eq!(
+        error(
+            rogue
+                .svm
+                .send_instruction(transfer(&wallet, &to, minimum - 1), &[wallet])
+        ),
+        ExecutionError::NotRentExempt(to)
+    );
+    assert_eq!(
+        error(
+            rogue
+                .svm
+                .send_instruction(transfer(&wallet, &to, LAMPORTS_PER_SOL - 1), &[wallet])
+        ),
+        ExecutionError::NotRentExempt(wallet)
+    );
+
+    // Emptying `wallet` closes it.
+    rogue
+        .svm
+        .send_instruction(transfer(&wallet, &to, LAMPORTS_PER_SOL), &[wallet])
+        .unwrap();
+    assert!(rogue.svm.get_account(&wallet).is_none());
+    assert_eq!(rogue.svm.balance(&to), LAMPORTS_PER_SOL);
+}
+
+#[test]
+fn failed_transactions_change_nothing() {
+    let mut rogue = Rogue::new();
+    let (wallet, owned, to) = (rogue.wallet, rogue.owned, Pubkey::new_unique());
+    let result = rogue.svm.send(
+        &[
+            transfer(&wallet, &to, LAMPORTS_PER_SOL / 2),
+            run(WRITE, vec![AccountMeta::new(owned, false)]),
+            run(BURN, vec![AccountMeta::new(owned, false)]),
+        ],
+        &[wallet],
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError {
+            index: 2,
+            error: ExecutionError::UnbalancedLamports,
+        }
+    );
+    assert_eq!(rogue.svm.balance(&wallet), LAMPORTS_PER_SOL);
+    assert!(rogue.svm.get_account(&to).is_none());
+    assert_eq!(rogue.svm.get_account(&owned).unwrap().data[0], 0);
+}
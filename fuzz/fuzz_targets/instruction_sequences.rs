@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rice_supply_fuzz::Action;
+
+fuzz_target!(|actions: Vec<Action>| {
+    rice_supply_fuzz::run(&actions);
+});
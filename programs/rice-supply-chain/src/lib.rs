@@ -1,7 +1,30 @@
+#![allow(clippy::too_many_arguments)]
+// anchor-lang 0.31 still emits `AccountInfo::realloc` from `#[program]`.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_hook::TransferHookAccount;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::Account as SplTokenAccount;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::spl_token_metadata_interface::state::{Field, TokenMetadata};
+use anchor_spl::token_interface::{
+    self, Burn, Mint, MintTo, TokenAccount, TokenMetadataInitialize, TokenMetadataUpdateField,
+};
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::account::ExtraAccountMeta;
+use spl_tlv_account_resolution::seeds::Seed;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
 
 declare_id!("Fj91LUA1dkxzpBsbZgpRNrnN6aEs4ZoryUDH89iB6757");
 
+// Seed arguments are consumed by the accounts' `#[instruction]` attributes
+// rather than by the handlers, but they are part of each instruction's ABI.
+#[allow(unused_variables)]
 #[program]
 pub mod rice_supply_chain {
     use super::*;
@@ -9,6 +32,7 @@ pub mod rice_supply_chain {
     // Chain Actor Instructions
     pub fn create_chain_actor(
         ctx: Context<CreateChainActor>,
+        actor_seed: String,
         name: String,
         actor_type: Vec<String>,
         farm_id: Option<Pubkey>,
@@ -23,6 +47,7 @@ pub mod rice_supply_chain {
         let public_key = chain_actor.key();
         
         chain_actor.public_key = public_key;
+        chain_actor.authority = ctx.accounts.user.key();
         chain_actor.name = name;
         chain_actor.actor_type = actor_type;
         chain_actor.farm_id = farm_id;
@@ -41,6 +66,7 @@ pub mod rice_supply_chain {
 
     pub fn update_chain_actor(
         ctx: Context<UpdateChainActor>,
+        actor_seed: String,
         name: Option<String>,
         actor_type: Option<Vec<String>>,
         farm_id: Option<Pubkey>,
@@ -90,7 +116,7 @@ pub mod rice_supply_chain {
         Ok(())
     }
 
-    pub fn delete_chain_actor(ctx: Context<DeleteChainActor>) -> Result<()> {
+    pub fn delete_chain_actor(ctx: Context<DeleteChainActor>, actor_seed: String) -> Result<()> {
         let chain_actor = &mut ctx.accounts.chain_actor;
         chain_actor.is_active = false;
         Ok(())
@@ -99,20 +125,30 @@ pub mod rice_supply_chain {
     // Production Season Instructions
     pub fn create_production_season(
         ctx: Context<CreateProductionSeason>,
+        season_seed: String,
         farmer_id: Pubkey,
         crop_year: String,
         processed_yield_kg: u64,
-        variety: Option<String>,
+        variety: Option<RiceVariety>,
         planned_practice: Option<String>,
         planting_date: Option<i64>,
-        irrigation_practice: Option<String>,
+        irrigation_practice: Option<WaterRegime>,
         fertilizer_used: Option<String>,
         pesticide_used: Option<String>,
         harvest_date: Option<i64>,
         total_yield_kg: Option<u64>,
         moisture_content: Option<u32>,
-        carbon_smart_certified: bool,
+        cultivated_area_m2: Option<u64>,
+        organic_amendments: Vec<OrganicAmendment>,
     ) -> Result<()> {
+        if let Some(variety) = &variety {
+            variety.validate()?;
+        }
+        require!(
+            organic_amendments.len() <= ProductionSeason::MAX_ORGANIC_AMENDMENTS,
+            ErrorCode::TooManyEntries
+        );
+
         let production_season = &mut ctx.accounts.production_season;
         let clock = Clock::get()?;
         let public_key = production_season.key();
@@ -130,34 +166,57 @@ pub mod rice_supply_chain {
         production_season.harvest_date = harvest_date;
         production_season.total_yield_kg = total_yield_kg;
         production_season.moisture_content = moisture_content;
-        production_season.carbon_smart_certified = carbon_smart_certified;
         production_season.validation_status = ValidationStatus::Pending;
         production_season.validator_id = None;
+        production_season.nitrogen_applied_g_per_ha = 0;
+        production_season.pre_harvest_clear_at = None;
+        production_season.post_harvest_loss_kg = 0;
+        production_season.milled_paddy_kg = 0;
+        production_season.cultivated_area_m2 = cultivated_area_m2;
+        production_season.organic_amendments = organic_amendments;
+        production_season.carbon_credits = None;
         production_season.created_at = clock.unix_timestamp;
         production_season.updated_at = clock.unix_timestamp;
-        
+        if let Some(substance_registry) = SubstanceRegistry::load(&ctx.accounts.substance_registry)? {
+            substance_registry.screen_season(production_season, clock.unix_timestamp);
+        }
+        production_season.methane_estimate_g = EmissionFactors::load(&ctx.accounts.emission_factors)?
+            .and_then(|emission_factors| emission_factors.methane_g(production_season));
+
         Ok(())
     }
 
     pub fn update_production_season(
         ctx: Context<UpdateProductionSeason>,
+        season_seed: String,
         crop_year: Option<String>,
         processed_yield_kg: Option<u64>,
-        variety: Option<String>,
+        variety: Option<RiceVariety>,
         planned_practice: Option<String>,
         planting_date: Option<i64>,
-        irrigation_practice: Option<String>,
+        irrigation_practice: Option<WaterRegime>,
         fertilizer_used: Option<String>,
         pesticide_used: Option<String>,
         harvest_date: Option<i64>,
         total_yield_kg: Option<u64>,
         moisture_content: Option<u32>,
-        carbon_smart_certified: Option<bool>,
-        validation_status: Option<ValidationStatus>,
-        validator_id: Option<Pubkey>,
+        cultivated_area_m2: Option<u64>,
+        organic_amendments: Option<Vec<OrganicAmendment>>,
     ) -> Result<()> {
         let production_season = &mut ctx.accounts.production_season;
         let clock = Clock::get()?;
+        // Issued credits stand on these fields, and on the approval that
+        // changing any of them withdraws.
+        require!(
+            production_season.carbon_credits.is_none()
+                || (planting_date.is_none()
+                    && irrigation_practice.is_none()
+                    && harvest_date.is_none()
+                    && total_yield_kg.is_none()
+                    && cultivated_area_m2.is_none()
+                    && organic_amendments.is_none()),
+            ErrorCode::CarbonCreditsAlreadyIssued
+        );
         
         if let Some(crop_year) = crop_year {
             production_season.crop_year = crop_year;
@@ -166,6 +225,7 @@ pub mod rice_supply_chain {
             production_season.processed_yield_kg = processed_yield_kg;
         }
         if let Some(variety) = variety {
+            variety.validate()?;
             production_season.variety = Some(variety);
         }
         if let Some(planned_practice) = planned_practice {
@@ -184,30 +244,111 @@ pub mod rice_supply_chain {
             production_season.pesticide_used = Some(pesticide_used);
         }
         if let Some(harvest_date) = harvest_date {
+            require!(
+                production_season
+                    .pre_harvest_clear_at
+                    .is_none_or(|clear_at| harvest_date >= clear_at),
+                ErrorCode::PreHarvestIntervalNotMet
+            );
             production_season.harvest_date = Some(harvest_date);
         }
         if let Some(total_yield_kg) = total_yield_kg {
+            let accounted_kg = production_season
+                .post_harvest_loss_kg
+                .checked_add(production_season.milled_paddy_kg)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            require!(total_yield_kg >= accounted_kg, ErrorCode::InsufficientQuantity);
             production_season.total_yield_kg = Some(total_yield_kg);
         }
         if let Some(moisture_content) = moisture_content {
             production_season.moisture_content = Some(moisture_content);
         }
-        if let Some(carbon_smart_certified) = carbon_smart_certified {
-            production_season.carbon_smart_certified = carbon_smart_certified;
+        if let Some(cultivated_area_m2) = cultivated_area_m2 {
+            production_season.cultivated_area_m2 = Some(cultivated_area_m2);
         }
-        if let Some(validation_status) = validation_status {
-            production_season.validation_status = validation_status;
+        let organic_amendments_changed = organic_amendments.is_some();
+        if let Some(organic_amendments) = organic_amendments {
+            require!(
+                organic_amendments.len() <= ProductionSeason::MAX_ORGANIC_AMENDMENTS,
+                ErrorCode::TooManyEntries
+            );
+            production_season.organic_amendments = organic_amendments;
         }
-        if let Some(validator_id) = validator_id {
-            production_season.validator_id = Some(validator_id);
+        // An approval covers the figures the validator saw.
+        if production_season.validation_status == ValidationStatus::Approved
+            && (planting_date.is_some()
+                || irrigation_practice.is_some()
+                || harvest_date.is_some()
+                || total_yield_kg.is_some()
+                || cultivated_area_m2.is_some()
+                || organic_amendments_changed)
+        {
+            production_season.validation_status = ValidationStatus::Pending;
         }
         
+        production_season.updated_at = clock.unix_timestamp;
+        if let Some(substance_registry) = SubstanceRegistry::load(&ctx.accounts.substance_registry)? {
+            substance_registry.screen_season(production_season, clock.unix_timestamp);
+        }
+        production_season.methane_estimate_g = EmissionFactors::load(&ctx.accounts.emission_factors)?
+            .and_then(|emission_factors| emission_factors.methane_g(production_season));
+        require!(
+            !(production_season.validation_status == ValidationStatus::Approved
+                && production_season.compliance_status == ComplianceStatus::NonCompliant),
+            ErrorCode::SeasonNotCompliant
+        );
+        Ok(())
+    }
+
+    /// Records the verdict of `validator`, an actor holding a valid
+    /// `Validator` accreditation, whose authority signs. Only validators
+    /// approve or reject seasons, and only an approval can be withdrawn to
+    /// `Pending`.
+    pub fn validate_production_season(
+        ctx: Context<ValidateProductionSeason>,
+        season_seed: String,
+        validation_status: ValidationStatus,
+    ) -> Result<()> {
+        let production_season = &mut ctx.accounts.production_season;
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.accreditation.is_valid_at(clock.unix_timestamp),
+            ErrorCode::AccreditationInactive
+        );
+        require!(
+            validation_status == production_season.validation_status
+                || production_season
+                    .validation_status
+                    .can_transition_to(&validation_status),
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            !(validation_status == ValidationStatus::Approved
+                && production_season.compliance_status == ComplianceStatus::NonCompliant),
+            ErrorCode::SeasonNotCompliant
+        );
+        // Issued credits stand on the approval.
+        require!(
+            production_season.carbon_credits.is_none()
+                || validation_status == production_season.validation_status,
+            ErrorCode::CarbonCreditsAlreadyIssued
+        );
+
+        production_season.validation_status = validation_status;
+        production_season.validator_id = Some(ctx.accounts.validator.key());
         production_season.updated_at = clock.unix_timestamp;
         Ok(())
     }
 
-    pub fn delete_production_season(ctx: Context<DeleteProductionSeason>) -> Result<()> {
+    pub fn delete_production_season(
+        ctx: Context<DeleteProductionSeason>,
+        season_seed: String,
+    ) -> Result<()> {
         let production_season = &mut ctx.accounts.production_season;
+        require!(
+            production_season.carbon_credits.is_none(),
+            ErrorCode::CarbonCreditsAlreadyIssued
+        );
         production_season.validation_status = ValidationStatus::Rejected;
         Ok(())
     }
@@ -215,26 +356,39 @@ pub mod rice_supply_chain {
     // Milled Rice Instructions
     pub fn create_milled_rice(
         ctx: Context<CreateMilledRice>,
+        milling_seed: String,
         farmer_id: Pubkey,
-        total_weight_kg: String,
-        milling_type: String,
-        quality: String,
+        total_weight_kg: u32,
+        milling_type: MillingType,
+        quality: QualityGrade,
         photo_urls: Vec<String>,
         moisture: u32,
         total_weight_processed_kg: u32,
+        by_products: ByProducts,
     ) -> Result<()> {
         let milled_rice = &mut ctx.accounts.milled_rice;
+        let production_season = &mut ctx.accounts.production_season;
         let clock = Clock::get()?;
         let public_key = milled_rice.key();
         
+        if let Some(harvest_date) = production_season.harvest_date {
+            require!(harvest_date <= clock.unix_timestamp, ErrorCode::BeforeHarvest);
+        }
+        production_season.record_milling(0, total_weight_processed_kg as u64)?;
+
         milled_rice.public_key = public_key;
         milled_rice.farmer_id = farmer_id;
+        milled_rice.production_season = production_season.key();
+        milled_rice.creator = ctx.accounts.user.key();
         milled_rice.total_weight_kg = total_weight_kg;
+        milled_rice.allocated_kg = 0;
         milled_rice.milling_type = milling_type;
         milled_rice.quality = quality;
         milled_rice.photo_urls = photo_urls;
         milled_rice.moisture = moisture;
         milled_rice.total_weight_processed_kg = total_weight_processed_kg;
+        milled_rice.by_products = by_products;
+        milled_rice.update_recovery()?;
         milled_rice.created_at = clock.unix_timestamp;
         milled_rice.updated_at = clock.unix_timestamp;
         
@@ -243,17 +397,23 @@ pub mod rice_supply_chain {
 
     pub fn update_milled_rice(
         ctx: Context<UpdateMilledRice>,
-        total_weight_kg: Option<String>,
-        milling_type: Option<String>,
-        quality: Option<String>,
+        milling_seed: String,
+        total_weight_kg: Option<u32>,
+        milling_type: Option<MillingType>,
+        quality: Option<QualityGrade>,
         photo_urls: Option<Vec<String>>,
         moisture: Option<u32>,
         total_weight_processed_kg: Option<u32>,
+        by_products: Option<ByProducts>,
     ) -> Result<()> {
         let milled_rice = &mut ctx.accounts.milled_rice;
         let clock = Clock::get()?;
         
         if let Some(total_weight_kg) = total_weight_kg {
+            require!(
+                total_weight_kg >= milled_rice.allocated_kg,
+                ErrorCode::InsufficientQuantity
+            );
             milled_rice.total_weight_kg = total_weight_kg;
         }
         if let Some(milling_type) = milling_type {
@@ -269,8 +429,16 @@ pub mod rice_supply_chain {
             milled_rice.moisture = moisture;
         }
         if let Some(total_weight_processed_kg) = total_weight_processed_kg {
+            ctx.accounts.production_season.record_milling(
+                milled_rice.total_weight_processed_kg as u64,
+                total_weight_processed_kg as u64,
+            )?;
             milled_rice.total_weight_processed_kg = total_weight_processed_kg;
         }
+        if let Some(by_products) = by_products {
+            milled_rice.by_products = by_products;
+        }
+        milled_rice.update_recovery()?;
         
         milled_rice.updated_at = clock.unix_timestamp;
         Ok(())
@@ -279,12 +447,18 @@ pub mod rice_supply_chain {
     // Rice Batch Instructions
     pub fn create_rice_batch(
         ctx: Context<CreateRiceBatch>,
+        batch_seed: String,
         milled_rice_id: Pubkey,
         batch_status: BatchStatus,
         quality_score: u32,
         weight_kg: u32,
         qr_code: String,
     ) -> Result<()> {
+        require!(qr_code.len() <= RiceBatch::MAX_QR_CODE_LEN, ErrorCode::StringTooLong);
+        // Batches reach later statuses only through their transitions.
+        require!(batch_status == BatchStatus::Created, ErrorCode::InvalidStatusTransition);
+        let gs1 = Gs1Key::from_digital_link(&qr_code)?;
+        ctx.accounts.milled_rice.allocate(0, weight_kg)?;
         let rice_batch = &mut ctx.accounts.rice_batch;
         let clock = Clock::get()?;
         let public_key = rice_batch.key();
@@ -295,41 +469,117 @@ pub mod rice_supply_chain {
         rice_batch.quality_score = quality_score;
         rice_batch.weight_kg = weight_kg;
         rice_batch.qr_code = qr_code;
+        rice_batch.gs1 = gs1;
+        rice_batch.effective_grade = None;
+        rice_batch.latest_quality_test = None;
+        rice_batch.current_warehouse = None;
+        rice_batch.current_holder = None;
+        rice_batch.creator = ctx.accounts.user.key();
+        rice_batch.token_mint = None;
+        rice_batch.storage_count = 0;
+        rice_batch.remaining_weight_g = weight_kg as u64 * 1_000;
+        rice_batch.next_pack_serial = 0;
         rice_batch.created_at = clock.unix_timestamp;
         rice_batch.updated_at = clock.unix_timestamp;
-        
+
+        let qr_index = &mut ctx.accounts.qr_index;
+        qr_index.rice_batch = public_key;
+        qr_index.payload_hash = QrIndex::payload_hash(&rice_batch.qr_code);
+        qr_index.created_at = clock.unix_timestamp;
+
         Ok(())
     }
 
+    /// A tokenized batch keeps the weight its token supply and metadata
+    /// were minted for.
     pub fn update_rice_batch(
         ctx: Context<UpdateRiceBatch>,
+        batch_seed: String,
         batch_status: Option<BatchStatus>,
         quality_score: Option<u32>,
         weight_kg: Option<u32>,
-        qr_code: Option<String>,
     ) -> Result<()> {
+        ctx.accounts
+            .rice_batch
+            .check_custody(ctx.accounts.holder.as_ref(), &ctx.accounts.user.key())?;
         let rice_batch = &mut ctx.accounts.rice_batch;
         let clock = Clock::get()?;
         
         if let Some(batch_status) = batch_status {
+            if batch_status != rice_batch.batch_status {
+                require!(
+                    rice_batch.batch_status.can_transition_to(&batch_status),
+                    ErrorCode::InvalidStatusTransition
+                );
+                require!(
+                    !(batch_status == BatchStatus::InTransit
+                        && rice_batch.current_warehouse.is_some()),
+                    ErrorCode::BatchInStorage
+                );
+            }
             rice_batch.batch_status = batch_status;
         }
         if let Some(quality_score) = quality_score {
             rice_batch.quality_score = quality_score;
         }
         if let Some(weight_kg) = weight_kg {
+            require!(
+                rice_batch.token_mint.is_none() || weight_kg == rice_batch.weight_kg,
+                ErrorCode::AlreadyTokenized
+            );
+            ctx.accounts.milled_rice.allocate(rice_batch.weight_kg, weight_kg)?;
+            let packed_weight_g = rice_batch.packed_weight_g();
+            rice_batch.remaining_weight_g = (weight_kg as u64 * 1_000)
+                .checked_sub(packed_weight_g)
+                .ok_or(ErrorCode::InsufficientQuantity)?;
             rice_batch.weight_kg = weight_kg;
         }
-        if let Some(qr_code) = qr_code {
-            rice_batch.qr_code = qr_code;
+
+        rice_batch.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    /// Changes the batch's QR code, moving its `QrIndex` entry from the old
+    /// payload to the new one. A code with the same payload hash keeps the
+    /// existing entry.
+    pub fn set_rice_batch_qr_code(
+        ctx: Context<SetRiceBatchQrCode>,
+        batch_seed: String,
+        qr_code: String,
+    ) -> Result<()> {
+        require!(qr_code.len() <= RiceBatch::MAX_QR_CODE_LEN, ErrorCode::StringTooLong);
+        let gs1 = Gs1Key::from_digital_link(&qr_code)?;
+        ctx.accounts
+            .rice_batch
+            .check_custody(ctx.accounts.holder.as_ref(), &ctx.accounts.user.key())?;
+        let rice_batch = &mut ctx.accounts.rice_batch;
+        let clock = Clock::get()?;
+
+        let old_qr_index = &ctx.accounts.old_qr_index;
+        let new_qr_index = &mut ctx.accounts.new_qr_index;
+        if new_qr_index.key() != old_qr_index.key() {
+            require_keys_eq!(new_qr_index.rice_batch, Pubkey::default(), ErrorCode::QrCodeInUse);
+            new_qr_index.rice_batch = rice_batch.key();
+            new_qr_index.payload_hash = QrIndex::payload_hash(&qr_code);
+            new_qr_index.created_at = clock.unix_timestamp;
+            old_qr_index.close(ctx.accounts.user.to_account_info())?;
         }
-        
+
+        rice_batch.qr_code = qr_code;
+        rice_batch.gs1 = gs1;
         rice_batch.updated_at = clock.unix_timestamp;
         Ok(())
     }
 
-    pub fn delete_rice_batch(ctx: Context<DeleteRiceBatch>) -> Result<()> {
+    pub fn delete_rice_batch(ctx: Context<DeleteRiceBatch>, batch_seed: String) -> Result<()> {
+        ctx.accounts
+            .rice_batch
+            .check_custody(ctx.accounts.holder.as_ref(), &ctx.accounts.user.key())?;
         let rice_batch = &mut ctx.accounts.rice_batch;
+        require!(
+            rice_batch.batch_status.can_transition_to(&BatchStatus::Consumed),
+            ErrorCode::InvalidStatusTransition
+        );
         rice_batch.batch_status = BatchStatus::Consumed;
         Ok(())
     }
@@ -337,16 +587,20 @@ pub mod rice_supply_chain {
     // Chain Transaction Instructions
     pub fn create_chain_transaction(
         ctx: Context<CreateChainTransaction>,
+        transaction_seed: String,
         from_actor_id: Pubkey,
         to_actor_id: Pubkey,
         rice_batch_ids: Vec<Pubkey>,
         amount: u64,
         payment_method: PaymentMethod,
         payment_reference: Option<String>,
-        geotag_latitude: Option<String>,
-        geotag_longitude: Option<String>,
-        quality: Option<String>,
+        geotag: Option<GeoPoint>,
+        quality: Option<QualityGrade>,
     ) -> Result<()> {
+        if let Some(geotag) = &geotag {
+            geotag.validate()?;
+        }
+
         let chain_transaction = &mut ctx.accounts.chain_transaction;
         let clock = Clock::get()?;
         let public_key = chain_transaction.key();
@@ -358,8 +612,7 @@ pub mod rice_supply_chain {
         chain_transaction.amount = amount;
         chain_transaction.payment_method = payment_method;
         chain_transaction.payment_reference = payment_reference;
-        chain_transaction.geotag_latitude = geotag_latitude;
-        chain_transaction.geotag_longitude = geotag_longitude;
+        chain_transaction.geotag = geotag;
         chain_transaction.quality = quality;
         chain_transaction.transaction_status = TransactionStatus::Pending;
         chain_transaction.created_at = clock.unix_timestamp;
@@ -368,14 +621,18 @@ pub mod rice_supply_chain {
         Ok(())
     }
 
+    /// Only the authority of `from_actor_id` may change a transaction.
+    /// Completing it hands every listed batch, which `from_actor_id` must
+    /// hold, to `to_actor_id`; the batches must then be passed, writable and
+    /// in order, as remaining accounts.
     pub fn update_chain_transaction(
         ctx: Context<UpdateChainTransaction>,
+        transaction_seed: String,
         amount: Option<u64>,
         payment_method: Option<PaymentMethod>,
         payment_reference: Option<String>,
-        geotag_latitude: Option<String>,
-        geotag_longitude: Option<String>,
-        quality: Option<String>,
+        geotag: Option<GeoPoint>,
+        quality: Option<QualityGrade>,
         transaction_status: Option<TransactionStatus>,
     ) -> Result<()> {
         let chain_transaction = &mut ctx.accounts.chain_transaction;
@@ -390,16 +647,37 @@ pub mod rice_supply_chain {
         if let Some(payment_reference) = payment_reference {
             chain_transaction.payment_reference = Some(payment_reference);
         }
-        if let Some(geotag_latitude) = geotag_latitude {
-            chain_transaction.geotag_latitude = Some(geotag_latitude);
-        }
-        if let Some(geotag_longitude) = geotag_longitude {
-            chain_transaction.geotag_longitude = Some(geotag_longitude);
+        if let Some(geotag) = geotag {
+            geotag.validate()?;
+            chain_transaction.geotag = Some(geotag);
         }
         if let Some(quality) = quality {
             chain_transaction.quality = Some(quality);
         }
         if let Some(transaction_status) = transaction_status {
+            require!(
+                transaction_status == chain_transaction.transaction_status
+                    || chain_transaction
+                        .transaction_status
+                        .can_transition_to(&transaction_status),
+                ErrorCode::InvalidStatusTransition
+            );
+            if transaction_status == TransactionStatus::Completed
+                && chain_transaction.transaction_status != TransactionStatus::Completed
+            {
+                let holder = chain_transaction.to_actor_id;
+                let from_actor = &ctx.accounts.from_actor;
+                let authority = ctx.accounts.user.key();
+                update_batches(ctx.remaining_accounts, &chain_transaction.rice_batch_ids, |rice_batch| {
+                    rice_batch.check_custody(Some(from_actor), &authority)?;
+                    // A tokenized batch's custody follows its tokens instead.
+                    if rice_batch.token_mint.is_none() {
+                        rice_batch.current_holder = Some(holder);
+                    }
+                    rice_batch.updated_at = clock.unix_timestamp;
+                    Ok(())
+                })?;
+            }
             chain_transaction.transaction_status = transaction_status;
         }
         
@@ -407,324 +685,3650 @@ pub mod rice_supply_chain {
         Ok(())
     }
 
-    pub fn delete_chain_transaction(ctx: Context<DeleteChainTransaction>) -> Result<()> {
+    pub fn delete_chain_transaction(
+        ctx: Context<DeleteChainTransaction>,
+        transaction_seed: String,
+    ) -> Result<()> {
         let chain_transaction = &mut ctx.accounts.chain_transaction;
+        require!(
+            chain_transaction
+                .transaction_status
+                .can_transition_to(&TransactionStatus::Cancelled),
+            ErrorCode::InvalidStatusTransition
+        );
         chain_transaction.transaction_status = TransactionStatus::Cancelled;
         Ok(())
     }
-}
 
-// Account Structures
-#[account]
-pub struct ChainActor {
-    pub public_key: Pubkey,
-    pub name: String,
-    pub actor_type: Vec<String>,
-    pub farm_id: Option<Pubkey>,
-    pub farmer_id: Option<Pubkey>,
-    pub assigned_tps: u64,
-    pub is_active: bool,
-    pub balance: u64,
-    pub pin: String,
-    pub organization: Organization,
-    pub address: Option<String>,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+    // Program Config Instructions
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let clock = Clock::get()?;
 
-#[account]
-pub struct ProductionSeason {
-    pub public_key: Pubkey,
-    pub farmer_id: Pubkey,
-    pub crop_year: String,
-    pub processed_yield_kg: u64,
-    pub variety: Option<String>,
-    pub planned_practice: Option<String>,
-    pub planting_date: Option<i64>,
-    pub irrigation_practice: Option<String>,
-    pub fertilizer_used: Option<String>,
-    pub pesticide_used: Option<String>,
-    pub harvest_date: Option<i64>,
-    pub total_yield_kg: Option<u64>,
-    pub moisture_content: Option<u32>,
-    pub carbon_smart_certified: bool,
-    pub validation_status: ValidationStatus,
-    pub validator_id: Option<Pubkey>,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+        config.admin = ctx.accounts.admin.key();
+        config.created_at = clock.unix_timestamp;
+        config.updated_at = clock.unix_timestamp;
 
-#[account]
-pub struct MilledRice {
-    pub public_key: Pubkey,
-    pub farmer_id: Pubkey,
-    pub total_weight_kg: String,
-    pub milling_type: String,
-    pub quality: String,
-    pub photo_urls: Vec<String>,
-    pub moisture: u32,
-    pub total_weight_processed_kg: u32,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+        Ok(())
+    }
 
-#[account]
-pub struct RiceBatch {
-    pub public_key: Pubkey,
-    pub milled_rice_id: Pubkey,
-    pub batch_status: BatchStatus,
-    pub quality_score: u32,
-    pub weight_kg: u32,
-    pub qr_code: String,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+    pub fn set_config_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let clock = Clock::get()?;
 
-#[account]
-pub struct ChainTransaction {
-    pub public_key: Pubkey,
-    pub from_actor_id: Pubkey,
-    pub to_actor_id: Pubkey,
-    pub rice_batch_ids: Vec<Pubkey>,
-    pub amount: u64,
-    pub payment_method: PaymentMethod,
-    pub payment_reference: Option<String>,
-    pub geotag_latitude: Option<String>,
-    pub geotag_longitude: Option<String>,
-    pub quality: Option<String>,
-    pub transaction_status: TransactionStatus,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+        config.admin = new_admin;
+        config.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
 
-// Enums
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum Organization {
-    Farmer,
-    Miller,
-    Distributor,
-    Retailer,
-    Government,
-    Cooperative,
-}
+    // Accreditation Instructions
+    /// Grants `kind` to the actor, or renews it, re-activating a revoked
+    /// accreditation with the new validity.
+    pub fn grant_accreditation(
+        ctx: Context<GrantAccreditation>,
+        kind: AccreditationKind,
+        valid_until: Option<i64>,
+    ) -> Result<()> {
+        let accreditation = &mut ctx.accounts.accreditation;
+        let clock = Clock::get()?;
+        if let Some(valid_until) = valid_until {
+            require!(valid_until > clock.unix_timestamp, ErrorCode::InvalidValidityPeriod);
+        }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum ValidationStatus {
-    Pending,
-    Approved,
-    Rejected,
-}
+        accreditation.public_key = accreditation.key();
+        accreditation.actor = ctx.accounts.chain_actor.key();
+        accreditation.kind = kind;
+        accreditation.granted_by = ctx.accounts.admin.key();
+        accreditation.valid_until = valid_until;
+        accreditation.is_active = true;
+        if accreditation.created_at == 0 {
+            accreditation.created_at = clock.unix_timestamp;
+        }
+        accreditation.updated_at = clock.unix_timestamp;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum BatchStatus {
-    Created,
-    InTransit,
-    Delivered,
-    Consumed,
-}
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum PaymentMethod {
-    Cash,
-    BankTransfer,
-    DigitalWallet,
-    Cryptocurrency,
-    Credit,
-}
+    pub fn revoke_accreditation(ctx: Context<RevokeAccreditation>) -> Result<()> {
+        let accreditation = &mut ctx.accounts.accreditation;
+        let clock = Clock::get()?;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum TransactionStatus {
-    Pending,
-    Completed,
-    Failed,
-    Cancelled,
-}
+        accreditation.is_active = false;
+        accreditation.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
 
-// Context Structs
-#[derive(Accounts)]
-#[instruction(actor_seed: String)]
-pub struct CreateChainActor<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + 32 + 64 + 128 + 32 + 32 + 8 + 1 + 8 + 64 + 1 + 64 + 64 + 8 + 8,
-        seeds = [b"chain_actor", actor_seed.as_bytes()],
-        bump
-    )]
-    pub chain_actor: Account<'info, ChainActor>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    // Certification Instructions
+    pub fn issue_certification(
+        ctx: Context<IssueCertification>,
+        certification_seed: String,
+        scheme: CertificationScheme,
+        subject_kind: SubjectKind,
+        issued_at: i64,
+        expires_at: i64,
+        evidence_hash: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.accreditation.is_valid_at(clock.unix_timestamp),
+            ErrorCode::AccreditationInactive
+        );
+        require!(expires_at > issued_at, ErrorCode::InvalidValidityPeriod);
+        subject_kind.check_account(&ctx.accounts.subject)?;
 
-#[derive(Accounts)]
-#[instruction(actor_seed: String)]
-pub struct UpdateChainActor<'info> {
-    #[account(
-        mut,
-        seeds = [b"chain_actor", actor_seed.as_bytes()],
-        bump
-    )]
-    pub chain_actor: Account<'info, ChainActor>,
-    pub user: Signer<'info>,
-}
+        let certification = &mut ctx.accounts.certification;
+        certification.public_key = certification.key();
+        certification.scheme = scheme;
+        certification.issuer = ctx.accounts.certifier.key();
+        certification.subject = ctx.accounts.subject.key();
+        certification.subject_kind = subject_kind;
+        certification.issued_at = issued_at;
+        certification.expires_at = expires_at;
+        certification.evidence_hash = evidence_hash;
+        certification.is_revoked = false;
+        certification.revoked_at = None;
+        certification.created_at = clock.unix_timestamp;
+        certification.updated_at = clock.unix_timestamp;
 
-#[derive(Accounts)]
-#[instruction(actor_seed: String)]
-pub struct DeleteChainActor<'info> {
-    #[account(
-        mut,
-        seeds = [b"chain_actor", actor_seed.as_bytes()],
-        bump
-    )]
-    pub chain_actor: Account<'info, ChainActor>,
-    pub user: Signer<'info>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(season_seed: String)]
-pub struct CreateProductionSeason<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + 32 + 32 + 64 + 8 + 64 + 64 + 8 + 64 + 64 + 64 + 8 + 8 + 4 + 1 + 1 + 32 + 64 + 8 + 8,
-        seeds = [b"production_season", season_seed.as_bytes()],
-        bump
-    )]
-    pub production_season: Account<'info, ProductionSeason>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn revoke_certification(
+        ctx: Context<RevokeCertification>,
+        certification_seed: String,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.accreditation.is_valid_at(clock.unix_timestamp),
+            ErrorCode::AccreditationInactive
+        );
 
-#[derive(Accounts)]
-#[instruction(season_seed: String)]
-pub struct UpdateProductionSeason<'info> {
-    #[account(
-        mut,
-        seeds = [b"production_season", season_seed.as_bytes()],
-        bump
-    )]
-    pub production_season: Account<'info, ProductionSeason>,
-    pub user: Signer<'info>,
-}
+        let certification = &mut ctx.accounts.certification;
+        require!(!certification.is_revoked, ErrorCode::CertificationRevoked);
+        certification.is_revoked = true;
+        certification.revoked_at = Some(clock.unix_timestamp);
+        certification.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(season_seed: String)]
-pub struct DeleteProductionSeason<'info> {
-    #[account(
-        mut,
-        seeds = [b"production_season", season_seed.as_bytes()],
-        bump
-    )]
-    pub production_season: Account<'info, ProductionSeason>,
-    pub user: Signer<'info>,
-}
+    // Input Application Instructions
+    pub fn add_input_application(
+        ctx: Context<AddInputApplication>,
+        application_seed: String,
+        input_kind: InputKind,
+        product: String,
+        active_ingredient: Option<String>,
+        rate_g_per_ha: u64,
+        nitrogen_content_bp: u16,
+        applied_at: i64,
+        pre_harvest_interval_days: Option<u16>,
+    ) -> Result<()> {
+        require!(product.len() <= InputApplication::MAX_NAME_LEN, ErrorCode::StringTooLong);
+        if let Some(active_ingredient) = &active_ingredient {
+            require!(
+                active_ingredient.len() <= InputApplication::MAX_NAME_LEN,
+                ErrorCode::StringTooLong
+            );
+        }
+        require!(nitrogen_content_bp <= 10_000, ErrorCode::InvalidPercentage);
+        let substance_registry = SubstanceRegistry::load(&ctx.accounts.substance_registry)?;
+        if let Some(entry) = active_ingredient
+            .as_deref()
+            .zip(substance_registry.as_ref())
+            .and_then(|(active_ingredient, substance_registry)| substance_registry.find(active_ingredient))
+        {
+            require!(entry.status != SubstanceStatus::Banned, ErrorCode::BannedSubstance);
+            if let Some(max_rate_g_per_ha) = entry.max_rate_g_per_ha {
+                require!(rate_g_per_ha <= max_rate_g_per_ha, ErrorCode::RateAboveMaximum);
+            }
+        }
 
-#[derive(Accounts)]
-#[instruction(milling_seed: String)]
-pub struct CreateMilledRice<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + 32 + 32 + 64 + 64 + 64 + 128 + 4 + 4 + 64 + 8 + 8,
-        seeds = [b"milled_rice", milling_seed.as_bytes()],
-        bump
-    )]
+        let input_application = &mut ctx.accounts.input_application;
+        let clock = Clock::get()?;
+
+        input_application.public_key = input_application.key();
+        input_application.production_season = ctx.accounts.production_season.key();
+        input_application.applicator = ctx.accounts.applicator.key();
+        input_application.input_kind = input_kind;
+        input_application.product = product;
+        input_application.active_ingredient = active_ingredient;
+        input_application.rate_g_per_ha = rate_g_per_ha;
+        input_application.nitrogen_content_bp = nitrogen_content_bp;
+        input_application.applied_at = applied_at;
+        input_application.pre_harvest_interval_days = pre_harvest_interval_days;
+        input_application.created_at = clock.unix_timestamp;
+        input_application.updated_at = clock.unix_timestamp;
+
+        let production_season = &mut ctx.accounts.production_season;
+        if let Some(harvest_date) = production_season.harvest_date {
+            require!(
+                input_application.pre_harvest_interval_met(harvest_date),
+                ErrorCode::PreHarvestIntervalNotMet
+            );
+        }
+        if let Some(clear_at) = input_application.pre_harvest_clear_at() {
+            production_season.pre_harvest_clear_at = production_season
+                .pre_harvest_clear_at
+                .max(Some(clear_at));
+        }
+        production_season.nitrogen_applied_g_per_ha = production_season
+            .nitrogen_applied_g_per_ha
+            .checked_add(input_application.nitrogen_g_per_ha())
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        production_season.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn remove_input_application(
+        ctx: Context<RemoveInputApplication>,
+        application_seed: String,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let nitrogen_g_per_ha = ctx.accounts.input_application.nitrogen_g_per_ha();
+
+        let production_season = &mut ctx.accounts.production_season;
+        production_season.nitrogen_applied_g_per_ha = production_season
+            .nitrogen_applied_g_per_ha
+            .saturating_sub(nitrogen_g_per_ha);
+        production_season.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    // Substance Registry Instructions
+    pub fn initialize_substance_registry(ctx: Context<InitializeSubstanceRegistry>) -> Result<()> {
+        let substance_registry = &mut ctx.accounts.substance_registry;
+        let clock = Clock::get()?;
+
+        substance_registry.entries = Vec::new();
+        substance_registry.created_at = clock.unix_timestamp;
+        substance_registry.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn upsert_substance(
+        ctx: Context<UpdateSubstanceRegistry>,
+        active_ingredient: String,
+        status: SubstanceStatus,
+        max_rate_g_per_ha: Option<u64>,
+    ) -> Result<()> {
+        let active_ingredient = SubstanceEntry::normalize(&active_ingredient);
+        require!(
+            !active_ingredient.is_empty()
+                && active_ingredient.len() <= SubstanceEntry::MAX_NAME_LEN,
+            ErrorCode::StringTooLong
+        );
+        let substance_registry = &mut ctx.accounts.substance_registry;
+        let clock = Clock::get()?;
+
+        match substance_registry
+            .entries
+            .iter_mut()
+            .find(|entry| entry.active_ingredient == active_ingredient)
+        {
+            Some(entry) => {
+                entry.status = status;
+                entry.max_rate_g_per_ha = max_rate_g_per_ha;
+            }
+            None => {
+                require!(
+                    substance_registry.entries.len() < SubstanceRegistry::MAX_ENTRIES,
+                    ErrorCode::RegistryFull
+                );
+                substance_registry.entries.push(SubstanceEntry {
+                    active_ingredient,
+                    status,
+                    max_rate_g_per_ha,
+                });
+            }
+        }
+
+        substance_registry.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    pub fn remove_substance(
+        ctx: Context<UpdateSubstanceRegistry>,
+        active_ingredient: String,
+    ) -> Result<()> {
+        let substance_registry = &mut ctx.accounts.substance_registry;
+        let clock = Clock::get()?;
+        let active_ingredient = SubstanceEntry::normalize(&active_ingredient);
+
+        substance_registry
+            .entries
+            .retain(|entry| entry.active_ingredient != active_ingredient);
+        substance_registry.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    // Emission Factor Instructions
+    pub fn initialize_emission_factors(ctx: Context<InitializeEmissionFactors>) -> Result<()> {
+        let emission_factors = &mut ctx.accounts.emission_factors;
+        let clock = Clock::get()?;
+
+        emission_factors.set_defaults();
+        emission_factors.created_at = clock.unix_timestamp;
+        emission_factors.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Replaces the factors. Seasons pick them up at their next update.
+    pub fn set_emission_factors(
+        ctx: Context<UpdateEmissionFactors>,
+        baseline_methane_g_per_ha_day: u32,
+        water_regime_scaling_bp: [u16; WaterRegime::COUNT],
+        amendment_conversion_bp: [u16; AmendmentKind::COUNT],
+        amendment_exponent_bp: u16,
+        methane_gwp_100: u16,
+    ) -> Result<()> {
+        require!(
+            water_regime_scaling_bp.iter().all(|bp| *bp <= 10_000)
+                && amendment_exponent_bp <= 10_000,
+            ErrorCode::InvalidPercentage
+        );
+        let emission_factors = &mut ctx.accounts.emission_factors;
+        let clock = Clock::get()?;
+
+        emission_factors.baseline_methane_g_per_ha_day = baseline_methane_g_per_ha_day;
+        emission_factors.water_regime_scaling_bp = water_regime_scaling_bp;
+        emission_factors.amendment_conversion_bp = amendment_conversion_bp;
+        emission_factors.amendment_exponent_bp = amendment_exponent_bp;
+        emission_factors.methane_gwp_100 = methane_gwp_100;
+        emission_factors.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    // Quality Test Instructions
+    /// Links a test into the batch's `previous_test` chain, which runs
+    /// newest sample first, and grades the batch when it is the newest.
+    /// Pass the chain from the batch's latest test down to the first one
+    /// sampled no later than this one as writable remaining accounts.
+    pub fn record_quality_test(
+        ctx: Context<RecordQualityTest>,
+        test_seed: String,
+        sample_date: i64,
+        moisture_bp: u16,
+        broken_grain_bp: u16,
+        chalkiness_bp: u16,
+        foreign_matter_bp: u16,
+        residue_results: Vec<ResidueResult>,
+        overall_grade: QualityGrade,
+        report_hash: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.accreditation.is_valid_at(clock.unix_timestamp),
+            ErrorCode::AccreditationInactive
+        );
+        require!(sample_date <= clock.unix_timestamp, ErrorCode::TimestampInFuture);
+        for bp in [moisture_bp, broken_grain_bp, chalkiness_bp, foreign_matter_bp] {
+            require!(bp <= 10_000, ErrorCode::InvalidPercentage);
+        }
+        require!(
+            residue_results.len() <= QualityTest::MAX_RESIDUE_RESULTS,
+            ErrorCode::TooManyEntries
+        );
+        for residue_result in &residue_results {
+            require!(
+                residue_result.analyte.len() <= ResidueResult::MAX_ANALYTE_LEN,
+                ErrorCode::StringTooLong
+            );
+        }
+
+        let rice_batch = &mut ctx.accounts.rice_batch;
+        let quality_test = &mut ctx.accounts.quality_test;
+        quality_test.public_key = quality_test.key();
+        quality_test.rice_batch = rice_batch.key();
+        quality_test.laboratory = ctx.accounts.laboratory.key();
+        quality_test.sample_date = sample_date;
+        quality_test.moisture_bp = moisture_bp;
+        quality_test.broken_grain_bp = broken_grain_bp;
+        quality_test.chalkiness_bp = chalkiness_bp;
+        quality_test.foreign_matter_bp = foreign_matter_bp;
+        quality_test.residue_results = residue_results;
+        quality_test.overall_grade = overall_grade.clone();
+        quality_test.report_hash = report_hash;
+        quality_test.is_valid = true;
+        quality_test.created_at = clock.unix_timestamp;
+        quality_test.updated_at = clock.unix_timestamp;
+
+        let mut chain_accounts = ctx.remaining_accounts.iter();
+        let mut next = rice_batch.latest_quality_test;
+        let mut newer = None;
+        while let Some(next_key) = next {
+            let info = chain_accounts
+                .next()
+                .ok_or(ErrorCode::MissingPreviousTest)?;
+            require_keys_eq!(info.key(), next_key, ErrorCode::MissingPreviousTest);
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::MissingPreviousTest);
+            let test = QualityTest::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            if test.sample_date <= sample_date {
+                break;
+            }
+            next = test.previous_test;
+            newer = Some((info, test));
+        }
+        quality_test.previous_test = next;
+
+        match newer {
+            None => {
+                rice_batch.effective_grade = Some(overall_grade);
+                rice_batch.latest_quality_test = Some(quality_test.key());
+            }
+            Some((info, mut test)) => {
+                require!(info.is_writable, ErrorCode::MissingPreviousTest);
+                test.previous_test = Some(quality_test.key());
+                test.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+        }
+        rice_batch.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Marks a test invalid. When it is the batch's latest test, the batch
+    /// falls back to the next older valid test; pass the chain of
+    /// `previous_test` accounts, newest first, as remaining accounts.
+    pub fn invalidate_quality_test(
+        ctx: Context<InvalidateQualityTest>,
+        test_seed: String,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let quality_test = &mut ctx.accounts.quality_test;
+        require!(quality_test.is_valid, ErrorCode::QualityTestInvalidated);
+        quality_test.is_valid = false;
+        quality_test.updated_at = clock.unix_timestamp;
+
+        let rice_batch = &mut ctx.accounts.rice_batch;
+        if rice_batch.latest_quality_test != Some(quality_test.key()) {
+            return Ok(());
+        }
+
+        let mut previous_accounts = ctx.remaining_accounts.iter();
+        let mut next = quality_test.previous_test;
+        let mut fallback = None;
+        while let Some(previous_key) = next {
+            let info = previous_accounts
+                .next()
+                .ok_or(ErrorCode::MissingPreviousTest)?;
+            require_keys_eq!(info.key(), previous_key, ErrorCode::MissingPreviousTest);
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::MissingPreviousTest);
+            let previous = QualityTest::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            if previous.is_valid {
+                fallback = Some(previous);
+                break;
+            }
+            next = previous.previous_test;
+        }
+
+        rice_batch.effective_grade = fallback.as_ref().map(|test| test.overall_grade.clone());
+        rice_batch.latest_quality_test = fallback.map(|test| test.public_key);
+        rice_batch.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    // Shipment Instructions
+    /// Plans a shipment of batches that all sit with one holder, who
+    /// consents by signing. Pass the batches, in `rice_batch_ids` order, as
+    /// remaining accounts.
+    pub fn create_shipment(
+        ctx: Context<CreateShipment>,
+        shipment_seed: String,
+        vehicle_id: String,
+        origin: GeoPoint,
+        destination: GeoPoint,
+        rice_batch_ids: Vec<Pubkey>,
+        seal_numbers: Vec<String>,
+    ) -> Result<()> {
+        require!(vehicle_id.len() <= Shipment::MAX_VEHICLE_ID_LEN, ErrorCode::StringTooLong);
+        origin.validate()?;
+        destination.validate()?;
+        require!(
+            !rice_batch_ids.is_empty() && rice_batch_ids.len() <= Shipment::MAX_BATCHES,
+            ErrorCode::TooManyEntries
+        );
+        require!(
+            rice_batch_ids
+                .iter()
+                .enumerate()
+                .all(|(index, rice_batch_id)| !rice_batch_ids[..index].contains(rice_batch_id)),
+            ErrorCode::DuplicateBatch
+        );
+        require!(seal_numbers.len() <= Shipment::MAX_SEALS, ErrorCode::TooManyEntries);
+        for seal_number in &seal_numbers {
+            require!(seal_number.len() <= Shipment::MAX_SEAL_LEN, ErrorCode::StringTooLong);
+        }
+        let holder = ctx.accounts.holder.as_ref();
+        let holder_authority = ctx.accounts.holder_authority.key();
+        read_batches(ctx.remaining_accounts, &rice_batch_ids, |rice_batch| {
+            rice_batch.check_custody(holder, &holder_authority)
+        })?;
+
+        let shipment = &mut ctx.accounts.shipment;
+        let clock = Clock::get()?;
+
+        shipment.public_key = shipment.key();
+        shipment.carrier = ctx.accounts.carrier.key();
+        shipment.vehicle_id = vehicle_id;
+        shipment.origin = origin;
+        shipment.destination = destination;
+        shipment.departed_at = None;
+        shipment.arrived_at = None;
+        shipment.rice_batch_ids = rice_batch_ids;
+        shipment.seal_numbers = seal_numbers;
+        shipment.shipment_status = ShipmentStatus::Planned;
+        shipment.created_at = clock.unix_timestamp;
+        shipment.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Marks the shipment departed and moves every carried batch to
+    /// `InTransit`. Pass the batches, in `rice_batch_ids` order, as writable
+    /// remaining accounts.
+    pub fn depart_shipment(ctx: Context<MoveShipment>, shipment_seed: String) -> Result<()> {
+        let shipment = &mut ctx.accounts.shipment;
+        let clock = Clock::get()?;
+        require!(
+            shipment.shipment_status == ShipmentStatus::Planned,
+            ErrorCode::InvalidStatusTransition
+        );
+
+        update_batches(ctx.remaining_accounts, &shipment.rice_batch_ids, |rice_batch| {
+            require!(
+                rice_batch.batch_status.can_transition_to(&BatchStatus::InTransit),
+                ErrorCode::InvalidStatusTransition
+            );
+            require!(rice_batch.current_warehouse.is_none(), ErrorCode::BatchInStorage);
+            rice_batch.batch_status = BatchStatus::InTransit;
+            rice_batch.updated_at = clock.unix_timestamp;
+            Ok(())
+        })?;
+
+        shipment.shipment_status = ShipmentStatus::InTransit;
+        shipment.departed_at = Some(clock.unix_timestamp);
+        shipment.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    /// Marks the shipment arrived and every carried batch `Delivered`. The
+    /// seal numbers found on arrival must match those recorded at creation.
+    pub fn arrive_shipment(
+        ctx: Context<MoveShipment>,
+        shipment_seed: String,
+        seal_numbers: Vec<String>,
+    ) -> Result<()> {
+        let shipment = &mut ctx.accounts.shipment;
+        let clock = Clock::get()?;
+        require!(
+            shipment.shipment_status == ShipmentStatus::InTransit,
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(shipment.seal_numbers == seal_numbers, ErrorCode::SealMismatch);
+
+        update_batches(ctx.remaining_accounts, &shipment.rice_batch_ids, |rice_batch| {
+            require!(
+                rice_batch.batch_status.can_transition_to(&BatchStatus::Delivered),
+                ErrorCode::InvalidStatusTransition
+            );
+            rice_batch.batch_status = BatchStatus::Delivered;
+            rice_batch.updated_at = clock.unix_timestamp;
+            Ok(())
+        })?;
+
+        shipment.shipment_status = ShipmentStatus::Arrived;
+        shipment.arrived_at = Some(clock.unix_timestamp);
+        shipment.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    // Warehouse Instructions
+    pub fn create_warehouse(
+        ctx: Context<CreateWarehouse>,
+        warehouse_seed: String,
+        name: String,
+        location: GeoPoint,
+        capacity_kg: u64,
+    ) -> Result<()> {
+        require!(name.len() <= Warehouse::MAX_NAME_LEN, ErrorCode::StringTooLong);
+        location.validate()?;
+
+        let warehouse = &mut ctx.accounts.warehouse;
+        let clock = Clock::get()?;
+
+        warehouse.public_key = warehouse.key();
+        warehouse.operator = ctx.accounts.operator.key();
+        warehouse.name = name;
+        warehouse.location = location;
+        warehouse.capacity_kg = capacity_kg;
+        warehouse.stored_kg = 0;
+        warehouse.stored_batch_ids = Vec::new();
+        warehouse.is_active = true;
+        warehouse.created_at = clock.unix_timestamp;
+        warehouse.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn update_warehouse(
+        ctx: Context<UpdateWarehouse>,
+        warehouse_seed: String,
+        name: Option<String>,
+        capacity_kg: Option<u64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        let warehouse = &mut ctx.accounts.warehouse;
+        let clock = Clock::get()?;
+
+        if let Some(name) = name {
+            require!(name.len() <= Warehouse::MAX_NAME_LEN, ErrorCode::StringTooLong);
+            warehouse.name = name;
+        }
+        if let Some(capacity_kg) = capacity_kg {
+            require!(capacity_kg >= warehouse.stored_kg, ErrorCode::WarehouseCapacityExceeded);
+            warehouse.capacity_kg = capacity_kg;
+        }
+        if let Some(is_active) = is_active {
+            warehouse.is_active = is_active;
+        }
+
+        warehouse.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    pub fn store_batch(ctx: Context<StoreBatch>, warehouse_seed: String) -> Result<()> {
+        ctx.accounts
+            .rice_batch
+            .check_custody(ctx.accounts.holder.as_ref(), &ctx.accounts.holder_authority.key())?;
+        let warehouse = &mut ctx.accounts.warehouse;
+        let rice_batch = &mut ctx.accounts.rice_batch;
+        let clock = Clock::get()?;
+        require!(warehouse.is_active, ErrorCode::WarehouseInactive);
+        require!(rice_batch.current_warehouse.is_none(), ErrorCode::BatchInStorage);
+        require!(
+            matches!(rice_batch.batch_status, BatchStatus::Created | BatchStatus::Delivered),
+            ErrorCode::InvalidStatusTransition
+        );
+        require!(
+            warehouse.stored_batch_ids.len() < Warehouse::MAX_STORED_BATCHES,
+            ErrorCode::TooManyEntries
+        );
+        let stored_kg = warehouse
+            .stored_kg
+            .checked_add(rice_batch.weight_kg as u64)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(stored_kg <= warehouse.capacity_kg, ErrorCode::WarehouseCapacityExceeded);
+
+        let storage_record = &mut ctx.accounts.storage_record;
+        storage_record.public_key = storage_record.key();
+        storage_record.rice_batch = rice_batch.key();
+        storage_record.warehouse = warehouse.key();
+        storage_record.weight_kg = rice_batch.weight_kg;
+        storage_record.stored_at = clock.unix_timestamp;
+        storage_record.released_at = None;
+
+        warehouse.stored_kg = stored_kg;
+        warehouse.stored_batch_ids.push(rice_batch.key());
+        warehouse.updated_at = clock.unix_timestamp;
+
+        rice_batch.current_warehouse = Some(warehouse.key());
+        rice_batch.storage_count = rice_batch
+            .storage_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        rice_batch.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn release_batch(ctx: Context<ReleaseBatch>, warehouse_seed: String) -> Result<()> {
+        let warehouse = &mut ctx.accounts.warehouse;
+        let rice_batch = &mut ctx.accounts.rice_batch;
+        let storage_record = &mut ctx.accounts.storage_record;
+        let clock = Clock::get()?;
+        require!(
+            rice_batch.current_warehouse == Some(warehouse.key()),
+            ErrorCode::BatchNotInWarehouse
+        );
+
+        storage_record.released_at = Some(clock.unix_timestamp);
+
+        warehouse.stored_kg = warehouse.stored_kg.saturating_sub(storage_record.weight_kg as u64);
+        warehouse
+            .stored_batch_ids
+            .retain(|rice_batch_id| *rice_batch_id != rice_batch.key());
+        warehouse.updated_at = clock.unix_timestamp;
+
+        rice_batch.current_warehouse = None;
+        rice_batch.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    // Post-Harvest Instructions
+    pub fn record_post_harvest_event(
+        ctx: Context<RecordPostHarvestEvent>,
+        event_seed: String,
+        drying_method: DryingMethod,
+        started_at: i64,
+        ended_at: i64,
+        start_moisture_bp: u16,
+        end_moisture_bp: u16,
+        weight_in_kg: u64,
+        weight_out_kg: u64,
+    ) -> Result<()> {
+        require!(ended_at >= started_at, ErrorCode::InvalidValidityPeriod);
+        require!(
+            start_moisture_bp <= 10_000 && end_moisture_bp <= start_moisture_bp,
+            ErrorCode::InvalidPercentage
+        );
+        require!(weight_out_kg <= weight_in_kg, ErrorCode::WeightIncrease);
+
+        let production_season = &mut ctx.accounts.production_season;
+        if let Some(harvest_date) = production_season.harvest_date {
+            require!(started_at >= harvest_date, ErrorCode::BeforeHarvest);
+        }
+        let post_harvest_loss_kg = production_season
+            .post_harvest_loss_kg
+            .checked_add(weight_in_kg - weight_out_kg)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        let accounted_kg = post_harvest_loss_kg
+            .checked_add(production_season.milled_paddy_kg)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(
+            accounted_kg <= production_season.total_yield_kg.unwrap_or(0),
+            ErrorCode::InsufficientQuantity
+        );
+
+        let post_harvest_event = &mut ctx.accounts.post_harvest_event;
+        let clock = Clock::get()?;
+
+        post_harvest_event.public_key = post_harvest_event.key();
+        post_harvest_event.production_season = production_season.key();
+        post_harvest_event.facility = ctx.accounts.facility.key();
+        post_harvest_event.drying_method = drying_method;
+        post_harvest_event.started_at = started_at;
+        post_harvest_event.ended_at = ended_at;
+        post_harvest_event.start_moisture_bp = start_moisture_bp;
+        post_harvest_event.end_moisture_bp = end_moisture_bp;
+        post_harvest_event.weight_in_kg = weight_in_kg;
+        post_harvest_event.weight_out_kg = weight_out_kg;
+        post_harvest_event.created_at = clock.unix_timestamp;
+        post_harvest_event.updated_at = clock.unix_timestamp;
+
+        production_season.post_harvest_loss_kg = post_harvest_loss_kg;
+        production_season.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Retail Pack Instructions
+    /// Divides `pack_count` packs of `pack_size_g` off the batch as one
+    /// `RetailPackRun` covering a contiguous serial range.
+    pub fn create_retail_packs(
+        ctx: Context<CreateRetailPacks>,
+        pack_size_g: u32,
+        pack_count: u32,
+    ) -> Result<()> {
+        require!(pack_size_g > 0 && pack_count > 0, ErrorCode::InvalidQuantity);
+        ctx.accounts
+            .rice_batch
+            .check_custody(Some(&ctx.accounts.packer), &ctx.accounts.authority.key())?;
+        let rice_batch = &mut ctx.accounts.rice_batch;
+        require!(
+            matches!(rice_batch.batch_status, BatchStatus::Created | BatchStatus::Delivered),
+            ErrorCode::InvalidStatusTransition
+        );
+        let packed_weight_g = pack_size_g as u64 * pack_count as u64;
+        rice_batch.remaining_weight_g = rice_batch
+            .remaining_weight_g
+            .checked_sub(packed_weight_g)
+            .ok_or(ErrorCode::InsufficientQuantity)?;
+
+        let retail_pack_run = &mut ctx.accounts.retail_pack_run;
+        let clock = Clock::get()?;
+
+        retail_pack_run.public_key = retail_pack_run.key();
+        retail_pack_run.rice_batch = rice_batch.key();
+        retail_pack_run.packer = ctx.accounts.packer.key();
+        retail_pack_run.pack_size_g = pack_size_g;
+        retail_pack_run.first_serial = rice_batch.next_pack_serial;
+        retail_pack_run.pack_count = pack_count;
+        retail_pack_run.created_at = clock.unix_timestamp;
+
+        rice_batch.next_pack_serial = rice_batch
+            .next_pack_serial
+            .checked_add(pack_count as u64)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        rice_batch.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Issuing Key Instructions
+    /// Registers an ed25519 key the miller signs pack QR payloads with.
+    pub fn register_issuing_key(
+        ctx: Context<RegisterIssuingKey>,
+        issuing_key: Pubkey,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.accreditation.is_valid_at(clock.unix_timestamp),
+            ErrorCode::NotMiller
+        );
+        let issuer_key = &mut ctx.accounts.issuer_key;
+
+        issuer_key.public_key = issuer_key.key();
+        issuer_key.miller = ctx.accounts.miller.key();
+        issuer_key.issuing_key = issuing_key;
+        issuer_key.is_active = true;
+        issuer_key.created_at = clock.unix_timestamp;
+        issuer_key.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn revoke_issuing_key(ctx: Context<RevokeIssuingKey>) -> Result<()> {
+        let issuer_key = &mut ctx.accounts.issuer_key;
+        let clock = Clock::get()?;
+
+        issuer_key.is_active = false;
+        issuer_key.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    // Verification Instructions
+    /// Read-only checks for other programs to CPI into. Each fails unless
+    /// the fact holds and otherwise returns a view of the record through
+    /// `set_return_data`, read back with `cpi::Return::get`. The facts are
+    /// only written by the batch's holder, a carrier on a shipment the
+    /// holder signed for, the seller of a chain transaction, a transfer of
+    /// the batch's tokens or an accredited validator.
+    pub fn assert_batch_status(
+        ctx: Context<AssertRiceBatch>,
+        batch_status: BatchStatus,
+    ) -> Result<BatchView> {
+        let rice_batch = &ctx.accounts.rice_batch;
+        require!(rice_batch.batch_status == batch_status, ErrorCode::BatchStatusMismatch);
+        Ok(BatchView::from(&**rice_batch))
+    }
+
+    pub fn assert_batch_holder(ctx: Context<AssertRiceBatch>, holder: Pubkey) -> Result<BatchView> {
+        let rice_batch = &ctx.accounts.rice_batch;
+        require!(rice_batch.current_holder == Some(holder), ErrorCode::NotBatchHolder);
+        Ok(BatchView::from(&**rice_batch))
+    }
+
+    pub fn assert_season_approved(ctx: Context<AssertProductionSeason>) -> Result<SeasonView> {
+        let production_season = &ctx.accounts.production_season;
+        require!(
+            production_season.validation_status == ValidationStatus::Approved,
+            ErrorCode::SeasonNotApproved
+        );
+        Ok(SeasonView {
+            production_season: production_season.key(),
+            farmer_id: production_season.farmer_id,
+            validator_id: production_season.validator_id,
+            compliance_status: production_season.compliance_status.clone(),
+            available_for_milling_kg: production_season.available_for_milling_kg(),
+            updated_at: production_season.updated_at,
+        })
+    }
+
+    // Token Instructions
+    /// Registers `wallet` as the token wallet of `chain_actor`: batch tokens
+    /// it receives move custody of the batch to the actor.
+    pub fn register_custodian(ctx: Context<RegisterCustodian>) -> Result<()> {
+        let custodian = &mut ctx.accounts.custodian;
+        let clock = Clock::get()?;
+
+        custodian.public_key = custodian.key();
+        custodian.chain_actor = ctx.accounts.chain_actor.key();
+        custodian.wallet = ctx.accounts.wallet.key();
+        custodian.created_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    /// Mints the batch as a Token-2022 token to the custodian's wallet,
+    /// which must belong to the batch's holder, or to an actor of its
+    /// creator while it has none. Batches in transit or consumed stay
+    /// untokenized. The mint carries its own metadata, with the batch
+    /// address as a field, and a transfer hook into this program that keeps
+    /// `current_holder` in step with the tokens. Mint and metadata authority
+    /// stay with the `["mint_authority"]` PDA, which mints nothing further.
+    pub fn tokenize_rice_batch(
+        ctx: Context<TokenizeRiceBatch>,
+        token_unit: TokenUnit,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        ctx.accounts
+            .rice_batch
+            .check_custody(Some(&ctx.accounts.chain_actor), &ctx.accounts.authority.key())?;
+        let rice_batch = &mut ctx.accounts.rice_batch;
+        let holder = ctx.accounts.custodian.chain_actor;
+        require!(
+            matches!(rice_batch.batch_status, BatchStatus::Created | BatchStatus::Delivered),
+            ErrorCode::InvalidStatusTransition
+        );
+        let supply = token_unit.supply(rice_batch.weight_kg);
+        require!(supply > 0, ErrorCode::InvalidQuantity);
+        let clock = Clock::get()?;
+
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let token_mint = ctx.accounts.token_mint.to_account_info();
+        let mint_authority = ctx.accounts.mint_authority.to_account_info();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"mint_authority", &[ctx.bumps.mint_authority]]];
+
+        let fields = [
+            ("rice_batch", rice_batch.key().to_string()),
+            ("unit", token_unit.label().to_string()),
+            ("weight_kg", rice_batch.weight_kg.to_string()),
+        ];
+        let metadata = TokenMetadata {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+            additional_metadata: fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+            ..Default::default()
+        };
+        // Token-2022 grows the mint to hold the metadata but leaves the rent to us.
+        let data_len = token_mint.data_len() + metadata.tlv_size_of()?;
+        let shortfall = Rent::get()?
+            .minimum_balance(data_len)
+            .saturating_sub(token_mint.lamports());
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: token_mint.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+        token_interface::token_metadata_initialize(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                TokenMetadataInitialize {
+                    program_id: token_program.clone(),
+                    metadata: token_mint.clone(),
+                    update_authority: mint_authority.clone(),
+                    mint_authority: mint_authority.clone(),
+                    mint: token_mint.clone(),
+                },
+                signer_seeds,
+            ),
+            name,
+            symbol,
+            uri,
+        )?;
+        for (key, value) in fields {
+            token_interface::token_metadata_update_field(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    TokenMetadataUpdateField {
+                        program_id: token_program.clone(),
+                        metadata: token_mint.clone(),
+                        update_authority: mint_authority.clone(),
+                    },
+                    signer_seeds,
+                ),
+                Field::Key(key.to_string()),
+                value,
+            )?;
+        }
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                token_program,
+                MintTo {
+                    mint: token_mint.clone(),
+                    to: ctx.accounts.holder_token.to_account_info(),
+                    authority: mint_authority,
+                },
+                signer_seeds,
+            ),
+            supply,
+        )?;
+
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &transfer_hook_accounts(&rice_batch.key())?,
+        )?;
+
+        rice_batch.token_mint = Some(token_mint.key());
+        rice_batch.current_holder = Some(holder);
+        rice_batch.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    /// Token-2022 calls this on every transfer of a batch token. The batch
+    /// goes to the actor registered for the destination wallet once that
+    /// wallet holds the whole supply, and to no one while the supply is
+    /// split or sits with an unregistered wallet.
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, _amount: u64) -> Result<()> {
+        let source_token = ctx.accounts.source_token.to_account_info();
+        let data = source_token.try_borrow_data()?;
+        let transferring = StateWithExtensions::<SplTokenAccount>::unpack(&data)?
+            .get_extension::<TransferHookAccount>()?
+            .transferring;
+        require!(bool::from(transferring), ErrorCode::NotTransferring);
+
+        let holder = if ctx.accounts.destination_token.amount == ctx.accounts.token_mint.supply {
+            registered_actor(&ctx.accounts.destination_custodian)?
+        } else {
+            None
+        };
+        let rice_batch = &mut ctx.accounts.rice_batch;
+        rice_batch.current_holder = holder;
+        rice_batch.updated_at = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    // Carbon Credit Instructions
+    /// Mints credits for the methane an approved, CarbonSmart-certified
+    /// season avoided to its farmer's registered wallet, one token per
+    /// tonne of CO2e from a mint of the season's own. Each season is
+    /// credited once, with the approving validator's signature, and only
+    /// for a drainage regime over a plausible area.
+    pub fn issue_carbon_credits(ctx: Context<IssueCarbonCredits>) -> Result<()> {
+        let production_season = &mut ctx.accounts.production_season;
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.accreditation.is_valid_at(clock.unix_timestamp),
+            ErrorCode::AccreditationInactive
+        );
+        production_season.check_creditable()?;
+        require!(
+            ctx.accounts.certification.certifies(
+                &production_season.key(),
+                &CertificationScheme::CarbonSmart,
+                clock.unix_timestamp
+            ),
+            ErrorCode::NotCarbonSmart
+        );
+        let emission_factors = &ctx.accounts.emission_factors;
+        let avoided_methane_g = emission_factors
+            .avoided_methane_g(production_season)
+            .ok_or(ErrorCode::MissingEmissionData)?;
+        let issued_kg_co2e = emission_factors.kg_co2e(avoided_methane_g)?;
+        require!(issued_kg_co2e > 0, ErrorCode::NoAvoidedEmissions);
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.credit_mint.to_account_info(),
+                    to: ctx.accounts.farmer_token.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[&[b"mint_authority", &[ctx.bumps.mint_authority]]],
+            ),
+            issued_kg_co2e,
+        )?;
+
+        production_season.carbon_credits = Some(CarbonCredits {
+            mint: ctx.accounts.credit_mint.key(),
+            avoided_methane_g,
+            issued_kg_co2e,
+            retired_kg_co2e: 0,
+            issued_at: clock.unix_timestamp,
+        });
+        production_season.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    /// Burns `amount_kg_co2e` of a season's credits from the owner's token
+    /// account and counts them as retired on the season.
+    pub fn retire_carbon_credits(
+        ctx: Context<RetireCarbonCredits>,
+        amount_kg_co2e: u64,
+    ) -> Result<()> {
+        require!(amount_kg_co2e > 0, ErrorCode::InvalidQuantity);
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.credit_mint.to_account_info(),
+                    from: ctx.accounts.owner_token.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount_kg_co2e,
+        )?;
+
+        let production_season = &mut ctx.accounts.production_season;
+        let clock = Clock::get()?;
+        let carbon_credits = production_season
+            .carbon_credits
+            .as_mut()
+            .ok_or(ErrorCode::CreditMintMismatch)?;
+        carbon_credits.retired_kg_co2e = carbon_credits
+            .retired_kg_co2e
+            .checked_add(amount_kg_co2e)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        production_season.updated_at = clock.unix_timestamp;
+
+        emit!(CarbonCreditsRetired {
+            production_season: production_season.key(),
+            owner: ctx.accounts.owner.key(),
+            amount_kg_co2e,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+/// Loads each `RiceBatch` in `batch_accounts`, which must match
+/// `rice_batch_ids` in order, and applies `check`.
+fn read_batches(
+    batch_accounts: &[AccountInfo],
+    rice_batch_ids: &[Pubkey],
+    mut check: impl FnMut(&RiceBatch) -> Result<()>,
+) -> Result<()> {
+    require!(
+        batch_accounts.len() == rice_batch_ids.len(),
+        ErrorCode::BatchAccountMismatch
+    );
+    for (info, rice_batch_id) in batch_accounts.iter().zip(rice_batch_ids) {
+        require_keys_eq!(info.key(), *rice_batch_id, ErrorCode::BatchAccountMismatch);
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::BatchAccountMismatch);
+
+        let rice_batch = RiceBatch::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        check(&rice_batch)?;
+    }
+    Ok(())
+}
+
+/// Loads each `RiceBatch` in `batch_accounts`, which must match
+/// `rice_batch_ids` in order, applies `update` and writes it back.
+fn update_batches(
+    batch_accounts: &[AccountInfo],
+    rice_batch_ids: &[Pubkey],
+    mut update: impl FnMut(&mut RiceBatch) -> Result<()>,
+) -> Result<()> {
+    require!(
+        batch_accounts.len() == rice_batch_ids.len(),
+        ErrorCode::BatchAccountMismatch
+    );
+    for (info, rice_batch_id) in batch_accounts.iter().zip(rice_batch_ids) {
+        require_keys_eq!(info.key(), *rice_batch_id, ErrorCode::BatchAccountMismatch);
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::BatchAccountMismatch);
+        require!(info.is_writable, ErrorCode::BatchAccountMismatch);
+
+        let mut rice_batch = RiceBatch::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        update(&mut rice_batch)?;
+        rice_batch.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    }
+    Ok(())
+}
+
+/// One in the 32.32 fixed point of `EmissionFactors::methane_g_under`.
+const FIXED_ONE: u128 = 1 << 32;
+
+/// `ln 2` in 32.32 fixed point.
+const FIXED_LN_2: u128 = 2_977_044_472;
+
+/// Base-2 logarithm of `x`, both in 32.32 fixed point; `x` is at least one.
+fn log2_fixed(x: u128) -> u128 {
+    let integer = x.ilog2().saturating_sub(32);
+    let mut y = x >> integer;
+    let mut log2 = (integer as u128) << 32;
+    // Squaring y doubles its logarithm, shifting out one fractional bit.
+    for bit in (0..32).rev() {
+        y = (y * y) >> 32;
+        if y >= 2 * FIXED_ONE {
+            y >>= 1;
+            log2 |= 1 << bit;
+        }
+    }
+    log2
+}
+
+/// Two to the power of `y`, both in 32.32 fixed point, or `None` when the
+/// result does not fit.
+fn exp2_fixed(y: u128) -> Option<u128> {
+    let integer = (y >> 32) as u32;
+    // 2^f = e^(f ln 2), summed as a Taylor series for the fraction f.
+    let z = ((y & (FIXED_ONE - 1)) * FIXED_LN_2) >> 32;
+    let mut sum = FIXED_ONE;
+    let mut term = FIXED_ONE;
+    for k in 1..20 {
+        term = term * z / (k * FIXED_ONE);
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+    (integer <= 90).then(|| sum << integer)
+}
+
+/// Accounts Token-2022 passes to `transfer_hook` after the four of the
+/// transfer and the `ExtraAccountMetaList`: the batch, and the custodian PDA
+/// of the destination token account's owner.
+fn transfer_hook_accounts(rice_batch: &Pubkey) -> Result<[ExtraAccountMeta; TRANSFER_HOOK_EXTRA_ACCOUNTS]> {
+    Ok([
+        ExtraAccountMeta::new_with_pubkey(rice_batch, false, true)?,
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: b"custodian".to_vec(),
+                },
+                Seed::AccountData {
+                    account_index: 2,
+                    data_index: 32,
+                    length: 32,
+                },
+            ],
+            false,
+            false,
+        )?,
+    ])
+}
+
+const TRANSFER_HOOK_EXTRA_ACCOUNTS: usize = 2;
+
+/// The actor a `Custodian` PDA registers, or `None` when nothing was
+/// registered at that address.
+fn registered_actor(custodian: &AccountInfo) -> Result<Option<Pubkey>> {
+    if *custodian.owner != crate::ID {
+        return Ok(None);
+    }
+    let custodian = Custodian::try_deserialize(&mut &custodian.try_borrow_data()?[..])?;
+    Ok(Some(custodian.chain_actor))
+}
+
+// Account Structures
+#[account]
+pub struct ChainActor {
+    pub public_key: Pubkey,
+    /// Wallet that created the actor and signs on its behalf.
+    pub authority: Pubkey,
+    pub name: String,
+    pub actor_type: Vec<String>,
+    pub farm_id: Option<Pubkey>,
+    pub farmer_id: Option<Pubkey>,
+    pub assigned_tps: u64,
+    pub is_active: bool,
+    pub balance: u64,
+    pub pin: String,
+    pub organization: Organization,
+    pub address: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[account]
+pub struct ProductionSeason {
+    pub public_key: Pubkey,
+    pub farmer_id: Pubkey,
+    pub crop_year: String,
+    pub processed_yield_kg: u64,
+    pub variety: Option<RiceVariety>,
+    pub planned_practice: Option<String>,
+    pub planting_date: Option<i64>,
+    pub irrigation_practice: Option<WaterRegime>,
+    /// Active ingredients applied, separated by commas, semicolons,
+    /// slashes, plus signs or newlines; screened against the substance
+    /// registry by exact name.
+    pub fertilizer_used: Option<String>,
+    /// Same format as `fertilizer_used`.
+    pub pesticide_used: Option<String>,
+    pub harvest_date: Option<i64>,
+    pub total_yield_kg: Option<u64>,
+    pub moisture_content: Option<u32>,
+    /// Set by `validate_production_season`; an approval falls back to
+    /// `Pending` when the dates, yield, area or practices change.
+    pub validation_status: ValidationStatus,
+    /// Accredited validator `ChainActor` behind `validation_status`.
+    pub validator_id: Option<Pubkey>,
+    /// Outcome of screening `fertilizer_used`/`pesticide_used` against the `SubstanceRegistry`.
+    pub compliance_status: ComplianceStatus,
+    /// Running total of nitrogen from recorded `InputApplication`s.
+    pub nitrogen_applied_g_per_ha: u64,
+    /// Earliest harvest the pre-harvest intervals of recorded
+    /// `InputApplication`s allow; removing an application leaves it as is.
+    pub pre_harvest_clear_at: Option<i64>,
+    /// Weight lost in recorded `PostHarvestEvent`s (drying shrink, cleaning).
+    pub post_harvest_loss_kg: u64,
+    /// Paddy taken by the season's `MilledRice` runs.
+    pub milled_paddy_kg: u64,
+    pub cultivated_area_m2: Option<u64>,
+    pub organic_amendments: Vec<OrganicAmendment>,
+    /// Methane emitted over the season by the `EmissionFactors` in force at
+    /// its last update, in grams. `None` until the area, both dates and the
+    /// irrigation practice are recorded.
+    pub methane_estimate_g: Option<u64>,
+    /// Set once by `issue_carbon_credits`.
+    pub carbon_credits: Option<CarbonCredits>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl ProductionSeason {
+    pub const MAX_ORGANIC_AMENDMENTS: usize = 4;
+    /// Largest field credits are issued for: 100 ha.
+    pub const MAX_CREDITED_AREA_M2: u64 = 1_000_000;
+    /// Harvest per hectare below which a season's declared area is not
+    /// believed for credits.
+    pub const MIN_CREDITED_YIELD_KG_PER_HA: u64 = 1_000;
+
+    /// Harvested paddy left for milling after post-harvest losses and
+    /// earlier milling runs.
+    pub fn available_for_milling_kg(&self) -> u64 {
+        self.total_yield_kg
+            .unwrap_or(0)
+            .saturating_sub(self.post_harvest_loss_kg)
+            .saturating_sub(self.milled_paddy_kg)
+    }
+
+    /// Takes `milled_kg` of paddy for a milling run that previously took
+    /// `replaced_kg`, failing if the season has not that much left.
+    pub fn record_milling(&mut self, replaced_kg: u64, milled_kg: u64) -> Result<()> {
+        self.milled_paddy_kg = self.milled_paddy_kg.saturating_sub(replaced_kg);
+        require!(
+            milled_kg <= self.available_for_milling_kg(),
+            ErrorCode::InsufficientQuantity
+        );
+        self.milled_paddy_kg += milled_kg;
+        Ok(())
+    }
+
+    /// Days from planting to harvest, when both are recorded in that order.
+    pub fn cultivation_days(&self) -> Option<u64> {
+        let seconds = self.harvest_date?.checked_sub(self.planting_date?)?;
+        (seconds > 0).then_some(seconds as u64 / 86_400)
+    }
+
+    /// Checks the season's practice and area can back carbon credits: the
+    /// field was drained rather than never flooded, and its declared area
+    /// is within the cap and matches the harvest.
+    pub fn check_creditable(&self) -> Result<()> {
+        let irrigation_practice = self.irrigation_practice.ok_or(ErrorCode::MissingEmissionData)?;
+        require!(irrigation_practice.is_creditable(), ErrorCode::RegimeNotCreditable);
+        let cultivated_area_m2 = self.cultivated_area_m2.ok_or(ErrorCode::MissingEmissionData)?;
+        let total_yield_kg = self.total_yield_kg.ok_or(ErrorCode::MissingEmissionData)?;
+        require!(
+            cultivated_area_m2 <= Self::MAX_CREDITED_AREA_M2
+                && total_yield_kg as u128 * 10_000
+                    >= cultivated_area_m2 as u128 * Self::MIN_CREDITED_YIELD_KG_PER_HA as u128,
+            ErrorCode::ImplausibleArea
+        );
+        Ok(())
+    }
+}
+
+impl RiceBatch {
+    pub const MAX_QR_CODE_LEN: usize = 128;
+
+    pub fn packed_weight_g(&self) -> u64 {
+        (self.weight_kg as u64 * 1_000).saturating_sub(self.remaining_weight_g)
+    }
+
+    /// Checks that `authority` speaks for the batch: it signs for `holder`,
+    /// the batch's `current_holder`, or it created a batch with no holder yet.
+    /// A tokenized batch without a holder has its supply split or with an
+    /// unregistered wallet, and no one speaks for it.
+    pub fn check_custody(&self, holder: Option<&Account<ChainActor>>, authority: &Pubkey) -> Result<()> {
+        let held = match (self.current_holder, holder) {
+            (Some(current_holder), Some(holder)) => {
+                holder.key() == current_holder && holder.authority == *authority
+            }
+            (Some(_), None) => false,
+            (None, _) => self.token_mint.is_none() && self.creator == *authority,
+        };
+        require!(held, ErrorCode::NotBatchHolder);
+        Ok(())
+    }
+}
+
+/// Reverse lookup from a scanned QR payload to its batch, at the PDA
+/// `["qr_index", sha256(payload)]`. One index per payload keeps codes unique.
+#[account]
+pub struct QrIndex {
+    pub rice_batch: Pubkey,
+    pub payload_hash: [u8; 32],
+    pub created_at: i64,
+}
+
+impl QrIndex {
+    /// GS1 Digital Links hash their canonical URI, so a code resolves to the
+    /// same batch whichever resolver domain or query string it carries.
+    pub fn payload_hash(qr_code: &str) -> [u8; 32] {
+        match Gs1Key::from_digital_link(qr_code) {
+            Ok(Some(gs1)) => hashv(&[gs1.canonical_uri().as_bytes()]).to_bytes(),
+            _ => hashv(&[qr_code.as_bytes()]).to_bytes(),
+        }
+    }
+}
+
+#[account]
+pub struct MilledRice {
+    pub public_key: Pubkey,
+    pub farmer_id: Pubkey,
+    /// Season of `farmer_id` the paddy was harvested in.
+    pub production_season: Pubkey,
+    /// Wallet that recorded the milling; only it may amend the record.
+    pub creator: Pubkey,
+    /// Milled rice output.
+    pub total_weight_kg: u32,
+    /// Output already put into `RiceBatch`es.
+    pub allocated_kg: u32,
+    pub milling_type: MillingType,
+    pub quality: QualityGrade,
+    pub photo_urls: Vec<String>,
+    pub moisture: u32,
+    /// Paddy input.
+    pub total_weight_processed_kg: u32,
+    pub by_products: ByProducts,
+    /// Milled output as a share of paddy input, in basis points.
+    pub milling_recovery_bp: u16,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl MilledRice {
+    /// Checks that output plus by-products does not exceed the paddy input
+    /// and recomputes `milling_recovery_bp`.
+    pub fn update_recovery(&mut self) -> Result<()> {
+        let accounted_kg = self.total_weight_kg as u64 + self.by_products.total_kg();
+        require!(
+            accounted_kg <= self.total_weight_processed_kg as u64,
+            ErrorCode::WeightIncrease
+        );
+        self.milling_recovery_bp = if self.total_weight_processed_kg == 0 {
+            0
+        } else {
+            (self.total_weight_kg as u64 * 10_000 / self.total_weight_processed_kg as u64) as u16
+        };
+        Ok(())
+    }
+
+    /// Puts `allocated_kg` of output into a batch that previously held
+    /// `replaced_kg`, failing if the run has not that much left.
+    pub fn allocate(&mut self, replaced_kg: u32, allocated_kg: u32) -> Result<()> {
+        let allocated_kg = self
+            .allocated_kg
+            .saturating_sub(replaced_kg)
+            .checked_add(allocated_kg)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(
+            allocated_kg <= self.total_weight_kg,
+            ErrorCode::InsufficientQuantity
+        );
+        self.allocated_kg = allocated_kg;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct RiceBatch {
+    pub public_key: Pubkey,
+    pub milled_rice_id: Pubkey,
+    pub batch_status: BatchStatus,
+    pub quality_score: u32,
+    pub weight_kg: u32,
+    pub qr_code: String,
+    /// GTIN, lot and serial parsed from `qr_code` when it is a GS1 Digital Link.
+    pub gs1: Option<Gs1Key>,
+    /// Grade from the most recently recorded valid `QualityTest`.
+    pub effective_grade: Option<QualityGrade>,
+    pub latest_quality_test: Option<Pubkey>,
+    pub current_warehouse: Option<Pubkey>,
+    /// Actor the batch went to in its most recently completed `ChainTransaction`,
+    /// or, once tokenized, the registered actor holding all of its tokens.
+    pub current_holder: Option<Pubkey>,
+    /// Wallet that created the batch; it speaks for the batch until the
+    /// batch has a `current_holder`.
+    pub creator: Pubkey,
+    /// Token-2022 mint created by `tokenize_rice_batch`.
+    pub token_mint: Option<Pubkey>,
+    /// Number of `StorageRecord`s created for this batch.
+    pub storage_count: u16,
+    /// Weight not yet divided into retail packs.
+    pub remaining_weight_g: u64,
+    /// Serial number the next `RetailPackRun` starts at.
+    pub next_pack_serial: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[account]
+pub struct ChainTransaction {
+    pub public_key: Pubkey,
+    pub from_actor_id: Pubkey,
+    pub to_actor_id: Pubkey,
+    pub rice_batch_ids: Vec<Pubkey>,
+    pub amount: u64,
+    pub payment_method: PaymentMethod,
+    pub payment_reference: Option<String>,
+    pub geotag: Option<GeoPoint>,
+    pub quality: Option<QualityGrade>,
+    pub transaction_status: TransactionStatus,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// What `assert_batch_status` and `assert_batch_holder` return to the caller.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct BatchView {
+    pub rice_batch: Pubkey,
+    pub milled_rice_id: Pubkey,
+    pub batch_status: BatchStatus,
+    pub current_holder: Option<Pubkey>,
+    pub weight_kg: u32,
+    pub effective_grade: Option<QualityGrade>,
+    pub updated_at: i64,
+}
+
+impl From<&RiceBatch> for BatchView {
+    fn from(rice_batch: &RiceBatch) -> Self {
+        BatchView {
+            rice_batch: rice_batch.public_key,
+            milled_rice_id: rice_batch.milled_rice_id,
+            batch_status: rice_batch.batch_status.clone(),
+            current_holder: rice_batch.current_holder,
+            weight_kg: rice_batch.weight_kg,
+            effective_grade: rice_batch.effective_grade.clone(),
+            updated_at: rice_batch.updated_at,
+        }
+    }
+}
+
+/// What `assert_season_approved` returns to the caller.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct SeasonView {
+    pub production_season: Pubkey,
+    pub farmer_id: Pubkey,
+    pub validator_id: Option<Pubkey>,
+    pub compliance_status: ComplianceStatus,
+    pub available_for_milling_kg: u64,
+    pub updated_at: i64,
+}
+
+#[account]
+pub struct InputApplication {
+    pub public_key: Pubkey,
+    pub production_season: Pubkey,
+    /// `ChainActor` that applied the input.
+    pub applicator: Pubkey,
+    pub input_kind: InputKind,
+    pub product: String,
+    pub active_ingredient: Option<String>,
+    /// Product applied per hectare, in grams (liquids converted by the client).
+    pub rate_g_per_ha: u64,
+    /// Nitrogen share of the product by mass, in basis points (urea is 4600).
+    pub nitrogen_content_bp: u16,
+    pub applied_at: i64,
+    /// Label pre-harvest interval required after this application.
+    pub pre_harvest_interval_days: Option<u16>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl InputApplication {
+    pub const MAX_NAME_LEN: usize = 64;
+
+    pub fn nitrogen_g_per_ha(&self) -> u64 {
+        (self.rate_g_per_ha as u128 * self.nitrogen_content_bp as u128 / 10_000) as u64
+    }
+
+    /// Earliest harvest this application's pre-harvest interval allows, if
+    /// it has one.
+    pub fn pre_harvest_clear_at(&self) -> Option<i64> {
+        self.pre_harvest_interval_days
+            .map(|days| self.applied_at.saturating_add(days as i64 * 86_400))
+    }
+
+    /// Whether a harvest at `harvest_date` respects this application's
+    /// pre-harvest interval. Applications without one always pass.
+    pub fn pre_harvest_interval_met(&self, harvest_date: i64) -> bool {
+        self.pre_harvest_clear_at()
+            .is_none_or(|clear_at| harvest_date >= clear_at)
+    }
+}
+
+#[account]
+pub struct SubstanceRegistry {
+    pub entries: Vec<SubstanceEntry>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl SubstanceRegistry {
+    pub const MAX_ENTRIES: usize = 64;
+
+    /// Reads the registry passed at its PDA, or `None` while the admin has
+    /// not initialized it; screening is skipped until then.
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.owner == &system_program::ID && info.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*info.owner, crate::ID, anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram);
+        Ok(Some(Self::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+    }
+
+    pub fn find(&self, active_ingredient: &str) -> Option<&SubstanceEntry> {
+        let active_ingredient = SubstanceEntry::normalize(active_ingredient);
+        self.entries
+            .iter()
+            .find(|entry| entry.active_ingredient == active_ingredient)
+    }
+
+    /// Entries named by one of the items `text` lists, once both are
+    /// normalized. A name inside a longer one does not match, so declaring
+    /// "dimethoate" does not flag an "omethoate" entry.
+    pub fn matches<'a>(&'a self, text: &str) -> impl Iterator<Item = &'a SubstanceEntry> + 'a {
+        let declared: Vec<String> = text
+            .split([',', ';', '/', '+', '\n'])
+            .map(SubstanceEntry::normalize)
+            .filter(|item| !item.is_empty())
+            .collect();
+        self.entries
+            .iter()
+            .filter(move |entry| declared.contains(&entry.active_ingredient))
+    }
+
+    /// Sets the season's `compliance_status` from the substances named in its
+    /// `fertilizer_used`/`pesticide_used` and emits a `SubstanceComplianceEvent`
+    /// for every banned or restricted match.
+    pub fn screen_season(&self, production_season: &mut ProductionSeason, timestamp: i64) {
+        let mut compliance_status = ComplianceStatus::Compliant;
+        let declared = [&production_season.fertilizer_used, &production_season.pesticide_used];
+        for entry in declared.into_iter().flatten().flat_map(|text| self.matches(text)) {
+            compliance_status = match (&compliance_status, &entry.status) {
+                (_, SubstanceStatus::Banned) => ComplianceStatus::NonCompliant,
+                (ComplianceStatus::Compliant, SubstanceStatus::Restricted) => ComplianceStatus::Restricted,
+                (current, _) => current.clone(),
+            };
+            emit!(SubstanceComplianceEvent {
+                production_season: production_season.public_key,
+                active_ingredient: entry.active_ingredient.clone(),
+                status: entry.status.clone(),
+                timestamp,
+            });
+        }
+        production_season.compliance_status = compliance_status;
+    }
+}
+
+/// Admin-set IPCC Tier 1 factors behind `ProductionSeason::methane_estimate_g`,
+/// at the PDA `["emission_factors"]`.
+#[account]
+pub struct EmissionFactors {
+    /// Emissions of a continuously flooded field without organic
+    /// amendments, in grams of CH4 per hectare per day.
+    pub baseline_methane_g_per_ha_day: u32,
+    /// Scaling factor of each `WaterRegime`, by variant, in basis points.
+    pub water_regime_scaling_bp: [u16; WaterRegime::COUNT],
+    /// Conversion factor of each `AmendmentKind` per tonne applied per
+    /// hectare, by variant, in basis points.
+    pub amendment_conversion_bp: [u16; AmendmentKind::COUNT],
+    /// Exponent of the organic amendment scaling factor, in basis points.
+    pub amendment_exponent_bp: u16,
+    /// 100-year global warming potential of methane, for carbon credits.
+    pub methane_gwp_100: u16,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl EmissionFactors {
+    pub const SIZE: usize = 4 + 2 * WaterRegime::COUNT + 2 * AmendmentKind::COUNT + 2 + 2 + 8 + 8;
+
+    /// Reads the factors passed at their PDA, or `None` while the admin has
+    /// not initialized them; seasons carry no estimate until then.
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.owner == &system_program::ID && info.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*info.owner, crate::ID, anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram);
+        Ok(Some(Self::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+    }
+
+    /// IPCC 2019 Refinement defaults, with the AR6 GWP of biogenic methane.
+    pub fn set_defaults(&mut self) {
+        self.baseline_methane_g_per_ha_day = 1_190;
+        self.water_regime_scaling_bp = [10_000, 7_100, 5_500, 5_400, 0];
+        self.amendment_conversion_bp = [10_000, 1_900, 1_700, 2_100, 4_500];
+        self.amendment_exponent_bp = 5_900;
+        self.methane_gwp_100 = 27;
+    }
+
+    /// Methane emitted over `production_season` under `water_regime`, in
+    /// grams: the baseline daily emissions scaled for the water regime and
+    /// organic amendments, over the cultivated area and the days from
+    /// planting to harvest. Computed in 32.32 fixed point so every validator
+    /// rounds alike; `None` when an input is missing or the result overflows.
+    pub fn methane_g_under(
+        &self,
+        production_season: &ProductionSeason,
+        water_regime: WaterRegime,
+    ) -> Option<u64> {
+        let area_days = (production_season.cultivation_days()? as u128)
+            .checked_mul(production_season.cultivated_area_m2? as u128)?;
+        // SFo = (1 + sum of rate in t/ha * CFOA) ^ exponent
+        let amendment_load: u128 = production_season
+            .organic_amendments
+            .iter()
+            .map(|amendment| {
+                amendment.rate_kg_per_ha as u128
+                    * self.amendment_conversion_bp[amendment.kind as usize] as u128
+                    * FIXED_ONE
+                    / 10_000_000
+            })
+            .sum();
+        let exponent = log2_fixed(FIXED_ONE + amendment_load) * self.amendment_exponent_bp as u128
+            / 10_000;
+        let amendment_scaling = exp2_fixed(exponent)?;
+        let methane_g = (self.baseline_methane_g_per_ha_day as u128)
+            .checked_mul(self.water_regime_scaling_bp[water_regime as usize] as u128)?
+            .checked_mul(amendment_scaling)?
+            .checked_mul(area_days)?
+            / (10_000 * 10_000 * FIXED_ONE);
+        u64::try_from(methane_g).ok()
+    }
+
+    /// Methane emitted over `production_season` under its recorded
+    /// irrigation practice.
+    pub fn methane_g(&self, production_season: &ProductionSeason) -> Option<u64> {
+        self.methane_g_under(production_season, production_season.irrigation_practice?)
+    }
+
+    /// Methane the season's water regime avoided against continuous
+    /// flooding of the same field, in grams.
+    pub fn avoided_methane_g(&self, production_season: &ProductionSeason) -> Option<u64> {
+        let methane_g = self.methane_g(production_season)?;
+        let baseline_g = self.methane_g_under(production_season, WaterRegime::ContinuousFlooding)?;
+        Some(baseline_g.saturating_sub(methane_g))
+    }
+
+    pub fn kg_co2e(&self, methane_g: u64) -> Result<u64> {
+        let co2e_g = methane_g
+            .checked_mul(self.methane_gwp_100 as u64)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(co2e_g / 1_000)
+    }
+}
+
+#[account]
+pub struct QualityTest {
+    pub public_key: Pubkey,
+    pub rice_batch: Pubkey,
+    /// Accredited laboratory `ChainActor` that ran the test.
+    pub laboratory: Pubkey,
+    pub sample_date: i64,
+    pub moisture_bp: u16,
+    pub broken_grain_bp: u16,
+    pub chalkiness_bp: u16,
+    pub foreign_matter_bp: u16,
+    pub residue_results: Vec<ResidueResult>,
+    pub overall_grade: QualityGrade,
+    /// SHA-256 of the signed laboratory report.
+    pub report_hash: [u8; 32],
+    pub is_valid: bool,
+    /// The batch's next test by sample date, going back.
+    pub previous_test: Option<Pubkey>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl QualityTest {
+    pub const MAX_RESIDUE_RESULTS: usize = 16;
+}
+
+#[account]
+pub struct Shipment {
+    pub public_key: Pubkey,
+    /// Carrier `ChainActor` responsible for the leg.
+    pub carrier: Pubkey,
+    pub vehicle_id: String,
+    pub origin: GeoPoint,
+    pub destination: GeoPoint,
+    pub departed_at: Option<i64>,
+    pub arrived_at: Option<i64>,
+    pub rice_batch_ids: Vec<Pubkey>,
+    pub seal_numbers: Vec<String>,
+    pub shipment_status: ShipmentStatus,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Shipment {
+    pub const MAX_VEHICLE_ID_LEN: usize = 32;
+    pub const MAX_BATCHES: usize = 16;
+    pub const MAX_SEALS: usize = 4;
+    pub const MAX_SEAL_LEN: usize = 32;
+}
+
+#[account]
+pub struct Warehouse {
+    pub public_key: Pubkey,
+    /// Operating `ChainActor`.
+    pub operator: Pubkey,
+    pub name: String,
+    pub location: GeoPoint,
+    pub capacity_kg: u64,
+    pub stored_kg: u64,
+    pub stored_batch_ids: Vec<Pubkey>,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Warehouse {
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_STORED_BATCHES: usize = 32;
+}
+
+/// One stay of a batch in a warehouse. Records are numbered per batch, so a
+/// batch's storage history is records `0..storage_count`.
+#[account]
+pub struct StorageRecord {
+    pub public_key: Pubkey,
+    pub rice_batch: Pubkey,
+    pub warehouse: Pubkey,
+    /// Batch weight when stored, released from the warehouse total on release.
+    pub weight_kg: u32,
+    pub stored_at: i64,
+    pub released_at: Option<i64>,
+}
+
+#[account]
+pub struct PostHarvestEvent {
+    pub public_key: Pubkey,
+    pub production_season: Pubkey,
+    /// `ChainActor` operating the drying facility.
+    pub facility: Pubkey,
+    pub drying_method: DryingMethod,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub start_moisture_bp: u16,
+    pub end_moisture_bp: u16,
+    pub weight_in_kg: u64,
+    pub weight_out_kg: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl PostHarvestEvent {
+    pub fn weight_loss_kg(&self) -> u64 {
+        self.weight_in_kg.saturating_sub(self.weight_out_kg)
+    }
+}
+
+/// A contiguous range of serialized retail packs cut from one batch. Pack
+/// `serial` in `first_serial..first_serial + pack_count` carries the code
+/// from `pack_code`, so individual bags need no accounts of their own.
+#[account]
+pub struct RetailPackRun {
+    pub public_key: Pubkey,
+    pub rice_batch: Pubkey,
+    /// `ChainActor` that packed the run.
+    pub packer: Pubkey,
+    pub pack_size_g: u32,
+    pub first_serial: u64,
+    pub pack_count: u32,
+    pub created_at: i64,
+}
+
+impl RetailPackRun {
+    pub fn contains(&self, serial: u64) -> bool {
+        serial >= self.first_serial && serial - self.first_serial < self.pack_count as u64
+    }
+
+    /// Code printed on pack `serial`: the first 16 bytes of
+    /// `sha256(rice_batch || serial_le)`.
+    pub fn pack_code(&self, serial: u64) -> [u8; 16] {
+        let hash = hashv(&[self.rice_batch.as_ref(), &serial.to_le_bytes()]);
+        let mut code = [0u8; 16];
+        code.copy_from_slice(&hash.to_bytes()[..16]);
+        code
+    }
+}
+
+#[account]
+pub struct IssuerKey {
+    pub public_key: Pubkey,
+    /// Miller `ChainActor` the key issues QR payloads for.
+    pub miller: Pubkey,
+    pub issuing_key: Pubkey,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Registers a wallet as the token wallet of a `ChainActor`, at the PDA
+/// `["custodian", wallet]`, so the transfer hook can tell who received a
+/// batch token.
+#[account]
+pub struct Custodian {
+    pub public_key: Pubkey,
+    pub chain_actor: Pubkey,
+    pub wallet: Pubkey,
+    pub created_at: i64,
+}
+
+#[account]
+pub struct ProgramConfig {
+    pub admin: Pubkey,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[account]
+pub struct Accreditation {
+    pub public_key: Pubkey,
+    pub actor: Pubkey,
+    pub kind: AccreditationKind,
+    pub granted_by: Pubkey,
+    pub valid_until: Option<i64>,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Accreditation {
+    pub fn is_valid_at(&self, timestamp: i64) -> bool {
+        self.is_active && self.valid_until.is_none_or(|valid_until| timestamp < valid_until)
+    }
+}
+
+#[account]
+pub struct Certification {
+    pub public_key: Pubkey,
+    pub scheme: CertificationScheme,
+    /// Accredited certifier `ChainActor` that issued the certification.
+    pub issuer: Pubkey,
+    pub subject: Pubkey,
+    pub subject_kind: SubjectKind,
+    pub issued_at: i64,
+    pub expires_at: i64,
+    /// SHA-256 of the off-chain audit report or certificate document.
+    pub evidence_hash: [u8; 32],
+    pub is_revoked: bool,
+    pub revoked_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Certification {
+    /// Whether the certification was in force at `timestamp`. A revoked
+    /// certification still covers the period before its revocation.
+    pub fn is_valid_at(&self, timestamp: i64) -> bool {
+        if timestamp < self.issued_at || timestamp >= self.expires_at {
+            return false;
+        }
+        match self.revoked_at {
+            Some(revoked_at) => timestamp < revoked_at,
+            None => !self.is_revoked,
+        }
+    }
+
+    pub fn certifies(&self, subject: &Pubkey, scheme: &CertificationScheme, timestamp: i64) -> bool {
+        self.subject == *subject && self.scheme == *scheme && self.is_valid_at(timestamp)
+    }
+}
+
+/// Whether any of `certifications` certifies the season or batch `subject`
+/// under `scheme` at `timestamp`.
+pub fn is_certified<'a>(
+    certifications: impl IntoIterator<Item = &'a Certification>,
+    subject: &Pubkey,
+    scheme: &CertificationScheme,
+    timestamp: i64,
+) -> bool {
+    certifications
+        .into_iter()
+        .any(|certification| certification.certifies(subject, scheme, timestamp))
+}
+
+// Enums
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum Organization {
+    Farmer,
+    Miller,
+    Distributor,
+    Retailer,
+    Government,
+    Cooperative,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum ValidationStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ValidationStatus {
+    /// Whether a season may move from this status to `next`. A verdict can
+    /// replace any status, but only an approval goes back to `Pending`.
+    pub fn can_transition_to(&self, next: &ValidationStatus) -> bool {
+        matches!(
+            (self, next),
+            (_, ValidationStatus::Approved | ValidationStatus::Rejected)
+                | (ValidationStatus::Approved, ValidationStatus::Pending)
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum BatchStatus {
+    Created,
+    InTransit,
+    Delivered,
+    Consumed,
+}
+
+impl BatchStatus {
+    /// Whether a batch may move from this status to `next`. `Consumed` is
+    /// terminal.
+    pub fn can_transition_to(&self, next: &BatchStatus) -> bool {
+        matches!(
+            (self, next),
+            (BatchStatus::Created | BatchStatus::Delivered, BatchStatus::InTransit)
+                | (BatchStatus::InTransit, BatchStatus::Delivered)
+                | (BatchStatus::Created | BatchStatus::Delivered, BatchStatus::Consumed)
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum PaymentMethod {
+    Cash,
+    BankTransfer,
+    DigitalWallet,
+    Cryptocurrency,
+    Credit,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum TransactionStatus {
+    Pending,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TransactionStatus {
+    /// Whether a transaction may move from this status to `next`. Only
+    /// `Pending` transactions can be settled, failed or cancelled.
+    pub fn can_transition_to(&self, next: &TransactionStatus) -> bool {
+        matches!(
+            (self, next),
+            (
+                TransactionStatus::Pending,
+                TransactionStatus::Completed | TransactionStatus::Failed | TransactionStatus::Cancelled
+            )
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum MillingType {
+    WellMilled,
+    RegularMilled,
+    Undermilled,
+    Brown,
+}
+
+/// Milled rice grades per the Philippine National Standard PNS/BAFS 290:2019.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum QualityGrade {
+    Premium,
+    Grade1,
+    Grade2,
+    Grade3,
+    OffGrade,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum RiceVariety {
+    /// NSIC-registered variety by Rc number, e.g. `NsicRc(222)` for NSIC Rc 222.
+    NsicRc(u16),
+    /// Variety registered under the former PSB by Rc number, e.g. `PsbRc(18)`.
+    PsbRc(u16),
+    Traditional,
+    Other,
+}
+
+impl RiceVariety {
+    pub const SIZE: usize = 1 + 2;
+
+    pub fn validate(&self) -> Result<()> {
+        if let RiceVariety::NsicRc(number) | RiceVariety::PsbRc(number) = self {
+            require!(*number > 0, ErrorCode::InvalidVarietyCode);
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AccreditationKind {
+    Certifier,
+    Laboratory,
+    Miller,
+    Validator,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum CertificationScheme {
+    CarbonSmart,
+    Organic,
+    PhilGap,
+    SustainableRicePlatform,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum SubjectKind {
+    ProductionSeason,
+    RiceBatch,
+}
+
+impl SubjectKind {
+    /// Checks that `account` is a program-owned record of this kind.
+    pub fn check_account(&self, account: &AccountInfo) -> Result<()> {
+        let discriminator = match self {
+            SubjectKind::ProductionSeason => ProductionSeason::DISCRIMINATOR,
+            SubjectKind::RiceBatch => RiceBatch::DISCRIMINATOR,
+        };
+        require!(
+            account.owner == &crate::ID && account.try_borrow_data()?.starts_with(discriminator),
+            ErrorCode::SubjectMismatch
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum InputKind {
+    Fertilizer,
+    Insecticide,
+    Herbicide,
+    Fungicide,
+    Molluscicide,
+    Other,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum SubstanceStatus {
+    Banned,
+    Restricted,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum ComplianceStatus {
+    Compliant,
+    /// Names a restricted substance; allowed but reported.
+    Restricted,
+    /// Names a banned substance; the season cannot be approved.
+    NonCompliant,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum ShipmentStatus {
+    Planned,
+    InTransit,
+    Arrived,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum DryingMethod {
+    SunDrying,
+    FlatBedDryer,
+    RecirculatingBatchDryer,
+    ContinuousFlowDryer,
+    Other,
+}
+
+/// How `tokenize_rice_batch` divides a batch.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TokenUnit {
+    /// A single non-fungible token for the whole batch.
+    Batch,
+    /// One token per kilogram, divisible to the gram.
+    Kilogram,
+}
+
+impl TokenUnit {
+    pub fn decimals(&self) -> u8 {
+        match self {
+            TokenUnit::Batch => 0,
+            TokenUnit::Kilogram => 3,
+        }
+    }
+
+    /// Base units minted for a batch of `weight_kg`.
+    pub fn supply(&self, weight_kg: u32) -> u64 {
+        match self {
+            TokenUnit::Batch => 1,
+            TokenUnit::Kilogram => weight_kg as u64 * 1_000,
+        }
+    }
+
+    /// Value of the `unit` metadata field.
+    pub fn label(&self) -> &'static str {
+        match self {
+            TokenUnit::Batch => "batch",
+            TokenUnit::Kilogram => "kg",
+        }
+    }
+}
+
+/// IPCC water regime of a cultivation period, which scales the methane
+/// emitted by a continuously flooded field.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum WaterRegime {
+    ContinuousFlooding,
+    SingleDrainage,
+    /// Alternate wetting and drying (AWD).
+    MultipleDrainage,
+    Rainfed,
+    Upland,
+}
+
+impl WaterRegime {
+    pub const COUNT: usize = 5;
+
+    /// Whether carbon credits are issued for the regime: only drainage of a
+    /// field that would otherwise stay flooded avoids methane against the
+    /// continuous-flooding baseline.
+    pub fn is_creditable(&self) -> bool {
+        matches!(self, WaterRegime::SingleDrainage | WaterRegime::MultipleDrainage)
+    }
+}
+
+/// Organic inputs the IPCC scales methane emissions for.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AmendmentKind {
+    /// Straw incorporated less than 30 days before cultivation.
+    StrawShortlyBefore,
+    /// Straw incorporated more than 30 days before cultivation.
+    StrawLongBefore,
+    Compost,
+    FarmyardManure,
+    GreenManure,
+}
+
+impl AmendmentKind {
+    pub const COUNT: usize = 5;
+}
+
+// Shared Types
+
+/// A WGS84 position in fixed-point microdegrees (1e-6 degree), usable on any
+/// location-bearing record.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct GeoPoint {
+    pub latitude_microdeg: i32,
+    pub longitude_microdeg: i32,
+    /// Horizontal accuracy radius in metres, when the capturing device reports one.
+    pub accuracy_m: Option<u32>,
+    pub captured_at: i64,
+}
+
+impl GeoPoint {
+    pub const SIZE: usize = 4 + 4 + (1 + 4) + 8;
+    pub const MAX_LATITUDE_MICRODEG: i32 = 90_000_000;
+    pub const MAX_LONGITUDE_MICRODEG: i32 = 180_000_000;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            (-Self::MAX_LATITUDE_MICRODEG..=Self::MAX_LATITUDE_MICRODEG)
+                .contains(&self.latitude_microdeg),
+            ErrorCode::LatitudeOutOfRange
+        );
+        require!(
+            (-Self::MAX_LONGITUDE_MICRODEG..=Self::MAX_LONGITUDE_MICRODEG)
+                .contains(&self.longitude_microdeg),
+            ErrorCode::LongitudeOutOfRange
+        );
+        Ok(())
+    }
+}
+
+/// GS1 keys of a batch whose QR code is a GS1 Digital Link URI,
+/// `https://<resolver>/01/<GTIN>/10/<lot>[/21/<serial>]`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct Gs1Key {
+    /// GTIN-14 as an integer; render zero-padded to 14 digits.
+    pub gtin: u64,
+    /// Batch/lot number (AI 10), percent-decoded.
+    pub lot: String,
+    /// Serial number (AI 21), when the code names a single item.
+    pub serial: Option<String>,
+}
+
+impl Gs1Key {
+    /// Longest lot number or serial GS1 allows.
+    pub const MAX_ATTRIBUTE_LEN: usize = 20;
+    pub const SIZE: usize =
+        8 + (4 + Self::MAX_ATTRIBUTE_LEN) + (1 + 4 + Self::MAX_ATTRIBUTE_LEN);
+    pub const CANONICAL_RESOLVER: &'static str = "https://id.gs1.org";
+
+    /// Parses `qr_code` as a GS1 Digital Link. Codes that are not http(s)
+    /// URIs with an `01` path segment followed by a GTIN-8, -12, -13 or -14
+    /// are opaque and give `Ok(None)`, so `https://example.com/2024/01/15`
+    /// is not mistaken for one; Digital Links need a valid check digit and a
+    /// lot, optionally followed by a serial.
+    pub fn from_digital_link(qr_code: &str) -> Result<Option<Self>> {
+        let Some(rest) = qr_code
+            .strip_prefix("https://")
+            .or_else(|| qr_code.strip_prefix("http://"))
+        else {
+            return Ok(None);
+        };
+        let rest = rest.split(['?', '#']).next().unwrap_or_default();
+        let Some((_, path)) = rest.split_once('/') else {
+            return Ok(None);
+        };
+        let segments: Vec<&str> = path.split('/').collect();
+        let Some(start) = segments.windows(2).position(|pair| {
+            pair[0] == "01" && Self::is_gtin_shaped(pair[1])
+        }) else {
+            return Ok(None);
+        };
+        let (gtin, lot, serial) = match segments[start..] {
+            ["01", gtin, "10", lot] => (gtin, lot, None),
+            ["01", gtin, "10", lot, "21", serial] => (gtin, lot, Some(serial)),
+            _ => return err!(ErrorCode::InvalidDigitalLink),
+        };
+        Ok(Some(Gs1Key {
+            gtin: Self::parse_gtin(gtin)?,
+            lot: Self::decode_attribute(lot)?,
+            serial: serial.map(Self::decode_attribute).transpose()?,
+        }))
+    }
+
+    /// The Digital Link URI of these keys under `resolver`, e.g.
+    /// `https://example.com`.
+    pub fn uri(&self, resolver: &str) -> String {
+        let mut uri = format!(
+            "{resolver}/01/{:014}/10/{}",
+            self.gtin,
+            Self::encode_attribute(&self.lot)
+        );
+        if let Some(serial) = &self.serial {
+            uri.push_str("/21/");
+            uri.push_str(&Self::encode_attribute(serial));
+        }
+        uri
+    }
+
+    pub fn canonical_uri(&self) -> String {
+        self.uri(Self::CANONICAL_RESOLVER)
+    }
+
+    /// GS1 mod-10 check digit of `body`, weighting digits 3, 1, 3, ... from
+    /// the right.
+    pub fn check_digit(mut body: u64) -> u64 {
+        let mut sum = 0;
+        let mut weight = 3;
+        while body > 0 {
+            sum += body % 10 * weight;
+            weight = 4 - weight;
+            body /= 10;
+        }
+        (10 - sum % 10) % 10
+    }
+
+    fn is_gtin_shaped(digits: &str) -> bool {
+        matches!(digits.len(), 8 | 12 | 13 | 14) && digits.bytes().all(|byte| byte.is_ascii_digit())
+    }
+
+    fn parse_gtin(digits: &str) -> Result<u64> {
+        require!(Self::is_gtin_shaped(digits), ErrorCode::InvalidDigitalLink);
+        let gtin: u64 = digits.parse().map_err(|_| ErrorCode::InvalidDigitalLink)?;
+        require!(
+            gtin % 10 == Self::check_digit(gtin / 10),
+            ErrorCode::InvalidDigitalLink
+        );
+        Ok(gtin)
+    }
+
+    /// Percent-decodes a lot or serial and checks it against GS1's
+    /// 82-character set.
+    fn decode_attribute(encoded: &str) -> Result<String> {
+        let bytes = encoded.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+        while index < bytes.len() {
+            if bytes[index] == b'%' {
+                let hex = bytes
+                    .get(index + 1..index + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .ok_or(ErrorCode::InvalidDigitalLink)?;
+                decoded.push(hex.iter().fold(0, |value, &digit| {
+                    value * 16 + char::from(digit).to_digit(16).unwrap_or_default() as u8
+                }));
+                index += 3;
+            } else {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+        require!(
+            (1..=Self::MAX_ATTRIBUTE_LEN).contains(&decoded.len())
+                && decoded.iter().all(|&byte| Self::is_cset82(byte)),
+            ErrorCode::InvalidDigitalLink
+        );
+        String::from_utf8(decoded).map_err(|_| ErrorCode::InvalidDigitalLink.into())
+    }
+
+    fn encode_attribute(value: &str) -> String {
+        value
+            .bytes()
+            .map(|byte| {
+                if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_') {
+                    char::from(byte).to_string()
+                } else {
+                    format!("%{byte:02X}")
+                }
+            })
+            .collect()
+    }
+
+    fn is_cset82(byte: u8) -> bool {
+        byte.is_ascii_alphanumeric() || b"!\"%&'()*+,-./:;<=>?_".contains(&byte)
+    }
+}
+
+/// Credits issued for a season's avoided methane. A token is a tonne of
+/// CO2e, so amounts are in kilograms.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct CarbonCredits {
+    /// The season's credit mint, `["carbon_credit_mint", production_season]`.
+    pub mint: Pubkey,
+    pub avoided_methane_g: u64,
+    pub issued_kg_co2e: u64,
+    /// Burnt through `retire_carbon_credits`.
+    pub retired_kg_co2e: u64,
+    pub issued_at: i64,
+}
+
+impl CarbonCredits {
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 8;
+    pub const DECIMALS: u8 = 3;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct OrganicAmendment {
+    pub kind: AmendmentKind,
+    /// Applied per hectare, in kilograms of dry weight for straw and of
+    /// fresh weight otherwise.
+    pub rate_kg_per_ha: u32,
+}
+
+impl OrganicAmendment {
+    pub const SIZE: usize = 1 + 4;
+}
+
+/// Milling outputs other than head rice, in kilograms.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct ByProducts {
+    pub husk_kg: u32,
+    pub bran_kg: u32,
+    pub brokens_kg: u32,
+    pub brewers_rice_kg: u32,
+}
+
+impl ByProducts {
+    pub const SIZE: usize = 4 + 4 + 4 + 4;
+
+    pub fn total_kg(&self) -> u64 {
+        self.husk_kg as u64 + self.bran_kg as u64 + self.brokens_kg as u64 + self.brewers_rice_kg as u64
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct SubstanceEntry {
+    /// Active ingredient name in `SubstanceEntry::normalize` form, e.g.
+    /// "endosulfan".
+    pub active_ingredient: String,
+    pub status: SubstanceStatus,
+    pub max_rate_g_per_ha: Option<u64>,
+}
+
+impl SubstanceEntry {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const SIZE: usize = (4 + Self::MAX_NAME_LEN) + 1 + (1 + 8);
+
+    /// Registry form of an active ingredient name: trimmed, lowercase, with
+    /// runs of whitespace collapsed to one space.
+    pub fn normalize(name: &str) -> String {
+        name.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_ascii_lowercase()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ResidueResult {
+    pub analyte: String,
+    pub measured_ug_per_kg: u32,
+    /// Maximum residue limit applied by the laboratory, if any.
+    pub mrl_ug_per_kg: Option<u32>,
+}
+
+impl ResidueResult {
+    pub const MAX_ANALYTE_LEN: usize = 32;
+    pub const SIZE: usize = (4 + Self::MAX_ANALYTE_LEN) + 4 + (1 + 4);
+
+    pub fn exceeds_mrl(&self) -> bool {
+        self.mrl_ug_per_kg
+            .is_some_and(|mrl_ug_per_kg| self.measured_ug_per_kg > mrl_ug_per_kg)
+    }
+}
+
+// Events
+#[event]
+pub struct SubstanceComplianceEvent {
+    pub production_season: Pubkey,
+    pub active_ingredient: String,
+    pub status: SubstanceStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CarbonCreditsRetired {
+    pub production_season: Pubkey,
+    pub owner: Pubkey,
+    pub amount_kg_co2e: u64,
+    pub timestamp: i64,
+}
+
+// Errors
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Latitude must be between -90 and 90 degrees")]
+    LatitudeOutOfRange,
+    #[msg("Longitude must be between -180 and 180 degrees")]
+    LongitudeOutOfRange,
+    #[msg("Registered variety codes must have a non-zero Rc number")]
+    InvalidVarietyCode,
+    #[msg("Signer is not the authority of this actor")]
+    Unauthorized,
+    #[msg("Chain actor is not active")]
+    ActorInactive,
+    #[msg("Actor does not hold a valid accreditation")]
+    AccreditationInactive,
+    #[msg("Validity period must end after it starts")]
+    InvalidValidityPeriod,
+    #[msg("Subject account does not match the declared subject kind")]
+    SubjectMismatch,
+    #[msg("Certification has already been revoked")]
+    CertificationRevoked,
+    #[msg("Certification was not issued by this certifier")]
+    NotIssuer,
+    #[msg("String exceeds the maximum allowed length")]
+    StringTooLong,
+    #[msg("Percentage in basis points must not exceed 10000")]
+    InvalidPercentage,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Season names a banned substance and cannot be approved")]
+    SeasonNotCompliant,
+    #[msg("Active ingredient is banned")]
+    BannedSubstance,
+    #[msg("Application rate exceeds the registered maximum")]
+    RateAboveMaximum,
+    #[msg("Registry is full")]
+    RegistryFull,
+    #[msg("Timestamp is in the future")]
+    TimestampInFuture,
+    #[msg("Too many entries")]
+    TooManyEntries,
+    #[msg("Quality test has already been invalidated")]
+    QualityTestInvalidated,
+    #[msg("Previous quality test accounts are missing or out of order")]
+    MissingPreviousTest,
+    #[msg("Status transition is not allowed")]
+    InvalidStatusTransition,
+    #[msg("Batch accounts do not match the recorded batch IDs")]
+    BatchAccountMismatch,
+    #[msg("Seal numbers do not match those recorded at departure")]
+    SealMismatch,
+    #[msg("Batch is held in a warehouse")]
+    BatchInStorage,
+    #[msg("Batch is not stored in this warehouse")]
+    BatchNotInWarehouse,
+    #[msg("Warehouse is not active")]
+    WarehouseInactive,
+    #[msg("Warehouse capacity exceeded")]
+    WarehouseCapacityExceeded,
+    #[msg("Output weight cannot exceed input weight")]
+    WeightIncrease,
+    #[msg("Event cannot start before the harvest date")]
+    BeforeHarvest,
+    #[msg("Quantity exceeds what is available")]
+    InsufficientQuantity,
+    #[msg("Quantity must be greater than zero")]
+    InvalidQuantity,
+    #[msg("Actor is not a miller")]
+    NotMiller,
+    #[msg("QR code is not a valid GS1 Digital Link with a GTIN and lot")]
+    InvalidDigitalLink,
+    #[msg("Batch is not in the expected status")]
+    BatchStatusMismatch,
+    #[msg("Actor does not hold the batch")]
+    NotBatchHolder,
+    #[msg("Production season is not approved")]
+    SeasonNotApproved,
+    #[msg("Batch has already been tokenized")]
+    AlreadyTokenized,
+    #[msg("Mint is not the token of this batch")]
+    BatchMintMismatch,
+    #[msg("Transfer hook was not invoked by a token transfer")]
+    NotTransferring,
+    #[msg("Season does not hold a valid CarbonSmart certification")]
+    NotCarbonSmart,
+    #[msg("Carbon credits have already been issued for this season")]
+    CarbonCreditsAlreadyIssued,
+    #[msg("Season lacks the area, dates or irrigation practice for an emission estimate")]
+    MissingEmissionData,
+    #[msg("Season avoided no emissions")]
+    NoAvoidedEmissions,
+    #[msg("Mint is not the carbon credit mint of this season")]
+    CreditMintMismatch,
+    #[msg("Rice batch is listed more than once")]
+    DuplicateBatch,
+    #[msg("QR code is already assigned to another batch")]
+    QrCodeInUse,
+    #[msg("Production season belongs to another farmer")]
+    SeasonFarmerMismatch,
+    #[msg("Irrigation practice does not earn carbon credits")]
+    RegimeNotCreditable,
+    #[msg("Cultivated area exceeds the cap or what the yield supports")]
+    ImplausibleArea,
+    #[msg("Harvest falls within an input application's pre-harvest interval")]
+    PreHarvestIntervalNotMet,
+}
+
+// Context Structs
+#[derive(Accounts)]
+#[instruction(actor_seed: String)]
+pub struct CreateChainActor<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 64 + 128 + 32 + 32 + 8 + 1 + 8 + 64 + 1 + 64 + 64 + 8 + 8,
+        seeds = [b"chain_actor", actor_seed.as_bytes()],
+        bump
+    )]
+    pub chain_actor: Account<'info, ChainActor>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(actor_seed: String)]
+pub struct UpdateChainActor<'info> {
+    #[account(
+        mut,
+        seeds = [b"chain_actor", actor_seed.as_bytes()],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub chain_actor: Account<'info, ChainActor>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(actor_seed: String)]
+pub struct DeleteChainActor<'info> {
+    #[account(
+        mut,
+        seeds = [b"chain_actor", actor_seed.as_bytes()],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub chain_actor: Account<'info, ChainActor>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_seed: String)]
+pub struct CreateProductionSeason<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 64 + 8 + (1 + RiceVariety::SIZE) + 64 + 8 + (1 + 1) + 64 + 64 + 8 + 8 + 4 + 1 + 32 + 1 + 8 + 8 + 64 + 8 + 8 + (1 + 8)
+            + (4 + ProductionSeason::MAX_ORGANIC_AMENDMENTS * OrganicAmendment::SIZE) + (1 + 8)
+            + (1 + CarbonCredits::SIZE) + (1 + 8) + 8,
+        seeds = [b"production_season", season_seed.as_bytes()],
+        bump
+    )]
+    pub production_season: Account<'info, ProductionSeason>,
+    /// CHECK: the registry PDA, read by `SubstanceRegistry::load`; may not be
+    /// initialized yet.
+    #[account(seeds = [b"substance_registry"], bump)]
+    pub substance_registry: UncheckedAccount<'info>,
+    /// CHECK: the factors PDA, read by `EmissionFactors::load`; may not be
+    /// initialized yet.
+    #[account(seeds = [b"emission_factors"], bump)]
+    pub emission_factors: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_seed: String)]
+pub struct UpdateProductionSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"production_season", season_seed.as_bytes()],
+        bump
+    )]
+    pub production_season: Account<'info, ProductionSeason>,
+    /// CHECK: the registry PDA, read by `SubstanceRegistry::load`; may not be
+    /// initialized yet.
+    #[account(seeds = [b"substance_registry"], bump)]
+    pub substance_registry: UncheckedAccount<'info>,
+    /// CHECK: the factors PDA, read by `EmissionFactors::load`; may not be
+    /// initialized yet.
+    #[account(seeds = [b"emission_factors"], bump)]
+    pub emission_factors: UncheckedAccount<'info>,
+    /// The season's farmer.
+    #[account(
+        address = production_season.farmer_id @ ErrorCode::Unauthorized,
+        constraint = farmer.authority == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub farmer: Account<'info, ChainActor>,
+    /// Signs for `farmer`.
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_seed: String)]
+pub struct ValidateProductionSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"production_season", season_seed.as_bytes()],
+        bump
+    )]
+    pub production_season: Account<'info, ProductionSeason>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = validator.is_active @ ErrorCode::ActorInactive
+    )]
+    pub validator: Account<'info, ChainActor>,
+    #[account(
+        seeds = [b"accreditation", validator.key().as_ref(), &[AccreditationKind::Validator as u8]],
+        bump
+    )]
+    pub accreditation: Account<'info, Accreditation>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_seed: String)]
+pub struct DeleteProductionSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"production_season", season_seed.as_bytes()],
+        bump
+    )]
+    pub production_season: Account<'info, ProductionSeason>,
+    /// The season's farmer.
+    #[account(
+        address = production_season.farmer_id @ ErrorCode::Unauthorized,
+        constraint = farmer.authority == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub farmer: Account<'info, ChainActor>,
+    /// Signs for `farmer`.
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(milling_seed: String, farmer_id: Pubkey)]
+pub struct CreateMilledRice<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 32 + 32 + 4 + 4 + 1 + 1 + 128 + 4 + 4 + ByProducts::SIZE + 2 + 64 + 8 + 8,
+        seeds = [b"milled_rice", milling_seed.as_bytes()],
+        bump
+    )]
+    pub milled_rice: Account<'info, MilledRice>,
+    #[account(
+        mut,
+        constraint = production_season.farmer_id == farmer_id @ ErrorCode::SeasonFarmerMismatch
+    )]
+    pub production_season: Account<'info, ProductionSeason>,
+    /// The season's farmer.
+    #[account(address = farmer_id @ ErrorCode::Unauthorized)]
+    pub farmer: Account<'info, ChainActor>,
+    /// The farmer signs for their paddy being milled, alongside the miller.
+    #[account(constraint = farmer.authority == farmer_authority.key() @ ErrorCode::Unauthorized)]
+    pub farmer_authority: Signer<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(milling_seed: String)]
+pub struct UpdateMilledRice<'info> {
+    #[account(
+        mut,
+        seeds = [b"milled_rice", milling_seed.as_bytes()],
+        bump,
+        constraint = milled_rice.creator == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub milled_rice: Account<'info, MilledRice>,
+    /// The season milled, whose available paddy a new input is checked against.
+    #[account(mut, address = milled_rice.production_season)]
+    pub production_season: Account<'info, ProductionSeason>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    batch_seed: String,
+    milled_rice_id: Pubkey,
+    batch_status: BatchStatus,
+    quality_score: u32,
+    weight_kg: u32,
+    qr_code: String
+)]
+pub struct CreateRiceBatch<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 1 + 4 + 4 + (4 + RiceBatch::MAX_QR_CODE_LEN) + (1 + Gs1Key::SIZE) + (1 + 1) + (1 + 32) + (1 + 32) + (1 + 32) + 32 + (1 + 32) + 2 + 8 + 8 + 64 + 8 + 8,
+        seeds = [b"rice_batch", batch_seed.as_bytes()],
+        bump
+    )]
+    pub rice_batch: Account<'info, RiceBatch>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 8,
+        seeds = [b"qr_index", &QrIndex::payload_hash(&qr_code)[..]],
+        bump
+    )]
+    pub qr_index: Account<'info, QrIndex>,
+    /// The milling run the batch comes from, which `user` recorded.
+    #[account(
+        mut,
+        address = milled_rice_id,
+        constraint = milled_rice.creator == user.key() @ ErrorCode::Unauthorized
+    )]
     pub milled_rice: Account<'info, MilledRice>,
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(batch_seed: String)]
+pub struct UpdateRiceBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"rice_batch", batch_seed.as_bytes()],
+        bump
+    )]
+    pub rice_batch: Account<'info, RiceBatch>,
+    /// The milling run the batch comes from, whose output a new weight is
+    /// checked against.
+    #[account(mut, address = rice_batch.milled_rice_id)]
+    pub milled_rice: Account<'info, MilledRice>,
+    /// The batch's current holder, or `None` while it has none.
+    pub holder: Option<Account<'info, ChainActor>>,
+    /// Signs for the holder, or is the batch's creator.
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(batch_seed: String, qr_code: String)]
+pub struct SetRiceBatchQrCode<'info> {
+    #[account(
+        mut,
+        seeds = [b"rice_batch", batch_seed.as_bytes()],
+        bump
+    )]
+    pub rice_batch: Account<'info, RiceBatch>,
+    /// Closed in the handler unless the new code hashes to the same entry.
+    #[account(
+        mut,
+        seeds = [b"qr_index", &QrIndex::payload_hash(&rice_batch.qr_code)[..]],
+        bump,
+        has_one = rice_batch
+    )]
+    pub old_qr_index: Account<'info, QrIndex>,
+    /// The batch's current holder, or `None` while it has none.
+    pub holder: Option<Account<'info, ChainActor>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 32 + 8,
+        seeds = [b"qr_index", &QrIndex::payload_hash(&qr_code)[..]],
+        bump
+    )]
+    pub new_qr_index: Account<'info, QrIndex>,
+    /// Signs for the holder, or is the batch's creator.
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(batch_seed: String)]
+pub struct DeleteRiceBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"rice_batch", batch_seed.as_bytes()],
+        bump
+    )]
+    pub rice_batch: Account<'info, RiceBatch>,
+    /// Closed so the consumed batch's code can label another batch.
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"qr_index", &QrIndex::payload_hash(&rice_batch.qr_code)[..]],
+        bump,
+        has_one = rice_batch
+    )]
+    pub qr_index: Account<'info, QrIndex>,
+    /// The batch's current holder, or `None` while it has none.
+    pub holder: Option<Account<'info, ChainActor>>,
+    /// Signs for the holder, or is the batch's creator.
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_seed: String)]
+pub struct CreateChainTransaction<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 32 + 128 + 8 + 1 + 64 + (1 + GeoPoint::SIZE) + (1 + 1) + 1 + 64 + 8 + 8,
+        seeds = [b"chain_transaction", transaction_seed.as_bytes()],
+        bump
+    )]
+    pub chain_transaction: Account<'info, ChainTransaction>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_seed: String)]
+pub struct UpdateChainTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"chain_transaction", transaction_seed.as_bytes()],
+        bump
+    )]
+    pub chain_transaction: Account<'info, ChainTransaction>,
+    #[account(
+        address = chain_transaction.from_actor_id @ ErrorCode::Unauthorized,
+        constraint = from_actor.authority == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub from_actor: Account<'info, ChainActor>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_seed: String)]
+pub struct DeleteChainTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"chain_transaction", transaction_seed.as_bytes()],
+        bump
+    )]
+    pub chain_transaction: Account<'info, ChainTransaction>,
+    #[account(
+        address = chain_transaction.from_actor_id @ ErrorCode::Unauthorized,
+        constraint = from_actor.authority == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub from_actor: Account<'info, ChainActor>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 8 + 8,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(kind: AccreditationKind)]
+pub struct GrantAccreditation<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 32 + 32 + 1 + 32 + (1 + 8) + 1 + 8 + 8,
+        seeds = [b"accreditation", chain_actor.key().as_ref(), &[kind as u8]],
+        bump
+    )]
+    pub accreditation: Account<'info, Accreditation>,
+    #[account(constraint = chain_actor.is_active @ ErrorCode::ActorInactive)]
+    pub chain_actor: Account<'info, ChainActor>,
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(milling_seed: String)]
-pub struct UpdateMilledRice<'info> {
+pub struct RevokeAccreditation<'info> {
+    #[account(mut)]
+    pub accreditation: Account<'info, Accreditation>,
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(certification_seed: String)]
+pub struct IssueCertification<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 32 + 32 + 1 + 8 + 8 + 32 + 1 + (1 + 8) + 8 + 8,
+        seeds = [b"certification", certification_seed.as_bytes()],
+        bump
+    )]
+    pub certification: Account<'info, Certification>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = certifier.is_active @ ErrorCode::ActorInactive
+    )]
+    pub certifier: Account<'info, ChainActor>,
+    #[account(
+        seeds = [b"accreditation", certifier.key().as_ref(), &[AccreditationKind::Certifier as u8]],
+        bump
+    )]
+    pub accreditation: Account<'info, Accreditation>,
+    /// CHECK: must be a `ProductionSeason` or `RiceBatch`, verified against `subject_kind` in the handler.
+    pub subject: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(certification_seed: String)]
+pub struct RevokeCertification<'info> {
     #[account(
         mut,
-        seeds = [b"milled_rice", milling_seed.as_bytes()],
+        seeds = [b"certification", certification_seed.as_bytes()],
+        bump,
+        constraint = certification.issuer == certifier.key() @ ErrorCode::NotIssuer
+    )]
+    pub certification: Account<'info, Certification>,
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub certifier: Account<'info, ChainActor>,
+    #[account(
+        seeds = [b"accreditation", certifier.key().as_ref(), &[AccreditationKind::Certifier as u8]],
         bump
     )]
-    pub milled_rice: Account<'info, MilledRice>,
-    pub user: Signer<'info>,
+    pub accreditation: Account<'info, Accreditation>,
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(batch_seed: String)]
-pub struct CreateRiceBatch<'info> {
+#[instruction(application_seed: String)]
+pub struct AddInputApplication<'info> {
     #[account(
         init,
-        payer = user,
-        space = 8 + 32 + 32 + 1 + 4 + 4 + 64 + 64 + 8 + 8,
-        seeds = [b"rice_batch", batch_seed.as_bytes()],
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 1 + (4 + 64) + (1 + 4 + 64) + 8 + 2 + 8 + (1 + 2) + 8 + 8,
+        seeds = [b"input_application", application_seed.as_bytes()],
+        bump
+    )]
+    pub input_application: Account<'info, InputApplication>,
+    /// Only the season's own farmer records inputs against it.
+    #[account(
+        mut,
+        constraint = production_season.farmer_id == applicator.key() @ ErrorCode::Unauthorized
+    )]
+    pub production_season: Account<'info, ProductionSeason>,
+    /// CHECK: the registry PDA, read by `SubstanceRegistry::load`; may not be
+    /// initialized yet.
+    #[account(seeds = [b"substance_registry"], bump)]
+    pub substance_registry: UncheckedAccount<'info>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = applicator.is_active @ ErrorCode::ActorInactive
+    )]
+    pub applicator: Account<'info, ChainActor>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(application_seed: String)]
+pub struct RemoveInputApplication<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"input_application", application_seed.as_bytes()],
+        bump,
+        has_one = production_season,
+        has_one = applicator @ ErrorCode::Unauthorized
+    )]
+    pub input_application: Account<'info, InputApplication>,
+    #[account(mut)]
+    pub production_season: Account<'info, ProductionSeason>,
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub applicator: Account<'info, ChainActor>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSubstanceRegistry<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 4 + SubstanceRegistry::MAX_ENTRIES * SubstanceEntry::SIZE + 8 + 8,
+        seeds = [b"substance_registry"],
+        bump
+    )]
+    pub substance_registry: Account<'info, SubstanceRegistry>,
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSubstanceRegistry<'info> {
+    #[account(mut, seeds = [b"substance_registry"], bump)]
+    pub substance_registry: Account<'info, SubstanceRegistry>,
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEmissionFactors<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + EmissionFactors::SIZE,
+        seeds = [b"emission_factors"],
+        bump
+    )]
+    pub emission_factors: Account<'info, EmissionFactors>,
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEmissionFactors<'info> {
+    #[account(mut, seeds = [b"emission_factors"], bump)]
+    pub emission_factors: Account<'info, EmissionFactors>,
+    #[account(seeds = [b"config"], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(test_seed: String)]
+pub struct RecordQualityTest<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 8 + 2 + 2 + 2 + 2
+            + (4 + QualityTest::MAX_RESIDUE_RESULTS * ResidueResult::SIZE)
+            + 1 + 32 + 1 + (1 + 32) + 8 + 8,
+        seeds = [b"quality_test", test_seed.as_bytes()],
         bump
     )]
+    pub quality_test: Box<Account<'info, QualityTest>>,
+    #[account(mut)]
     pub rice_batch: Account<'info, RiceBatch>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = laboratory.is_active @ ErrorCode::ActorInactive
+    )]
+    pub laboratory: Account<'info, ChainActor>,
+    #[account(
+        seeds = [b"accreditation", laboratory.key().as_ref(), &[AccreditationKind::Laboratory as u8]],
+        bump
+    )]
+    pub accreditation: Account<'info, Accreditation>,
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(batch_seed: String)]
-pub struct UpdateRiceBatch<'info> {
+#[instruction(test_seed: String)]
+pub struct InvalidateQualityTest<'info> {
     #[account(
         mut,
-        seeds = [b"rice_batch", batch_seed.as_bytes()],
+        seeds = [b"quality_test", test_seed.as_bytes()],
+        bump,
+        has_one = rice_batch,
+        has_one = laboratory @ ErrorCode::Unauthorized
+    )]
+    pub quality_test: Box<Account<'info, QualityTest>>,
+    #[account(mut)]
+    pub rice_batch: Account<'info, RiceBatch>,
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub laboratory: Account<'info, ChainActor>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(shipment_seed: String)]
+pub struct CreateShipment<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + (4 + Shipment::MAX_VEHICLE_ID_LEN) + GeoPoint::SIZE + GeoPoint::SIZE
+            + (1 + 8) + (1 + 8)
+            + (4 + Shipment::MAX_BATCHES * 32)
+            + (4 + Shipment::MAX_SEALS * (4 + Shipment::MAX_SEAL_LEN))
+            + 1 + 8 + 8,
+        seeds = [b"shipment", shipment_seed.as_bytes()],
+        bump
+    )]
+    pub shipment: Box<Account<'info, Shipment>>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = carrier.is_active @ ErrorCode::ActorInactive
+    )]
+    pub carrier: Account<'info, ChainActor>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// The batches' current holder, or `None` while they have none.
+    pub holder: Option<Account<'info, ChainActor>>,
+    /// Consents to the shipment for the holder, or is the batches' creator.
+    pub holder_authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(shipment_seed: String)]
+pub struct MoveShipment<'info> {
+    #[account(
+        mut,
+        seeds = [b"shipment", shipment_seed.as_bytes()],
+        bump,
+        has_one = carrier @ ErrorCode::Unauthorized
+    )]
+    pub shipment: Box<Account<'info, Shipment>>,
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub carrier: Account<'info, ChainActor>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(warehouse_seed: String)]
+pub struct CreateWarehouse<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + (4 + Warehouse::MAX_NAME_LEN) + GeoPoint::SIZE + 8 + 8
+            + (4 + Warehouse::MAX_STORED_BATCHES * 32) + 1 + 8 + 8,
+        seeds = [b"warehouse", warehouse_seed.as_bytes()],
         bump
     )]
+    pub warehouse: Box<Account<'info, Warehouse>>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = operator.is_active @ ErrorCode::ActorInactive
+    )]
+    pub operator: Account<'info, ChainActor>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(warehouse_seed: String)]
+pub struct UpdateWarehouse<'info> {
+    #[account(
+        mut,
+        seeds = [b"warehouse", warehouse_seed.as_bytes()],
+        bump,
+        has_one = operator @ ErrorCode::Unauthorized
+    )]
+    pub warehouse: Box<Account<'info, Warehouse>>,
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub operator: Account<'info, ChainActor>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(warehouse_seed: String)]
+pub struct StoreBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"warehouse", warehouse_seed.as_bytes()],
+        bump,
+        has_one = operator @ ErrorCode::Unauthorized
+    )]
+    pub warehouse: Box<Account<'info, Warehouse>>,
+    #[account(mut)]
     pub rice_batch: Account<'info, RiceBatch>,
-    pub user: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 4 + 8 + (1 + 8),
+        seeds = [
+            b"storage_record",
+            rice_batch.key().as_ref(),
+            &rice_batch.storage_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub storage_record: Account<'info, StorageRecord>,
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub operator: Account<'info, ChainActor>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// The batch's current holder, or `None` while it has none.
+    pub holder: Option<Account<'info, ChainActor>>,
+    /// Consents to storage for the holder, or is the batch's creator.
+    pub holder_authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(batch_seed: String)]
-pub struct DeleteRiceBatch<'info> {
+#[instruction(warehouse_seed: String)]
+pub struct ReleaseBatch<'info> {
     #[account(
         mut,
-        seeds = [b"rice_batch", batch_seed.as_bytes()],
+        seeds = [b"warehouse", warehouse_seed.as_bytes()],
+        bump,
+        has_one = operator @ ErrorCode::Unauthorized
+    )]
+    pub warehouse: Box<Account<'info, Warehouse>>,
+    #[account(mut)]
+    pub rice_batch: Account<'info, RiceBatch>,
+    #[account(
+        mut,
+        seeds = [
+            b"storage_record",
+            rice_batch.key().as_ref(),
+            &rice_batch.storage_count.saturating_sub(1).to_le_bytes()
+        ],
+        bump,
+        has_one = rice_batch,
+        has_one = warehouse
+    )]
+    pub storage_record: Account<'info, StorageRecord>,
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub operator: Account<'info, ChainActor>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_seed: String)]
+pub struct RecordPostHarvestEvent<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 2 + 2 + 8 + 8 + 8 + 8,
+        seeds = [b"post_harvest_event", event_seed.as_bytes()],
+        bump
+    )]
+    pub post_harvest_event: Account<'info, PostHarvestEvent>,
+    #[account(mut)]
+    pub production_season: Account<'info, ProductionSeason>,
+    /// The season's farmer.
+    #[account(address = production_season.farmer_id @ ErrorCode::Unauthorized)]
+    pub farmer: Account<'info, ChainActor>,
+    /// The farmer signs for their own harvest, whether they dry it
+    /// themselves or hand it to another facility.
+    #[account(constraint = farmer.authority == farmer_authority.key() @ ErrorCode::Unauthorized)]
+    pub farmer_authority: Signer<'info>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = facility.is_active @ ErrorCode::ActorInactive
+    )]
+    pub facility: Account<'info, ChainActor>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateRetailPacks<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 4 + 8 + 4 + 8,
+        seeds = [
+            b"retail_pack_run",
+            rice_batch.key().as_ref(),
+            &rice_batch.next_pack_serial.to_le_bytes()
+        ],
         bump
     )]
+    pub retail_pack_run: Account<'info, RetailPackRun>,
+    #[account(mut)]
     pub rice_batch: Account<'info, RiceBatch>,
-    pub user: Signer<'info>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = packer.is_active @ ErrorCode::ActorInactive
+    )]
+    pub packer: Account<'info, ChainActor>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(transaction_seed: String)]
-pub struct CreateChainTransaction<'info> {
+#[instruction(issuing_key: Pubkey)]
+pub struct RegisterIssuingKey<'info> {
     #[account(
         init,
-        payer = user,
-        space = 8 + 32 + 32 + 32 + 128 + 8 + 1 + 64 + 64 + 64 + 64 + 1 + 64 + 8 + 8,
-        seeds = [b"chain_transaction", transaction_seed.as_bytes()],
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 1 + 8 + 8,
+        seeds = [b"issuer_key", miller.key().as_ref(), issuing_key.as_ref()],
         bump
     )]
-    pub chain_transaction: Account<'info, ChainTransaction>,
+    pub issuer_key: Account<'info, IssuerKey>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = miller.is_active @ ErrorCode::ActorInactive
+    )]
+    pub miller: Account<'info, ChainActor>,
+    /// Millers are accredited by the admin; the actor's self-declared
+    /// organization does not count.
+    #[account(
+        seeds = [b"accreditation", miller.key().as_ref(), &[AccreditationKind::Miller as u8]],
+        bump
+    )]
+    pub accreditation: Account<'info, Accreditation>,
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(transaction_seed: String)]
-pub struct UpdateChainTransaction<'info> {
+pub struct RevokeIssuingKey<'info> {
+    #[account(mut, has_one = miller @ ErrorCode::Unauthorized)]
+    pub issuer_key: Account<'info, IssuerKey>,
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub miller: Account<'info, ChainActor>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AssertRiceBatch<'info> {
+    pub rice_batch: Account<'info, RiceBatch>,
+}
+
+#[derive(Accounts)]
+pub struct AssertProductionSeason<'info> {
+    pub production_season: Account<'info, ProductionSeason>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterCustodian<'info> {
     #[account(
-        mut,
-        seeds = [b"chain_transaction", transaction_seed.as_bytes()],
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 8,
+        seeds = [b"custodian", wallet.key().as_ref()],
         bump
     )]
-    pub chain_transaction: Account<'info, ChainTransaction>,
-    pub user: Signer<'info>,
+    pub custodian: Account<'info, Custodian>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = chain_actor.is_active @ ErrorCode::ActorInactive
+    )]
+    pub chain_actor: Account<'info, ChainActor>,
+    pub wallet: Signer<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(transaction_seed: String)]
-pub struct DeleteChainTransaction<'info> {
+#[instruction(token_unit: TokenUnit)]
+pub struct TokenizeRiceBatch<'info> {
+    #[account(mut, constraint = rice_batch.token_mint.is_none() @ ErrorCode::AlreadyTokenized)]
+    pub rice_batch: Box<Account<'info, RiceBatch>>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"batch_mint", rice_batch.key().as_ref()],
+        bump,
+        mint::decimals = token_unit.decimals(),
+        mint::authority = mint_authority,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = mint_authority,
+        extensions::metadata_pointer::metadata_address = token_mint,
+        extensions::transfer_hook::authority = mint_authority,
+        extensions::transfer_hook::program_id = crate::ID
+    )]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: PDA holding mint and metadata authority over every mint of the program.
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    /// CHECK: written by the handler in the transfer hook interface's layout.
+    #[account(
+        init,
+        payer = authority,
+        space = ExtraAccountMetaList::size_of(TRANSFER_HOOK_EXTRA_ACCOUNTS)?,
+        seeds = [b"extra-account-metas", token_mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    #[account(has_one = chain_actor @ ErrorCode::Unauthorized)]
+    pub custodian: Box<Account<'info, Custodian>>,
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = chain_actor.is_active @ ErrorCode::ActorInactive
+    )]
+    pub chain_actor: Box<Account<'info, ChainActor>>,
+    /// CHECK: the custodian's wallet, owner of the minted tokens.
+    #[account(address = custodian.wallet)]
+    pub wallet: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = token_mint,
+        associated_token::authority = wallet,
+        associated_token::token_program = token_program
+    )]
+    pub holder_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The accounts of the transfer hook interface's `Execute` instruction.
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = token_mint)]
+    pub source_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(token::mint = token_mint)]
+    pub destination_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: owner or delegate of the source, verified by Token-2022.
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: the mint's transfer hook accounts, only located here.
+    #[account(seeds = [b"extra-account-metas", token_mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
     #[account(
         mut,
-        seeds = [b"chain_transaction", transaction_seed.as_bytes()],
+        constraint = rice_batch.token_mint == Some(token_mint.key()) @ ErrorCode::BatchMintMismatch
+    )]
+    pub rice_batch: Account<'info, RiceBatch>,
+    /// CHECK: may be uninitialized; read only when this program owns it.
+    #[account(seeds = [b"custodian", destination_token.owner.as_ref()], bump)]
+    pub destination_custodian: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IssueCarbonCredits<'info> {
+    #[account(
+        mut,
+        constraint = production_season.validation_status == ValidationStatus::Approved @ ErrorCode::SeasonNotApproved,
+        constraint = production_season.carbon_credits.is_none() @ ErrorCode::CarbonCreditsAlreadyIssued
+    )]
+    pub production_season: Box<Account<'info, ProductionSeason>>,
+    /// The validator that approved the season, whose authority signs.
+    #[account(
+        constraint = production_season.validator_id == Some(validator.key()) @ ErrorCode::Unauthorized,
+        constraint = validator.authority == validator_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub validator: Box<Account<'info, ChainActor>>,
+    #[account(
+        seeds = [b"accreditation", validator.key().as_ref(), &[AccreditationKind::Validator as u8]],
         bump
     )]
-    pub chain_transaction: Account<'info, ChainTransaction>,
-    pub user: Signer<'info>,
+    pub accreditation: Box<Account<'info, Accreditation>>,
+    pub validator_authority: Signer<'info>,
+    /// The season's CarbonSmart certification, checked in the handler.
+    pub certification: Box<Account<'info, Certification>>,
+    #[account(seeds = [b"emission_factors"], bump)]
+    pub emission_factors: Box<Account<'info, EmissionFactors>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"carbon_credit_mint", production_season.key().as_ref()],
+        bump,
+        mint::decimals = CarbonCredits::DECIMALS,
+        mint::authority = mint_authority,
+        mint::token_program = token_program
+    )]
+    pub credit_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: PDA holding mint and metadata authority over every mint of the program.
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    #[account(constraint = custodian.chain_actor == production_season.farmer_id @ ErrorCode::Unauthorized)]
+    pub custodian: Box<Account<'info, Custodian>>,
+    /// CHECK: the farmer's registered wallet, owner of the minted credits.
+    #[account(address = custodian.wallet)]
+    pub wallet: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = credit_mint,
+        associated_token::authority = wallet,
+        associated_token::token_program = token_program
+    )]
+    pub farmer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RetireCarbonCredits<'info> {
+    #[account(
+        mut,
+        constraint = production_season.carbon_credits.as_ref().map(|carbon_credits| carbon_credits.mint)
+            == Some(credit_mint.key()) @ ErrorCode::CreditMintMismatch
+    )]
+    pub production_season: Box<Account<'info, ProductionSeason>>,
+    #[account(mut)]
+    pub credit_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = credit_mint, token::authority = owner)]
+    pub owner_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
 }
@@ -0,0 +1,412 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use common::{assert_error, season_args, Fixture};
+use rice_supply_chain::{
+    AccreditationKind, CarbonCreditsRetired, CertificationScheme, ErrorCode, Organization,
+    ProductionSeason, SubjectKind, ValidationStatus, WaterRegime,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+const PLANTING: i64 = 1_690_000_000;
+/// 110 days after planting.
+const HARVEST: i64 = PLANTING + 110 * 86_400;
+
+fn update_args(season_seed: &str) -> args::UpdateProductionSeason {
+    args::UpdateProductionSeason {
+        season_seed: season_seed.to_string(),
+        crop_year: None,
+        processed_yield_kg: None,
+        variety: None,
+        planned_practice: None,
+        planting_date: None,
+        irrigation_practice: None,
+        fertilizer_used: None,
+        pesticide_used: None,
+        harvest_date: None,
+        total_yield_kg: None,
+        moisture_content: None,
+        cultivated_area_m2: None,
+        organic_amendments: None,
+    }
+}
+
+struct Credits {
+    fixture: Fixture,
+    season: Pubkey,
+    /// The farmer's authority and actor.
+    farmer: (Pubkey, Pubkey),
+    /// The farmer's registered token wallet.
+    wallet: Pubkey,
+    certifier: (Pubkey, Pubkey),
+    validator: (Pubkey, Pubkey),
+}
+
+impl Credits {
+    /// A two-hectare, 110-day season yielding 10 t, of a farmer with a
+    /// registered wallet.
+    fn new(irrigation_practice: WaterRegime) -> Self {
+        let mut fixture = Fixture::new();
+        let (authority, farmer) = fixture.actor("farmer", Organization::Farmer);
+        let wallet = fixture.wallet();
+        fixture
+            .send(
+                instructions::register_custodian(&authority, &farmer, &wallet),
+                &[authority, wallet],
+            )
+            .unwrap();
+
+        let user = fixture.wallet();
+        let mut args = season_args("season-1");
+        args.farmer_id = farmer;
+        args.planting_date = Some(PLANTING);
+        args.harvest_date = Some(HARVEST);
+        args.irrigation_practice = Some(irrigation_practice);
+        args.cultivated_area_m2 = Some(20_000);
+        args.total_yield_kg = Some(10_000);
+        fixture
+            .send(instructions::create_production_season(&user, args), &[user])
+            .unwrap();
+
+        let certifier = fixture.accredited("certifier", AccreditationKind::Certifier);
+        let validator = fixture.accredited("validator", AccreditationKind::Validator);
+        Self {
+            fixture,
+            season: pda::production_season("season-1").0,
+            farmer: (authority, farmer),
+            wallet,
+            certifier,
+            validator,
+        }
+    }
+
+    fn update(&mut self, args: args::UpdateProductionSeason) -> rice_supply_svm::TransactionResult {
+        let (authority, farmer) = self.farmer;
+        self.fixture.send(
+            instructions::update_production_season(&authority, &farmer, args),
+            &[authority],
+        )
+    }
+
+    fn approve(&mut self) {
+        self.fixture
+            .validate(self.validator, "season-1", ValidationStatus::Approved)
+            .unwrap();
+    }
+
+    fn certify(&mut self, certification_seed: &str, scheme: CertificationScheme) {
+        let (authority, certifier) = self.certifier;
+        self.fixture
+            .send(
+                instructions::issue_certification(
+                    &authority,
+                    &certifier,
+                    &self.season,
+                    args::IssueCertification {
+                        certification_seed: certification_seed.to_string(),
+                        scheme,
+                        subject_kind: SubjectKind::ProductionSeason,
+                        issued_at: HARVEST,
+                        expires_at: HARVEST + 365 * 86_400,
+                        evidence_hash: [7; 32],
+                    },
+                ),
+                &[authority],
+            )
+            .unwrap();
+    }
+
+    fn issue(&mut self, certification_seed: &str) -> rice_supply_svm::TransactionResult {
+        let validator = self.validator;
+        self.issue_as(validator, certification_seed)
+    }
+
+    /// Issues with `validator`, an `(authority, actor)` pair, signing.
+    fn issue_as(
+        &mut self,
+        (authority, validator): (Pubkey, Pubkey),
+        certification_seed: &str,
+    ) -> rice_supply_svm::TransactionResult {
+        let payer = self.fixture.wallet();
+        let instruction = instructions::issue_carbon_credits(
+            &payer,
+            &authority,
+            &validator,
+            &self.season,
+            &pda::certification(certification_seed).0,
+            &self.wallet,
+        );
+        self.fixture.send(instruction, &[payer, authority])
+    }
+
+    fn production_season(&self) -> ProductionSeason {
+        self.fixture.svm.account(&self.season).unwrap()
+    }
+
+    fn balance(&self) -> u64 {
+        let address = pda::carbon_credit_account(&self.season, &self.wallet);
+        self.fixture
+            .svm
+            .account::<TokenAccount>(&address)
+            .unwrap()
+            .amount
+    }
+}
+
+#[test]
+fn issue_carbon_credits_mints_avoided_methane_to_the_farmer() {
+    let mut credits = Credits::new(WaterRegime::MultipleDrainage);
+    credits.approve();
+    credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+    credits.issue("carbon-smart").unwrap();
+
+    // 1.19 kg CH4/ha/day * (1 - 0.55) * 110 days * 2 ha = 117.81 kg CH4,
+    // at a GWP of 27.
+    let carbon_credits = credits.production_season().carbon_credits.unwrap();
+    let mint = pda::carbon_credit_mint(&credits.season).0;
+    assert_eq!(carbon_credits.mint, mint);
+    assert_eq!(carbon_credits.avoided_methane_g, 117_810);
+    assert_eq!(carbon_credits.issued_kg_co2e, 3_180);
+    assert_eq!(carbon_credits.retired_kg_co2e, 0);
+    assert_eq!(credits.balance(), 3_180);
+
+    let mint = credits.fixture.svm.account::<Mint>(&mint).unwrap();
+    assert_eq!(mint.decimals, 3);
+    assert_eq!(mint.supply, 3_180);
+}
+
+#[test]
+fn issue_carbon_credits_requires_an_approved_carbon_smart_season() {
+    let mut credits = Credits::new(WaterRegime::MultipleDrainage);
+    credits.certify("organic", CertificationScheme::Organic);
+    credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+    assert_error(credits.issue("carbon-smart"), ErrorCode::SeasonNotApproved);
+
+    credits.approve();
+    assert_error(credits.issue("organic"), ErrorCode::NotCarbonSmart);
+    let (authority, certifier) = credits.certifier;
+    credits
+        .fixture
+        .send(
+            instructions::revoke_certification(&authority, &certifier, "carbon-smart"),
+            &[authority],
+        )
+        .unwrap();
+    assert_error(credits.issue("carbon-smart"), ErrorCode::NotCarbonSmart);
+}
+
+#[test]
+fn issue_carbon_credits_requires_the_approving_validators_signature() {
+    let mut credits = Credits::new(WaterRegime::MultipleDrainage);
+    credits.approve();
+    credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+
+    let (authority, validator) = credits.validator;
+    let stranger = credits.fixture.wallet();
+    assert_error(
+        credits.issue_as((stranger, validator), "carbon-smart"),
+        ErrorCode::Unauthorized,
+    );
+    let other = credits
+        .fixture
+        .accredited("other-validator", AccreditationKind::Validator);
+    assert_error(
+        credits.issue_as(other, "carbon-smart"),
+        ErrorCode::Unauthorized,
+    );
+    let mut instruction = instructions::issue_carbon_credits(
+        &stranger,
+        &authority,
+        &validator,
+        &credits.season,
+        &pda::certification("carbon-smart").0,
+        &credits.wallet,
+    );
+    instruction.accounts[3].is_signer = false;
+    assert!(credits.fixture.send(instruction, &[stranger]).is_err());
+
+    let admin = credits.fixture.admin;
+    credits
+        .fixture
+        .send(
+            instructions::revoke_accreditation(&admin, &validator, AccreditationKind::Validator),
+            &[admin],
+        )
+        .unwrap();
+    assert_error(
+        credits.issue("carbon-smart"),
+        ErrorCode::AccreditationInactive,
+    );
+}
+
+#[test]
+fn issue_carbon_credits_only_credits_drained_fields_of_plausible_area() {
+    for irrigation_practice in [
+        WaterRegime::ContinuousFlooding,
+        WaterRegime::Rainfed,
+        WaterRegime::Upland,
+    ] {
+        let mut credits = Credits::new(irrigation_practice);
+        credits.approve();
+        credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+        assert_error(
+            credits.issue("carbon-smart"),
+            ErrorCode::RegimeNotCreditable,
+        );
+    }
+
+    // 10 t over 200 ha is not a harvest, and 101 ha is over the cap.
+    for (cultivated_area_m2, total_yield_kg) in [(2_000_000, 10_000), (1_010_000, 1_000_000)] {
+        let mut credits = Credits::new(WaterRegime::MultipleDrainage);
+        let mut args = update_args("season-1");
+        args.cultivated_area_m2 = Some(cultivated_area_m2);
+        args.total_yield_kg = Some(total_yield_kg);
+        credits.update(args).unwrap();
+        credits.approve();
+        credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+        assert_error(credits.issue("carbon-smart"), ErrorCode::ImplausibleArea);
+    }
+}
+
+#[test]
+fn issue_carbon_credits_needs_avoided_emissions() {
+    let mut credits = Credits::new(WaterRegime::MultipleDrainage);
+    let admin = credits.fixture.admin;
+    credits
+        .fixture
+        .send(
+            instructions::set_emission_factors(
+                &admin,
+                args::SetEmissionFactors {
+                    baseline_methane_g_per_ha_day: 1_190,
+                    water_regime_scaling_bp: [10_000, 10_000, 10_000, 5_400, 0],
+                    amendment_conversion_bp: [10_000, 1_900, 1_700, 2_100, 4_500],
+                    amendment_exponent_bp: 5_900,
+                    methane_gwp_100: 27,
+                },
+            ),
+            &[admin],
+        )
+        .unwrap();
+    credits.approve();
+    credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+    assert_error(credits.issue("carbon-smart"), ErrorCode::NoAvoidedEmissions);
+
+    let mut credits = Credits::new(WaterRegime::MultipleDrainage);
+    let mut args = update_args("season-1");
+    args.harvest_date = Some(PLANTING - 86_400);
+    credits.update(args).unwrap();
+    credits.approve();
+    credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+    assert_error(
+        credits.issue("carbon-smart"),
+        ErrorCode::MissingEmissionData,
+    );
+}
+
+#[test]
+fn issue_carbon_credits_runs_once_and_freezes_its_inputs() {
+    let mut credits = Credits::new(WaterRegime::SingleDrainage);
+    credits.approve();
+    credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+    credits.issue("carbon-smart").unwrap();
+    // Single drainage scales emissions by 0.71.
+    let carbon_credits = credits.production_season().carbon_credits.unwrap();
+    assert_eq!(carbon_credits.avoided_methane_g, 75_922);
+
+    // The season's credit mint already exists.
+    assert!(credits.issue("carbon-smart").is_err());
+    let mut args = update_args("season-1");
+    args.cultivated_area_m2 = Some(40_000);
+    assert_error(credits.update(args), ErrorCode::CarbonCreditsAlreadyIssued);
+    let mut args = update_args("season-1");
+    args.moisture_content = Some(13);
+    credits.update(args).unwrap();
+}
+
+#[test]
+fn credited_seasons_keep_their_approval() {
+    let mut credits = Credits::new(WaterRegime::SingleDrainage);
+    credits.approve();
+    credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+    credits.issue("carbon-smart").unwrap();
+
+    // A new yield would send the season back to `Pending`.
+    let mut args = update_args("season-1");
+    args.total_yield_kg = Some(9_000);
+    assert_error(credits.update(args), ErrorCode::CarbonCreditsAlreadyIssued);
+    let (authority, farmer) = credits.farmer;
+    assert_error(
+        credits.fixture.send(
+            instructions::delete_production_season(&authority, &farmer, "season-1"),
+            &[authority],
+        ),
+        ErrorCode::CarbonCreditsAlreadyIssued,
+    );
+    for validation_status in [ValidationStatus::Pending, ValidationStatus::Rejected] {
+        assert_error(
+            credits
+                .fixture
+                .validate(credits.validator, "season-1", validation_status),
+            ErrorCode::CarbonCreditsAlreadyIssued,
+        );
+    }
+    assert!(credits.production_season().validation_status == ValidationStatus::Approved);
+}
+
+#[test]
+fn retire_carbon_credits_burns_and_counts_them() {
+    let mut credits = Credits::new(WaterRegime::MultipleDrainage);
+    credits.approve();
+    credits.certify("carbon-smart", CertificationScheme::CarbonSmart);
+    credits.issue("carbon-smart").unwrap();
+
+    let wallet = credits.wallet;
+    let stranger = credits.fixture.wallet();
+    assert!(credits
+        .fixture
+        .send(
+            instructions::retire_carbon_credits(&stranger, &credits.season, 1_000),
+            &[stranger],
+        )
+        .is_err());
+
+    let metadata = credits
+        .fixture
+        .send(
+            instructions::retire_carbon_credits(&wallet, &credits.season, 1_000),
+            &[wallet],
+        )
+        .unwrap();
+    let events = metadata.events::<CarbonCreditsRetired>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].production_season, credits.season);
+    assert_eq!(events[0].owner, wallet);
+    assert_eq!(events[0].amount_kg_co2e, 1_000);
+
+    assert_eq!(credits.balance(), 2_180);
+    let carbon_credits = credits.production_season().carbon_credits.unwrap();
+    assert_eq!(carbon_credits.retired_kg_co2e, 1_000);
+    let mint = pda::carbon_credit_mint(&credits.season).0;
+    assert_eq!(
+        credits.fixture.svm.account::<Mint>(&mint).unwrap().supply,
+        2_180
+    );
+
+    assert!(credits
+        .fixture
+        .send(
+            instructions::retire_carbon_credits(&wallet, &credits.season, 2_181),
+            &[wallet],
+        )
+        .is_err());
+    assert_error(
+        credits.fixture.send(
+            instructions::retire_carbon_credits(&wallet, &credits.season, 0),
+            &[wallet],
+        ),
+        ErrorCode::InvalidQuantity,
+    );
+}
@@ -0,0 +1,419 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::prelude::Pubkey;
+use common::{assert_error, Fixture};
+use rice_supply_chain::{
+    is_certified, Accreditation, AccreditationKind, Certification, CertificationScheme, ErrorCode,
+    Organization, ProgramConfig, SubjectKind,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+fn issue_args(certification_seed: &str, subject_kind: SubjectKind) -> args::IssueCertification {
+    args::IssueCertification {
+        certification_seed: certification_seed.to_string(),
+        scheme: CertificationScheme::Organic,
+        subject_kind,
+        issued_at: 1_700_000_000,
+        expires_at: 1_731_536_000,
+        evidence_hash: [7; 32],
+    }
+}
+
+#[test]
+fn set_config_admin_hands_over_the_config() {
+    let mut fixture = Fixture::new();
+    let admin = fixture.admin;
+    let new_admin = fixture.wallet();
+
+    assert_error(
+        fixture.send(
+            instructions::set_config_admin(&new_admin, new_admin),
+            &[new_admin],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    fixture
+        .send(instructions::set_config_admin(&admin, new_admin), &[admin])
+        .unwrap();
+
+    let config: ProgramConfig = fixture.svm.account(&pda::config().0).unwrap();
+    assert_eq!(config.admin, new_admin);
+    assert_error(
+        fixture.send(instructions::set_config_admin(&admin, admin), &[admin]),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn initialize_config_runs_once() {
+    let mut fixture = Fixture::new();
+    let other = fixture.wallet();
+    assert!(fixture
+        .send(instructions::initialize_config(&other), &[other])
+        .is_err());
+    let config: ProgramConfig = fixture.svm.account(&pda::config().0).unwrap();
+    assert_eq!(config.admin, fixture.admin);
+}
+
+#[test]
+fn grant_accreditation_is_admin_only() {
+    let mut fixture = Fixture::new();
+    let (authority, actor) = fixture.actor("lab-1", Organization::Government);
+    assert_error(
+        fixture.send(
+            instructions::grant_accreditation(
+                &authority,
+                &actor,
+                AccreditationKind::Laboratory,
+                None,
+            ),
+            &[authority],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::grant_accreditation(
+                &admin,
+                &actor,
+                AccreditationKind::Laboratory,
+                Some(1_800_000_000),
+            ),
+            &[admin],
+        )
+        .unwrap();
+    let address = pda::accreditation(&actor, AccreditationKind::Laboratory).0;
+    let accreditation: Accreditation = fixture.svm.account(&address).unwrap();
+    assert_eq!(accreditation.actor, actor);
+    assert_eq!(accreditation.granted_by, admin);
+    assert!(accreditation.is_valid_at(1_799_999_999));
+    assert!(!accreditation.is_valid_at(1_800_000_000));
+}
+
+#[test]
+fn grant_accreditation_rejects_a_past_expiry_and_inactive_actors() {
+    let mut fixture = Fixture::new();
+    let admin = fixture.admin;
+    let (authority, actor) = fixture.actor("lab-1", Organization::Government);
+    assert_error(
+        fixture.send(
+            instructions::grant_accreditation(
+                &admin,
+                &actor,
+                AccreditationKind::Laboratory,
+                Some(1_600_000_000),
+            ),
+            &[admin],
+        ),
+        ErrorCode::InvalidValidityPeriod,
+    );
+
+    fixture
+        .send(
+            instructions::delete_chain_actor(&authority, "lab-1"),
+            &[authority],
+        )
+        .unwrap();
+    assert_error(
+        fixture.send(
+            instructions::grant_accreditation(&admin, &actor, AccreditationKind::Laboratory, None),
+            &[admin],
+        ),
+        ErrorCode::ActorInactive,
+    );
+}
+
+#[test]
+fn revoke_accreditation_deactivates_it() {
+    let mut fixture = Fixture::new();
+    let (authority, actor) = fixture.accredited("cert-1", AccreditationKind::Certifier);
+    assert_error(
+        fixture.send(
+            instructions::revoke_accreditation(&authority, &actor, AccreditationKind::Certifier),
+            &[authority],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::revoke_accreditation(&admin, &actor, AccreditationKind::Certifier),
+            &[admin],
+        )
+        .unwrap();
+    let address = pda::accreditation(&actor, AccreditationKind::Certifier).0;
+    let accreditation: Accreditation = fixture.svm.account(&address).unwrap();
+    assert!(!accreditation.is_active);
+}
+
+#[test]
+fn grant_accreditation_reinstates_a_revoked_accreditation() {
+    let mut fixture = Fixture::new();
+    let (_, actor) = fixture.accredited("cert-1", AccreditationKind::Certifier);
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::revoke_accreditation(&admin, &actor, AccreditationKind::Certifier),
+            &[admin],
+        )
+        .unwrap();
+    let address = pda::accreditation(&actor, AccreditationKind::Certifier).0;
+    let revoked: Accreditation = fixture.svm.account(&address).unwrap();
+
+    fixture
+        .send(
+            instructions::grant_accreditation(
+                &admin,
+                &actor,
+                AccreditationKind::Certifier,
+                Some(1_900_000_000),
+            ),
+            &[admin],
+        )
+        .unwrap();
+    let accreditation: Accreditation = fixture.svm.account(&address).unwrap();
+    assert!(accreditation.is_active);
+    assert_eq!(accreditation.valid_until, Some(1_900_000_000));
+    assert_eq!(accreditation.created_at, revoked.created_at);
+}
+
+#[test]
+fn issue_certification_records_the_certificate() {
+    let mut fixture = Fixture::new();
+    let (authority, certifier) = fixture.accredited("cert-1", AccreditationKind::Certifier);
+    let season = fixture.season("season-1", None);
+    fixture
+        .send(
+            instructions::issue_certification(
+                &authority,
+                &certifier,
+                &season,
+                issue_args("organic-1", SubjectKind::ProductionSeason),
+            ),
+            &[authority],
+        )
+        .unwrap();
+
+    let certification: Certification = fixture
+        .svm
+        .account(&pda::certification("organic-1").0)
+        .unwrap();
+    assert_eq!(certification.issuer, certifier);
+    assert_eq!(certification.subject, season);
+    assert_eq!(certification.evidence_hash, [7; 32]);
+    assert!(certification.certifies(&season, &CertificationScheme::Organic, 1_700_000_000));
+    assert!(!certification.certifies(&season, &CertificationScheme::PhilGap, 1_700_000_000));
+}
+
+#[test]
+fn is_certified_checks_subject_scheme_and_period() {
+    let mut fixture = Fixture::new();
+    let (authority, certifier) = fixture.accredited("cert-1", AccreditationKind::Certifier);
+    let season = fixture.season("season-1", None);
+    let batch = fixture.batch("batch-1", 1_000);
+    let mut batch_args = issue_args("philgap-1", SubjectKind::RiceBatch);
+    batch_args.scheme = CertificationScheme::PhilGap;
+    for (subject, args) in [
+        (
+            season,
+            issue_args("organic-1", SubjectKind::ProductionSeason),
+        ),
+        (batch, batch_args),
+    ] {
+        fixture
+            .send(
+                instructions::issue_certification(&authority, &certifier, &subject, args),
+                &[authority],
+            )
+            .unwrap();
+    }
+    let certifications: Vec<Certification> = ["organic-1", "philgap-1"]
+        .iter()
+        .map(|seed| fixture.svm.account(&pda::certification(seed).0).unwrap())
+        .collect();
+
+    let organic = CertificationScheme::Organic;
+    let philgap = CertificationScheme::PhilGap;
+    assert!(is_certified(
+        &certifications,
+        &season,
+        &organic,
+        1_700_000_000
+    ));
+    assert!(is_certified(
+        &certifications,
+        &season,
+        &organic,
+        1_731_535_999
+    ));
+    assert!(!is_certified(
+        &certifications,
+        &season,
+        &organic,
+        1_699_999_999
+    ));
+    assert!(!is_certified(
+        &certifications,
+        &season,
+        &organic,
+        1_731_536_000
+    ));
+    assert!(!is_certified(
+        &certifications,
+        &season,
+        &philgap,
+        1_710_000_000
+    ));
+    assert!(is_certified(
+        &certifications,
+        &batch,
+        &philgap,
+        1_710_000_000
+    ));
+    assert!(!is_certified(
+        &certifications,
+        &batch,
+        &organic,
+        1_710_000_000
+    ));
+    assert!(!is_certified(&[], &batch, &philgap, 1_710_000_000));
+}
+
+#[test]
+fn issue_certification_checks_the_issuer() {
+    let mut fixture = Fixture::new();
+    let (authority, certifier) = fixture.accredited("cert-1", AccreditationKind::Certifier);
+    let (lab_authority, laboratory) = fixture.accredited("lab-1", AccreditationKind::Laboratory);
+    let season = fixture.season("season-1", None);
+    let args = || issue_args("organic-1", SubjectKind::ProductionSeason);
+
+    let stranger = fixture.wallet();
+    assert_error(
+        fixture.send(
+            instructions::issue_certification(&stranger, &certifier, &season, args()),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    // A laboratory accreditation does not allow certifying.
+    assert_error(
+        fixture.send(
+            instructions::issue_certification(&lab_authority, &laboratory, &season, args()),
+            &[lab_authority],
+        ),
+        AnchorError::AccountNotInitialized,
+    );
+
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::revoke_accreditation(&admin, &certifier, AccreditationKind::Certifier),
+            &[admin],
+        )
+        .unwrap();
+    assert_error(
+        fixture.send(
+            instructions::issue_certification(&authority, &certifier, &season, args()),
+            &[authority],
+        ),
+        ErrorCode::AccreditationInactive,
+    );
+}
+
+#[test]
+fn issue_certification_checks_subject_and_period() {
+    let mut fixture = Fixture::new();
+    let (authority, certifier) = fixture.accredited("cert-1", AccreditationKind::Certifier);
+    let season = fixture.season("season-1", None);
+
+    assert_error(
+        fixture.send(
+            instructions::issue_certification(
+                &authority,
+                &certifier,
+                &season,
+                issue_args("organic-1", SubjectKind::RiceBatch),
+            ),
+            &[authority],
+        ),
+        ErrorCode::SubjectMismatch,
+    );
+    assert_error(
+        fixture.send(
+            instructions::issue_certification(
+                &authority,
+                &certifier,
+                &Pubkey::new_unique(),
+                issue_args("organic-1", SubjectKind::ProductionSeason),
+            ),
+            &[authority],
+        ),
+        ErrorCode::SubjectMismatch,
+    );
+
+    let mut args = issue_args("organic-1", SubjectKind::ProductionSeason);
+    args.expires_at = args.issued_at;
+    assert_error(
+        fixture.send(
+            instructions::issue_certification(&authority, &certifier, &season, args),
+            &[authority],
+        ),
+        ErrorCode::InvalidValidityPeriod,
+    );
+}
+
+#[test]
+fn revoke_certification_is_issuer_only_and_once() {
+    let mut fixture = Fixture::new();
+    let (authority, certifier) = fixture.accredited("cert-1", AccreditationKind::Certifier);
+    let (other_authority, other_certifier) =
+        fixture.accredited("cert-2", AccreditationKind::Certifier);
+    let batch = fixture.batch("batch-1", 1_000);
+    fixture
+        .send(
+            instructions::issue_certification(
+                &authority,
+                &certifier,
+                &batch,
+                issue_args("organic-1", SubjectKind::RiceBatch),
+            ),
+            &[authority],
+        )
+        .unwrap();
+
+    assert_error(
+        fixture.send(
+            instructions::revoke_certification(&other_authority, &other_certifier, "organic-1"),
+            &[other_authority],
+        ),
+        ErrorCode::NotIssuer,
+    );
+
+    fixture.svm.warp_to_timestamp(1_710_000_000);
+    fixture
+        .send(
+            instructions::revoke_certification(&authority, &certifier, "organic-1"),
+            &[authority],
+        )
+        .unwrap();
+    let certification: Certification = fixture
+        .svm
+        .account(&pda::certification("organic-1").0)
+        .unwrap();
+    assert_eq!(certification.revoked_at, Some(1_710_000_000));
+    assert!(certification.is_valid_at(1_709_999_999));
+    assert!(!certification.is_valid_at(1_710_000_000));
+
+    assert_error(
+        fixture.send(
+            instructions::revoke_certification(&authority, &certifier, "organic-1"),
+            &[authority],
+        ),
+        ErrorCode::CertificationRevoked,
+    );
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::prelude::Pubkey;
+use common::{actor_args, assert_error, Fixture};
+use rice_supply_chain::{ChainActor, ErrorCode, Organization};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+use rice_supply_svm::ExecutionError;
+
+fn update_args(actor_seed: &str) -> args::UpdateChainActor {
+    args::UpdateChainActor {
+        actor_seed: actor_seed.to_string(),
+        name: None,
+        actor_type: None,
+        farm_id: None,
+        farmer_id: None,
+        assigned_tps: None,
+        pin: None,
+        organization: None,
+        address: None,
+        is_active: None,
+        balance: None,
+    }
+}
+
+#[test]
+fn create_chain_actor_initializes_the_record() {
+    let mut fixture = Fixture::new();
+    let (authority, actor) = fixture.actor("farmer-1", Organization::Farmer);
+
+    let (address, bump) = pda::chain_actor("farmer-1");
+    assert_eq!(actor, address);
+    assert_eq!(
+        Pubkey::create_program_address(
+            &[b"chain_actor", b"farmer-1", &[bump]],
+            &rice_supply_chain::ID
+        )
+        .unwrap(),
+        actor
+    );
+
+    let chain_actor: ChainActor = fixture.svm.account(&actor).unwrap();
+    assert_eq!(chain_actor.public_key, actor);
+    assert_eq!(chain_actor.authority, authority);
+    assert_eq!(chain_actor.name, "Actor farmer-1");
+    assert!(chain_actor.organization == Organization::Farmer);
+    assert!(chain_actor.is_active);
+    assert_eq!(chain_actor.balance, 0);
+    assert_eq!(chain_actor.created_at, fixture.svm.clock().unix_timestamp);
+    assert_eq!(
+        fixture.svm.get_account(&actor).unwrap().owner,
+        rice_supply_chain::ID
+    );
+}
+
+#[test]
+fn create_chain_actor_requires_the_payer_signature() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let result = fixture.send(
+        instructions::create_chain_actor(&user, actor_args("farmer-1", Organization::Farmer)),
+        &[],
+    );
+    assert_eq!(
+        result.unwrap_err().error,
+        ExecutionError::MissingSignature(user)
+    );
+    assert!(fixture
+        .svm
+        .get_account(&pda::chain_actor("farmer-1").0)
+        .is_none());
+}
+
+#[test]
+fn create_chain_actor_rejects_a_taken_seed() {
+    let mut fixture = Fixture::new();
+    fixture.actor("farmer-1", Organization::Farmer);
+    let user = fixture.wallet();
+    let result = fixture.send(
+        instructions::create_chain_actor(&user, actor_args("farmer-1", Organization::Miller)),
+        &[user],
+    );
+    assert!(result.is_err());
+    let chain_actor: ChainActor = fixture
+        .svm
+        .account(&pda::chain_actor("farmer-1").0)
+        .unwrap();
+    assert!(chain_actor.organization == Organization::Farmer);
+}
+
+#[test]
+fn create_chain_actor_rejects_an_address_not_derived_from_the_seed() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut instruction =
+        instructions::create_chain_actor(&user, actor_args("farmer-1", Organization::Farmer));
+    instruction.accounts[0].pubkey = pda::chain_actor("farmer-2").0;
+    assert_error(
+        fixture.send(instruction, &[user]),
+        AnchorError::ConstraintSeeds,
+    );
+}
+
+#[test]
+fn create_chain_actor_rejects_an_oversize_name() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut args = actor_args("farmer-1", Organization::Farmer);
+    args.name = "x".repeat(512);
+    assert_error(
+        fixture.send(instructions::create_chain_actor(&user, args), &[user]),
+        AnchorError::AccountDidNotSerialize,
+    );
+}
+
+#[test]
+fn update_chain_actor_changes_only_given_fields() {
+    let mut fixture = Fixture::new();
+    let (authority, actor) = fixture.actor("farmer-1", Organization::Farmer);
+    fixture.svm.warp_to_timestamp(1_700_000_600);
+
+    let mut args = update_args("farmer-1");
+    args.name = Some("Dela Cruz Farm".to_string());
+    args.assigned_tps = Some(3);
+    args.balance = Some(2_500);
+    fixture
+        .send(
+            instructions::update_chain_actor(&authority, args),
+            &[authority],
+        )
+        .unwrap();
+
+    let chain_actor: ChainActor = fixture.svm.account(&actor).unwrap();
+    assert_eq!(chain_actor.name, "Dela Cruz Farm");
+    assert_eq!(chain_actor.assigned_tps, 3);
+    assert_eq!(chain_actor.balance, 2_500);
+    assert_eq!(chain_actor.pin, "1234");
+    assert_eq!(chain_actor.created_at, 1_700_000_000);
+    assert_eq!(chain_actor.updated_at, 1_700_000_600);
+}
+
+#[test]
+fn update_chain_actor_rejects_an_unknown_seed() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    assert_error(
+        fixture.send(
+            instructions::update_chain_actor(&user, update_args("nobody")),
+            &[user],
+        ),
+        AnchorError::AccountNotInitialized,
+    );
+}
+
+#[test]
+fn update_and_delete_chain_actor_require_the_actor_authority() {
+    let mut fixture = Fixture::new();
+    let (authority, actor) = fixture.actor("farmer-1", Organization::Farmer);
+    let stranger = fixture.wallet();
+
+    let mut args = update_args("farmer-1");
+    args.name = Some("Taken Over".to_string());
+    assert_error(
+        fixture.send(
+            instructions::update_chain_actor(&stranger, args),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    assert_error(
+        fixture.send(
+            instructions::delete_chain_actor(&stranger, "farmer-1"),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    let result = fixture.send(
+        instructions::delete_chain_actor(&authority, "farmer-1"),
+        &[stranger],
+    );
+    assert_eq!(
+        result.unwrap_err().error,
+        ExecutionError::MissingSignature(authority)
+    );
+
+    let chain_actor: ChainActor = fixture.svm.account(&actor).unwrap();
+    assert_eq!(chain_actor.name, "Actor farmer-1");
+    assert!(chain_actor.is_active);
+}
+
+#[test]
+fn delete_chain_actor_deactivates_the_actor() {
+    let mut fixture = Fixture::new();
+    let (authority, actor) = fixture.actor("farmer-1", Organization::Farmer);
+    fixture
+        .send(
+            instructions::delete_chain_actor(&authority, "farmer-1"),
+            &[authority],
+        )
+        .unwrap();
+
+    let chain_actor: ChainActor = fixture.svm.account(&actor).unwrap();
+    assert!(!chain_actor.is_active);
+}
+
+#[test]
+fn failed_transaction_rolls_back_earlier_instructions() {
+    let mut fixture = Fixture::new();
+    fixture.actor("farmer-1", Organization::Farmer);
+    let user = fixture.wallet();
+    let balance = fixture.svm.balance(&user);
+    let result = fixture.svm.send(
+        &[
+            instructions::create_chain_actor(&user, actor_args("farmer-2", Organization::Farmer)),
+            instructions::create_chain_actor(&user, actor_args("farmer-1", Organization::Miller)),
+        ],
+        &[user],
+    );
+    assert_eq!(result.unwrap_err().index, 1);
+    assert!(fixture
+        .svm
+        .get_account(&pda::chain_actor("farmer-2").0)
+        .is_none());
+    assert_eq!(fixture.svm.balance(&user), balance);
+}
@@ -0,0 +1,333 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{assert_error, geo_point, Fixture};
+use rice_supply_chain::{
+    ChainTransaction, ErrorCode, GeoPoint, Organization, PaymentMethod, QualityGrade, RiceBatch,
+    TransactionStatus,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+fn create_args(
+    transaction_seed: &str,
+    rice_batch_ids: Vec<Pubkey>,
+) -> args::CreateChainTransaction {
+    args::CreateChainTransaction {
+        transaction_seed: transaction_seed.to_string(),
+        from_actor_id: pda::chain_actor("farmer-1").0,
+        to_actor_id: pda::chain_actor("miller-1").0,
+        rice_batch_ids,
+        amount: 125_000,
+        payment_method: PaymentMethod::BankTransfer,
+        payment_reference: Some("LBP-0042".to_string()),
+        geotag: Some(geo_point(15_580_000, 120_970_000)),
+        quality: Some(QualityGrade::Grade1),
+    }
+}
+
+fn update_args(transaction_seed: &str) -> args::UpdateChainTransaction {
+    args::UpdateChainTransaction {
+        transaction_seed: transaction_seed.to_string(),
+        amount: None,
+        payment_method: None,
+        payment_reference: None,
+        geotag: None,
+        quality: None,
+        transaction_status: None,
+    }
+}
+
+#[test]
+fn create_chain_transaction_starts_pending() {
+    let mut fixture = Fixture::new();
+    let batch = fixture.batch("batch-1", 1_000);
+    let user = fixture.wallet();
+    fixture
+        .send(
+            instructions::create_chain_transaction(&user, create_args("tx-1", vec![batch])),
+            &[user],
+        )
+        .unwrap();
+
+    let address = pda::chain_transaction("tx-1").0;
+    let transaction: ChainTransaction = fixture.svm.account(&address).unwrap();
+    assert_eq!(transaction.public_key, address);
+    assert_eq!(transaction.rice_batch_ids, vec![batch]);
+    assert_eq!(transaction.amount, 125_000);
+    assert!(transaction.transaction_status == TransactionStatus::Pending);
+    assert_eq!(transaction.geotag.unwrap().latitude_microdeg, 15_580_000);
+}
+
+#[test]
+fn create_chain_transaction_rejects_an_invalid_geotag() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut args = create_args("tx-1", Vec::new());
+    args.geotag = Some(geo_point(90_000_001, 0));
+    assert_error(
+        fixture.send(instructions::create_chain_transaction(&user, args), &[user]),
+        ErrorCode::LatitudeOutOfRange,
+    );
+
+    let mut args = create_args("tx-1", Vec::new());
+    args.geotag = Some(geo_point(0, -180_000_001));
+    assert_error(
+        fixture.send(instructions::create_chain_transaction(&user, args), &[user]),
+        ErrorCode::LongitudeOutOfRange,
+    );
+}
+
+#[test]
+fn create_chain_transaction_accepts_the_range_bounds() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let corners = [
+        (
+            GeoPoint::MAX_LATITUDE_MICRODEG,
+            GeoPoint::MAX_LONGITUDE_MICRODEG,
+        ),
+        (
+            -GeoPoint::MAX_LATITUDE_MICRODEG,
+            -GeoPoint::MAX_LONGITUDE_MICRODEG,
+        ),
+    ];
+    for (index, (latitude, longitude)) in corners.into_iter().enumerate() {
+        let transaction_seed = format!("tx-{index}");
+        let mut args = create_args(&transaction_seed, Vec::new());
+        args.geotag = Some(GeoPoint {
+            accuracy_m: None,
+            ..geo_point(latitude, longitude)
+        });
+        fixture
+            .send(instructions::create_chain_transaction(&user, args), &[user])
+            .unwrap();
+
+        let transaction: ChainTransaction = fixture
+            .svm
+            .account(&pda::chain_transaction(&transaction_seed).0)
+            .unwrap();
+        let geotag = transaction.geotag.unwrap();
+        assert_eq!(
+            (geotag.latitude_microdeg, geotag.longitude_microdeg),
+            (latitude, longitude)
+        );
+        assert_eq!(geotag.accuracy_m, None);
+        assert_eq!(geotag.captured_at, 1_700_000_000);
+    }
+
+    // A transaction may carry no position at all.
+    let mut args = create_args("tx-2", Vec::new());
+    args.geotag = None;
+    fixture
+        .send(instructions::create_chain_transaction(&user, args), &[user])
+        .unwrap();
+    let transaction: ChainTransaction = fixture
+        .svm
+        .account(&pda::chain_transaction("tx-2").0)
+        .unwrap();
+    assert!(transaction.geotag.is_none());
+}
+
+#[test]
+fn update_chain_transaction_settles_the_transaction() {
+    let mut fixture = Fixture::new();
+    let (user, seller) = fixture.actor("farmer-1", Organization::Farmer);
+    fixture
+        .send(
+            instructions::create_chain_transaction(&user, create_args("tx-1", Vec::new())),
+            &[user],
+        )
+        .unwrap();
+
+    let mut args = update_args("tx-1");
+    args.amount = Some(130_000);
+    args.payment_method = Some(PaymentMethod::DigitalWallet);
+    args.transaction_status = Some(TransactionStatus::Completed);
+    fixture
+        .send(
+            instructions::update_chain_transaction(&user, &seller, args, &[]),
+            &[user],
+        )
+        .unwrap();
+
+    let transaction: ChainTransaction = fixture
+        .svm
+        .account(&pda::chain_transaction("tx-1").0)
+        .unwrap();
+    assert_eq!(transaction.amount, 130_000);
+    assert!(transaction.payment_method == PaymentMethod::DigitalWallet);
+    assert!(transaction.transaction_status == TransactionStatus::Completed);
+    assert_eq!(transaction.payment_reference.as_deref(), Some("LBP-0042"));
+
+    let mut args = update_args("tx-1");
+    args.geotag = Some(geo_point(0, 180_000_001));
+    assert_error(
+        fixture.send(
+            instructions::update_chain_transaction(&user, &seller, args, &[]),
+            &[user],
+        ),
+        ErrorCode::LongitudeOutOfRange,
+    );
+}
+
+#[test]
+fn delete_chain_transaction_cancels_the_transaction() {
+    let mut fixture = Fixture::new();
+    let (user, seller) = fixture.actor("farmer-1", Organization::Farmer);
+    fixture
+        .send(
+            instructions::create_chain_transaction(&user, create_args("tx-1", Vec::new())),
+            &[user],
+        )
+        .unwrap();
+    fixture
+        .send(
+            instructions::delete_chain_transaction(&user, &seller, "tx-1"),
+            &[user],
+        )
+        .unwrap();
+
+    let transaction: ChainTransaction = fixture
+        .svm
+        .account(&pda::chain_transaction("tx-1").0)
+        .unwrap();
+    assert!(transaction.transaction_status == TransactionStatus::Cancelled);
+}
+
+#[test]
+fn completing_a_chain_transaction_hands_its_batches_to_the_buyer() {
+    let mut fixture = Fixture::new();
+    let (user, seller) = fixture.actor("farmer-1", Organization::Farmer);
+    let batches = [
+        fixture.batch_created_by("batch-1", 1_000, &user),
+        fixture.batch_created_by("batch-2", 500, &user),
+    ];
+    fixture
+        .send(
+            instructions::create_chain_transaction(&user, create_args("tx-1", batches.to_vec())),
+            &[user],
+        )
+        .unwrap();
+
+    let mut args = update_args("tx-1");
+    args.transaction_status = Some(TransactionStatus::Completed);
+    assert_error(
+        fixture.send(
+            instructions::update_chain_transaction(&user, &seller, args, &batches[..1]),
+            &[user],
+        ),
+        ErrorCode::BatchAccountMismatch,
+    );
+
+    let mut args = update_args("tx-1");
+    args.transaction_status = Some(TransactionStatus::Completed);
+    fixture
+        .send(
+            instructions::update_chain_transaction(&user, &seller, args, &batches),
+            &[user],
+        )
+        .unwrap();
+    for batch in batches {
+        let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+        assert_eq!(
+            rice_batch.current_holder,
+            Some(pda::chain_actor("miller-1").0)
+        );
+    }
+}
+
+#[test]
+fn only_the_seller_may_change_a_chain_transaction() {
+    let mut fixture = Fixture::new();
+    let (user, seller) = fixture.actor("farmer-1", Organization::Farmer);
+    let batch = fixture.batch_created_by("batch-1", 1_000, &user);
+    fixture
+        .send(
+            instructions::create_chain_transaction(&user, create_args("tx-1", vec![batch])),
+            &[user],
+        )
+        .unwrap();
+
+    let (buyer_authority, buyer) = fixture.actor("miller-1", Organization::Miller);
+    for (authority, actor) in [(buyer_authority, seller), (buyer_authority, buyer)] {
+        let mut args = update_args("tx-1");
+        args.transaction_status = Some(TransactionStatus::Completed);
+        assert_error(
+            fixture.send(
+                instructions::update_chain_transaction(&authority, &actor, args, &[batch]),
+                &[authority],
+            ),
+            ErrorCode::Unauthorized,
+        );
+        assert_error(
+            fixture.send(
+                instructions::delete_chain_transaction(&authority, &actor, "tx-1"),
+                &[authority],
+            ),
+            ErrorCode::Unauthorized,
+        );
+    }
+}
+
+#[test]
+fn completing_a_chain_transaction_requires_the_seller_to_hold_its_batches() {
+    let mut fixture = Fixture::new();
+    let (user, seller) = fixture.actor("farmer-1", Organization::Farmer);
+    let batch = fixture.batch("batch-1", 1_000);
+    fixture
+        .send(
+            instructions::create_chain_transaction(&user, create_args("tx-1", vec![batch])),
+            &[user],
+        )
+        .unwrap();
+
+    let mut args = update_args("tx-1");
+    args.transaction_status = Some(TransactionStatus::Completed);
+    assert_error(
+        fixture.send(
+            instructions::update_chain_transaction(&user, &seller, args, &[batch]),
+            &[user],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert_eq!(rice_batch.current_holder, None);
+}
+
+#[test]
+fn a_settled_chain_transaction_cannot_be_cancelled() {
+    let mut fixture = Fixture::new();
+    let (user, seller) = fixture.actor("farmer-1", Organization::Farmer);
+    fixture
+        .send(
+            instructions::create_chain_transaction(&user, create_args("tx-1", Vec::new())),
+            &[user],
+        )
+        .unwrap();
+    let mut args = update_args("tx-1");
+    args.transaction_status = Some(TransactionStatus::Completed);
+    fixture
+        .send(
+            instructions::update_chain_transaction(&user, &seller, args, &[]),
+            &[user],
+        )
+        .unwrap();
+
+    assert_error(
+        fixture.send(
+            instructions::delete_chain_transaction(&user, &seller, "tx-1"),
+            &[user],
+        ),
+        ErrorCode::InvalidStatusTransition,
+    );
+    let mut args = update_args("tx-1");
+    args.transaction_status = Some(TransactionStatus::Failed);
+    assert_error(
+        fixture.send(
+            instructions::update_chain_transaction(&user, &seller, args, &[]),
+            &[user],
+        ),
+        ErrorCode::InvalidStatusTransition,
+    );
+}
@@ -0,0 +1,313 @@
+//! Fixtures shared by the integration tests: an `Svm` with funded wallets
+//! and helpers that create the records most flows start from.
+
+#![allow(dead_code)]
+
+use anchor_lang::prelude::Pubkey;
+use rice_supply_chain::{
+    AccreditationKind, BatchStatus, ByProducts, GeoPoint, MillingType, Organization, PaymentMethod,
+    QualityGrade, TransactionStatus, ValidationStatus,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+use rice_supply_svm::{Svm, TransactionResult, LAMPORTS_PER_SOL};
+
+pub struct Fixture {
+    pub svm: Svm,
+    /// Program config admin, set by `Fixture::new`.
+    pub admin: Pubkey,
+}
+
+impl Fixture {
+    /// Config, an empty substance registry and the default emission
+    /// factors, owned by a funded admin.
+    pub fn new() -> Self {
+        let mut fixture = Self::bare();
+        let admin = fixture.admin;
+        fixture
+            .send(
+                instructions::initialize_substance_registry(&admin),
+                &[admin],
+            )
+            .unwrap();
+        fixture
+            .send(instructions::initialize_emission_factors(&admin), &[admin])
+            .unwrap();
+        fixture
+    }
+
+    /// Only the config, as on a deployment predating the registries.
+    pub fn bare() -> Self {
+        let mut svm = Svm::new();
+        let admin = Pubkey::new_unique();
+        svm.airdrop(&admin, 100 * LAMPORTS_PER_SOL);
+        let mut fixture = Self { svm, admin };
+        fixture
+            .send(instructions::initialize_config(&admin), &[admin])
+            .unwrap();
+        fixture
+    }
+
+    pub fn send(
+        &mut self,
+        instruction: anchor_lang::solana_program::instruction::Instruction,
+        signers: &[Pubkey],
+    ) -> TransactionResult {
+        self.svm.send_instruction(instruction, signers)
+    }
+
+    /// A new wallet holding 10 SOL.
+    pub fn wallet(&mut self) -> Pubkey {
+        let wallet = Pubkey::new_unique();
+        self.svm.airdrop(&wallet, 10 * LAMPORTS_PER_SOL);
+        wallet
+    }
+
+    /// Creates an actor under a new wallet; returns `(authority, actor)`.
+    pub fn actor(&mut self, actor_seed: &str, organization: Organization) -> (Pubkey, Pubkey) {
+        let authority = self.wallet();
+        self.send(
+            instructions::create_chain_actor(&authority, actor_args(actor_seed, organization)),
+            &[authority],
+        )
+        .unwrap();
+        (authority, pda::chain_actor(actor_seed).0)
+    }
+
+    /// An actor holding an active accreditation of `kind`.
+    pub fn accredited(&mut self, actor_seed: &str, kind: AccreditationKind) -> (Pubkey, Pubkey) {
+        let (authority, actor) = self.actor(actor_seed, Organization::Government);
+        let admin = self.admin;
+        self.send(
+            instructions::grant_accreditation(&admin, &actor, kind, None),
+            &[admin],
+        )
+        .unwrap();
+        (authority, actor)
+    }
+
+    /// Records `validation_status` on a season as `validator`, an
+    /// `(authority, actor)` pair.
+    pub fn validate(
+        &mut self,
+        (authority, validator): (Pubkey, Pubkey),
+        season_seed: &str,
+        validation_status: ValidationStatus,
+    ) -> TransactionResult {
+        self.send(
+            instructions::validate_production_season(
+                &authority,
+                &validator,
+                season_seed,
+                validation_status,
+            ),
+            &[authority],
+        )
+    }
+
+    pub fn season(&mut self, season_seed: &str, total_yield_kg: Option<u64>) -> Pubkey {
+        self.farmer_season(season_seed, Pubkey::new_unique(), total_yield_kg)
+    }
+
+    /// A season grown by the actor `farmer`.
+    pub fn farmer_season(
+        &mut self,
+        season_seed: &str,
+        farmer: Pubkey,
+        total_yield_kg: Option<u64>,
+    ) -> Pubkey {
+        let user = self.wallet();
+        let mut args = season_args(season_seed);
+        args.farmer_id = farmer;
+        args.total_yield_kg = total_yield_kg;
+        self.send(instructions::create_production_season(&user, args), &[user])
+            .unwrap();
+        pda::production_season(season_seed).0
+    }
+
+    /// Mills a new season of a new farmer as `user`, with the farmer
+    /// signing; `args.farmer_id` is replaced by that farmer.
+    pub fn mill(&mut self, user: &Pubkey, mut args: args::CreateMilledRice) -> TransactionResult {
+        let (farmer_authority, farmer) = self.actor(
+            &format!("farmer-{}", args.milling_seed),
+            Organization::Farmer,
+        );
+        let season_seed = format!("{}-season", args.milling_seed);
+        let total_yield_kg = u64::from(args.total_weight_processed_kg).max(1_000);
+        let season = self.farmer_season(&season_seed, farmer, Some(total_yield_kg));
+        args.farmer_id = farmer;
+        self.send(
+            instructions::create_milled_rice(user, &season, &farmer_authority, args),
+            &[*user, farmer_authority],
+        )
+    }
+
+    /// The milling run `user` records its batches against, milled on first
+    /// use with enough output for every batch of a test.
+    pub fn milling(&mut self, user: &Pubkey) -> Pubkey {
+        let milling_seed = format!("mill-{}", &user.to_string()[..16]);
+        let milled_rice = pda::milled_rice(&milling_seed).0;
+        if self.svm.get_account(&milled_rice).is_none() {
+            let mut args = milled_rice_args(&milling_seed);
+            args.total_weight_kg = 65_000;
+            args.total_weight_processed_kg = 100_000;
+            self.mill(user, args).unwrap();
+        }
+        milled_rice
+    }
+
+    pub fn batch(&mut self, batch_seed: &str, weight_kg: u32) -> Pubkey {
+        let user = self.wallet();
+        self.batch_created_by(batch_seed, weight_kg, &user)
+    }
+
+    /// Completes a chain transaction handing `batch` from the actor `from`,
+    /// signed for by `from_authority`, to the actor `to`.
+    pub fn hand_over(
+        &mut self,
+        transaction_seed: &str,
+        batch: Pubkey,
+        from_authority: Pubkey,
+        from: Pubkey,
+        to: Pubkey,
+    ) {
+        let args = args::CreateChainTransaction {
+            transaction_seed: transaction_seed.to_string(),
+            from_actor_id: from,
+            to_actor_id: to,
+            rice_batch_ids: vec![batch],
+            amount: 100_000,
+            payment_method: PaymentMethod::Cash,
+            payment_reference: None,
+            geotag: None,
+            quality: None,
+        };
+        self.send(
+            instructions::create_chain_transaction(&from_authority, args),
+            &[from_authority],
+        )
+        .unwrap();
+        let args = args::UpdateChainTransaction {
+            transaction_seed: transaction_seed.to_string(),
+            amount: None,
+            payment_method: None,
+            payment_reference: None,
+            geotag: None,
+            quality: None,
+            transaction_status: Some(TransactionStatus::Completed),
+        };
+        self.send(
+            instructions::update_chain_transaction(&from_authority, &from, args, &[batch]),
+            &[from_authority],
+        )
+        .unwrap();
+    }
+
+    /// A batch whose creator is the funded wallet `creator`.
+    pub fn batch_created_by(
+        &mut self,
+        batch_seed: &str,
+        weight_kg: u32,
+        creator: &Pubkey,
+    ) -> Pubkey {
+        let milled_rice = self.milling(creator);
+        let args = batch_args(
+            &milled_rice,
+            batch_seed,
+            weight_kg,
+            &format!("QR-{batch_seed}"),
+        );
+        self.send(instructions::create_rice_batch(creator, args), &[*creator])
+            .unwrap();
+        pda::rice_batch(batch_seed).0
+    }
+}
+
+pub fn actor_args(actor_seed: &str, organization: Organization) -> args::CreateChainActor {
+    args::CreateChainActor {
+        actor_seed: actor_seed.to_string(),
+        name: format!("Actor {actor_seed}"),
+        actor_type: vec!["producer".to_string()],
+        farm_id: None,
+        farmer_id: None,
+        assigned_tps: 0,
+        pin: "1234".to_string(),
+        organization,
+        address: Some("Nueva Ecija".to_string()),
+    }
+}
+
+pub fn season_args(season_seed: &str) -> args::CreateProductionSeason {
+    args::CreateProductionSeason {
+        season_seed: season_seed.to_string(),
+        farmer_id: Pubkey::new_unique(),
+        crop_year: "2024-WS".to_string(),
+        processed_yield_kg: 0,
+        variety: None,
+        planned_practice: None,
+        planting_date: None,
+        irrigation_practice: None,
+        fertilizer_used: None,
+        pesticide_used: None,
+        harvest_date: None,
+        total_yield_kg: None,
+        moisture_content: None,
+        cultivated_area_m2: None,
+        organic_amendments: Vec::new(),
+    }
+}
+
+pub fn milled_rice_args(milling_seed: &str) -> args::CreateMilledRice {
+    args::CreateMilledRice {
+        milling_seed: milling_seed.to_string(),
+        farmer_id: Pubkey::new_unique(),
+        total_weight_kg: 650,
+        milling_type: MillingType::WellMilled,
+        quality: QualityGrade::Grade1,
+        photo_urls: Vec::new(),
+        moisture: 14,
+        total_weight_processed_kg: 1_000,
+        by_products: ByProducts {
+            husk_kg: 200,
+            bran_kg: 80,
+            brokens_kg: 50,
+            brewers_rice_kg: 20,
+        },
+    }
+}
+
+/// A batch from `milled_rice`, which the creating wallet must have recorded.
+pub fn batch_args(
+    milled_rice: &Pubkey,
+    batch_seed: &str,
+    weight_kg: u32,
+    qr_code: &str,
+) -> args::CreateRiceBatch {
+    args::CreateRiceBatch {
+        batch_seed: batch_seed.to_string(),
+        milled_rice_id: *milled_rice,
+        batch_status: BatchStatus::Created,
+        quality_score: 90,
+        weight_kg,
+        qr_code: qr_code.to_string(),
+    }
+}
+
+pub fn geo_point(latitude_microdeg: i32, longitude_microdeg: i32) -> GeoPoint {
+    GeoPoint {
+        latitude_microdeg,
+        longitude_microdeg,
+        accuracy_m: Some(5),
+        captured_at: 1_700_000_000,
+    }
+}
+
+/// Asserts the transaction failed with the Anchor error `code`.
+#[track_caller]
+pub fn assert_error(result: TransactionResult, code: impl Into<u32>) {
+    let code = code.into();
+    match result {
+        Ok(_) => panic!("expected error {code}, transaction succeeded"),
+        Err(error) => assert_eq!(error.custom_code(), Some(code), "{error}"),
+    }
+}
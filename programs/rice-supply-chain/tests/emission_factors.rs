@@ -0,0 +1,240 @@
+mod common;
+
+use common::{assert_error, season_args, Fixture};
+use rice_supply_chain::{
+    AmendmentKind, EmissionFactors, ErrorCode, OrganicAmendment, Organization, ProductionSeason,
+    WaterRegime,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+const PLANTING: i64 = 1_690_000_000;
+/// 110 days after planting.
+const HARVEST: i64 = PLANTING + 110 * 86_400;
+
+fn update_args(season_seed: &str) -> args::UpdateProductionSeason {
+    args::UpdateProductionSeason {
+        season_seed: season_seed.to_string(),
+        crop_year: None,
+        processed_yield_kg: None,
+        variety: None,
+        planned_practice: None,
+        planting_date: None,
+        irrigation_practice: None,
+        fertilizer_used: None,
+        pesticide_used: None,
+        harvest_date: None,
+        total_yield_kg: None,
+        moisture_content: None,
+        cultivated_area_m2: None,
+        organic_amendments: None,
+    }
+}
+
+/// A two-hectare, 110-day, continuously flooded season.
+fn flooded_season_args(season_seed: &str) -> args::CreateProductionSeason {
+    let mut args = season_args(season_seed);
+    args.planting_date = Some(PLANTING);
+    args.harvest_date = Some(HARVEST);
+    args.irrigation_practice = Some(WaterRegime::ContinuousFlooding);
+    args.cultivated_area_m2 = Some(20_000);
+    args
+}
+
+fn factors_args(baseline_methane_g_per_ha_day: u32) -> args::SetEmissionFactors {
+    args::SetEmissionFactors {
+        baseline_methane_g_per_ha_day,
+        water_regime_scaling_bp: [10_000, 7_100, 5_500, 5_400, 0],
+        amendment_conversion_bp: [10_000, 1_900, 1_700, 2_100, 4_500],
+        amendment_exponent_bp: 5_900,
+        methane_gwp_100: 27,
+    }
+}
+
+fn methane_estimate_g(fixture: &Fixture, season_seed: &str) -> Option<u64> {
+    let address = pda::production_season(season_seed).0;
+    let production_season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    production_season.methane_estimate_g
+}
+
+#[test]
+fn initialize_emission_factors_holds_ipcc_defaults() {
+    let fixture = Fixture::new();
+    let emission_factors: EmissionFactors =
+        fixture.svm.account(&pda::emission_factors().0).unwrap();
+    assert_eq!(emission_factors.baseline_methane_g_per_ha_day, 1_190);
+    assert_eq!(
+        emission_factors.water_regime_scaling_bp,
+        [10_000, 7_100, 5_500, 5_400, 0]
+    );
+    assert_eq!(emission_factors.amendment_exponent_bp, 5_900);
+    assert_eq!(emission_factors.methane_gwp_100, 27);
+}
+
+#[test]
+fn create_production_season_estimates_methane() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture
+        .send(
+            instructions::create_production_season(&user, flooded_season_args("flooded")),
+            &[user],
+        )
+        .unwrap();
+    // 1.19 kg CH4/ha/day * 110 days * 2 ha.
+    assert_eq!(methane_estimate_g(&fixture, "flooded"), Some(261_800));
+
+    let mut args = flooded_season_args("composted");
+    args.organic_amendments = vec![OrganicAmendment {
+        kind: AmendmentKind::Compost,
+        rate_kg_per_ha: 5_000,
+    }];
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+    // Scaled by (1 + 5 t/ha * 0.17) ^ 0.59.
+    assert_eq!(methane_estimate_g(&fixture, "composted"), Some(376_357));
+
+    let mut args = flooded_season_args("unmeasured");
+    args.cultivated_area_m2 = None;
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+    assert_eq!(methane_estimate_g(&fixture, "unmeasured"), None);
+}
+
+#[test]
+fn seasons_carry_no_estimate_until_the_factors_are_initialized() {
+    let mut fixture = Fixture::bare();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let mut args = flooded_season_args("season-1");
+    args.farmer_id = farmer;
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+    assert_eq!(methane_estimate_g(&fixture, "season-1"), None);
+
+    let admin = fixture.admin;
+    fixture
+        .send(instructions::initialize_emission_factors(&admin), &[admin])
+        .unwrap();
+    let mut args = update_args("season-1");
+    args.total_yield_kg = Some(9_000);
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    assert_eq!(methane_estimate_g(&fixture, "season-1"), Some(261_800));
+}
+
+#[test]
+fn create_production_season_leaves_an_estimate_too_large_to_store_unset() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut args = flooded_season_args("season-1");
+    args.cultivated_area_m2 = Some(u64::MAX);
+    args.organic_amendments = vec![OrganicAmendment {
+        kind: AmendmentKind::StrawShortlyBefore,
+        rate_kg_per_ha: u32::MAX,
+    }];
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+    assert_eq!(methane_estimate_g(&fixture, "season-1"), None);
+}
+
+#[test]
+fn create_production_season_limits_organic_amendments() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut args = flooded_season_args("season-1");
+    args.organic_amendments = vec![
+        OrganicAmendment {
+            kind: AmendmentKind::GreenManure,
+            rate_kg_per_ha: 1_000,
+        };
+        ProductionSeason::MAX_ORGANIC_AMENDMENTS + 1
+    ];
+    assert_error(
+        fixture.send(instructions::create_production_season(&user, args), &[user]),
+        ErrorCode::TooManyEntries,
+    );
+}
+
+#[test]
+fn update_production_season_recomputes_the_estimate() {
+    let mut fixture = Fixture::new();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let mut args = flooded_season_args("season-1");
+    args.farmer_id = farmer;
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+
+    let mut args = update_args("season-1");
+    args.irrigation_practice = Some(WaterRegime::Upland);
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    assert_eq!(methane_estimate_g(&fixture, "season-1"), Some(0));
+
+    let mut args = update_args("season-1");
+    args.harvest_date = Some(PLANTING - 86_400);
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    assert_eq!(methane_estimate_g(&fixture, "season-1"), None);
+}
+
+#[test]
+fn set_emission_factors_is_admin_only_and_applies_on_update() {
+    let mut fixture = Fixture::new();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let mut args = flooded_season_args("season-1");
+    args.farmer_id = farmer;
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+
+    let stranger = fixture.wallet();
+    assert_error(
+        fixture.send(
+            instructions::set_emission_factors(&stranger, factors_args(1_300)),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    let admin = fixture.admin;
+    let mut args = factors_args(1_300);
+    args.water_regime_scaling_bp[0] = 10_001;
+    assert_error(
+        fixture.send(instructions::set_emission_factors(&admin, args), &[admin]),
+        ErrorCode::InvalidPercentage,
+    );
+    fixture
+        .send(
+            instructions::set_emission_factors(&admin, factors_args(1_300)),
+            &[admin],
+        )
+        .unwrap();
+
+    // Stored estimates only move when the season is next updated.
+    assert_eq!(methane_estimate_g(&fixture, "season-1"), Some(261_800));
+    let mut args = update_args("season-1");
+    args.total_yield_kg = Some(9_000);
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    assert_eq!(methane_estimate_g(&fixture, "season-1"), Some(286_000));
+}
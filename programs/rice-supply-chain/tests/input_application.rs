@@ -0,0 +1,365 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{assert_error, Fixture};
+use rice_supply_chain::{
+    ErrorCode, InputApplication, InputKind, Organization, ProductionSeason, SubstanceRegistry,
+    SubstanceStatus,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+fn urea(application_seed: &str) -> args::AddInputApplication {
+    args::AddInputApplication {
+        application_seed: application_seed.to_string(),
+        input_kind: InputKind::Fertilizer,
+        product: "Urea 46-0-0".to_string(),
+        active_ingredient: None,
+        rate_g_per_ha: 100_000,
+        nitrogen_content_bp: 4_600,
+        applied_at: 1_690_000_000,
+        pre_harvest_interval_days: None,
+    }
+}
+
+fn pesticide(
+    application_seed: &str,
+    active_ingredient: &str,
+    rate_g_per_ha: u64,
+) -> args::AddInputApplication {
+    args::AddInputApplication {
+        application_seed: application_seed.to_string(),
+        input_kind: InputKind::Insecticide,
+        product: "Field spray".to_string(),
+        active_ingredient: Some(active_ingredient.to_string()),
+        rate_g_per_ha,
+        nitrogen_content_bp: 0,
+        applied_at: 1_690_000_000,
+        pre_harvest_interval_days: Some(14),
+    }
+}
+
+struct Farm {
+    fixture: Fixture,
+    authority: Pubkey,
+    applicator: Pubkey,
+    season: Pubkey,
+}
+
+fn farm() -> Farm {
+    let mut fixture = Fixture::new();
+    let (authority, applicator) = fixture.actor("farmer-1", Organization::Farmer);
+    let season = fixture.farmer_season("season-1", applicator, Some(5_000));
+    Farm {
+        fixture,
+        authority,
+        applicator,
+        season,
+    }
+}
+
+impl Farm {
+    fn add(&mut self, args: args::AddInputApplication) -> rice_supply_svm::TransactionResult {
+        let instruction = instructions::add_input_application(
+            &self.authority,
+            &self.applicator,
+            &self.season,
+            args,
+        );
+        self.fixture.send(instruction, &[self.authority])
+    }
+
+    fn harvest(&mut self, harvest_date: i64) -> rice_supply_svm::TransactionResult {
+        let args = args::UpdateProductionSeason {
+            season_seed: "season-1".to_string(),
+            crop_year: None,
+            processed_yield_kg: None,
+            variety: None,
+            planned_practice: None,
+            planting_date: None,
+            irrigation_practice: None,
+            fertilizer_used: None,
+            pesticide_used: None,
+            harvest_date: Some(harvest_date),
+            total_yield_kg: None,
+            moisture_content: None,
+            cultivated_area_m2: None,
+            organic_amendments: None,
+        };
+        let instruction =
+            instructions::update_production_season(&self.authority, &self.applicator, args);
+        self.fixture.send(instruction, &[self.authority])
+    }
+
+    fn upsert(&mut self, active_ingredient: &str, status: SubstanceStatus, max_rate: Option<u64>) {
+        let admin = self.fixture.admin;
+        self.fixture
+            .send(
+                instructions::upsert_substance(
+                    &admin,
+                    active_ingredient.to_string(),
+                    status,
+                    max_rate,
+                ),
+                &[admin],
+            )
+            .unwrap();
+    }
+}
+
+#[test]
+fn upsert_substance_normalizes_and_replaces_entries() {
+    let mut farm = farm();
+    farm.upsert("Chlorpyrifos", SubstanceStatus::Restricted, Some(500));
+    farm.upsert("chlorpyrifos", SubstanceStatus::Banned, None);
+    farm.upsert("Carbofuran", SubstanceStatus::Banned, None);
+
+    let registry: SubstanceRegistry = farm
+        .fixture
+        .svm
+        .account(&pda::substance_registry().0)
+        .unwrap();
+    assert_eq!(registry.entries.len(), 2);
+    let entry = registry.find("CHLORPYRIFOS").unwrap();
+    assert_eq!(entry.active_ingredient, "chlorpyrifos");
+    assert!(entry.status == SubstanceStatus::Banned);
+    assert_eq!(entry.max_rate_g_per_ha, None);
+
+    let admin = farm.fixture.admin;
+    farm.fixture
+        .send(
+            instructions::remove_substance(&admin, "CARBOFURAN".to_string()),
+            &[admin],
+        )
+        .unwrap();
+    let registry: SubstanceRegistry = farm
+        .fixture
+        .svm
+        .account(&pda::substance_registry().0)
+        .unwrap();
+    assert!(registry.find("carbofuran").is_none());
+}
+
+#[test]
+fn upsert_substance_is_admin_only_and_bounded() {
+    let mut farm = farm();
+    let stranger = farm.fixture.wallet();
+    assert_error(
+        farm.fixture.send(
+            instructions::upsert_substance(
+                &stranger,
+                "paraquat".to_string(),
+                SubstanceStatus::Banned,
+                None,
+            ),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    assert_error(
+        farm.fixture.send(
+            instructions::remove_substance(&stranger, "paraquat".to_string()),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    let admin = farm.fixture.admin;
+    for active_ingredient in [String::new(), "x".repeat(33)] {
+        assert_error(
+            farm.fixture.send(
+                instructions::upsert_substance(
+                    &admin,
+                    active_ingredient,
+                    SubstanceStatus::Banned,
+                    None,
+                ),
+                &[admin],
+            ),
+            ErrorCode::StringTooLong,
+        );
+    }
+
+    for index in 0..SubstanceRegistry::MAX_ENTRIES {
+        farm.upsert(
+            &format!("substance-{index}"),
+            SubstanceStatus::Restricted,
+            None,
+        );
+    }
+    assert_error(
+        farm.fixture.send(
+            instructions::upsert_substance(
+                &admin,
+                "one-more".to_string(),
+                SubstanceStatus::Banned,
+                None,
+            ),
+            &[admin],
+        ),
+        ErrorCode::RegistryFull,
+    );
+}
+
+#[test]
+fn add_input_application_accumulates_nitrogen() {
+    let mut farm = farm();
+    farm.add(urea("urea-1")).unwrap();
+    farm.add(urea("urea-2")).unwrap();
+
+    let application: InputApplication = farm
+        .fixture
+        .svm
+        .account(&pda::input_application("urea-1").0)
+        .unwrap();
+    assert_eq!(application.production_season, farm.season);
+    assert_eq!(application.applicator, farm.applicator);
+    assert_eq!(application.nitrogen_g_per_ha(), 46_000);
+
+    let season: ProductionSeason = farm.fixture.svm.account(&farm.season).unwrap();
+    assert_eq!(season.nitrogen_applied_g_per_ha, 92_000);
+}
+
+#[test]
+fn input_applications_prove_the_pre_harvest_interval() {
+    let mut farm = farm();
+    farm.add(urea("urea-1")).unwrap();
+    farm.add(pesticide("spray-1", "cypermethrin", 50)).unwrap();
+    let application = |seed: &str| -> InputApplication {
+        farm.fixture
+            .svm
+            .account(&pda::input_application(seed).0)
+            .unwrap()
+    };
+
+    // Sprayed at 1_690_000_000 with a 14-day interval.
+    let spray = application("spray-1");
+    assert_eq!(spray.pre_harvest_interval_days, Some(14));
+    assert!(spray.pre_harvest_interval_met(1_690_000_000 + 14 * 86_400));
+    assert!(!spray.pre_harvest_interval_met(1_690_000_000 + 14 * 86_400 - 1));
+    assert!(application("urea-1").pre_harvest_interval_met(1_690_000_000));
+}
+
+#[test]
+fn harvest_date_must_clear_every_pre_harvest_interval() {
+    let mut farm = farm();
+    farm.add(pesticide("spray-1", "cypermethrin", 50)).unwrap();
+    let clear_at = 1_690_000_000 + 14 * 86_400;
+    let season: ProductionSeason = farm.fixture.svm.account(&farm.season).unwrap();
+    assert_eq!(season.pre_harvest_clear_at, Some(clear_at));
+
+    assert_error(
+        farm.harvest(clear_at - 1),
+        ErrorCode::PreHarvestIntervalNotMet,
+    );
+    farm.harvest(clear_at).unwrap();
+
+    // A spray recorded after the harvest date would break the interval.
+    let mut late = pesticide("spray-2", "cypermethrin", 50);
+    late.applied_at = clear_at - 86_400;
+    assert_error(farm.add(late), ErrorCode::PreHarvestIntervalNotMet);
+    let mut early = pesticide("spray-2", "cypermethrin", 50);
+    early.applied_at = clear_at - 14 * 86_400;
+    farm.add(early).unwrap();
+}
+
+#[test]
+fn add_input_application_enforces_the_substance_registry() {
+    let mut farm = farm();
+    farm.upsert("carbofuran", SubstanceStatus::Banned, None);
+    farm.upsert("cypermethrin", SubstanceStatus::Restricted, Some(50));
+
+    assert_error(
+        farm.add(pesticide("spray-1", "Carbofuran", 10)),
+        ErrorCode::BannedSubstance,
+    );
+    assert_error(
+        farm.add(pesticide("spray-1", "cypermethrin", 51)),
+        ErrorCode::RateAboveMaximum,
+    );
+    farm.add(pesticide("spray-1", "cypermethrin", 50)).unwrap();
+}
+
+#[test]
+fn add_input_application_validates_its_arguments() {
+    let mut farm = farm();
+    let mut args = urea("urea-1");
+    args.nitrogen_content_bp = 10_001;
+    assert_error(farm.add(args), ErrorCode::InvalidPercentage);
+
+    let mut args = urea("urea-1");
+    args.product = "p".repeat(InputApplication::MAX_NAME_LEN + 1);
+    assert_error(farm.add(args), ErrorCode::StringTooLong);
+
+    let mut args = urea("urea-1");
+    args.active_ingredient = Some("a".repeat(InputApplication::MAX_NAME_LEN + 1));
+    assert_error(farm.add(args), ErrorCode::StringTooLong);
+}
+
+#[test]
+fn add_input_application_checks_the_applicator() {
+    let mut farm = farm();
+    let stranger = farm.fixture.wallet();
+    let instruction = instructions::add_input_application(
+        &stranger,
+        &farm.applicator,
+        &farm.season,
+        urea("urea-1"),
+    );
+    assert_error(
+        farm.fixture.send(instruction, &[stranger]),
+        ErrorCode::Unauthorized,
+    );
+
+    // Another farmer cannot record inputs against this farmer's season.
+    let (neighbour, neighbour_actor) = farm.fixture.actor("farmer-2", Organization::Farmer);
+    let instruction = instructions::add_input_application(
+        &neighbour,
+        &neighbour_actor,
+        &farm.season,
+        urea("urea-1"),
+    );
+    assert_error(
+        farm.fixture.send(instruction, &[neighbour]),
+        ErrorCode::Unauthorized,
+    );
+
+    let authority = farm.authority;
+    farm.fixture
+        .send(
+            instructions::delete_chain_actor(&authority, "farmer-1"),
+            &[authority],
+        )
+        .unwrap();
+    assert_error(farm.add(urea("urea-1")), ErrorCode::ActorInactive);
+}
+
+#[test]
+fn remove_input_application_closes_the_record() {
+    let mut farm = farm();
+    farm.add(urea("urea-1")).unwrap();
+    let address = pda::input_application("urea-1").0;
+    let rent = farm.fixture.svm.balance(&address);
+    let balance = farm.fixture.svm.balance(&farm.authority);
+
+    let stranger = farm.fixture.wallet();
+    let instruction =
+        instructions::remove_input_application(&stranger, &farm.applicator, &farm.season, "urea-1");
+    assert_error(
+        farm.fixture.send(instruction, &[stranger]),
+        ErrorCode::Unauthorized,
+    );
+
+    let instruction = instructions::remove_input_application(
+        &farm.authority,
+        &farm.applicator,
+        &farm.season,
+        "urea-1",
+    );
+    farm.fixture.send(instruction, &[farm.authority]).unwrap();
+
+    assert!(farm.fixture.svm.get_account(&address).is_none());
+    assert_eq!(farm.fixture.svm.balance(&farm.authority), balance + rent);
+    let season: ProductionSeason = farm.fixture.svm.account(&farm.season).unwrap();
+    assert_eq!(season.nitrogen_applied_g_per_ha, 0);
+}
@@ -0,0 +1,753 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::prelude::Pubkey;
+use common::{assert_error, geo_point, Fixture};
+use rice_supply_chain::{
+    BatchStatus, ErrorCode, Organization, RiceBatch, Shipment, ShipmentStatus, StorageRecord,
+    Warehouse,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+fn shipment_args(shipment_seed: &str, rice_batch_ids: Vec<Pubkey>) -> args::CreateShipment {
+    args::CreateShipment {
+        shipment_seed: shipment_seed.to_string(),
+        vehicle_id: "NCR-4821".to_string(),
+        origin: geo_point(15_580_000, 120_970_000),
+        destination: geo_point(14_600_000, 120_980_000),
+        rice_batch_ids,
+        seal_numbers: vec!["S-001".to_string(), "S-002".to_string()],
+    }
+}
+
+fn warehouse_args(warehouse_seed: &str, capacity_kg: u64) -> args::CreateWarehouse {
+    args::CreateWarehouse {
+        warehouse_seed: warehouse_seed.to_string(),
+        name: "Cabanatuan Depot".to_string(),
+        location: geo_point(15_490_000, 120_970_000),
+        capacity_kg,
+    }
+}
+
+fn seals() -> Vec<String> {
+    vec!["S-001".to_string(), "S-002".to_string()]
+}
+
+struct Chain {
+    fixture: Fixture,
+    authority: Pubkey,
+    /// Carrier and warehouse operator.
+    actor: Pubkey,
+    batches: Vec<Pubkey>,
+}
+
+fn chain() -> Chain {
+    let mut fixture = Fixture::new();
+    let (authority, actor) = fixture.actor("logistics-1", Organization::Distributor);
+    let batches = vec![
+        fixture.batch_created_by("batch-1", 1_000, &authority),
+        fixture.batch_created_by("batch-2", 500, &authority),
+    ];
+    Chain {
+        fixture,
+        authority,
+        actor,
+        batches,
+    }
+}
+
+impl Chain {
+    fn send(
+        &mut self,
+        instruction: anchor_lang::solana_program::instruction::Instruction,
+    ) -> rice_supply_svm::TransactionResult {
+        self.fixture.send(instruction, &[self.authority])
+    }
+
+    fn status(&self, batch: usize) -> BatchStatus {
+        let rice_batch: RiceBatch = self.fixture.svm.account(&self.batches[batch]).unwrap();
+        rice_batch.batch_status
+    }
+
+    fn storage_count(&self, batch: usize) -> u16 {
+        let rice_batch: RiceBatch = self.fixture.svm.account(&self.batches[batch]).unwrap();
+        rice_batch.storage_count
+    }
+
+    fn store(&mut self, warehouse_seed: &str, batch: usize) -> rice_supply_svm::TransactionResult {
+        let instruction = instructions::store_batch(
+            &self.authority,
+            &self.actor,
+            warehouse_seed,
+            &self.batches[batch],
+            self.storage_count(batch),
+            None,
+            &self.authority,
+        );
+        self.send(instruction)
+    }
+
+    fn release(
+        &mut self,
+        warehouse_seed: &str,
+        batch: usize,
+    ) -> rice_supply_svm::TransactionResult {
+        let instruction = instructions::release_batch(
+            &self.authority,
+            &self.actor,
+            warehouse_seed,
+            &self.batches[batch],
+            self.storage_count(batch),
+        );
+        self.send(instruction)
+    }
+}
+
+#[test]
+fn shipment_moves_batches_through_transit() {
+    let mut chain = chain();
+    let batches = chain.batches.clone();
+    chain
+        .send(instructions::create_shipment(
+            &chain.authority,
+            &chain.actor,
+            None,
+            &chain.authority,
+            shipment_args("ship-1", batches.clone()),
+        ))
+        .unwrap();
+    let address = pda::shipment("ship-1").0;
+    let shipment: Shipment = chain.fixture.svm.account(&address).unwrap();
+    assert!(shipment.shipment_status == ShipmentStatus::Planned);
+    assert_eq!(shipment.carrier, chain.actor);
+
+    assert_error(
+        chain.send(instructions::arrive_shipment(
+            &chain.authority,
+            &chain.actor,
+            "ship-1",
+            seals(),
+            &batches,
+        )),
+        ErrorCode::InvalidStatusTransition,
+    );
+
+    chain.fixture.svm.warp_to_timestamp(1_700_003_600);
+    chain
+        .send(instructions::depart_shipment(
+            &chain.authority,
+            &chain.actor,
+            "ship-1",
+            &batches,
+        ))
+        .unwrap();
+    assert!(chain.status(0) == BatchStatus::InTransit);
+    assert!(chain.status(1) == BatchStatus::InTransit);
+
+    assert_error(
+        chain.send(instructions::arrive_shipment(
+            &chain.authority,
+            &chain.actor,
+            "ship-1",
+            vec!["S-001".to_string()],
+            &batches,
+        )),
+        ErrorCode::SealMismatch,
+    );
+    chain.fixture.svm.warp_to_timestamp(1_700_020_000);
+    chain
+        .send(instructions::arrive_shipment(
+            &chain.authority,
+            &chain.actor,
+            "ship-1",
+            seals(),
+            &batches,
+        ))
+        .unwrap();
+    assert!(chain.status(0) == BatchStatus::Delivered);
+
+    let shipment: Shipment = chain.fixture.svm.account(&address).unwrap();
+    assert!(shipment.shipment_status == ShipmentStatus::Arrived);
+    assert_eq!(shipment.departed_at, Some(1_700_003_600));
+    assert_eq!(shipment.arrived_at, Some(1_700_020_000));
+
+    assert_error(
+        chain.send(instructions::depart_shipment(
+            &chain.authority,
+            &chain.actor,
+            "ship-1",
+            &batches,
+        )),
+        ErrorCode::InvalidStatusTransition,
+    );
+}
+
+#[test]
+fn batches_ride_one_shipment_at_a_time() {
+    let mut chain = chain();
+    let batches = chain.batches.clone();
+    for shipment_seed in ["ship-1", "ship-2"] {
+        chain
+            .send(instructions::create_shipment(
+                &chain.authority,
+                &chain.actor,
+                None,
+                &chain.authority,
+                shipment_args(shipment_seed, batches.clone()),
+            ))
+            .unwrap();
+    }
+    let depart = |chain: &mut Chain, shipment_seed: &str| {
+        chain.send(instructions::depart_shipment(
+            &chain.authority,
+            &chain.actor,
+            shipment_seed,
+            &batches,
+        ))
+    };
+
+    depart(&mut chain, "ship-1").unwrap();
+    assert_error(
+        depart(&mut chain, "ship-2"),
+        ErrorCode::InvalidStatusTransition,
+    );
+    let shipment: Shipment = chain
+        .fixture
+        .svm
+        .account(&pda::shipment("ship-2").0)
+        .unwrap();
+    assert!(shipment.shipment_status == ShipmentStatus::Planned);
+
+    // Once delivered, the batches go on to the next leg.
+    chain
+        .send(instructions::arrive_shipment(
+            &chain.authority,
+            &chain.actor,
+            "ship-1",
+            seals(),
+            &batches,
+        ))
+        .unwrap();
+    depart(&mut chain, "ship-2").unwrap();
+    assert!(chain.status(0) == BatchStatus::InTransit);
+    assert!(chain.status(1) == BatchStatus::InTransit);
+}
+
+#[test]
+fn depart_shipment_requires_every_batch_in_order() {
+    let mut chain = chain();
+    let batches = chain.batches.clone();
+    chain
+        .send(instructions::create_shipment(
+            &chain.authority,
+            &chain.actor,
+            None,
+            &chain.authority,
+            shipment_args("ship-1", batches.clone()),
+        ))
+        .unwrap();
+
+    for accounts in [vec![batches[0]], vec![batches[1], batches[0]]] {
+        assert_error(
+            chain.send(instructions::depart_shipment(
+                &chain.authority,
+                &chain.actor,
+                "ship-1",
+                &accounts,
+            )),
+            ErrorCode::BatchAccountMismatch,
+        );
+    }
+    let mut instruction =
+        instructions::depart_shipment(&chain.authority, &chain.actor, "ship-1", &batches);
+    instruction.accounts.last_mut().unwrap().is_writable = false;
+    assert_error(chain.send(instruction), ErrorCode::BatchAccountMismatch);
+
+    // A consumed batch cannot ship, and the whole departure fails with it.
+    chain
+        .send(instructions::delete_rice_batch(
+            &chain.authority,
+            None,
+            "batch-2",
+            "QR-batch-2",
+        ))
+        .unwrap();
+    assert_error(
+        chain.send(instructions::depart_shipment(
+            &chain.authority,
+            &chain.actor,
+            "ship-1",
+            &batches,
+        )),
+        ErrorCode::InvalidStatusTransition,
+    );
+    assert!(chain.status(0) == BatchStatus::Created);
+}
+
+#[test]
+fn shipments_are_carrier_only() {
+    let mut chain = chain();
+    let batches = chain.batches.clone();
+    let stranger = chain.fixture.wallet();
+    assert_error(
+        chain.fixture.send(
+            instructions::create_shipment(
+                &stranger,
+                &chain.actor,
+                None,
+                &stranger,
+                shipment_args("ship-1", batches.clone()),
+            ),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    chain
+        .send(instructions::create_shipment(
+            &chain.authority,
+            &chain.actor,
+            None,
+            &chain.authority,
+            shipment_args("ship-1", batches.clone()),
+        ))
+        .unwrap();
+
+    let (other_authority, other_carrier) =
+        chain.fixture.actor("carrier-2", Organization::Distributor);
+    assert_error(
+        chain.fixture.send(
+            instructions::depart_shipment(&other_authority, &other_carrier, "ship-1", &batches),
+            &[other_authority],
+        ),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn shipments_require_the_batch_holder() {
+    let mut chain = chain();
+    let batches = chain.batches.clone();
+    let (authority, actor) = (chain.authority, chain.actor);
+    let (carrier_authority, carrier) = chain.fixture.actor("carrier-2", Organization::Distributor);
+    let create = |holder: Option<&Pubkey>, holder_authority: &Pubkey, rice_batch_ids| {
+        instructions::create_shipment(
+            &carrier_authority,
+            &carrier,
+            holder,
+            holder_authority,
+            shipment_args("ship-1", rice_batch_ids),
+        )
+    };
+
+    // A carrier cannot take another actor's batches on its own say-so.
+    assert_error(
+        chain.fixture.send(
+            create(None, &carrier_authority, batches.clone()),
+            &[carrier_authority],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+
+    // Once handed over, only the new holder consents, and for its batches only.
+    let (retailer_authority, retailer) = chain.fixture.actor("retailer-1", Organization::Retailer);
+    chain
+        .fixture
+        .hand_over("tx-1", batches[0], authority, actor, retailer);
+    assert_error(
+        chain.fixture.send(
+            create(None, &authority, vec![batches[0]]),
+            &[carrier_authority, authority],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+    assert_error(
+        chain.fixture.send(
+            create(Some(&retailer), &retailer_authority, batches.clone()),
+            &[carrier_authority, retailer_authority],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+    chain
+        .fixture
+        .send(
+            create(Some(&retailer), &retailer_authority, vec![batches[0]]),
+            &[carrier_authority, retailer_authority],
+        )
+        .unwrap();
+    let shipment: Shipment = chain
+        .fixture
+        .svm
+        .account(&pda::shipment("ship-1").0)
+        .unwrap();
+    assert_eq!(shipment.carrier, carrier);
+    assert_eq!(shipment.rice_batch_ids, vec![batches[0]]);
+}
+
+#[test]
+fn create_shipment_requires_every_batch_in_order() {
+    let mut chain = chain();
+    let batches = chain.batches.clone();
+    let (authority, actor) = (chain.authority, chain.actor);
+    let mut instruction = instructions::create_shipment(
+        &authority,
+        &actor,
+        None,
+        &authority,
+        shipment_args("ship-1", batches.clone()),
+    );
+    instruction.accounts.pop();
+    assert_error(chain.send(instruction), ErrorCode::BatchAccountMismatch);
+
+    let mut instruction = instructions::create_shipment(
+        &authority,
+        &actor,
+        None,
+        &authority,
+        shipment_args("ship-1", batches),
+    );
+    let length = instruction.accounts.len();
+    instruction.accounts.swap(length - 2, length - 1);
+    assert_error(chain.send(instruction), ErrorCode::BatchAccountMismatch);
+}
+
+#[test]
+fn create_shipment_validates_its_arguments() {
+    let mut chain = chain();
+    let batches = chain.batches.clone();
+    let (authority, actor) = (chain.authority, chain.actor);
+
+    let mut args = shipment_args("ship-1", batches.clone());
+    args.vehicle_id = "v".repeat(33);
+    assert_error(
+        chain.send(instructions::create_shipment(
+            &authority, &actor, None, &authority, args,
+        )),
+        ErrorCode::StringTooLong,
+    );
+
+    let mut args = shipment_args("ship-1", batches.clone());
+    args.seal_numbers = vec!["S".to_string(); 5];
+    assert_error(
+        chain.send(instructions::create_shipment(
+            &authority, &actor, None, &authority, args,
+        )),
+        ErrorCode::TooManyEntries,
+    );
+
+    let mut args = shipment_args("ship-1", batches.clone());
+    args.seal_numbers = vec!["s".repeat(33)];
+    assert_error(
+        chain.send(instructions::create_shipment(
+            &authority, &actor, None, &authority, args,
+        )),
+        ErrorCode::StringTooLong,
+    );
+
+    let args = shipment_args("ship-1", Vec::new());
+    assert_error(
+        chain.send(instructions::create_shipment(
+            &authority, &actor, None, &authority, args,
+        )),
+        ErrorCode::TooManyEntries,
+    );
+
+    let args = shipment_args("ship-1", vec![batches[0], batches[1], batches[0]]);
+    assert_error(
+        chain.send(instructions::create_shipment(
+            &authority, &actor, None, &authority, args,
+        )),
+        ErrorCode::DuplicateBatch,
+    );
+
+    let mut args = shipment_args("ship-1", batches);
+    args.destination = geo_point(-90_000_001, 0);
+    assert_error(
+        chain.send(instructions::create_shipment(
+            &authority, &actor, None, &authority, args,
+        )),
+        ErrorCode::LatitudeOutOfRange,
+    );
+}
+
+#[test]
+fn warehouse_stores_and_releases_batches() {
+    let mut chain = chain();
+    let (authority, actor) = (chain.authority, chain.actor);
+    chain
+        .send(instructions::create_warehouse(
+            &authority,
+            &actor,
+            warehouse_args("depot-1", 1_200),
+        ))
+        .unwrap();
+
+    chain.store("depot-1", 0).unwrap();
+    let warehouse_address = pda::warehouse("depot-1").0;
+    let warehouse: Warehouse = chain.fixture.svm.account(&warehouse_address).unwrap();
+    assert_eq!(warehouse.stored_kg, 1_000);
+    assert_eq!(warehouse.stored_batch_ids, vec![chain.batches[0]]);
+
+    assert_error(chain.store("depot-1", 0), ErrorCode::BatchInStorage);
+    assert_error(
+        chain.store("depot-1", 1),
+        ErrorCode::WarehouseCapacityExceeded,
+    );
+
+    chain.fixture.svm.warp_to_timestamp(1_700_086_400);
+    chain.release("depot-1", 0).unwrap();
+    let record_address = pda::storage_record(&chain.batches[0], 0).0;
+    let record: StorageRecord = chain.fixture.svm.account(&record_address).unwrap();
+    assert_eq!(record.warehouse, warehouse_address);
+    assert_eq!(record.weight_kg, 1_000);
+    assert_eq!(record.released_at, Some(1_700_086_400));
+    let warehouse: Warehouse = chain.fixture.svm.account(&warehouse_address).unwrap();
+    assert_eq!(warehouse.stored_kg, 0);
+    assert!(warehouse.stored_batch_ids.is_empty());
+
+    assert_error(chain.release("depot-1", 0), ErrorCode::BatchNotInWarehouse);
+    // A second stay gets the next storage record.
+    chain.store("depot-1", 0).unwrap();
+    assert_eq!(chain.storage_count(0), 2);
+    assert!(chain
+        .fixture
+        .svm
+        .account::<StorageRecord>(&pda::storage_record(&chain.batches[0], 1).0)
+        .is_ok());
+}
+
+#[test]
+fn batches_move_between_warehouses() {
+    let mut chain = chain();
+    let (authority, actor) = (chain.authority, chain.actor);
+    for warehouse_seed in ["depot-1", "depot-2"] {
+        chain
+            .send(instructions::create_warehouse(
+                &authority,
+                &actor,
+                warehouse_args(warehouse_seed, 10_000),
+            ))
+            .unwrap();
+    }
+    let (depot_1, depot_2) = (pda::warehouse("depot-1").0, pda::warehouse("depot-2").0);
+    let current_warehouse = |chain: &Chain| {
+        let rice_batch: RiceBatch = chain.fixture.svm.account(&chain.batches[0]).unwrap();
+        rice_batch.current_warehouse
+    };
+
+    chain.fixture.svm.warp_to_timestamp(1_700_010_000);
+    chain.store("depot-1", 0).unwrap();
+    assert_eq!(current_warehouse(&chain), Some(depot_1));
+    assert_error(chain.store("depot-2", 0), ErrorCode::BatchInStorage);
+    // The open storage record names the warehouse holding the batch.
+    assert_error(chain.release("depot-2", 0), AnchorError::ConstraintHasOne);
+    let record: StorageRecord = chain
+        .fixture
+        .svm
+        .account(&pda::storage_record(&chain.batches[0], 0).0)
+        .unwrap();
+    assert_eq!(record.stored_at, 1_700_010_000);
+    assert_eq!(record.released_at, None);
+
+    chain.fixture.svm.warp_to_timestamp(1_700_090_000);
+    chain.release("depot-1", 0).unwrap();
+    assert_eq!(current_warehouse(&chain), None);
+    chain.store("depot-2", 0).unwrap();
+    assert_eq!(current_warehouse(&chain), Some(depot_2));
+    let record: StorageRecord = chain
+        .fixture
+        .svm
+        .account(&pda::storage_record(&chain.batches[0], 1).0)
+        .unwrap();
+    assert_eq!(record.warehouse, depot_2);
+    assert_eq!(record.stored_at, 1_700_090_000);
+}
+
+#[test]
+fn store_batch_requires_the_holder() {
+    let mut chain = chain();
+    let (authority, actor) = (chain.authority, chain.actor);
+    chain
+        .send(instructions::create_warehouse(
+            &authority,
+            &actor,
+            warehouse_args("depot-1", 10_000),
+        ))
+        .unwrap();
+
+    // A batch created by someone else stays out until its creator signs.
+    let (creator, farmer) = chain.fixture.actor("farmer-1", Organization::Farmer);
+    let batch = chain.fixture.batch_created_by("batch-3", 100, &creator);
+    let store = |storage_count: u16, holder: Option<&Pubkey>, holder_authority: &Pubkey| {
+        instructions::store_batch(
+            &authority,
+            &actor,
+            "depot-1",
+            &batch,
+            storage_count,
+            holder,
+            holder_authority,
+        )
+    };
+    assert_error(
+        chain.fixture.send(store(0, None, &authority), &[authority]),
+        ErrorCode::NotBatchHolder,
+    );
+    chain
+        .fixture
+        .send(store(0, None, &creator), &[authority, creator])
+        .unwrap();
+    let rice_batch: RiceBatch = chain.fixture.svm.account(&batch).unwrap();
+    assert_eq!(rice_batch.creator, creator);
+    assert_eq!(
+        rice_batch.current_warehouse,
+        Some(pda::warehouse("depot-1").0)
+    );
+    chain
+        .send(instructions::release_batch(
+            &authority, &actor, "depot-1", &batch, 1,
+        ))
+        .unwrap();
+
+    // Once the batch is handed over, only its holder can consent.
+    let (retailer_authority, retailer) = chain.fixture.actor("retailer-1", Organization::Retailer);
+    chain
+        .fixture
+        .hand_over("tx-1", batch, creator, farmer, retailer);
+    assert_error(
+        chain
+            .fixture
+            .send(store(1, None, &creator), &[authority, creator]),
+        ErrorCode::NotBatchHolder,
+    );
+    assert_error(
+        chain
+            .fixture
+            .send(store(1, Some(&retailer), &creator), &[authority, creator]),
+        ErrorCode::NotBatchHolder,
+    );
+    chain
+        .fixture
+        .send(
+            store(1, Some(&retailer), &retailer_authority),
+            &[authority, retailer_authority],
+        )
+        .unwrap();
+}
+
+#[test]
+fn stored_batches_cannot_ship() {
+    let mut chain = chain();
+    let (authority, actor) = (chain.authority, chain.actor);
+    let batches = chain.batches.clone();
+    chain
+        .send(instructions::create_warehouse(
+            &authority,
+            &actor,
+            warehouse_args("depot-1", 10_000),
+        ))
+        .unwrap();
+    chain.store("depot-1", 1).unwrap();
+    chain
+        .send(instructions::create_shipment(
+            &authority,
+            &actor,
+            None,
+            &authority,
+            shipment_args("ship-1", batches.clone()),
+        ))
+        .unwrap();
+    assert_error(
+        chain.send(instructions::depart_shipment(
+            &authority, &actor, "ship-1", &batches,
+        )),
+        ErrorCode::BatchInStorage,
+    );
+}
+
+#[test]
+fn update_warehouse_guards_capacity_and_activity() {
+    let mut chain = chain();
+    let (authority, actor) = (chain.authority, chain.actor);
+    chain
+        .send(instructions::create_warehouse(
+            &authority,
+            &actor,
+            warehouse_args("depot-1", 2_000),
+        ))
+        .unwrap();
+    chain.store("depot-1", 0).unwrap();
+    let update = |capacity_kg, is_active| args::UpdateWarehouse {
+        warehouse_seed: "depot-1".to_string(),
+        name: None,
+        capacity_kg,
+        is_active,
+    };
+
+    assert_error(
+        chain.send(instructions::update_warehouse(
+            &authority,
+            &actor,
+            update(Some(999), None),
+        )),
+        ErrorCode::WarehouseCapacityExceeded,
+    );
+    let stranger = chain.fixture.wallet();
+    assert_error(
+        chain.fixture.send(
+            instructions::update_warehouse(&stranger, &actor, update(None, Some(false))),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    let mut args = update(None, None);
+    args.name = Some("n".repeat(65));
+    assert_error(
+        chain.send(instructions::update_warehouse(&authority, &actor, args)),
+        ErrorCode::StringTooLong,
+    );
+
+    chain
+        .send(instructions::update_warehouse(
+            &authority,
+            &actor,
+            update(Some(1_500), Some(false)),
+        ))
+        .unwrap();
+    let warehouse: Warehouse = chain
+        .fixture
+        .svm
+        .account(&pda::warehouse("depot-1").0)
+        .unwrap();
+    assert_eq!(warehouse.capacity_kg, 1_500);
+    assert!(!warehouse.is_active);
+    assert_error(chain.store("depot-1", 1), ErrorCode::WarehouseInactive);
+}
+
+#[test]
+fn create_warehouse_validates_its_arguments() {
+    let mut chain = chain();
+    let (authority, actor) = (chain.authority, chain.actor);
+    let mut args = warehouse_args("depot-1", 1_000);
+    args.name = "n".repeat(65);
+    assert_error(
+        chain.send(instructions::create_warehouse(&authority, &actor, args)),
+        ErrorCode::StringTooLong,
+    );
+
+    let mut args = warehouse_args("depot-1", 1_000);
+    args.location = geo_point(0, 180_000_001);
+    assert_error(
+        chain.send(instructions::create_warehouse(&authority, &actor, args)),
+        ErrorCode::LongitudeOutOfRange,
+    );
+
+    let stranger = chain.fixture.wallet();
+    assert_error(
+        chain.fixture.send(
+            instructions::create_warehouse(&stranger, &actor, warehouse_args("depot-1", 1_000)),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+}
@@ -0,0 +1,401 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{assert_error, milled_rice_args, Fixture};
+use rice_supply_chain::{
+    ByProducts, DryingMethod, ErrorCode, Organization, PostHarvestEvent, ProductionSeason,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+fn drying(event_seed: &str, weight_in_kg: u64, weight_out_kg: u64) -> args::RecordPostHarvestEvent {
+    args::RecordPostHarvestEvent {
+        event_seed: event_seed.to_string(),
+        drying_method: DryingMethod::FlatBedDryer,
+        started_at: 1_699_100_000,
+        ended_at: 1_699_140_000,
+        start_moisture_bp: 2_400,
+        end_moisture_bp: 1_400,
+        weight_in_kg,
+        weight_out_kg,
+    }
+}
+
+/// A farmer actor and a season of theirs yielding `total_yield_kg`.
+fn harvest(fixture: &mut Fixture, total_yield_kg: u64) -> (Pubkey, Pubkey, Pubkey) {
+    let (farmer_authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let season = fixture.farmer_season("season-1", farmer, Some(total_yield_kg));
+    (farmer_authority, farmer, season)
+}
+
+#[test]
+fn record_post_harvest_event_tracks_losses() {
+    let mut fixture = Fixture::new();
+    let (authority, facility) = fixture.actor("dryer-1", Organization::Cooperative);
+    let (farmer_authority, farmer, season) = harvest(&mut fixture, 5_000);
+    fixture
+        .send(
+            instructions::record_post_harvest_event(
+                &authority,
+                &facility,
+                &season,
+                &farmer,
+                &farmer_authority,
+                drying("dry-1", 5_000, 4_400),
+            ),
+            &[authority, farmer_authority],
+        )
+        .unwrap();
+    // The farmer drying at their own facility signs once.
+    fixture
+        .send(
+            instructions::record_post_harvest_event(
+                &farmer_authority,
+                &farmer,
+                &season,
+                &farmer,
+                &farmer_authority,
+                drying("dry-2", 4_400, 4_300),
+            ),
+            &[farmer_authority],
+        )
+        .unwrap();
+
+    let event: PostHarvestEvent = fixture
+        .svm
+        .account(&pda::post_harvest_event("dry-1").0)
+        .unwrap();
+    assert_eq!(event.facility, facility);
+    assert_eq!(event.weight_loss_kg(), 600);
+    let season: ProductionSeason = fixture.svm.account(&season).unwrap();
+    assert_eq!(season.post_harvest_loss_kg, 700);
+    assert_eq!(season.available_for_milling_kg(), 4_300);
+}
+
+#[test]
+fn record_post_harvest_event_validates_its_arguments() {
+    let mut fixture = Fixture::new();
+    let (authority, facility) = fixture.actor("dryer-1", Organization::Cooperative);
+    let (farmer_authority, farmer, season) = harvest(&mut fixture, 1_000);
+    let mut send = |args| {
+        fixture.send(
+            instructions::record_post_harvest_event(
+                &authority,
+                &facility,
+                &season,
+                &farmer,
+                &farmer_authority,
+                args,
+            ),
+            &[authority, farmer_authority],
+        )
+    };
+
+    assert_error(send(drying("dry-1", 900, 901)), ErrorCode::WeightIncrease);
+    assert_error(
+        send(drying("dry-1", 2_000, 500)),
+        ErrorCode::InsufficientQuantity,
+    );
+
+    let mut args = drying("dry-1", 1_000, 900);
+    args.ended_at = args.started_at - 1;
+    assert_error(send(args), ErrorCode::InvalidValidityPeriod);
+
+    let mut args = drying("dry-1", 1_000, 900);
+    args.end_moisture_bp = args.start_moisture_bp + 1;
+    assert_error(send(args), ErrorCode::InvalidPercentage);
+}
+
+#[test]
+fn record_post_harvest_event_cannot_predate_the_harvest() {
+    let mut fixture = Fixture::new();
+    let (authority, facility) = fixture.actor("dryer-1", Organization::Cooperative);
+    let (farmer_authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let user = fixture.wallet();
+    let mut args = common::season_args("season-1");
+    args.farmer_id = farmer;
+    args.total_yield_kg = Some(1_000);
+    args.harvest_date = Some(1_699_200_000);
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+    let season = pda::production_season("season-1").0;
+
+    assert_error(
+        fixture.send(
+            instructions::record_post_harvest_event(
+                &authority,
+                &facility,
+                &season,
+                &farmer,
+                &farmer_authority,
+                drying("dry-1", 1_000, 900),
+            ),
+            &[authority, farmer_authority],
+        ),
+        ErrorCode::BeforeHarvest,
+    );
+
+    let stranger = fixture.wallet();
+    let mut args = drying("dry-1", 1_000, 900);
+    args.started_at = 1_699_200_000;
+    args.ended_at = 1_699_240_000;
+    assert_error(
+        fixture.send(
+            instructions::record_post_harvest_event(
+                &stranger,
+                &facility,
+                &season,
+                &farmer,
+                &farmer_authority,
+                args,
+            ),
+            &[stranger, farmer_authority],
+        ),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn record_post_harvest_event_requires_the_seasons_farmer() {
+    let mut fixture = Fixture::new();
+    let (authority, facility) = fixture.actor("dryer-1", Organization::Cooperative);
+    let (farmer_authority, farmer, season) = harvest(&mut fixture, 1_000);
+    let (neighbour_authority, neighbour) = fixture.actor("farmer-2", Organization::Farmer);
+
+    // Without the farmer's signature a facility cannot write off their harvest.
+    assert_error(
+        fixture.send(
+            instructions::record_post_harvest_event(
+                &authority,
+                &facility,
+                &season,
+                &farmer,
+                &authority,
+                drying("dry-1", 1_000, 900),
+            ),
+            &[authority],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    // Nor can another farmer stand in for them.
+    assert_error(
+        fixture.send(
+            instructions::record_post_harvest_event(
+                &authority,
+                &facility,
+                &season,
+                &neighbour,
+                &neighbour_authority,
+                drying("dry-1", 1_000, 900),
+            ),
+            &[authority, neighbour_authority],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    fixture
+        .send(
+            instructions::record_post_harvest_event(
+                &authority,
+                &facility,
+                &season,
+                &farmer,
+                &farmer_authority,
+                drying("dry-1", 1_000, 900),
+            ),
+            &[authority, farmer_authority],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&season).unwrap();
+    assert_eq!(season.post_harvest_loss_kg, 100);
+}
+
+#[test]
+fn total_yield_cannot_drop_below_recorded_losses() {
+    let mut fixture = Fixture::new();
+    let (farmer_authority, farmer, season) = harvest(&mut fixture, 1_000);
+    fixture
+        .send(
+            instructions::record_post_harvest_event(
+                &farmer_authority,
+                &farmer,
+                &season,
+                &farmer,
+                &farmer_authority,
+                drying("dry-1", 1_000, 900),
+            ),
+            &[farmer_authority],
+        )
+        .unwrap();
+
+    let update = |total_yield_kg| args::UpdateProductionSeason {
+        season_seed: "season-1".to_string(),
+        crop_year: None,
+        processed_yield_kg: None,
+        variety: None,
+        planned_practice: None,
+        planting_date: None,
+        irrigation_practice: None,
+        fertilizer_used: None,
+        pesticide_used: None,
+        harvest_date: None,
+        total_yield_kg: Some(total_yield_kg),
+        moisture_content: None,
+        cultivated_area_m2: None,
+        organic_amendments: None,
+    };
+    assert_error(
+        fixture.send(
+            instructions::update_production_season(&farmer_authority, &farmer, update(99)),
+            &[farmer_authority],
+        ),
+        ErrorCode::InsufficientQuantity,
+    );
+    fixture
+        .send(
+            instructions::update_production_season(&farmer_authority, &farmer, update(100)),
+            &[farmer_authority],
+        )
+        .unwrap();
+}
+
+#[test]
+fn post_harvest_losses_come_out_of_unmilled_paddy() {
+    let mut fixture = Fixture::new();
+    let (farmer_authority, farmer, season) = harvest(&mut fixture, 1_000);
+    let unharvested = fixture.farmer_season("season-2", farmer, None);
+    let dry = |fixture: &mut Fixture, season: &Pubkey, args| {
+        fixture.send(
+            instructions::record_post_harvest_event(
+                &farmer_authority,
+                &farmer,
+                season,
+                &farmer,
+                &farmer_authority,
+                args,
+            ),
+            &[farmer_authority],
+        )
+    };
+
+    // No paddy is on record before the yield is.
+    assert_error(
+        dry(&mut fixture, &unharvested, drying("dry-1", 10, 9)),
+        ErrorCode::InsufficientQuantity,
+    );
+
+    let miller = fixture.wallet();
+    let mut args = milled_rice_args("mill-1");
+    args.farmer_id = farmer;
+    args.total_weight_kg = 0;
+    args.by_products = ByProducts::default();
+    args.total_weight_processed_kg = 900;
+    fixture
+        .send(
+            instructions::create_milled_rice(&miller, &season, &farmer_authority, args),
+            &[miller, farmer_authority],
+        )
+        .unwrap();
+    assert_error(
+        dry(&mut fixture, &season, drying("dry-1", 1_000, 899)),
+        ErrorCode::InsufficientQuantity,
+    );
+    dry(&mut fixture, &season, drying("dry-1", 1_000, 900)).unwrap();
+    let production_season: ProductionSeason = fixture.svm.account(&season).unwrap();
+    assert_eq!(production_season.post_harvest_loss_kg, 100);
+    assert_eq!(production_season.available_for_milling_kg(), 0);
+}
+
+#[test]
+fn milling_is_limited_to_the_paddy_left_after_losses() {
+    let mut fixture = Fixture::new();
+    let (farmer_authority, farmer, season) = harvest(&mut fixture, 1_000);
+    fixture
+        .send(
+            instructions::record_post_harvest_event(
+                &farmer_authority,
+                &farmer,
+                &season,
+                &farmer,
+                &farmer_authority,
+                drying("dry-1", 1_000, 900),
+            ),
+            &[farmer_authority],
+        )
+        .unwrap();
+    let miller = fixture.wallet();
+    let mut mill = |milling_seed: &str, total_weight_processed_kg| {
+        let mut args = milled_rice_args(milling_seed);
+        args.farmer_id = farmer;
+        args.total_weight_kg = 0;
+        args.by_products = ByProducts::default();
+        args.total_weight_processed_kg = total_weight_processed_kg;
+        fixture.send(
+            instructions::create_milled_rice(&miller, &season, &farmer_authority, args),
+            &[miller, farmer_authority],
+        )
+    };
+
+    // 1,000 kg harvested, but only 900 kg survived drying.
+    assert_error(mill("mill-1", 1_000), ErrorCode::InsufficientQuantity);
+    mill("mill-1", 600).unwrap();
+    assert_error(mill("mill-2", 301), ErrorCode::InsufficientQuantity);
+    mill("mill-2", 300).unwrap();
+    let production_season: ProductionSeason = fixture.svm.account(&season).unwrap();
+    assert_eq!(production_season.milled_paddy_kg, 900);
+    assert_eq!(production_season.available_for_milling_kg(), 0);
+
+    // A corrected input only counts the difference.
+    let update = |total_weight_processed_kg| args::UpdateMilledRice {
+        milling_seed: "mill-1".to_string(),
+        total_weight_kg: None,
+        milling_type: None,
+        quality: None,
+        photo_urls: None,
+        moisture: None,
+        total_weight_processed_kg: Some(total_weight_processed_kg),
+        by_products: None,
+    };
+    assert_error(
+        fixture.send(
+            instructions::update_milled_rice(&miller, &season, update(601)),
+            &[miller],
+        ),
+        ErrorCode::InsufficientQuantity,
+    );
+    fixture
+        .send(
+            instructions::update_milled_rice(&miller, &season, update(500)),
+            &[miller],
+        )
+        .unwrap();
+    let production_season: ProductionSeason = fixture.svm.account(&season).unwrap();
+    assert_eq!(production_season.milled_paddy_kg, 800);
+    assert_eq!(production_season.available_for_milling_kg(), 100);
+
+    // Nor can the yield drop below what was lost and milled.
+    let update = |total_yield_kg| args::UpdateProductionSeason {
+        season_seed: "season-1".to_string(),
+        crop_year: None,
+        processed_yield_kg: None,
+        variety: None,
+        planned_practice: None,
+        planting_date: None,
+        irrigation_practice: None,
+        fertilizer_used: None,
+        pesticide_used: None,
+        harvest_date: None,
+        total_yield_kg: Some(total_yield_kg),
+        moisture_content: None,
+        cultivated_area_m2: None,
+        organic_amendments: None,
+    };
+    assert_error(
+        fixture.send(
+            instructions::update_production_season(&farmer_authority, &farmer, update(899)),
+            &[farmer_authority],
+        ),
+        ErrorCode::InsufficientQuantity,
+    );
+}
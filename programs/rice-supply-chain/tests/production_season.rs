@@ -0,0 +1,511 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use common::{assert_error, season_args, Fixture};
+use rice_supply_chain::{
+    AccreditationKind, ChainActor, ComplianceStatus, ErrorCode, Organization, ProductionSeason,
+    RiceVariety, SubstanceComplianceEvent, SubstanceStatus, ValidationStatus,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+use rice_supply_svm::ExecutionError;
+
+fn update_args(season_seed: &str) -> args::UpdateProductionSeason {
+    args::UpdateProductionSeason {
+        season_seed: season_seed.to_string(),
+        crop_year: None,
+        processed_yield_kg: None,
+        variety: None,
+        planned_practice: None,
+        planting_date: None,
+        irrigation_practice: None,
+        fertilizer_used: None,
+        pesticide_used: None,
+        harvest_date: None,
+        total_yield_kg: None,
+        moisture_content: None,
+        cultivated_area_m2: None,
+        organic_amendments: None,
+    }
+}
+
+#[test]
+fn create_production_season_starts_pending_and_compliant() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut args = season_args("season-1");
+    args.variety = Some(RiceVariety::NsicRc(222));
+    args.total_yield_kg = Some(5_000);
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+
+    let address = pda::production_season("season-1").0;
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert_eq!(season.public_key, address);
+    assert_eq!(season.crop_year, "2024-WS");
+    assert!(season.variety == Some(RiceVariety::NsicRc(222)));
+    assert!(season.validation_status == ValidationStatus::Pending);
+    assert!(season.compliance_status == ComplianceStatus::Compliant);
+    assert_eq!(season.nitrogen_applied_g_per_ha, 0);
+    assert_eq!(season.available_for_milling_kg(), 5_000);
+}
+
+#[test]
+fn create_production_season_rejects_a_zero_variety_code() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut args = season_args("season-1");
+    args.variety = Some(RiceVariety::PsbRc(0));
+    assert_error(
+        fixture.send(instructions::create_production_season(&user, args), &[user]),
+        ErrorCode::InvalidVarietyCode,
+    );
+}
+
+#[test]
+fn update_production_season_validates_the_variety() {
+    let mut fixture = Fixture::new();
+    let (authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let address = fixture.farmer_season("season-1", farmer, Some(4_000));
+
+    let mut args = update_args("season-1");
+    args.variety = Some(RiceVariety::NsicRc(0));
+    assert_error(
+        fixture.send(
+            instructions::update_production_season(&authority, &farmer, args),
+            &[authority],
+        ),
+        ErrorCode::InvalidVarietyCode,
+    );
+
+    let mut args = update_args("season-1");
+    args.variety = Some(RiceVariety::PsbRc(18));
+    fixture
+        .send(
+            instructions::update_production_season(&authority, &farmer, args),
+            &[authority],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.variety == Some(RiceVariety::PsbRc(18)));
+}
+
+#[test]
+fn create_production_season_rejects_an_oversize_crop_year() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut args = season_args("season-1");
+    args.crop_year = "y".repeat(512);
+    assert_error(
+        fixture.send(instructions::create_production_season(&user, args), &[user]),
+        AnchorError::AccountDidNotSerialize,
+    );
+}
+
+#[test]
+fn create_production_season_rejects_a_substitute_registry() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut instruction = instructions::create_production_season(&user, season_args("season-1"));
+    instruction.accounts[1].pubkey = pda::config().0;
+    assert_error(
+        fixture.send(instruction, &[user]),
+        AnchorError::ConstraintSeeds,
+    );
+}
+
+#[test]
+fn validate_production_season_approves_a_compliant_season() {
+    let mut fixture = Fixture::new();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let address = fixture.farmer_season("season-1", farmer, Some(4_000));
+    let mut args = update_args("season-1");
+    args.harvest_date = Some(1_699_000_000);
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    let validator = fixture.accredited("validator", AccreditationKind::Validator);
+    fixture
+        .validate(validator, "season-1", ValidationStatus::Approved)
+        .unwrap();
+
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.validation_status == ValidationStatus::Approved);
+    assert_eq!(season.validator_id, Some(validator.1));
+    assert_eq!(season.harvest_date, Some(1_699_000_000));
+    assert_eq!(season.total_yield_kg, Some(4_000));
+}
+
+#[test]
+fn validate_production_season_requires_an_accredited_validator() {
+    let mut fixture = Fixture::new();
+    let address = fixture.season("season-1", Some(4_000));
+    let unaccredited = fixture.actor("official", Organization::Government);
+    assert_error(
+        fixture.validate(unaccredited, "season-1", ValidationStatus::Approved),
+        AnchorError::AccountNotInitialized,
+    );
+    let laboratory = fixture.accredited("laboratory", AccreditationKind::Laboratory);
+    assert_error(
+        fixture.validate(laboratory, "season-1", ValidationStatus::Approved),
+        AnchorError::AccountNotInitialized,
+    );
+
+    let (_, validator) = fixture.accredited("validator", AccreditationKind::Validator);
+    let stranger = fixture.wallet();
+    assert_error(
+        fixture.validate(
+            (stranger, validator),
+            "season-1",
+            ValidationStatus::Approved,
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::revoke_accreditation(&admin, &validator, AccreditationKind::Validator),
+            &[admin],
+        )
+        .unwrap();
+    let authority = fixture
+        .svm
+        .account::<ChainActor>(&validator)
+        .unwrap()
+        .authority;
+    assert_error(
+        fixture.validate(
+            (authority, validator),
+            "season-1",
+            ValidationStatus::Approved,
+        ),
+        ErrorCode::AccreditationInactive,
+    );
+
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.validation_status == ValidationStatus::Pending);
+    assert_eq!(season.validator_id, None);
+}
+
+#[test]
+fn only_an_approval_goes_back_to_pending() {
+    let mut fixture = Fixture::new();
+    let address = fixture.season("season-1", Some(4_000));
+    let validator = fixture.accredited("validator", AccreditationKind::Validator);
+    fixture
+        .validate(validator, "season-1", ValidationStatus::Rejected)
+        .unwrap();
+    assert_error(
+        fixture.validate(validator, "season-1", ValidationStatus::Pending),
+        ErrorCode::InvalidStatusTransition,
+    );
+
+    fixture
+        .validate(validator, "season-1", ValidationStatus::Approved)
+        .unwrap();
+    fixture
+        .validate(validator, "season-1", ValidationStatus::Pending)
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.validation_status == ValidationStatus::Pending);
+}
+
+#[test]
+fn update_production_season_sends_changed_figures_back_to_validation() {
+    let mut fixture = Fixture::new();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let address = fixture.farmer_season("season-1", farmer, Some(4_000));
+    let validator = fixture.accredited("validator", AccreditationKind::Validator);
+    fixture
+        .validate(validator, "season-1", ValidationStatus::Approved)
+        .unwrap();
+
+    let mut args = update_args("season-1");
+    args.moisture_content = Some(14);
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.validation_status == ValidationStatus::Approved);
+
+    let mut args = update_args("season-1");
+    args.total_yield_kg = Some(8_000);
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.validation_status == ValidationStatus::Pending);
+    assert_eq!(season.validator_id, Some(validator.1));
+}
+
+#[test]
+fn update_production_season_screens_declared_substances() {
+    let mut fixture = Fixture::new();
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::upsert_substance(
+                &admin,
+                "Endosulfan".to_string(),
+                SubstanceStatus::Banned,
+                None,
+            ),
+            &[admin],
+        )
+        .unwrap();
+    fixture
+        .send(
+            instructions::upsert_substance(
+                &admin,
+                "Glyphosate".to_string(),
+                SubstanceStatus::Restricted,
+                None,
+            ),
+            &[admin],
+        )
+        .unwrap();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let address = fixture.farmer_season("season-1", farmer, None);
+
+    let mut args = update_args("season-1");
+    args.pesticide_used = Some("Glyphosate".to_string());
+    let metadata = fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.compliance_status == ComplianceStatus::Restricted);
+    let events = metadata.events::<SubstanceComplianceEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].production_season, address);
+    assert_eq!(events[0].active_ingredient, "glyphosate");
+    assert!(events[0].status == SubstanceStatus::Restricted);
+
+    let mut args = update_args("season-1");
+    args.fertilizer_used = Some("Urea, ENDOSULFAN".to_string());
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.compliance_status == ComplianceStatus::NonCompliant);
+
+    let validator = fixture.accredited("validator", AccreditationKind::Validator);
+    assert_error(
+        fixture.validate(validator, "season-1", ValidationStatus::Approved),
+        ErrorCode::SeasonNotCompliant,
+    );
+}
+
+#[test]
+fn create_production_season_screens_declared_substances() {
+    let mut fixture = Fixture::new();
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::upsert_substance(
+                &admin,
+                "Carbofuran".to_string(),
+                SubstanceStatus::Banned,
+                None,
+            ),
+            &[admin],
+        )
+        .unwrap();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let mut args = season_args("season-1");
+    args.farmer_id = farmer;
+    args.pesticide_used = Some("carbofuran".to_string());
+    let metadata = fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+
+    let address = pda::production_season("season-1").0;
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.compliance_status == ComplianceStatus::NonCompliant);
+    let events = metadata.events::<SubstanceComplianceEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].production_season, address);
+    assert!(events[0].status == SubstanceStatus::Banned);
+
+    // Correcting the declaration clears the flag.
+    let mut args = update_args("season-1");
+    args.pesticide_used = Some("Mancozeb".to_string());
+    let metadata = fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.compliance_status == ComplianceStatus::Compliant);
+    assert!(metadata.events::<SubstanceComplianceEvent>().is_empty());
+}
+
+#[test]
+fn screening_matches_whole_active_ingredient_names() {
+    let mut fixture = Fixture::new();
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::upsert_substance(
+                &admin,
+                " Omethoate ".to_string(),
+                SubstanceStatus::Banned,
+                None,
+            ),
+            &[admin],
+        )
+        .unwrap();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let address = fixture.farmer_season("season-1", farmer, None);
+
+    // Registered dimethoate contains the banned omethoate's name.
+    let mut args = update_args("season-1");
+    args.pesticide_used = Some("Dimethoate".to_string());
+    let metadata = fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.compliance_status == ComplianceStatus::Compliant);
+    assert!(metadata.events::<SubstanceComplianceEvent>().is_empty());
+
+    let mut args = update_args("season-1");
+    args.pesticide_used = Some("Mancozeb /  OMETHOATE\n".to_string());
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, args),
+            &[user],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.compliance_status == ComplianceStatus::NonCompliant);
+}
+
+#[test]
+fn seasons_are_not_screened_before_the_registry_exists() {
+    let mut fixture = Fixture::bare();
+    let admin = fixture.admin;
+    fixture
+        .send(instructions::initialize_emission_factors(&admin), &[admin])
+        .unwrap();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let mut args = season_args("season-1");
+    args.farmer_id = farmer;
+    args.pesticide_used = Some("Endosulfan".to_string());
+    fixture
+        .send(instructions::create_production_season(&user, args), &[user])
+        .unwrap();
+
+    let address = pda::production_season("season-1").0;
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.compliance_status == ComplianceStatus::Compliant);
+
+    // Once initialized, the registry screens the next update.
+    fixture
+        .send(
+            instructions::initialize_substance_registry(&admin),
+            &[admin],
+        )
+        .unwrap();
+    fixture
+        .send(
+            instructions::upsert_substance(
+                &admin,
+                "Endosulfan".to_string(),
+                SubstanceStatus::Banned,
+                None,
+            ),
+            &[admin],
+        )
+        .unwrap();
+    fixture
+        .send(
+            instructions::update_production_season(&user, &farmer, update_args("season-1")),
+            &[user],
+        )
+        .unwrap();
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.compliance_status == ComplianceStatus::NonCompliant);
+}
+
+#[test]
+fn delete_production_season_rejects_the_season() {
+    let mut fixture = Fixture::new();
+    let (user, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let address = fixture.farmer_season("season-1", farmer, None);
+    fixture
+        .send(
+            instructions::delete_production_season(&user, &farmer, "season-1"),
+            &[user],
+        )
+        .unwrap();
+
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert!(season.validation_status == ValidationStatus::Rejected);
+}
+
+#[test]
+fn only_the_farmer_may_change_or_withdraw_a_season() {
+    let mut fixture = Fixture::new();
+    let (farmer_authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let address = fixture.farmer_season("season-1", farmer, Some(4_000));
+    let (other_authority, other_farmer) = fixture.actor("farmer-2", Organization::Farmer);
+    let stranger = fixture.wallet();
+
+    let args = || args::UpdateProductionSeason {
+        total_yield_kg: Some(9_000),
+        ..update_args("season-1")
+    };
+    assert_error(
+        fixture.send(
+            instructions::update_production_season(&stranger, &farmer, args()),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    assert_error(
+        fixture.send(
+            instructions::update_production_season(&other_authority, &other_farmer, args()),
+            &[other_authority],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    let result = fixture.send(
+        instructions::update_production_season(&farmer_authority, &farmer, args()),
+        &[],
+    );
+    assert_eq!(
+        result.unwrap_err().error,
+        ExecutionError::MissingSignature(farmer_authority)
+    );
+    assert_error(
+        fixture.send(
+            instructions::delete_production_season(&stranger, &farmer, "season-1"),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    let season: ProductionSeason = fixture.svm.account(&address).unwrap();
+    assert_eq!(season.total_yield_kg, Some(4_000));
+    assert!(season.validation_status == ValidationStatus::Pending);
+}
@@ -0,0 +1,309 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{assert_error, Fixture};
+use rice_supply_chain::{
+    AccreditationKind, ErrorCode, QualityGrade, QualityTest, ResidueResult, RiceBatch,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+fn test_args(test_seed: &str, overall_grade: QualityGrade) -> args::RecordQualityTest {
+    args::RecordQualityTest {
+        test_seed: test_seed.to_string(),
+        sample_date: 1_699_990_000,
+        moisture_bp: 1_350,
+        broken_grain_bp: 1_000,
+        chalkiness_bp: 400,
+        foreign_matter_bp: 10,
+        residue_results: vec![ResidueResult {
+            analyte: "chlorpyrifos".to_string(),
+            measured_ug_per_kg: 20,
+            mrl_ug_per_kg: Some(500),
+        }],
+        overall_grade,
+        report_hash: [3; 32],
+    }
+}
+
+struct Lab {
+    fixture: Fixture,
+    authority: Pubkey,
+    laboratory: Pubkey,
+    batch: Pubkey,
+}
+
+fn lab() -> Lab {
+    let mut fixture = Fixture::new();
+    let (authority, laboratory) = fixture.accredited("lab-1", AccreditationKind::Laboratory);
+    let batch = fixture.batch("batch-1", 1_000);
+    Lab {
+        fixture,
+        authority,
+        laboratory,
+        batch,
+    }
+}
+
+impl Lab {
+    fn record(&mut self, args: args::RecordQualityTest) -> rice_supply_svm::TransactionResult {
+        let chain = self.chain_to(args.sample_date);
+        let instruction = instructions::record_quality_test(
+            &self.authority,
+            &self.laboratory,
+            &self.batch,
+            &chain,
+            args,
+        );
+        self.fixture.send(instruction, &[self.authority])
+    }
+
+    /// The batch's tests, newest first, down to the first one sampled no
+    /// later than `sample_date`.
+    fn chain_to(&self, sample_date: i64) -> Vec<Pubkey> {
+        let mut chain = Vec::new();
+        let mut next = self.batch().latest_quality_test;
+        while let Some(key) = next {
+            chain.push(key);
+            let test: QualityTest = self.fixture.svm.account(&key).unwrap();
+            if test.sample_date <= sample_date {
+                break;
+            }
+            next = test.previous_test;
+        }
+        chain
+    }
+
+    fn invalidate(
+        &mut self,
+        test_seed: &str,
+        previous_tests: &[Pubkey],
+    ) -> rice_supply_svm::TransactionResult {
+        let instruction = instructions::invalidate_quality_test(
+            &self.authority,
+            &self.laboratory,
+            &self.batch,
+            test_seed,
+            previous_tests,
+        );
+        self.fixture.send(instruction, &[self.authority])
+    }
+
+    fn batch(&self) -> RiceBatch {
+        self.fixture.svm.account(&self.batch).unwrap()
+    }
+}
+
+#[test]
+fn record_quality_test_sets_the_effective_grade() {
+    let mut lab = lab();
+    lab.record(test_args("test-1", QualityGrade::Grade2))
+        .unwrap();
+    lab.record(test_args("test-2", QualityGrade::Premium))
+        .unwrap();
+
+    let first = pda::quality_test("test-1").0;
+    let second = pda::quality_test("test-2").0;
+    let test: QualityTest = lab.fixture.svm.account(&second).unwrap();
+    assert_eq!(test.laboratory, lab.laboratory);
+    assert_eq!(test.previous_test, Some(first));
+    assert!(test.is_valid);
+    assert!(!test.residue_results[0].exceeds_mrl());
+
+    let batch = lab.batch();
+    assert!(batch.effective_grade == Some(QualityGrade::Premium));
+    assert_eq!(batch.latest_quality_test, Some(second));
+}
+
+#[test]
+fn record_quality_test_grades_by_the_newest_sample() {
+    let mut lab = lab();
+    let sampled = |test_seed: &str, overall_grade, sample_date| args::RecordQualityTest {
+        sample_date,
+        ..test_args(test_seed, overall_grade)
+    };
+    lab.record(sampled("test-1", QualityGrade::Grade1, 1_699_000_000))
+        .unwrap();
+    lab.record(sampled("test-2", QualityGrade::Grade3, 1_699_990_000))
+        .unwrap();
+
+    // A late report of an earlier sample joins the chain between them.
+    let late = sampled("test-3", QualityGrade::Premium, 1_699_500_000);
+    let instruction =
+        instructions::record_quality_test(&lab.authority, &lab.laboratory, &lab.batch, &[], late);
+    assert_error(
+        lab.fixture.send(instruction, &[lab.authority]),
+        ErrorCode::MissingPreviousTest,
+    );
+    lab.record(sampled("test-3", QualityGrade::Premium, 1_699_500_000))
+        .unwrap();
+
+    let (first, second, third) = (
+        pda::quality_test("test-1").0,
+        pda::quality_test("test-2").0,
+        pda::quality_test("test-3").0,
+    );
+    let batch = lab.batch();
+    assert!(batch.effective_grade == Some(QualityGrade::Grade3));
+    assert_eq!(batch.latest_quality_test, Some(second));
+    let previous_test = |test: Pubkey| {
+        lab.fixture
+            .svm
+            .account::<QualityTest>(&test)
+            .unwrap()
+            .previous_test
+    };
+    assert_eq!(previous_test(second), Some(third));
+    assert_eq!(previous_test(third), Some(first));
+
+    // Invalidating the newest falls back to the next newest sample.
+    lab.invalidate("test-2", &[third, first]).unwrap();
+    assert!(lab.batch().effective_grade == Some(QualityGrade::Premium));
+}
+
+#[test]
+fn residue_results_flag_readings_above_the_mrl() {
+    let mut lab = lab();
+    let residue = |analyte: &str, measured_ug_per_kg, mrl_ug_per_kg| ResidueResult {
+        analyte: analyte.to_string(),
+        measured_ug_per_kg,
+        mrl_ug_per_kg,
+    };
+    let mut args = test_args("test-1", QualityGrade::OffGrade);
+    args.residue_results = vec![
+        residue("chlorpyrifos", 501, Some(500)),
+        residue("cypermethrin", 500, Some(500)),
+        residue("tricyclazole", 9_000, None),
+    ];
+    lab.record(args).unwrap();
+
+    let test: QualityTest = lab
+        .fixture
+        .svm
+        .account(&pda::quality_test("test-1").0)
+        .unwrap();
+    assert_eq!(test.rice_batch, lab.batch);
+    assert_eq!(test.moisture_bp, 1_350);
+    assert_eq!(test.report_hash, [3; 32]);
+    let exceeded: Vec<_> = test
+        .residue_results
+        .iter()
+        .map(ResidueResult::exceeds_mrl)
+        .collect();
+    assert_eq!(exceeded, [true, false, false]);
+}
+
+#[test]
+fn record_quality_test_validates_its_arguments() {
+    let mut lab = lab();
+    let mut args = test_args("test-1", QualityGrade::Grade1);
+    args.sample_date = lab.fixture.svm.clock().unix_timestamp + 1;
+    assert_error(lab.record(args), ErrorCode::TimestampInFuture);
+
+    let mut args = test_args("test-1", QualityGrade::Grade1);
+    args.chalkiness_bp = 10_001;
+    assert_error(lab.record(args), ErrorCode::InvalidPercentage);
+
+    let mut args = test_args("test-1", QualityGrade::Grade1);
+    args.residue_results[0].analyte = "a".repeat(ResidueResult::MAX_ANALYTE_LEN + 1);
+    assert_error(lab.record(args), ErrorCode::StringTooLong);
+
+    let mut args = test_args("test-1", QualityGrade::Grade1);
+    args.residue_results =
+        vec![args.residue_results[0].clone(); QualityTest::MAX_RESIDUE_RESULTS + 1];
+    assert_error(lab.record(args), ErrorCode::TooManyEntries);
+}
+
+#[test]
+fn record_quality_test_requires_an_accredited_laboratory() {
+    let mut lab = lab();
+    let stranger = lab.fixture.wallet();
+    let instruction = instructions::record_quality_test(
+        &stranger,
+        &lab.laboratory,
+        &lab.batch,
+        &[],
+        test_args("test-1", QualityGrade::Grade1),
+    );
+    assert_error(
+        lab.fixture.send(instruction, &[stranger]),
+        ErrorCode::Unauthorized,
+    );
+
+    let admin = lab.fixture.admin;
+    lab.fixture
+        .send(
+            instructions::revoke_accreditation(
+                &admin,
+                &lab.laboratory,
+                AccreditationKind::Laboratory,
+            ),
+            &[admin],
+        )
+        .unwrap();
+    assert_error(
+        lab.record(test_args("test-1", QualityGrade::Grade1)),
+        ErrorCode::AccreditationInactive,
+    );
+}
+
+#[test]
+fn invalidate_quality_test_falls_back_to_the_previous_valid_test() {
+    let mut lab = lab();
+    lab.record(test_args("test-1", QualityGrade::Grade3))
+        .unwrap();
+    lab.record(test_args("test-2", QualityGrade::Grade1))
+        .unwrap();
+    lab.record(test_args("test-3", QualityGrade::Premium))
+        .unwrap();
+    let first = pda::quality_test("test-1").0;
+    let second = pda::quality_test("test-2").0;
+
+    // An older test is invalidated without touching the batch.
+    lab.invalidate("test-2", &[]).unwrap();
+    assert!(lab.batch().effective_grade == Some(QualityGrade::Premium));
+
+    assert_error(
+        lab.invalidate("test-3", &[]),
+        ErrorCode::MissingPreviousTest,
+    );
+    assert_error(
+        lab.invalidate("test-3", &[first, second]),
+        ErrorCode::MissingPreviousTest,
+    );
+    lab.invalidate("test-3", &[second, first]).unwrap();
+
+    let batch = lab.batch();
+    assert!(batch.effective_grade == Some(QualityGrade::Grade3));
+    assert_eq!(batch.latest_quality_test, Some(first));
+
+    assert_error(
+        lab.invalidate("test-3", &[]),
+        ErrorCode::QualityTestInvalidated,
+    );
+    lab.invalidate("test-1", &[]).unwrap();
+    let batch = lab.batch();
+    assert!(batch.effective_grade.is_none());
+    assert_eq!(batch.latest_quality_test, None);
+}
+
+#[test]
+fn invalidate_quality_test_is_laboratory_only() {
+    let mut lab = lab();
+    lab.record(test_args("test-1", QualityGrade::Grade1))
+        .unwrap();
+    let (other_authority, other_laboratory) = lab
+        .fixture
+        .accredited("lab-2", AccreditationKind::Laboratory);
+    let instruction = instructions::invalidate_quality_test(
+        &other_authority,
+        &other_laboratory,
+        &lab.batch,
+        "test-1",
+        &[],
+    );
+    assert_error(
+        lab.fixture.send(instruction, &[other_authority]),
+        ErrorCode::Unauthorized,
+    );
+}
@@ -0,0 +1,269 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hashv;
+use common::{assert_error, Fixture};
+use rice_supply_chain::{
+    AccreditationKind, ErrorCode, IssuerKey, Organization, RetailPackRun, RiceBatch,
+};
+use rice_supply_client::instructions;
+use rice_supply_client::pda;
+
+#[test]
+fn create_retail_packs_assigns_consecutive_serials() {
+    let mut fixture = Fixture::new();
+    let (authority, packer) = fixture.actor("miller-1", Organization::Miller);
+    let batch = fixture.batch_created_by("batch-1", 100, &authority);
+
+    fixture
+        .send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 0, 25_000, 2),
+            &[authority],
+        )
+        .unwrap();
+    fixture
+        .send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 2, 5_000, 10),
+            &[authority],
+        )
+        .unwrap();
+
+    let second: RetailPackRun = fixture
+        .svm
+        .account(&pda::retail_pack_run(&batch, 2).0)
+        .unwrap();
+    assert_eq!(second.packer, packer);
+    assert_eq!(second.first_serial, 2);
+    assert!(second.contains(11));
+    assert!(!second.contains(12));
+    assert_ne!(second.pack_code(2), second.pack_code(3));
+
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert_eq!(rice_batch.next_pack_serial, 12);
+    assert_eq!(rice_batch.remaining_weight_g, 0);
+    assert_eq!(rice_batch.packed_weight_g(), 100_000);
+
+    assert_error(
+        fixture.send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 12, 1, 1),
+            &[authority],
+        ),
+        ErrorCode::InsufficientQuantity,
+    );
+}
+
+#[test]
+fn one_run_describes_every_pack_code() {
+    let mut fixture = Fixture::new();
+    let (authority, packer) = fixture.actor("miller-1", Organization::Miller);
+    let batch = fixture.batch_created_by("batch-1", 1_000, &authority);
+
+    // Weights are multiplied without overflowing.
+    assert_error(
+        fixture.send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 0, u32::MAX, u32::MAX),
+            &[authority],
+        ),
+        ErrorCode::InsufficientQuantity,
+    );
+    fixture
+        .send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 0, 1_000, 1_000),
+            &[authority],
+        )
+        .unwrap();
+
+    let run: RetailPackRun = fixture
+        .svm
+        .account(&pda::retail_pack_run(&batch, 0).0)
+        .unwrap();
+    assert!(run.contains(0) && run.contains(999) && !run.contains(1_000));
+    for serial in [0, 499, 999] {
+        let hash = hashv(&[batch.as_ref(), &u64::to_le_bytes(serial)]);
+        assert_eq!(run.pack_code(serial), hash.to_bytes()[..16]);
+    }
+}
+
+#[test]
+fn create_retail_packs_validates_the_run() {
+    let mut fixture = Fixture::new();
+    let (authority, packer) = fixture.actor("miller-1", Organization::Miller);
+    let batch = fixture.batch_created_by("batch-1", 100, &authority);
+
+    for (pack_size_g, pack_count) in [(0, 10), (1_000, 0)] {
+        assert_error(
+            fixture.send(
+                instructions::create_retail_packs(
+                    &authority,
+                    &packer,
+                    &batch,
+                    0,
+                    pack_size_g,
+                    pack_count,
+                ),
+                &[authority],
+            ),
+            ErrorCode::InvalidQuantity,
+        );
+    }
+
+    let stranger = fixture.wallet();
+    assert_error(
+        fixture.send(
+            instructions::create_retail_packs(&stranger, &packer, &batch, 0, 1_000, 1),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    fixture
+        .send(
+            instructions::delete_rice_batch(&authority, None, "batch-1", "QR-batch-1"),
+            &[authority],
+        )
+        .unwrap();
+    assert_error(
+        fixture.send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 0, 1_000, 1),
+            &[authority],
+        ),
+        ErrorCode::InvalidStatusTransition,
+    );
+}
+
+#[test]
+fn create_retail_packs_rejects_a_stale_serial() {
+    let mut fixture = Fixture::new();
+    let (authority, packer) = fixture.actor("miller-1", Organization::Miller);
+    let batch = fixture.batch_created_by("batch-1", 100, &authority);
+    fixture
+        .send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 0, 1_000, 5),
+            &[authority],
+        )
+        .unwrap();
+
+    let mut instruction =
+        instructions::create_retail_packs(&authority, &packer, &batch, 5, 1_000, 5);
+    instruction.accounts[0].pubkey = pda::retail_pack_run(&batch, 0).0;
+    assert!(fixture.send(instruction, &[authority]).is_err());
+}
+
+#[test]
+fn create_retail_packs_requires_the_batch_holder() {
+    let mut fixture = Fixture::new();
+    let (authority, packer) = fixture.actor("miller-1", Organization::Miller);
+    let batch = fixture.batch_created_by("batch-1", 100, &authority);
+    let (retailer_authority, retailer) = fixture.actor("retailer-1", Organization::Retailer);
+
+    assert_error(
+        fixture.send(
+            instructions::create_retail_packs(&retailer_authority, &retailer, &batch, 0, 1_000, 1),
+            &[retailer_authority],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+
+    fixture.hand_over("tx-1", batch, authority, packer, retailer);
+    assert_error(
+        fixture.send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 0, 1_000, 1),
+            &[authority],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+    fixture
+        .send(
+            instructions::create_retail_packs(&retailer_authority, &retailer, &batch, 0, 1_000, 1),
+            &[retailer_authority],
+        )
+        .unwrap();
+}
+
+#[test]
+fn issuing_keys_are_registered_by_accredited_millers() {
+    let mut fixture = Fixture::new();
+    let (authority, miller) = fixture.accredited("miller-1", AccreditationKind::Miller);
+    let issuing_key = Pubkey::new_unique();
+    fixture
+        .send(
+            instructions::register_issuing_key(&authority, &miller, issuing_key),
+            &[authority],
+        )
+        .unwrap();
+
+    let address = pda::issuer_key(&miller, &issuing_key).0;
+    let issuer_key: IssuerKey = fixture.svm.account(&address).unwrap();
+    assert_eq!(issuer_key.miller, miller);
+    assert_eq!(issuer_key.issuing_key, issuing_key);
+    assert!(issuer_key.is_active);
+
+    // Declaring the miller organization is not enough.
+    let (other_authority, other) = fixture.actor("miller-2", Organization::Miller);
+    assert_error(
+        fixture.send(
+            instructions::register_issuing_key(&other_authority, &other, issuing_key),
+            &[other_authority],
+        ),
+        AnchorError::AccountNotInitialized,
+    );
+    let stranger = fixture.wallet();
+    assert_error(
+        fixture.send(
+            instructions::register_issuing_key(&stranger, &miller, Pubkey::new_unique()),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    let admin = fixture.admin;
+    fixture
+        .send(
+            instructions::revoke_accreditation(&admin, &miller, AccreditationKind::Miller),
+            &[admin],
+        )
+        .unwrap();
+    assert_error(
+        fixture.send(
+            instructions::register_issuing_key(&authority, &miller, Pubkey::new_unique()),
+            &[authority],
+        ),
+        ErrorCode::NotMiller,
+    );
+}
+
+#[test]
+fn revoke_issuing_key_is_miller_only() {
+    let mut fixture = Fixture::new();
+    let (authority, miller) = fixture.accredited("miller-1", AccreditationKind::Miller);
+    let (other_authority, other_miller) = fixture.accredited("miller-2", AccreditationKind::Miller);
+    let issuing_key = Pubkey::new_unique();
+    fixture
+        .send(
+            instructions::register_issuing_key(&authority, &miller, issuing_key),
+            &[authority],
+        )
+        .unwrap();
+
+    // The other miller's actor does not own the key.
+    let mut instruction =
+        instructions::revoke_issuing_key(&other_authority, &other_miller, &issuing_key);
+    instruction.accounts[0].pubkey = pda::issuer_key(&miller, &issuing_key).0;
+    assert_error(
+        fixture.send(instruction, &[other_authority]),
+        ErrorCode::Unauthorized,
+    );
+
+    fixture
+        .send(
+            instructions::revoke_issuing_key(&authority, &miller, &issuing_key),
+            &[authority],
+        )
+        .unwrap();
+    let issuer_key: IssuerKey = fixture
+        .svm
+        .account(&pda::issuer_key(&miller, &issuing_key).0)
+        .unwrap();
+    assert!(!issuer_key.is_active);
+}
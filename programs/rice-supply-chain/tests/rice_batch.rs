@@ -0,0 +1,933 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use common::{assert_error, batch_args, milled_rice_args, season_args, Fixture};
+use rice_supply_chain::{
+    BatchStatus, ByProducts, ErrorCode, Gs1Key, MilledRice, MillingType, Organization,
+    ProductionSeason, QrIndex, QualityGrade, RiceBatch,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+use rice_supply_svm::ExecutionError;
+
+fn update_milled_rice_args(milling_seed: &str) -> args::UpdateMilledRice {
+    args::UpdateMilledRice {
+        milling_seed: milling_seed.to_string(),
+        total_weight_kg: None,
+        milling_type: None,
+        quality: None,
+        photo_urls: None,
+        moisture: None,
+        total_weight_processed_kg: None,
+        by_products: None,
+    }
+}
+
+fn update_batch_args(batch_seed: &str) -> args::UpdateRiceBatch {
+    args::UpdateRiceBatch {
+        batch_seed: batch_seed.to_string(),
+        batch_status: None,
+        quality_score: None,
+        weight_kg: None,
+    }
+}
+
+#[test]
+fn create_milled_rice_records_recovery() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture.mill(&user, milled_rice_args("mill-1")).unwrap();
+
+    let milled_rice: MilledRice = fixture.svm.account(&pda::milled_rice("mill-1").0).unwrap();
+    assert_eq!(milled_rice.creator, user);
+    assert_eq!(milled_rice.total_weight_kg, 650);
+    assert_eq!(milled_rice.by_products.total_kg(), 350);
+    assert_eq!(milled_rice.milling_recovery_bp, 6_500);
+}
+
+#[test]
+fn create_milled_rice_rejects_output_above_input() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let mut args = milled_rice_args("mill-1");
+    args.by_products.husk_kg = 400;
+    assert_error(fixture.mill(&user, args), ErrorCode::WeightIncrease);
+}
+
+#[test]
+fn milling_outputs_cannot_exceed_the_input() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    // By-products far above any input do not wrap around.
+    let mut args = milled_rice_args("mill-1");
+    args.by_products.husk_kg = u32::MAX;
+    args.by_products.bran_kg = u32::MAX;
+    assert_error(fixture.mill(&user, args), ErrorCode::WeightIncrease);
+
+    // A run with nothing milled has no recovery.
+    let mut args = milled_rice_args("mill-2");
+    args.total_weight_kg = 0;
+    args.total_weight_processed_kg = 0;
+    args.by_products = ByProducts::default();
+    fixture.mill(&user, args).unwrap();
+    let milled_rice: MilledRice = fixture.svm.account(&pda::milled_rice("mill-2").0).unwrap();
+    assert_eq!(milled_rice.milling_recovery_bp, 0);
+
+    // Corrected by-products are held to the same limit.
+    fixture.mill(&user, milled_rice_args("mill-3")).unwrap();
+    let season = pda::production_season("mill-3-season").0;
+    let mut args = update_milled_rice_args("mill-3");
+    args.by_products = Some(ByProducts {
+        brewers_rice_kg: 21,
+        ..milled_rice_args("mill-3").by_products
+    });
+    assert_error(
+        fixture.send(
+            instructions::update_milled_rice(&user, &season, args),
+            &[user],
+        ),
+        ErrorCode::WeightIncrease,
+    );
+    let milled_rice: MilledRice = fixture.svm.account(&pda::milled_rice("mill-3").0).unwrap();
+    assert_eq!(milled_rice.by_products.brewers_rice_kg, 20);
+}
+
+#[test]
+fn create_milled_rice_rejects_unknown_type_and_grade_codes() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let (farmer_authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let mut args = milled_rice_args("mill-1");
+    args.farmer_id = farmer;
+    let season = fixture.farmer_season("season", farmer, Some(1_000));
+    let instruction = instructions::create_milled_rice(&user, &season, &farmer_authority, args);
+    // Discriminator, seed, `farmer_id` and `total_weight_kg` come first.
+    let milling_type = 8 + 4 + "mill-1".len() + 32 + 4;
+    for at in [milling_type, milling_type + 1] {
+        let mut instruction = instruction.clone();
+        instruction.data[at] = 9;
+        assert_error(
+            fixture.send(instruction, &[user, farmer_authority]),
+            AnchorError::InstructionDidNotDeserialize,
+        );
+    }
+    fixture
+        .send(instruction, &[user, farmer_authority])
+        .unwrap();
+}
+
+#[test]
+fn create_milled_rice_requires_a_harvested_season_of_the_farmer() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let (farmer_authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let args = || args::CreateMilledRice {
+        farmer_id: farmer,
+        ..milled_rice_args("mill-1")
+    };
+    let other_season = fixture.season("other-season", Some(1_000));
+    assert_error(
+        fixture.send(
+            instructions::create_milled_rice(&user, &other_season, &farmer_authority, args()),
+            &[user, farmer_authority],
+        ),
+        ErrorCode::SeasonFarmerMismatch,
+    );
+
+    let mut season = season_args("season");
+    season.farmer_id = farmer;
+    season.harvest_date = Some(fixture.svm.clock().unix_timestamp + 86_400);
+    fixture
+        .send(
+            instructions::create_production_season(&user, season),
+            &[user],
+        )
+        .unwrap();
+    assert_error(
+        fixture.send(
+            instructions::create_milled_rice(
+                &user,
+                &pda::production_season("season").0,
+                &farmer_authority,
+                args(),
+            ),
+            &[user, farmer_authority],
+        ),
+        ErrorCode::BeforeHarvest,
+    );
+}
+
+#[test]
+fn create_milled_rice_requires_the_farmer() {
+    let mut fixture = Fixture::new();
+    let miller = fixture.wallet();
+    let (farmer_authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let season = fixture.farmer_season("season", farmer, Some(1_000));
+    let args = || args::CreateMilledRice {
+        farmer_id: farmer,
+        ..milled_rice_args("mill-1")
+    };
+
+    // A stranger cannot stand in for the farmer, nor mill without them.
+    let stranger = fixture.wallet();
+    assert_error(
+        fixture.send(
+            instructions::create_milled_rice(&miller, &season, &stranger, args()),
+            &[miller, stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    let result = fixture.send(
+        instructions::create_milled_rice(&miller, &season, &farmer_authority, args()),
+        &[miller],
+    );
+    assert_eq!(
+        result.unwrap_err().error,
+        ExecutionError::MissingSignature(farmer_authority)
+    );
+    let production_season: ProductionSeason = fixture.svm.account(&season).unwrap();
+    assert_eq!(production_season.available_for_milling_kg(), 1_000);
+
+    fixture
+        .send(
+            instructions::create_milled_rice(&miller, &season, &farmer_authority, args()),
+            &[miller, farmer_authority],
+        )
+        .unwrap();
+    let milled_rice: MilledRice = fixture.svm.account(&pda::milled_rice("mill-1").0).unwrap();
+    assert_eq!(milled_rice.creator, miller);
+}
+
+#[test]
+fn update_milled_rice_recomputes_recovery() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture.mill(&user, milled_rice_args("mill-1")).unwrap();
+    let season = pda::production_season("mill-1-season").0;
+
+    let mut args = update_milled_rice_args("mill-1");
+    args.total_weight_kg = Some(600);
+    args.milling_type = Some(MillingType::Brown);
+    args.quality = Some(QualityGrade::Premium);
+    args.by_products = Some(ByProducts::default());
+    fixture
+        .send(
+            instructions::update_milled_rice(&user, &season, args),
+            &[user],
+        )
+        .unwrap();
+    let milled_rice: MilledRice = fixture.svm.account(&pda::milled_rice("mill-1").0).unwrap();
+    assert_eq!(milled_rice.milling_recovery_bp, 6_000);
+    assert!(milled_rice.milling_type == MillingType::Brown);
+
+    let mut args = update_milled_rice_args("mill-1");
+    args.total_weight_processed_kg = Some(500);
+    assert_error(
+        fixture.send(
+            instructions::update_milled_rice(&user, &season, args),
+            &[user],
+        ),
+        ErrorCode::WeightIncrease,
+    );
+}
+
+#[test]
+fn update_milled_rice_requires_its_creator() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture.mill(&user, milled_rice_args("mill-1")).unwrap();
+    let season = pda::production_season("mill-1-season").0;
+    let stranger = fixture.wallet();
+
+    let mut args = update_milled_rice_args("mill-1");
+    args.total_weight_kg = Some(100);
+    assert_error(
+        fixture.send(
+            instructions::update_milled_rice(&stranger, &season, args),
+            &[stranger],
+        ),
+        ErrorCode::Unauthorized,
+    );
+    let result = fixture.send(
+        instructions::update_milled_rice(&user, &season, update_milled_rice_args("mill-1")),
+        &[],
+    );
+    assert_eq!(
+        result.unwrap_err().error,
+        ExecutionError::MissingSignature(user)
+    );
+    let milled_rice: MilledRice = fixture.svm.account(&pda::milled_rice("mill-1").0).unwrap();
+    assert_eq!(milled_rice.total_weight_kg, 650);
+}
+
+#[test]
+fn create_rice_batch_indexes_the_qr_code() {
+    let mut fixture = Fixture::new();
+    let batch = fixture.batch("batch-1", 1_000);
+
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert!(rice_batch.batch_status == BatchStatus::Created);
+    assert_eq!(rice_batch.qr_code, "QR-batch-1");
+    assert_eq!(rice_batch.remaining_weight_g, 1_000_000);
+    assert_eq!(rice_batch.storage_count, 0);
+
+    let qr_index: QrIndex = fixture.svm.account(&pda::qr_index("QR-batch-1").0).unwrap();
+    assert_eq!(qr_index.rice_batch, batch);
+    assert_eq!(qr_index.payload_hash, QrIndex::payload_hash("QR-batch-1"));
+}
+
+#[test]
+fn create_rice_batch_requires_the_creators_milling() {
+    let mut fixture = Fixture::new();
+    let miller = fixture.wallet();
+    let milled_rice = fixture.milling(&miller);
+    let user = fixture.wallet();
+    assert_error(
+        fixture.send(
+            instructions::create_rice_batch(
+                &user,
+                batch_args(&milled_rice, "batch-1", 500, "QR-batch-1"),
+            ),
+            &[user],
+        ),
+        ErrorCode::Unauthorized,
+    );
+
+    // An account that is not a milling run is refused as well.
+    let season = pda::production_season(&format!("mill-{}-season", &miller.to_string()[..16])).0;
+    assert!(fixture
+        .send(
+            instructions::create_rice_batch(
+                &user,
+                batch_args(&season, "batch-1", 500, "QR-batch-1")
+            ),
+            &[user],
+        )
+        .is_err());
+    assert!(fixture
+        .svm
+        .get_account(&pda::rice_batch("batch-1").0)
+        .is_none());
+}
+
+#[test]
+fn create_rice_batch_starts_every_batch_created() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let milled_rice = fixture.milling(&user);
+    for batch_status in [
+        BatchStatus::InTransit,
+        BatchStatus::Delivered,
+        BatchStatus::Consumed,
+    ] {
+        let mut args = batch_args(&milled_rice, "batch-1", 500, "QR-batch-1");
+        args.batch_status = batch_status;
+        assert_error(
+            fixture.send(instructions::create_rice_batch(&user, args), &[user]),
+            ErrorCode::InvalidStatusTransition,
+        );
+    }
+    let batch = fixture.batch_created_by("batch-1", 500, &user);
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert!(rice_batch.batch_status == BatchStatus::Created);
+}
+
+#[test]
+fn rice_batches_cannot_exceed_the_milled_output() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture.mill(&user, milled_rice_args("mill-1")).unwrap();
+    let milled_rice = pda::milled_rice("mill-1").0;
+    let season = pda::production_season("mill-1-season").0;
+    let create = |batch_seed: &str, weight_kg| {
+        let qr_code = format!("QR-{batch_seed}");
+        instructions::create_rice_batch(
+            &user,
+            batch_args(&milled_rice, batch_seed, weight_kg, &qr_code),
+        )
+    };
+
+    // 650 kg of output: a 400 kg batch leaves 250 kg.
+    fixture.send(create("batch-1", 400), &[user]).unwrap();
+    assert_error(
+        fixture.send(create("batch-2", 251), &[user]),
+        ErrorCode::InsufficientQuantity,
+    );
+    fixture.send(create("batch-2", 250), &[user]).unwrap();
+    let milling: MilledRice = fixture.svm.account(&milled_rice).unwrap();
+    assert_eq!(milling.allocated_kg, 650);
+
+    // Neither a heavier batch nor a smaller output frees the run up.
+    let mut args = update_batch_args("batch-1");
+    args.weight_kg = Some(401);
+    assert_error(
+        fixture.send(
+            instructions::update_rice_batch(&user, None, &milled_rice, args),
+            &[user],
+        ),
+        ErrorCode::InsufficientQuantity,
+    );
+    let mut args = update_milled_rice_args("mill-1");
+    args.total_weight_kg = Some(649);
+    assert_error(
+        fixture.send(
+            instructions::update_milled_rice(&user, &season, args),
+            &[user],
+        ),
+        ErrorCode::InsufficientQuantity,
+    );
+
+    // Lightening a batch returns its weight to the run.
+    let mut args = update_batch_args("batch-1");
+    args.weight_kg = Some(300);
+    fixture
+        .send(
+            instructions::update_rice_batch(&user, None, &milled_rice, args),
+            &[user],
+        )
+        .unwrap();
+    let milling: MilledRice = fixture.svm.account(&milled_rice).unwrap();
+    assert_eq!(milling.allocated_kg, 550);
+}
+
+#[test]
+fn create_rice_batch_rejects_a_qr_code_in_use() {
+    let mut fixture = Fixture::new();
+    fixture.batch("batch-1", 1_000);
+    let user = fixture.wallet();
+    let milled_rice = fixture.milling(&user);
+    let result = fixture.send(
+        instructions::create_rice_batch(
+            &user,
+            batch_args(&milled_rice, "batch-2", 500, "QR-batch-1"),
+        ),
+        &[user],
+    );
+    assert!(result.is_err());
+    assert!(fixture
+        .svm
+        .get_account(&pda::rice_batch("batch-2").0)
+        .is_none());
+}
+
+#[test]
+fn create_rice_batch_rejects_an_oversize_qr_code() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let milled_rice = fixture.milling(&user);
+    let qr_code = "q".repeat(RiceBatch::MAX_QR_CODE_LEN + 1);
+    assert_error(
+        fixture.send(
+            instructions::create_rice_batch(
+                &user,
+                batch_args(&milled_rice, "batch-1", 500, &qr_code),
+            ),
+            &[user],
+        ),
+        ErrorCode::StringTooLong,
+    );
+}
+
+#[test]
+fn create_rice_batch_parses_a_digital_link() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let milled_rice = fixture.milling(&user);
+    let qr_code = "https://rice.example.ph/01/4800016644504/10/LOT%2F24-A?linkType=all";
+    fixture
+        .send(
+            instructions::create_rice_batch(
+                &user,
+                batch_args(&milled_rice, "batch-1", 500, qr_code),
+            ),
+            &[user],
+        )
+        .unwrap();
+
+    let batch = pda::rice_batch("batch-1").0;
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    let gs1 = rice_batch.gs1.unwrap();
+    assert_eq!(gs1.gtin, 4_800_016_644_504);
+    assert_eq!(gs1.lot, "LOT/24-A");
+    assert_eq!(gs1.serial, None);
+
+    // The index is keyed by the canonical link, whatever resolver was printed.
+    let canonical = "https://id.gs1.org/01/04800016644504/10/LOT%2F24-A";
+    assert_eq!(gs1.canonical_uri(), canonical);
+    let qr_index: QrIndex = fixture.svm.account(&pda::qr_index(canonical).0).unwrap();
+    assert_eq!(qr_index.rice_batch, batch);
+    assert_eq!(pda::qr_index(qr_code), pda::qr_index(canonical));
+
+    let result = fixture.send(
+        instructions::create_rice_batch(&user, batch_args(&milled_rice, "batch-2", 500, canonical)),
+        &[user],
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn create_rice_batch_rejects_a_malformed_digital_link() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let milled_rice = fixture.milling(&user);
+    for qr_code in [
+        // Bad check digit.
+        "https://id.gs1.org/01/04800016644505/10/LOT1",
+        // No lot.
+        "https://id.gs1.org/01/04800016644504",
+        "https://id.gs1.org/01/04800016644504/21/SERIAL1",
+        // Lot longer than 20 characters.
+        "https://id.gs1.org/01/04800016644504/10/ABCDEFGHIJKLMNOPQRSTU",
+        // Outside the GS1 character set.
+        "https://id.gs1.org/01/04800016644504/10/LOT%20ONE",
+    ] {
+        assert_error(
+            fixture.send(
+                instructions::create_rice_batch(
+                    &user,
+                    batch_args(&milled_rice, "batch-1", 500, qr_code),
+                ),
+                &[user],
+            ),
+            ErrorCode::InvalidDigitalLink,
+        );
+    }
+
+    fixture
+        .send(
+            instructions::create_rice_batch(
+                &user,
+                batch_args(
+                    &milled_rice,
+                    "batch-1",
+                    500,
+                    "https://rice.example.ph/batches/1",
+                ),
+            ),
+            &[user],
+        )
+        .unwrap();
+    let rice_batch: RiceBatch = fixture.svm.account(&pda::rice_batch("batch-1").0).unwrap();
+    assert!(rice_batch.gs1.is_none());
+}
+
+#[test]
+fn create_rice_batch_keeps_urls_without_a_gtin_opaque() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let milled_rice = fixture.milling(&user);
+    for (batch_seed, qr_code) in [
+        ("batch-1", "https://x.com/2024/01/15"),
+        ("batch-2", "https://rice.example.ph/01/LOT1/10/LOT1"),
+        ("batch-3", "https://rice.example.ph/stores/01/2024-05"),
+    ] {
+        fixture
+            .send(
+                instructions::create_rice_batch(
+                    &user,
+                    batch_args(&milled_rice, batch_seed, 500, qr_code),
+                ),
+                &[user],
+            )
+            .unwrap();
+        let rice_batch: RiceBatch = fixture.svm.account(&pda::rice_batch(batch_seed).0).unwrap();
+        assert!(rice_batch.gs1.is_none());
+        assert_eq!(rice_batch.qr_code, qr_code);
+    }
+}
+
+#[test]
+fn update_rice_batch_keeps_packed_weight() {
+    let mut fixture = Fixture::new();
+    let (authority, packer) = fixture.actor("miller-1", Organization::Miller);
+    let batch = fixture.batch_created_by("batch-1", 100, &authority);
+    let milled_rice = fixture.milling(&authority);
+    fixture
+        .send(
+            instructions::create_retail_packs(&authority, &packer, &batch, 0, 5_000, 10),
+            &[authority],
+        )
+        .unwrap();
+
+    let mut args = update_batch_args("batch-1");
+    args.weight_kg = Some(80);
+    args.quality_score = Some(75);
+    fixture
+        .send(
+            instructions::update_rice_batch(&authority, None, &milled_rice, args),
+            &[authority],
+        )
+        .unwrap();
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert_eq!(rice_batch.weight_kg, 80);
+    assert_eq!(rice_batch.quality_score, 75);
+    assert_eq!(rice_batch.remaining_weight_g, 30_000);
+
+    let mut args = update_batch_args("batch-1");
+    args.weight_kg = Some(49);
+    assert_error(
+        fixture.send(
+            instructions::update_rice_batch(&authority, None, &milled_rice, args),
+            &[authority],
+        ),
+        ErrorCode::InsufficientQuantity,
+    );
+}
+
+#[test]
+fn set_rice_batch_qr_code_moves_the_index() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let batch = fixture.batch_created_by("batch-1", 1_000, &user);
+    let old_index = pda::qr_index("QR-batch-1").0;
+    let old_index_lamports = fixture.svm.balance(&old_index);
+    let balance = fixture.svm.balance(&user);
+
+    fixture
+        .send(
+            instructions::set_rice_batch_qr_code(
+                &user,
+                None,
+                "batch-1",
+                "QR-batch-1",
+                "QR-relabel".to_string(),
+            ),
+            &[user],
+        )
+        .unwrap();
+
+    assert!(fixture.svm.get_account(&old_index).is_none());
+    let new_index = pda::qr_index("QR-relabel").0;
+    let qr_index: QrIndex = fixture.svm.account(&new_index).unwrap();
+    assert_eq!(qr_index.rice_batch, batch);
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert_eq!(rice_batch.qr_code, "QR-relabel");
+    // Same-size index accounts: the closed one's rent pays for the new one.
+    assert_eq!(fixture.svm.balance(&new_index), old_index_lamports);
+    assert_eq!(fixture.svm.balance(&user), balance);
+}
+
+#[test]
+fn a_released_qr_code_can_label_another_batch() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture.batch_created_by("batch-1", 1_000, &user);
+    fixture
+        .send(
+            instructions::set_rice_batch_qr_code(
+                &user,
+                None,
+                "batch-1",
+                "QR-batch-1",
+                "QR-relabel".to_string(),
+            ),
+            &[user],
+        )
+        .unwrap();
+
+    let milled_rice = fixture.milling(&user);
+    fixture
+        .send(
+            instructions::create_rice_batch(
+                &user,
+                batch_args(&milled_rice, "batch-2", 500, "QR-batch-1"),
+            ),
+            &[user],
+        )
+        .unwrap();
+
+    // A scanned code resolves with one derivation.
+    let lookup = |fixture: &Fixture, qr_code: &str| {
+        let qr_index: QrIndex = fixture.svm.account(&pda::qr_index(qr_code).0).unwrap();
+        qr_index.rice_batch
+    };
+    assert_eq!(lookup(&fixture, "QR-batch-1"), pda::rice_batch("batch-2").0);
+    assert_eq!(lookup(&fixture, "QR-relabel"), pda::rice_batch("batch-1").0);
+}
+
+#[test]
+fn set_rice_batch_qr_code_replaces_the_gs1_keys() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let batch = fixture.batch_created_by("batch-1", 1_000, &user);
+    let digital_link = "https://id.gs1.org/01/04800016644504/10/LOT1/21/0042";
+    fixture
+        .send(
+            instructions::set_rice_batch_qr_code(
+                &user,
+                None,
+                "batch-1",
+                "QR-batch-1",
+                digital_link.to_string(),
+            ),
+            &[user],
+        )
+        .unwrap();
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert!(
+        rice_batch.gs1
+            == Some(Gs1Key {
+                gtin: 4_800_016_644_504,
+                lot: "LOT1".to_string(),
+                serial: Some("0042".to_string()),
+            })
+    );
+
+    fixture
+        .send(
+            instructions::set_rice_batch_qr_code(
+                &user,
+                None,
+                "batch-1",
+                digital_link,
+                "QR-relabel".to_string(),
+            ),
+            &[user],
+        )
+        .unwrap();
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert!(rice_batch.gs1.is_none());
+}
+
+#[test]
+fn set_rice_batch_qr_code_rejects_a_stale_index() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture.batch_created_by("batch-1", 1_000, &user);
+    fixture.batch("batch-2", 1_000);
+    let result = fixture.send(
+        instructions::set_rice_batch_qr_code(
+            &user,
+            None,
+            "batch-1",
+            "QR-batch-2",
+            "QR-new".to_string(),
+        ),
+        &[user],
+    );
+    assert!(matches!(
+        result.unwrap_err().error,
+        ExecutionError::Program(_)
+    ));
+}
+
+#[test]
+fn set_rice_batch_qr_code_keeps_an_index_with_the_same_hash() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let batch = fixture.batch_created_by("batch-1", 1_000, &user);
+    let digital_link = "https://id.gs1.org/01/04800016644504/10/LOT1";
+    let set = |fixture: &mut Fixture, old: &str, new: &str| {
+        fixture.send(
+            instructions::set_rice_batch_qr_code(&user, None, "batch-1", old, new.to_string()),
+            &[user],
+        )
+    };
+    set(&mut fixture, "QR-batch-1", digital_link).unwrap();
+    let index = pda::qr_index(digital_link).0;
+    let before: QrIndex = fixture.svm.account(&index).unwrap();
+
+    // Re-setting the same code, or the same GS1 keys under another resolver
+    // domain, leaves the index entry alone.
+    set(&mut fixture, digital_link, digital_link).unwrap();
+    let moved = "https://rice.example.ph/01/04800016644504/10/LOT1";
+    set(&mut fixture, digital_link, moved).unwrap();
+
+    let qr_index: QrIndex = fixture.svm.account(&index).unwrap();
+    assert_eq!(qr_index.rice_batch, batch);
+    assert_eq!(qr_index.created_at, before.created_at);
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert_eq!(rice_batch.qr_code, moved);
+}
+
+#[test]
+fn set_rice_batch_qr_code_rejects_another_batchs_code() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture.batch_created_by("batch-1", 1_000, &user);
+    let other = fixture.batch("batch-2", 1_000);
+    assert_error(
+        fixture.send(
+            instructions::set_rice_batch_qr_code(
+                &user,
+                None,
+                "batch-1",
+                "QR-batch-1",
+                "QR-batch-2".to_string(),
+            ),
+            &[user],
+        ),
+        ErrorCode::QrCodeInUse,
+    );
+    let qr_index: QrIndex = fixture.svm.account(&pda::qr_index("QR-batch-2").0).unwrap();
+    assert_eq!(qr_index.rice_batch, other);
+    assert!(fixture
+        .svm
+        .get_account(&pda::qr_index("QR-batch-1").0)
+        .is_some());
+}
+
+#[test]
+fn set_rice_batch_qr_code_requires_the_batch_holder() {
+    let mut fixture = Fixture::new();
+    let (authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let batch = fixture.batch_created_by("batch-1", 1_000, &authority);
+    let stranger = fixture.wallet();
+    assert_error(
+        fixture.send(
+            instructions::set_rice_batch_qr_code(
+                &stranger,
+                None,
+                "batch-1",
+                "QR-batch-1",
+                "QR-forged".to_string(),
+            ),
+            &[stranger],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+
+    let (buyer_authority, buyer) = fixture.actor("miller-1", Organization::Miller);
+    fixture.hand_over("tx-1", batch, authority, farmer, buyer);
+    assert_error(
+        fixture.send(
+            instructions::set_rice_batch_qr_code(
+                &authority,
+                Some(&farmer),
+                "batch-1",
+                "QR-batch-1",
+                "QR-forged".to_string(),
+            ),
+            &[authority],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+    fixture
+        .send(
+            instructions::set_rice_batch_qr_code(
+                &buyer_authority,
+                Some(&buyer),
+                "batch-1",
+                "QR-batch-1",
+                "QR-relabel".to_string(),
+            ),
+            &[buyer_authority],
+        )
+        .unwrap();
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert_eq!(rice_batch.qr_code, "QR-relabel");
+}
+
+#[test]
+fn delete_rice_batch_consumes_the_batch() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    let batch = fixture.batch_created_by("batch-1", 1_000, &user);
+    fixture
+        .send(
+            instructions::delete_rice_batch(&user, None, "batch-1", "QR-batch-1"),
+            &[user],
+        )
+        .unwrap();
+
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert!(rice_batch.batch_status == BatchStatus::Consumed);
+    // Its index entry went with it, so it cannot be deleted twice.
+    assert_error(
+        fixture.send(
+            instructions::delete_rice_batch(&user, None, "batch-1", "QR-batch-1"),
+            &[user],
+        ),
+        AnchorError::AccountNotInitialized,
+    );
+}
+
+#[test]
+fn delete_rice_batch_releases_its_qr_code() {
+    let mut fixture = Fixture::new();
+    let user = fixture.wallet();
+    fixture.batch_created_by("batch-1", 100, &user);
+    fixture
+        .send(
+            instructions::delete_rice_batch(&user, None, "batch-1", "QR-batch-1"),
+            &[user],
+        )
+        .unwrap();
+    assert!(fixture
+        .svm
+        .get_account(&pda::qr_index("QR-batch-1").0)
+        .is_none());
+
+    let milled_rice = fixture.milling(&user);
+    fixture
+        .send(
+            instructions::create_rice_batch(
+                &user,
+                batch_args(&milled_rice, "batch-2", 100, "QR-batch-1"),
+            ),
+            &[user],
+        )
+        .unwrap();
+    let qr_index: QrIndex = fixture.svm.account(&pda::qr_index("QR-batch-1").0).unwrap();
+    assert_eq!(qr_index.rice_batch, pda::rice_batch("batch-2").0);
+}
+
+#[test]
+fn update_rice_batch_requires_the_batch_holder() {
+    let mut fixture = Fixture::new();
+    let (authority, farmer) = fixture.actor("farmer-1", Organization::Farmer);
+    let batch = fixture.batch_created_by("batch-1", 1_000, &authority);
+    let milled_rice = fixture.milling(&authority);
+    let stranger = fixture.wallet();
+
+    let mut args = update_batch_args("batch-1");
+    args.batch_status = Some(BatchStatus::Delivered);
+    assert_error(
+        fixture.send(
+            instructions::update_rice_batch(&stranger, None, &milled_rice, args),
+            &[stranger],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+    assert_error(
+        fixture.send(
+            instructions::delete_rice_batch(&stranger, None, "batch-1", "QR-batch-1"),
+            &[stranger],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+
+    // Created batches ship before they are delivered.
+    let mut args = update_batch_args("batch-1");
+    args.batch_status = Some(BatchStatus::Delivered);
+    assert_error(
+        fixture.send(
+            instructions::update_rice_batch(&authority, None, &milled_rice, args),
+            &[authority],
+        ),
+        ErrorCode::InvalidStatusTransition,
+    );
+
+    // Once handed over, only the new holder speaks for the batch.
+    let (buyer_authority, buyer) = fixture.actor("miller-1", Organization::Miller);
+    fixture.hand_over("tx-1", batch, authority, farmer, buyer);
+    let mut args = update_batch_args("batch-1");
+    args.quality_score = Some(60);
+    assert_error(
+        fixture.send(
+            instructions::update_rice_batch(&authority, Some(&farmer), &milled_rice, args),
+            &[authority],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+    let mut args = update_batch_args("batch-1");
+    args.quality_score = Some(60);
+    fixture
+        .send(
+            instructions::update_rice_batch(&buyer_authority, Some(&buyer), &milled_rice, args),
+            &[buyer_authority],
+        )
+        .unwrap();
+    let rice_batch: RiceBatch = fixture.svm.account(&batch).unwrap();
+    assert_eq!(rice_batch.quality_score, 60);
+}
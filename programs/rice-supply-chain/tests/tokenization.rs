@@ -0,0 +1,421 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::InstructionData;
+use anchor_spl::token_2022::spl_token_2022::extension::metadata_pointer::MetadataPointer;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Mint as SplMint;
+use anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use common::{assert_error, batch_args, Fixture};
+use rice_supply_chain::{BatchStatus, ErrorCode, Organization, TokenUnit};
+use rice_supply_client::accounts::RiceBatch;
+use rice_supply_client::instructions::{self, args};
+use rice_supply_client::pda;
+
+/// A registered actor and the wallet its batch tokens live in.
+#[derive(Clone, Copy)]
+struct Party {
+    authority: Pubkey,
+    actor: Pubkey,
+    wallet: Pubkey,
+}
+
+struct Tokens {
+    fixture: Fixture,
+    rice_batch: Pubkey,
+    /// Created the batch and sold it to the miller.
+    seller: Party,
+    miller: Party,
+}
+
+impl Tokens {
+    /// A 1,000 kg batch sold to a miller, both parties with a registered
+    /// token wallet.
+    fn new() -> Self {
+        let unregistered = Party {
+            authority: Pubkey::default(),
+            actor: Pubkey::default(),
+            wallet: Pubkey::default(),
+        };
+        let mut tokens = Self {
+            fixture: Fixture::new(),
+            rice_batch: pda::rice_batch("batch-1").0,
+            seller: unregistered,
+            miller: unregistered,
+        };
+        tokens.seller = tokens.party("seller", Organization::Farmer);
+        tokens.miller = tokens.party("miller", Organization::Miller);
+        let (seller, miller) = (tokens.seller, tokens.miller);
+        let milled_rice = tokens.fixture.milling(&seller.authority);
+        tokens
+            .fixture
+            .send(
+                instructions::create_rice_batch(
+                    &seller.authority,
+                    batch_args(&milled_rice, "batch-1", 1_000, "QR-batch-1"),
+                ),
+                &[seller.authority],
+            )
+            .unwrap();
+        tokens.fixture.hand_over(
+            "sale",
+            tokens.rice_batch,
+            seller.authority,
+            seller.actor,
+            miller.actor,
+        );
+        tokens
+    }
+
+    fn party(&mut self, actor_seed: &str, organization: Organization) -> Party {
+        let (authority, actor) = self.fixture.actor(actor_seed, organization);
+        let wallet = self.fixture.wallet();
+        self.fixture
+            .send(
+                instructions::register_custodian(&authority, &actor, &wallet),
+                &[authority, wallet],
+            )
+            .unwrap();
+        Party {
+            authority,
+            actor,
+            wallet,
+        }
+    }
+
+    fn tokenize(
+        &mut self,
+        party: Party,
+        token_unit: TokenUnit,
+    ) -> rice_supply_svm::TransactionResult {
+        let instruction = instructions::tokenize_rice_batch(
+            &party.authority,
+            &party.actor,
+            &party.wallet,
+            &self.rice_batch,
+            args::TokenizeRiceBatch {
+                token_unit,
+                name: "Dinorado 24DS-07".to_string(),
+                symbol: "RICE".to_string(),
+                uri: "https://id.gs1.org/01/04800016644511/10/24DS-07".to_string(),
+            },
+        );
+        self.fixture.send(instruction, &[party.authority])
+    }
+
+    fn transfer(
+        &mut self,
+        from: &Pubkey,
+        to: &Pubkey,
+        token_unit: TokenUnit,
+        amount: u64,
+    ) -> rice_supply_svm::TransactionResult {
+        let payer = self.fixture.wallet();
+        self.fixture
+            .send(
+                instructions::create_batch_token_account(&payer, to, &self.rice_batch),
+                &[payer],
+            )
+            .unwrap();
+        let instruction =
+            instructions::transfer_batch_tokens(from, to, &self.rice_batch, token_unit, amount);
+        self.fixture.send(instruction, &[*from])
+    }
+
+    fn balance(&self, wallet: &Pubkey) -> u64 {
+        let address = pda::batch_token_account(&self.rice_batch, wallet);
+        self.fixture
+            .svm
+            .account::<TokenAccount>(&address)
+            .unwrap()
+            .amount
+    }
+
+    fn rice_batch(&self) -> RiceBatch {
+        self.fixture.svm.account(&self.rice_batch).unwrap()
+    }
+}
+
+#[test]
+fn tokenize_rice_batch_mints_an_nft_describing_the_batch() {
+    let mut tokens = Tokens::new();
+    let miller = tokens.miller;
+    tokens.tokenize(miller, TokenUnit::Batch).unwrap();
+
+    let rice_batch = tokens.rice_batch();
+    let token_mint = pda::batch_mint(&tokens.rice_batch).0;
+    assert_eq!(rice_batch.token_mint, Some(token_mint));
+    assert_eq!(rice_batch.current_holder, Some(miller.actor));
+    assert_eq!(tokens.balance(&miller.wallet), 1);
+
+    let mint = tokens.fixture.svm.account::<Mint>(&token_mint).unwrap();
+    assert_eq!(mint.supply, 1);
+    assert_eq!(mint.decimals, 0);
+    assert_eq!(
+        Option::<Pubkey>::from(mint.mint_authority),
+        Some(pda::mint_authority().0)
+    );
+
+    let data = &tokens.fixture.svm.get_account(&token_mint).unwrap().data;
+    let state = StateWithExtensions::<SplMint>::unpack(data).unwrap();
+    let pointer = state.get_extension::<MetadataPointer>().unwrap();
+    assert_eq!(
+        Option::<Pubkey>::from(pointer.metadata_address),
+        Some(token_mint)
+    );
+    let metadata = state.get_variable_len_extension::<TokenMetadata>().unwrap();
+    assert_eq!(metadata.symbol, "RICE");
+    assert_eq!(
+        metadata.additional_metadata,
+        vec![
+            ("rice_batch".to_string(), tokens.rice_batch.to_string()),
+            ("unit".to_string(), "batch".to_string()),
+            ("weight_kg".to_string(), "1000".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn tokenize_rice_batch_requires_the_current_holder() {
+    let mut tokens = Tokens::new();
+    let stranger = tokens.party("stranger", Organization::Distributor);
+    assert_error(
+        tokens.tokenize(stranger, TokenUnit::Batch),
+        ErrorCode::NotBatchHolder,
+    );
+    // The creator no longer speaks for a batch it has sold.
+    let seller = tokens.seller;
+    assert_error(
+        tokens.tokenize(seller, TokenUnit::Batch),
+        ErrorCode::NotBatchHolder,
+    );
+    let miller = tokens.miller;
+    tokens.tokenize(miller, TokenUnit::Batch).unwrap();
+    assert!(tokens.tokenize(miller, TokenUnit::Kilogram).is_err());
+}
+
+#[test]
+fn tokenize_rice_batch_rejects_batches_in_transit_or_consumed() {
+    let mut tokens = Tokens::new();
+    let miller = tokens.miller;
+    let milled_rice = tokens.rice_batch().milled_rice_id;
+    let update = |batch_status| {
+        instructions::update_rice_batch(
+            &miller.authority,
+            Some(&miller.actor),
+            &milled_rice,
+            args::UpdateRiceBatch {
+                batch_seed: "batch-1".to_string(),
+                batch_status: Some(batch_status),
+                quality_score: None,
+                weight_kg: None,
+            },
+        )
+    };
+    tokens
+        .fixture
+        .send(update(BatchStatus::InTransit), &[miller.authority])
+        .unwrap();
+    assert_error(
+        tokens.tokenize(miller, TokenUnit::Batch),
+        ErrorCode::InvalidStatusTransition,
+    );
+
+    tokens
+        .fixture
+        .send(update(BatchStatus::Delivered), &[miller.authority])
+        .unwrap();
+    tokens
+        .fixture
+        .send(
+            instructions::delete_rice_batch(
+                &miller.authority,
+                Some(&miller.actor),
+                "batch-1",
+                "QR-batch-1",
+            ),
+            &[miller.authority],
+        )
+        .unwrap();
+    assert_error(
+        tokens.tokenize(miller, TokenUnit::Batch),
+        ErrorCode::InvalidStatusTransition,
+    );
+}
+
+#[test]
+fn transfers_between_registered_actors_move_custody() {
+    let mut tokens = Tokens::new();
+    let miller = tokens.miller;
+    let buyer = tokens.party("buyer", Organization::Distributor);
+    tokens.tokenize(miller, TokenUnit::Batch).unwrap();
+
+    tokens
+        .transfer(&miller.wallet, &buyer.wallet, TokenUnit::Batch, 1)
+        .unwrap();
+    assert_eq!(tokens.balance(&buyer.wallet), 1);
+    assert_eq!(tokens.rice_batch().current_holder, Some(buyer.actor));
+    let payer = tokens.fixture.wallet();
+    tokens
+        .fixture
+        .send(
+            instructions::assert_batch_holder(&tokens.rice_batch, buyer.actor),
+            &[payer],
+        )
+        .unwrap();
+
+    // A marketplace escrow is not a registered actor.
+    let escrow = tokens.fixture.wallet();
+    tokens
+        .transfer(&buyer.wallet, &escrow, TokenUnit::Batch, 1)
+        .unwrap();
+    assert_eq!(tokens.rice_batch().current_holder, None);
+}
+
+#[test]
+fn kilogram_tokens_move_custody_only_with_the_whole_supply() {
+    let mut tokens = Tokens::new();
+    let miller = tokens.miller;
+    let buyer = tokens.party("buyer", Organization::Distributor);
+    tokens.tokenize(miller, TokenUnit::Kilogram).unwrap();
+    assert_eq!(tokens.balance(&miller.wallet), 1_000_000);
+
+    tokens
+        .transfer(&miller.wallet, &buyer.wallet, TokenUnit::Kilogram, 400_000)
+        .unwrap();
+    assert_eq!(tokens.rice_batch().current_holder, None);
+
+    tokens
+        .transfer(&miller.wallet, &buyer.wallet, TokenUnit::Kilogram, 600_000)
+        .unwrap();
+    assert_eq!(tokens.balance(&buyer.wallet), 1_000_000);
+    assert_eq!(tokens.rice_batch().current_holder, Some(buyer.actor));
+}
+
+#[test]
+fn transfer_hook_rejects_calls_outside_a_transfer() {
+    let mut tokens = Tokens::new();
+    let miller = tokens.miller;
+    tokens.tokenize(miller, TokenUnit::Batch).unwrap();
+
+    let token_account = pda::batch_token_account(&tokens.rice_batch, &miller.wallet);
+    let token_mint = pda::batch_mint(&tokens.rice_batch).0;
+    let instruction = Instruction {
+        program_id: rice_supply_chain::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(token_account, false),
+            AccountMeta::new_readonly(token_mint, false),
+            AccountMeta::new_readonly(token_account, false),
+            AccountMeta::new_readonly(miller.wallet, false),
+            AccountMeta::new_readonly(pda::extra_account_metas(&token_mint).0, false),
+            AccountMeta::new(tokens.rice_batch, false),
+            AccountMeta::new_readonly(pda::custodian(&miller.wallet).0, false),
+        ],
+        data: args::TransferHook { _amount: 1 }.data(),
+    };
+    let payer = tokens.fixture.wallet();
+    assert_error(
+        tokens.fixture.send(instruction, &[payer]),
+        ErrorCode::NotTransferring,
+    );
+}
+
+#[test]
+fn split_batches_have_no_custodian() {
+    let mut tokens = Tokens::new();
+    let (seller, miller) = (tokens.seller, tokens.miller);
+    let buyer = tokens.party("buyer", Organization::Distributor);
+    tokens.tokenize(miller, TokenUnit::Kilogram).unwrap();
+    tokens
+        .transfer(&miller.wallet, &buyer.wallet, TokenUnit::Kilogram, 400_000)
+        .unwrap();
+
+    // The creator sold the batch, and the token holders each hold a part.
+    for (authority, holder) in [
+        (seller.authority, None),
+        (seller.authority, Some(&seller.actor)),
+        (miller.authority, None),
+        (miller.authority, Some(&miller.actor)),
+    ] {
+        assert_error(
+            tokens.fixture.send(
+                instructions::delete_rice_batch(&authority, holder, "batch-1", "QR-batch-1"),
+                &[authority],
+            ),
+            ErrorCode::NotBatchHolder,
+        );
+    }
+    assert!(tokens.rice_batch().batch_status == BatchStatus::Created);
+}
+
+#[test]
+fn tokenized_batches_without_a_holder_stay_with_no_one() {
+    let mut tokens = Tokens::new();
+    let seller = tokens.seller;
+    // Tokenized by its creator before any sale.
+    let milled_rice = tokens.fixture.milling(&seller.authority);
+    tokens
+        .fixture
+        .send(
+            instructions::create_rice_batch(
+                &seller.authority,
+                batch_args(&milled_rice, "batch-2", 500, "QR-batch-2"),
+            ),
+            &[seller.authority],
+        )
+        .unwrap();
+    tokens.rice_batch = pda::rice_batch("batch-2").0;
+    tokens.tokenize(seller, TokenUnit::Batch).unwrap();
+
+    let escrow = tokens.fixture.wallet();
+    tokens
+        .transfer(&seller.wallet, &escrow, TokenUnit::Batch, 1)
+        .unwrap();
+    assert_eq!(tokens.rice_batch().current_holder, None);
+    assert_error(
+        tokens.fixture.send(
+            instructions::delete_rice_batch(&seller.authority, None, "batch-2", "QR-batch-2"),
+            &[seller.authority],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+}
+
+#[test]
+fn tokenized_batches_keep_their_weight() {
+    let mut tokens = Tokens::new();
+    let miller = tokens.miller;
+    tokens.tokenize(miller, TokenUnit::Kilogram).unwrap();
+    let milled_rice = tokens.rice_batch().milled_rice_id;
+    let update = |weight_kg, quality_score| {
+        instructions::update_rice_batch(
+            &miller.authority,
+            Some(&miller.actor),
+            &milled_rice,
+            args::UpdateRiceBatch {
+                batch_seed: "batch-1".to_string(),
+                batch_status: None,
+                quality_score,
+                weight_kg,
+            },
+        )
+    };
+
+    assert_error(
+        tokens
+            .fixture
+            .send(update(Some(900), None), &[miller.authority]),
+        ErrorCode::AlreadyTokenized,
+    );
+    tokens
+        .fixture
+        .send(update(Some(1_000), Some(85)), &[miller.authority])
+        .unwrap();
+    let rice_batch = tokens.rice_batch();
+    assert_eq!(rice_batch.weight_kg, 1_000);
+    assert_eq!(rice_batch.quality_score, 85);
+}
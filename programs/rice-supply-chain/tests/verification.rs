@@ -0,0 +1,243 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::set_return_data;
+use common::{assert_error, Fixture};
+use rice_supply_chain::cpi::accounts::{AssertProductionSeason, AssertRiceBatch};
+use rice_supply_chain::{
+    AccreditationKind, BatchStatus, BatchView, ErrorCode, Organization, SeasonView,
+    ValidationStatus,
+};
+use rice_supply_client::instructions::{self, args};
+use rice_supply_svm::Account;
+
+/// An example lender that takes a rice batch as collateral. It accepts the
+/// batch only if the borrower holds it, it has been delivered and its season
+/// was approved, and returns the collateral weight in kilograms.
+mod lender {
+    use super::*;
+
+    pub const ID: Pubkey = Pubkey::new_from_array([7; 32]);
+
+    /// Accounts: the rice-supply-chain program, the batch, the season.
+    /// Data: the borrower's `ChainActor` address.
+    pub fn process<'info>(
+        _program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> ProgramResult {
+        let [program, rice_batch, production_season] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let borrower = Pubkey::try_from_slice(data)?;
+        let batch_accounts = || AssertRiceBatch {
+            rice_batch: rice_batch.clone(),
+        };
+
+        let batch: BatchView = rice_supply_chain::cpi::assert_batch_holder(
+            CpiContext::new(program.clone(), batch_accounts()),
+            borrower,
+        )?
+        .get();
+        rice_supply_chain::cpi::assert_batch_status(
+            CpiContext::new(program.clone(), batch_accounts()),
+            BatchStatus::Delivered,
+        )?;
+        let season: SeasonView = rice_supply_chain::cpi::assert_season_approved(CpiContext::new(
+            program.clone(),
+            AssertProductionSeason {
+                production_season: production_season.clone(),
+            },
+        ))?
+        .get();
+        if season.available_for_milling_kg < u64::from(batch.weight_kg) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        set_return_data(&u64::from(batch.weight_kg).to_le_bytes());
+        Ok(())
+    }
+
+    pub fn open_loan(
+        rice_batch: &Pubkey,
+        production_season: &Pubkey,
+        borrower: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: vec![
+                AccountMeta::new_readonly(rice_supply_chain::ID, false),
+                AccountMeta::new_readonly(*rice_batch, false),
+                AccountMeta::new_readonly(*production_season, false),
+            ],
+            data: borrower.to_bytes().to_vec(),
+        }
+    }
+}
+
+/// A delivered batch sold to `buyer` and an approved 1,200 kg season.
+struct Collateral {
+    fixture: Fixture,
+    rice_batch: Pubkey,
+    production_season: Pubkey,
+    buyer: Pubkey,
+    validator: (Pubkey, Pubkey),
+}
+
+impl Collateral {
+    fn new() -> Self {
+        let mut fixture = Fixture::new();
+        fixture.svm.add_program(lender::ID, lender::process);
+        let (_, buyer) = fixture.actor("buyer", Organization::Distributor);
+        let (seller_authority, seller) = fixture.actor("seller", Organization::Farmer);
+
+        let rice_batch = fixture.batch_created_by("batch-1", 1_000, &seller_authority);
+        let milled_rice = fixture.milling(&seller_authority);
+        for batch_status in [BatchStatus::InTransit, BatchStatus::Delivered] {
+            fixture
+                .send(
+                    instructions::update_rice_batch(
+                        &seller_authority,
+                        None,
+                        &milled_rice,
+                        args::UpdateRiceBatch {
+                            batch_seed: "batch-1".to_string(),
+                            batch_status: Some(batch_status),
+                            quality_score: None,
+                            weight_kg: None,
+                        },
+                    ),
+                    &[seller_authority],
+                )
+                .unwrap();
+        }
+        fixture.hand_over("sale", rice_batch, seller_authority, seller, buyer);
+
+        let production_season = fixture.season("season-1", Some(1_200));
+        let validator = fixture.accredited("validator", AccreditationKind::Validator);
+        let mut collateral = Self {
+            fixture,
+            rice_batch,
+            production_season,
+            buyer,
+            validator,
+        };
+        collateral.validate(ValidationStatus::Approved);
+        collateral
+    }
+
+    fn validate(&mut self, validation_status: ValidationStatus) {
+        self.fixture
+            .validate(self.validator, "season-1", validation_status)
+            .unwrap();
+    }
+
+    fn open_loan(&mut self, borrower: &Pubkey) -> rice_supply_svm::TransactionResult {
+        let payer = self.fixture.wallet();
+        let instruction = lender::open_loan(&self.rice_batch, &self.production_season, borrower);
+        self.fixture.send(instruction, &[payer])
+    }
+}
+
+#[test]
+fn assert_instructions_return_views_of_the_record() {
+    let mut collateral = Collateral::new();
+    let payer = collateral.fixture.wallet();
+
+    let metadata = collateral
+        .fixture
+        .send(
+            instructions::assert_batch_holder(&collateral.rice_batch, collateral.buyer),
+            &[payer],
+        )
+        .unwrap();
+    let (program_id, data) = metadata.return_data.unwrap();
+    assert_eq!(program_id, rice_supply_chain::ID);
+    let batch = BatchView::try_from_slice(&data).unwrap();
+    assert_eq!(batch.rice_batch, collateral.rice_batch);
+    assert!(batch.batch_status == BatchStatus::Delivered);
+    assert_eq!(batch.current_holder, Some(collateral.buyer));
+    assert_eq!(batch.weight_kg, 1_000);
+
+    let metadata = collateral
+        .fixture
+        .send(
+            instructions::assert_season_approved(&collateral.production_season),
+            &[payer],
+        )
+        .unwrap();
+    let season = SeasonView::try_from_slice(&metadata.return_data.unwrap().1).unwrap();
+    assert_eq!(season.production_season, collateral.production_season);
+    assert_eq!(season.available_for_milling_kg, 1_200);
+}
+
+#[test]
+fn assert_instructions_fail_when_the_fact_does_not_hold() {
+    let mut collateral = Collateral::new();
+    let payer = collateral.fixture.wallet();
+    assert_error(
+        collateral.fixture.send(
+            instructions::assert_batch_status(&collateral.rice_batch, BatchStatus::InTransit),
+            &[payer],
+        ),
+        ErrorCode::BatchStatusMismatch,
+    );
+    assert_error(
+        collateral.fixture.send(
+            instructions::assert_batch_holder(&collateral.rice_batch, Pubkey::new_unique()),
+            &[payer],
+        ),
+        ErrorCode::NotBatchHolder,
+    );
+
+    collateral.validate(ValidationStatus::Rejected);
+    assert_error(
+        collateral.fixture.send(
+            instructions::assert_season_approved(&collateral.production_season),
+            &[payer],
+        ),
+        ErrorCode::SeasonNotApproved,
+    );
+}
+
+#[test]
+fn consumer_program_verifies_collateral_through_cpi() {
+    let mut collateral = Collateral::new();
+    let buyer = collateral.buyer;
+    let metadata = collateral.open_loan(&buyer).unwrap();
+    let (program_id, data) = metadata.return_data.unwrap();
+    assert_eq!(program_id, lender::ID);
+    assert_eq!(data, 1_000u64.to_le_bytes());
+
+    assert_error(
+        collateral.open_loan(&Pubkey::new_unique()),
+        ErrorCode::NotBatchHolder,
+    );
+    collateral.validate(ValidationStatus::Rejected);
+    assert_error(collateral.open_loan(&buyer), ErrorCode::SeasonNotApproved);
+}
+
+#[test]
+fn consumer_program_rejects_a_forged_batch() {
+    let mut collateral = Collateral::new();
+    let buyer = collateral.buyer;
+    let genuine = collateral
+        .fixture
+        .svm
+        .get_account(&collateral.rice_batch)
+        .unwrap()
+        .clone();
+    collateral.fixture.svm.set_account(
+        collateral.rice_batch,
+        Account {
+            owner: lender::ID,
+            ..genuine
+        },
+    );
+    assert_error(
+        collateral.open_loan(&buyer),
+        anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram,
+    );
+}
@@ -66,7 +66,7 @@ const deleteChainActor = asyncHandler(async (req, res) => {
   const { publicKey } = req.params;
   
   // Instead of deleting, we mark as inactive
-  const result = await solanaService.updateChainActor(publicKey, { isActive: false });
+  const result = await solanaService.deleteChainActor(publicKey, req.query.seed);
   
   res.json(formatResponse(result, 'Chain actor deactivated successfully'));
 });
@@ -67,7 +67,7 @@ const updateChainTransaction = asyncHandler(async (req, res) => {
 const deleteChainTransaction = asyncHandler(async (req, res) => {
   const { publicKey } = req.params;
   
-  const result = await solanaService.deleteChainTransaction(publicKey);
+  const result = await solanaService.deleteChainTransaction(publicKey, req.query.seed);
   
   res.json(formatResponse(result, 'Chain transaction deleted successfully'));
 });
@@ -79,7 +79,7 @@ const updateProductionSeason = asyncHandler(async (req, res) => {
 const deleteProductionSeason = asyncHandler(async (req, res) => {
   const { publicKey } = req.params;
   
-  const result = await solanaService.deleteProductionSeason(publicKey);
+  const result = await solanaService.deleteProductionSeason(publicKey, req.query.seed);
   
   res.json(formatResponse(result, 'Production season deleted successfully'));
 });
@@ -82,7 +82,7 @@ const updateRiceBatch = asyncHandler(async (req, res) => {
 const deleteRiceBatch = asyncHandler(async (req, res) => {
   const { publicKey } = req.params;
   
-  const result = await solanaService.deleteRiceBatch(publicKey);
+  const result = await solanaService.deleteRiceBatch(publicKey, req.query.seed);
   
   res.json(formatResponse(result, 'Rice batch deleted successfully'));
 });
@@ -66,13 +66,11 @@ router.post('/production-seasons',
 );
 router.put('/production-seasons/:publicKey',
   validatePublicKey('publicKey'),
-  validateOptionalPublicKeys(['validatorId']),
   validateNumericField('processedYieldKg', 0),
   validateNumericField('totalYieldKg', 0),
   validateNumericField('moistureContent', 0, 10000),
   validateDateField('plantingDate'),
   validateDateField('harvestDate'),
-  validateEnumField('validationStatus', ['pending', 'validated', 'rejected']),
   productionSeasonController.updateProductionSeason
 );
 router.delete('/production-seasons/:publicKey',
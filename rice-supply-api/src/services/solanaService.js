@@ -5,6 +5,34 @@ const fs = require('fs');
 const path = require('path');
 const { logger } = require('../utils/helpers');
 
+// Program accounts are PDAs of a caller-chosen seed, and every instruction
+// takes that seed as its first argument. Callers pass it as `seed`; it must
+// derive the address the request names.
+function seedFor(programId, prefix, publicKey, seed) {
+  if (!seed) {
+    throw new Error(`seed is required to address ${prefix} ${publicKey} on chain`);
+  }
+  const [address] = PublicKey.findProgramAddressSync(
+    [Buffer.from(prefix), Buffer.from(seed)],
+    programId
+  );
+  if (!address.equals(new PublicKey(publicKey))) {
+    throw new Error(`seed ${seed} does not derive ${prefix} ${publicKey}`);
+  }
+  return seed;
+}
+
+// Anchor encodes enum variants as `{ variantName: {} }`.
+function toEnum(value) {
+  if (!value) return null;
+  const variant = value.charAt(0).toLowerCase() + value.slice(1);
+  return { [variant]: {} };
+}
+
+function toUnixSeconds(date) {
+  return date ? new BN(Math.floor(new Date(date).getTime() / 1000)) : null;
+}
+
 class SolanaService {
   constructor() {
     // Set up data directory for file persistence
@@ -85,9 +113,9 @@ class SolanaService {
       
       // Load the IDL and initialize the Anchor program
       try {
-        const idlPath = path.join(__dirname, './rice_supply_chain_deployed.json');
+        const idlPath = path.join(__dirname, '../rice_supply_chain_deployed.json');
         const idl = JSON.parse(fs.readFileSync(idlPath, 'utf8'));
-        this.program = new Program(idl, this.programId, this.provider);
+        this.program = new Program({ ...idl, address: this.programId.toBase58() }, this.provider);
         logger.info('Anchor program initialized successfully');
       } catch (idlError) {
         logger.warn('Failed to load IDL, using direct blockchain access:', idlError.message);
@@ -288,20 +316,21 @@ class SolanaService {
       
       const tx = await this.program.methods
         .updateChainActor(
+          seedFor(this.programId, 'chain_actor', publicKey, data.seed),
           data.name || null,
           data.actorType || null,
           data.farmId ? new PublicKey(data.farmId) : null,
           data.farmerId ? new PublicKey(data.farmerId) : null,
-          data.assignedTps ? new BN(data.assignedTps) : null,
-          data.isActive !== undefined ? data.isActive : null,
-          data.balance ? new BN(data.balance) : null,
+          data.assignedTps !== undefined ? new BN(data.assignedTps) : null,
           data.pin || null,
-          data.organization || null,
-          data.address || null
+          toEnum(data.organization),
+          data.address || null,
+          data.isActive !== undefined ? data.isActive : null,
+          data.balance !== undefined ? new BN(data.balance) : null
         )
-        .accounts({
+        .accountsPartial({
           chainActor: chainActorPubkey,
-          user: this.wallet.publicKey,
+          authority: this.wallet.publicKey,
         })
         .rpc();
 
@@ -450,26 +479,34 @@ class SolanaService {
       }
       
       const productionSeasonPubkey = new PublicKey(publicKey);
+      // The wallet signs for the season's farmer.
+      const season = await this.program.account.productionSeason.fetch(productionSeasonPubkey);
       
       const tx = await this.program.methods
         .updateProductionSeason(
+          seedFor(this.programId, 'production_season', publicKey, data.seed),
           data.cropYear || null,
-          data.processedYieldKg ? new BN(data.processedYieldKg) : null,
-          data.variety || null,
+          data.processedYieldKg !== undefined ? new BN(data.processedYieldKg) : null,
+          toEnum(data.variety),
           data.plannedPractice || null,
-          data.plantingDate ? new BN(Math.floor(new Date(data.plantingDate).getTime() / 1000)) : null,
-          data.irrigationPractice || null,
+          toUnixSeconds(data.plantingDate),
+          toEnum(data.irrigationPractice),
           data.fertilizerUsed || null,
           data.pesticideUsed || null,
-          data.harvestDate ? new BN(Math.floor(new Date(data.harvestDate).getTime() / 1000)) : null,
-          data.totalYieldKg ? new BN(data.totalYieldKg) : null,
-          data.moistureContent || null,
-          data.carbonSmartCertified !== undefined ? data.carbonSmartCertified : null,
-          data.validationStatus || null,
-          data.validatorId ? new PublicKey(data.validatorId) : null
+          toUnixSeconds(data.harvestDate),
+          data.totalYieldKg !== undefined ? new BN(data.totalYieldKg) : null,
+          data.moistureContent !== undefined ? data.moistureContent : null,
+          data.cultivatedAreaM2 !== undefined ? new BN(data.cultivatedAreaM2) : null,
+          data.organicAmendments
+            ? data.organicAmendments.map((amendment) => ({
+                kind: toEnum(amendment.kind),
+                rateKgPerHa: amendment.rateKgPerHa,
+              }))
+            : null
         )
-        .accounts({
+        .accountsPartial({
           productionSeason: productionSeasonPubkey,
+          farmer: season.farmerId,
           user: this.wallet.publicKey,
         })
         .rpc();
@@ -598,14 +635,16 @@ class SolanaService {
       
       const tx = await this.program.methods
         .updateMilledRice(
-          data.totalWeightKg || null,
-          data.millingType || null,
-          data.quality || null,
+          seedFor(this.programId, 'milled_rice', publicKey, data.seed),
+          data.totalWeightKg !== undefined ? data.totalWeightKg : null,
+          toEnum(data.millingType),
+          toEnum(data.quality),
           data.photoUrls || null,
-          data.moisture || null,
-          data.totalWeightProcessedKg || null
+          data.moisture !== undefined ? data.moisture : null,
+          data.totalWeightProcessedKg !== undefined ? data.totalWeightProcessedKg : null,
+          data.byProducts || null
         )
-        .accounts({
+        .accountsPartial({
           milledRice: milledRicePubkey,
           user: this.wallet.publicKey,
         })
@@ -736,21 +775,20 @@ class SolanaService {
       }
       
       const riceBatchPubkey = new PublicKey(publicKey);
+      // The wallet signs for the batch's holder, or as its creator.
+      const batch = await this.program.account.riceBatch.fetch(riceBatchPubkey);
       
       const tx = await this.program.methods
         .updateRiceBatch(
-          data.qrCode || null,
-          data.millingId ? new PublicKey(data.millingId) : null,
-          data.batchWeightKg ? new BN(data.batchWeightKg) : null,
-          data.moistureContent || null,
-          data.currentHolderId ? new PublicKey(data.currentHolderId) : null,
-          data.pricePerKg ? new BN(data.pricePerKg) : null,
-          data.dryingId ? new PublicKey(data.dryingId) : null,
-          data.validator ? new PublicKey(data.validator) : null,
-          data.status || null
+          seedFor(this.programId, 'rice_batch', publicKey, data.seed),
+          toEnum(data.status),
+          data.qualityScore !== undefined ? data.qualityScore : null,
+          data.batchWeightKg !== undefined ? data.batchWeightKg : null
         )
-        .accounts({
+        .accountsPartial({
           riceBatch: riceBatchPubkey,
+          milledRice: batch.milledRiceId,
+          holder: batch.currentHolder,
           user: this.wallet.publicKey,
         })
         .rpc();
@@ -912,19 +950,22 @@ class SolanaService {
       }
       
       const chainTransactionPubkey = new PublicKey(publicKey);
+      // Only the seller's authority may change the transaction.
+      const transaction = await this.program.account.chainTransaction.fetch(chainTransactionPubkey);
       
       const tx = await this.program.methods
         .updateChainTransaction(
-          data.pricePerKg ? new BN(data.pricePerKg) : null,
-          data.moisture || null,
-          data.quality || null,
+          seedFor(this.programId, 'chain_transaction', publicKey, data.seed),
+          data.amount !== undefined ? new BN(data.amount) : null,
+          toEnum(data.paymentMethod),
           data.paymentReference || null,
-          data.paymentMethod || null,
-          data.status || null,
-          data.geotagging || null
+          data.geotag || null,
+          toEnum(data.quality),
+          toEnum(data.status)
         )
-        .accounts({
+        .accountsPartial({
           chainTransaction: chainTransactionPubkey,
+          fromActor: transaction.fromActorId,
           user: this.wallet.publicKey,
         })
         .rpc();
@@ -1079,24 +1120,88 @@ class SolanaService {
   }
 
   // Delete methods (mark as inactive instead of actual deletion)
-  async deleteChainActor(publicKey) {
-    return this.updateChainActor(publicKey, { isActive: false });
+  async deleteChainActor(publicKey, seed) {
+    return this.deleteRecord(
+      'deleteChainActor',
+      'chain_actor',
+      publicKey,
+      seed,
+      'chainActor',
+      'authority'
+    );
   }
 
-  async deleteProductionSeason(publicKey) {
-    return this.updateProductionSeason(publicKey, { isActive: false });
+  async deleteProductionSeason(publicKey, seed) {
+    const season = this.program
+      ? await this.program.account.productionSeason.fetch(new PublicKey(publicKey))
+      : null;
+    return this.deleteRecord(
+      'deleteProductionSeason',
+      'production_season',
+      publicKey,
+      seed,
+      'productionSeason',
+      'user',
+      { farmer: season && season.farmerId }
+    );
   }
 
+  // Milled rice has no delete instruction; its records are kept for lineage.
   async deleteMilledRice(publicKey) {
-    return this.updateMilledRice(publicKey, { isActive: false });
+    if (!this.program) {
+      logger.info(`Mock milled_rice deleted: ${publicKey}`);
+      return { transaction: 'mock-delete-transaction' };
+    }
+    throw new Error(`milled rice ${publicKey} cannot be deleted on chain`);
+  }
+
+  async deleteRiceBatch(publicKey, seed) {
+    const batch = this.program
+      ? await this.program.account.riceBatch.fetch(new PublicKey(publicKey))
+      : null;
+    // The batch's QR index entry is closed with it; `rice_batch` follows the discriminator.
+    const qrIndexes = this.program
+      ? await this.program.account.qrIndex.all([{ memcmp: { offset: 8, bytes: publicKey } }])
+      : [];
+    return this.deleteRecord('deleteRiceBatch', 'rice_batch', publicKey, seed, 'riceBatch', 'user', {
+      qrIndex: qrIndexes.length > 0 ? qrIndexes[0].publicKey : null,
+      holder: batch && batch.currentHolder,
+    });
   }
 
-  async deleteRiceBatch(publicKey) {
-    return this.updateRiceBatch(publicKey, { isActive: false });
+  async deleteChainTransaction(publicKey, seed) {
+    const transaction = this.program
+      ? await this.program.account.chainTransaction.fetch(new PublicKey(publicKey))
+      : null;
+    return this.deleteRecord(
+      'deleteChainTransaction',
+      'chain_transaction',
+      publicKey,
+      seed,
+      'chainTransaction',
+      'user',
+      { fromActor: transaction && transaction.fromActorId }
+    );
   }
 
-  async deleteChainTransaction(publicKey) {
-    return this.updateChainTransaction(publicKey, { isActive: false });
+  // The program's delete instructions deactivate, reject or cancel the record
+  // rather than closing its account.
+  async deleteRecord(method, prefix, publicKey, seed, accountName, signerName = 'user', accounts = {}) {
+    if (!this.program) {
+      logger.info(`Mock ${prefix} deleted: ${publicKey}`);
+      return { transaction: 'mock-delete-transaction' };
+    }
+
+    const tx = await this.program.methods[method](seedFor(this.programId, prefix, publicKey, seed))
+      .accountsPartial({
+        [accountName]: new PublicKey(publicKey),
+        ...accounts,
+        [signerName]: this.wallet.publicKey,
+      })
+      .rpc();
+
+    logger.info(`${prefix} deleted: ${publicKey}`);
+    return { transaction: tx };
   }
 }
 